@@ -572,24 +572,26 @@ pub unsafe fn dispatch_gemm_batch_f32(
             }
         }
         GemmBackend::Pire => {
-            for i in 0..batch_size {
-                pire_gemm_f32::pire_sgemm(
-                    m,
-                    n,
-                    k,
-                    alpha,
-                    a.offset(i as isize * stridea),
-                    a_rs as usize,
-                    a_cs as usize,
-                    b.offset(i as isize * strideb),
-                    b_rs as usize,
-                    b_cs as usize,
-                    beta,
-                    c.offset(i as isize * stridec),
-                    c_rs as usize,
-                    c_cs as usize,
-                );
-            }
+            pire_gemm_f32::pire_sgemm_batch_strided(
+                batch_size,
+                m,
+                n,
+                k,
+                alpha,
+                a,
+                a_rs as usize,
+                a_cs as usize,
+                stridea as usize,
+                b,
+                b_rs as usize,
+                b_cs as usize,
+                strideb as usize,
+                beta,
+                c,
+                c_rs as usize,
+                c_cs as usize,
+                stridec as usize,
+            );
         }
     }
 }
@@ -0,0 +1,113 @@
+//! Tile configuration and os support for the AMX kernels of the gemm crates
+
+use core::arch::asm;
+
+/// Memory operand of `ldtilecfg`, always palette 1 (8 tiles of at most 16 rows and 64 bytes per row)
+#[repr(C, align(64))]
+pub struct TileConfig {
+    palette_id: u8,
+    start_row: u8,
+    reserved: [u8; 14],
+    colsb: [u16; 16],
+    rows: [u8; 16],
+}
+
+impl Default for TileConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TileConfig {
+    pub const MAX_ROWS: usize = 16;
+    pub const MAX_COLSB: usize = 64;
+
+    /// All tiles unconfigured, using a tile with 0 rows faults
+    pub const fn new() -> Self {
+        Self { palette_id: 1, start_row: 0, reserved: [0; 14], colsb: [0; 16], rows: [0; 16] }
+    }
+
+    pub fn set_tile(&mut self, tile: usize, rows: usize, colsb: usize) {
+        assert!(tile < 8 && rows <= Self::MAX_ROWS && colsb <= Self::MAX_COLSB);
+        self.rows[tile] = rows as u8;
+        self.colsb[tile] = colsb as u16;
+    }
+
+    /// # Safety
+    ///
+    /// AMX must be available and permitted by the os, see [`crate::CpuFeatures::amx_tile`]
+    #[inline(always)]
+    pub unsafe fn load(&self) {
+        asm!("ldtilecfg ({})", in(reg) self as *const Self, options(att_syntax, nostack, readonly));
+    }
+}
+
+/// Returns the tile state to init state, so that the os does not save / restore it on context switch
+///
+/// # Safety
+///
+/// AMX must be available and permitted by the os
+#[inline(always)]
+pub unsafe fn tile_release() {
+    asm!("tilerelease", options(att_syntax, nostack, nomem));
+}
+
+// linux keeps the tile data state disabled until the process asks for it, the permission is per process
+#[cfg(target_os = "linux")]
+pub(crate) fn request_amx_permission() -> bool {
+    const ARCH_REQ_XCOMP_PERM: libc::c_long = 0x1023;
+    const XFEATURE_XTILEDATA: libc::c_long = 18;
+    unsafe { libc::syscall(libc::SYS_arch_prctl, ARCH_REQ_XCOMP_PERM, XFEATURE_XTILEDATA) == 0 }
+}
+
+// no portable way to check that the os saves the tile state, keep amx off
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn request_amx_permission() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tile_config_layout() {
+        // 64 byte memory operand of ldtilecfg: palette at 0, colsb of tile t at 16 + 2t, rows of tile t at 48 + t
+        assert_eq!(core::mem::size_of::<TileConfig>(), 64);
+        assert_eq!(core::mem::align_of::<TileConfig>(), 64);
+        let mut cfg = TileConfig::new();
+        cfg.set_tile(0, 16, 64);
+        cfg.set_tile(5, 3, 12);
+        cfg.set_tile(7, 1, 4);
+        let bytes = unsafe { &*(&cfg as *const TileConfig as *const [u8; 64]) };
+        assert_eq!(bytes[0], 1);
+        assert!(bytes[1..16].iter().all(|&x| x == 0));
+        assert_eq!(u16::from_le_bytes([bytes[16], bytes[17]]), 64);
+        assert_eq!(u16::from_le_bytes([bytes[26], bytes[27]]), 12);
+        assert_eq!(u16::from_le_bytes([bytes[30], bytes[31]]), 4);
+        assert_eq!((bytes[48], bytes[53], bytes[55]), (16, 3, 1));
+        // unused tiles stay unconfigured
+        assert!(bytes[18..26].iter().chain(&bytes[28..30]).chain(&bytes[32..48]).all(|&x| x == 0));
+        assert!(bytes[49..53].iter().chain(&bytes[56..]).all(|&x| x == 0) && bytes[54] == 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tile_config_too_many_rows() {
+        TileConfig::new().set_tile(0, TileConfig::MAX_ROWS + 1, 64);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_tile_config_too_wide() {
+        TileConfig::new().set_tile(0, 16, TileConfig::MAX_COLSB + 1);
+    }
+
+    #[test]
+    fn test_amx_needs_tile_permission() {
+        // amx compute features are only reported along with the tile state granted by the os
+        let ft = crate::RUNTIME_HW_CONFIG.cpu_ft();
+        assert!(!ft.amx_int8 || ft.amx_tile);
+        assert!(!ft.amx_bf16 || ft.amx_tile);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::thread_pool::run_tasks;
+use crate::{pire_num_threads, split_range, PirePar};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// batch items are disjoint, so pointers can be shared between the threads working on different items
+struct SyncPtr<T>(*const T);
+
+unsafe impl<T> Sync for SyncPtr<T> {}
+
+impl<T> SyncPtr<T> {
+    fn get(&self) -> *const T {
+        self.0
+    }
+}
+
+/// All threads of the pool, to be split between the problems of a batched or grouped call
+pub fn pool_par() -> PirePar {
+    let num_threads = pire_num_threads();
+    PirePar::new(num_threads, num_threads, 1, 1, 1, 1)
+}
+
+/// Default par of a batched call, all threads of the pool for the whole batch
+///
+/// Unlike [`PirePar::default`] the number of threads is not capped by m and n of a single item,
+/// [`batch_par`] applies the cap to the threads of each item instead. A single item runs as a plain gemm.
+pub fn batch_par_default(batch: usize, m: usize, n: usize) -> PirePar {
+    if batch <= 1 {
+        return PirePar::default(m, n);
+    }
+    pool_par()
+}
+
+/// Splits the threads of `par` between batch items and the gemm of each item,
+/// returns the number of items run in parallel and the par used for the items of each of them
+///
+/// Threads left over by the split go to the first items running in parallel, one each.
+/// `par` is used as is for every item when the items run one after another, i.e. `batch == 1` or a single thread
+pub fn batch_par(batch: usize, m: usize, n: usize, par: &PirePar) -> (usize, Vec<PirePar>) {
+    let batch_par = batch.min(par.num_threads).max(1);
+    if batch_par == 1 {
+        return (1, vec![*par]);
+    }
+    let (task_threads, rem) = (par.num_threads / batch_par, par.num_threads % batch_par);
+    let pars = (0..batch_par).map(|t_id| PirePar::from_num_threads(task_threads + (t_id < rem) as usize, m, n));
+    (batch_par, pars.collect())
+}
+
+/// Runs `f(a + i * stride_a, b + i * stride_b, c + i * stride_c, par)` for every batch item i,
+/// consecutive items are distributed over the threads of `par` as given by [`batch_par`]
+///
+/// The gemms of items running in parallel take their own workers from the thread pool.
+///
+/// # Safety
+///
+/// Pointers of every batch item must be valid for `f` and c of distinct items must not overlap
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn run_batch_strided<TA, TB, TC, G: Fn(*const TA, *const TB, *mut TC, &PirePar) + Sync>(
+    batch: usize,
+    m: usize,
+    n: usize,
+    a: *const TA,
+    stride_a: usize,
+    b: *const TB,
+    stride_b: usize,
+    c: *mut TC,
+    stride_c: usize,
+    par: &PirePar,
+    f: G,
+) {
+    if batch == 0 {
+        return;
+    }
+    let (batch_par, pars) = batch_par(batch, m, n, par);
+    let (a, b, c) = (SyncPtr(a), SyncPtr(b), SyncPtr(c as *const TC));
+    run_tasks(batch_par, |t_id| {
+        let (start, end) = split_range(batch, 1, t_id, batch_par);
+        let par = &pars[t_id];
+        // items of one thread run one after another, so they reuse the same packing buffer from PACK_POOL
+        for i in start..end {
+            f(a.get().add(i * stride_a), b.get().add(i * stride_b), c.get().add(i * stride_c) as *mut TC, par);
+        }
+    });
+}
+
+/// Problems of a grouped gemm that share dims, scalars and strides, strides follow the convention of the gemm crates
+#[derive(Clone, Copy, Debug)]
+pub struct GemmGroup<TAS, TBS> {
+    pub m: usize,
+    pub n: usize,
+    pub k: usize,
+    pub alpha: TAS,
+    pub a_rs: usize,
+    pub a_cs: usize,
+    pub b_rs: usize,
+    pub b_cs: usize,
+    pub beta: TBS,
+    pub c_rs: usize,
+    pub c_cs: usize,
+    // number of problems in the group
+    pub size: usize,
+}
+
+/// Runs `f(group, a[i], b[i], c[i], par)` for every problem i of the groups,
+/// where a, b, c hold the pointers of all groups one after another (as in the group batch api of mkl)
+///
+/// Problems are not split with the full par, each of the `par.num_threads` threads picks the next problem
+/// once it is done with its current one, largest problems first. Threads are shared between problems only
+/// when there are fewer problems than threads, the gemms running in parallel then take their own workers
+/// from the thread pool.
+///
+/// # Safety
+///
+/// Pointers of every problem must be valid for `f` and c of distinct problems must not overlap
+pub unsafe fn run_grouped<TAS, TBS, TA, TB, TC, G>(
+    groups: &[GemmGroup<TAS, TBS>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+    f: G,
+) where
+    TAS: Sync,
+    TBS: Sync,
+    G: Fn(&GemmGroup<TAS, TBS>, *const TA, *const TB, *mut TC, &PirePar) + Sync,
+{
+    let total: usize = groups.iter().map(|g| g.size).sum();
+    assert!(a.len() >= total && b.len() >= total && c.len() >= total, "fewer pointers than problems in groups");
+    // (group id, problem id), problems with empty c have nothing to compute
+    let mut problems = vec![];
+    let mut p_id = 0;
+    for (g_id, g) in groups.iter().enumerate() {
+        if g.m != 0 && g.n != 0 {
+            problems.extend((p_id..p_id + g.size).map(|p| (g_id, p)));
+        }
+        p_id += g.size;
+    }
+    if problems.is_empty() {
+        return;
+    }
+    problems.sort_by_key(|&(g_id, _)| {
+        let g = &groups[g_id];
+        core::cmp::Reverse(g.m * g.n * g.k.max(1))
+    });
+    let num_threads = par.num_threads;
+    let num_tasks = problems.len().min(num_threads).max(1);
+    let task_threads = num_threads / num_tasks;
+    let next = AtomicUsize::new(0);
+    let (a, b, c) = (SyncPtr(a.as_ptr()), SyncPtr(b.as_ptr()), SyncPtr(c.as_ptr()));
+    run_tasks(num_tasks, |_| loop {
+        let i = next.fetch_add(1, Ordering::Relaxed);
+        if i >= problems.len() {
+            break;
+        }
+        let (g_id, p_id) = problems[i];
+        let g = &groups[g_id];
+        let par = PirePar::from_num_threads(task_threads, g.m, g.n);
+        f(g, *a.get().add(p_id), *b.get().add(p_id), *c.get().add(p_id), &par);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_par_small_items() {
+        // small items do not cap the threads of the batch, only the threads of each item
+        let par = PirePar::new(16, 16, 1, 1, 1, 1);
+        let (items_par, item_pars) = batch_par(1000, 16, 16, &par);
+        assert_eq!(items_par, 16);
+        assert!(item_pars.iter().all(|p| p.num_threads == 1));
+
+        let (items_par, _) = batch_par(1000, 16, 16, &batch_par_default(1000, 16, 16));
+        assert_eq!(items_par, pire_num_threads().min(1000));
+    }
+
+    #[test]
+    fn test_batch_par_remainder() {
+        // 8 threads over 3 items in parallel, the 2 threads left over go to the first two
+        let par = PirePar::new(8, 8, 1, 1, 1, 1);
+        let (items_par, item_pars) = batch_par(3, 2000, 2000, &par);
+        assert_eq!(items_par, 3);
+        assert_eq!(item_pars.iter().map(|p| p.num_threads).collect::<Vec<_>>(), [3, 3, 2]);
+    }
+}
@@ -0,0 +1,118 @@
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GemmOperand {
+    A,
+    B,
+    C,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GemmError {
+    // stride of zero along a dimension with more than one element
+    ZeroStride(GemmOperand),
+    // distinct elements of c map to the same memory location
+    OverlappingStride,
+    BufferTooSmall { operand: GemmOperand, required: usize, len: usize },
+    // extent of the matrix given by its dims and strides does not fit in usize
+    SizeOverflow(GemmOperand),
+}
+
+impl core::fmt::Display for GemmError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            GemmError::ZeroStride(x) => write!(f, "zero stride for matrix {:?}", x),
+            GemmError::OverlappingStride => write!(f, "strides of matrix C map distinct elements to the same location"),
+            GemmError::BufferTooSmall { operand, required, len } => {
+                write!(f, "buffer of matrix {:?} has length {}, but at least {} is required", operand, len, required)
+            }
+            GemmError::SizeOverflow(x) => write!(f, "size of matrix {:?} overflows usize", x),
+        }
+    }
+}
+
+impl std::error::Error for GemmError {}
+
+/// Row and column strides (in elements) of a matrix stored in a slice
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MatLayout {
+    pub rs: usize,
+    pub cs: usize,
+}
+
+impl MatLayout {
+    pub fn strided(rs: usize, cs: usize) -> Self {
+        Self { rs, cs }
+    }
+    pub fn row_major(ld: usize) -> Self {
+        Self { rs: ld, cs: 1 }
+    }
+    pub fn col_major(ld: usize) -> Self {
+        Self { rs: 1, cs: ld }
+    }
+}
+
+// minimum slice length for m x n matrix with given layout, None if it does not fit in usize
+fn required_len(m: usize, n: usize, l: MatLayout) -> Option<usize> {
+    if m == 0 || n == 0 {
+        return Some(0);
+    }
+    (m - 1).checked_mul(l.rs)?.checked_add((n - 1).checked_mul(l.cs)?)?.checked_add(1)
+}
+
+fn check_strides(m: usize, n: usize, l: MatLayout, operand: GemmOperand) -> Result<(), GemmError> {
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if (m > 1 && l.rs == 0) || (n > 1 && l.cs == 0) {
+        return Err(GemmError::ZeroStride(operand));
+    }
+    Ok(())
+}
+
+fn check_len<T>(x: &[T], m: usize, n: usize, l: MatLayout, operand: GemmOperand) -> Result<(), GemmError> {
+    let required = required_len(m, n, l).ok_or(GemmError::SizeOverflow(operand))?;
+    if x.len() < required {
+        return Err(GemmError::BufferTooSmall { operand, required, len: x.len() });
+    }
+    Ok(())
+}
+
+/// Validates the arguments of a slice based gemm call, c is m x n, a is m x k, b is k x n
+#[allow(clippy::too_many_arguments)]
+pub fn check_gemm_args<TA, TB, TC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    a: &[TA],
+    a_l: MatLayout,
+    b: &[TB],
+    b_l: MatLayout,
+    c: &[TC],
+    c_l: MatLayout,
+) -> Result<(), GemmError> {
+    check_strides(m, k, a_l, GemmOperand::A)?;
+    check_strides(k, n, b_l, GemmOperand::B)?;
+    check_strides(m, n, c_l, GemmOperand::C)?;
+    // c is written by multiple threads, so each element needs its own location
+    if m > 1 && n > 1 {
+        let c_m = m.checked_mul(c_l.rs).ok_or(GemmError::SizeOverflow(GemmOperand::C))?;
+        let c_n = n.checked_mul(c_l.cs).ok_or(GemmError::SizeOverflow(GemmOperand::C))?;
+        if c_l.cs < c_m && c_l.rs < c_n {
+            return Err(GemmError::OverlappingStride);
+        }
+    }
+    check_len(a, m, k, a_l, GemmOperand::A)?;
+    check_len(b, k, n, b_l, GemmOperand::B)?;
+    check_len(c, m, n, c_l, GemmOperand::C)?;
+    Ok(())
+}
+
+/// Applies `f` to every element of the m x n matrix c, for k == 0 the product is empty
+/// and the checked entry points only scale c by beta
+pub fn scale_c<T: Copy>(m: usize, n: usize, c: &mut [T], c_l: MatLayout, f: impl Fn(T) -> T) {
+    for j in 0..n {
+        for i in 0..m {
+            let idx = i * c_l.rs + j * c_l.cs;
+            c[idx] = f(c[idx]);
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::UnaryFn;
+use half::{bf16, f16};
+
+/// Output type of [`DequantFn`]
+pub trait DequantOut: Copy {
+    /// Whether the result is stored into the i32 slot of the accumulator, i.e. c is the i32 c of the gemm,
+    /// smaller types are stored into their own c and the i32 c is a separate buffer
+    const IN_PLACE: bool;
+    fn from_f32(x: f32) -> Self;
+}
+
+impl DequantOut for f32 {
+    const IN_PLACE: bool = true;
+    #[inline(always)]
+    fn from_f32(x: f32) -> Self {
+        x
+    }
+}
+
+impl DequantOut for f16 {
+    const IN_PLACE: bool = false;
+    #[inline(always)]
+    fn from_f32(x: f32) -> Self {
+        f16::from_f32(x)
+    }
+}
+
+impl DequantOut for bf16 {
+    const IN_PLACE: bool = false;
+    #[inline(always)]
+    fn from_f32(x: f32) -> Self {
+        bf16::from_f32(x)
+    }
+}
+
+/// Epilogue of integer gemm, writes `c[i, j] = scale_a[i] * scale_b[j] * acc[i, j] + bias[j]`
+/// in the store step of the kernel
+///
+/// f32 results go into the i32 slot of `acc[i, j]`, bf16 and f16 results into `c[i, j]`
+#[derive(Copy, Clone)]
+pub struct DequantFn<T: DequantOut = f32> {
+    scale_a: *const f32,
+    scale_b: *const f32,
+    bias: *const f32,
+    c: *mut T,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+}
+
+// scales and bias are only read, every thread writes its own part of c
+unsafe impl<T: DequantOut> Sync for DequantFn<T> {}
+
+impl<T: DequantOut> DequantFn<T> {
+    /// `scale_a` has `m` and `scale_b` `n` elements, `bias` has `n` elements or is null for no bias
+    pub fn new(
+        scale_a: *const f32,
+        scale_b: *const f32,
+        bias: *const f32,
+        c: *mut T,
+        c_rs: usize,
+        c_cs: usize,
+    ) -> Self {
+        Self { scale_a, scale_b, bias, c, c_rs, c_cs, i0: 0, j0: 0 }
+    }
+
+    /// `c[i, j] = bias[j]` for the `k = 0` case where the kernel is not run
+    ///
+    /// # Safety
+    ///
+    /// c must be valid for `m x n` with the strides given in [`DequantFn::new`]
+    pub unsafe fn fill_bias(&self, m: usize, n: usize) {
+        for j in 0..n {
+            let bias_j = if self.bias.is_null() { 0.0 } else { *self.bias.add(j) };
+            for i in 0..m {
+                *self.c.add(i * self.c_rs + j * self.c_cs) = T::from_f32(bias_j);
+            }
+        }
+    }
+}
+
+impl<T: DequantOut> UnaryFn<i32> for DequantFn<T> {
+    #[inline(always)]
+    unsafe fn call(self, c: *mut i32, m: usize) {
+        self.call_tile(c, m, 0, 0);
+    }
+
+    #[inline(always)]
+    unsafe fn call_tile(self, c: *mut i32, m: usize, i: usize, j: usize) {
+        // the m elements are rows i..i+m of column j, contiguous in c (or in the buffer of the kernel)
+        let (i, j) = (self.i0 + i, self.j0 + j);
+        let scale_b_j = *self.scale_b.add(j);
+        let bias_j = if self.bias.is_null() { 0.0 } else { *self.bias.add(j) };
+        for t in 0..m {
+            let x = *self.scale_a.add(i + t) * scale_b_j * *c.add(t) as f32 + bias_j;
+            if T::IN_PLACE {
+                *(c.add(t) as *mut T) = T::from_f32(x);
+            } else {
+                *self.c.add((i + t) * self.c_rs + j * self.c_cs) = T::from_f32(x);
+            }
+        }
+    }
+
+    #[inline(always)]
+    fn at(self, i: usize, j: usize) -> Self {
+        Self { i0: self.i0 + i, j0: self.j0 + j, ..self }
+    }
+}
@@ -6,13 +6,66 @@ use core::mem::size_of;
 use once_cell::sync::Lazy;
 use std::sync::{Barrier, Mutex, MutexGuard, RwLock, RwLockReadGuard};
 
+#[cfg(target_arch = "x86_64")]
+pub mod amx;
+pub mod batch;
+pub mod checked;
+pub mod dequant;
 pub mod range_rwlock;
+pub mod src_b;
+pub mod thread_pool;
+
+pub use thread_pool::run_par;
 
 #[derive(Copy, Clone)]
 pub struct IdentityFn;
 
 pub trait UnaryFn<T>: Copy + std::marker::Sync {
+    /// # Safety
+    ///
+    /// `c` must be valid for reads and writes of `m` contiguous elements
     unsafe fn call(self, c: *mut T, m: usize);
+    /// Same as `call` for rows `i..i + m` of column `j` of c, counted from the block given to `at`,
+    /// the column may live in a buffer of the kernel rather than in c
+    ///
+    /// # Safety
+    ///
+    /// `c` must be valid for reads and writes of `m` contiguous elements
+    /// and the rows and column must be inside the c the function is made for
+    #[inline(always)]
+    unsafe fn call_tile(self, c: *mut T, m: usize, _i: usize, _j: usize) {
+        self.call(c, m);
+    }
+    /// The function for the block of c starting at row `i` and column `j` of the current block,
+    /// only functions depending on the position in c need to track it
+    #[inline(always)]
+    fn at(self, _i: usize, _j: usize) -> Self {
+        self
+    }
+}
+
+/// `f` for the transposed c, for gemv kernels that run on the transposed problem
+#[derive(Copy, Clone)]
+pub struct TransposedFn<F>(pub F);
+
+impl<T, F: UnaryFn<T>> UnaryFn<T> for TransposedFn<F> {
+    #[inline(always)]
+    unsafe fn call(self, c: *mut T, m: usize) {
+        self.call_tile(c, m, 0, 0);
+    }
+
+    #[inline(always)]
+    unsafe fn call_tile(self, c: *mut T, m: usize, i: usize, j: usize) {
+        // the column of the transposed c is a row of c
+        for t in 0..m {
+            self.0.call_tile(c.add(t), 1, j, i + t);
+        }
+    }
+
+    #[inline(always)]
+    fn at(self, i: usize, j: usize) -> Self {
+        Self(self.0.at(j, i))
+    }
 }
 
 impl<T> UnaryFn<T> for IdentityFn {
@@ -62,9 +115,14 @@ pub struct CpuFeatures {
     pub avx2: bool,
     pub avx512f: bool,
     pub avx512f16: bool,
-    // pub avx512bf16: bool,
+    pub avx512bf16: bool,
     pub avx512bw: bool,
     pub avx512_vnni: bool,
+    pub avx_vnni: bool,
+    /// amx features are only set if the os granted the tile data state to the process
+    pub amx_tile: bool,
+    pub amx_int8: bool,
+    pub amx_bf16: bool,
     pub fma: bool,
     pub fma4: bool,
     pub f16c: bool,
@@ -86,11 +144,33 @@ pub struct CpuFeatures {
     pub neon: bool,
     pub fp16: bool,
     pub f32mm: bool,
+    pub f64mm: bool,
     pub fcma: bool,
     pub i8mm: bool,
+    pub dotprod: bool,
+    pub bf16: bool,
+}
+
+#[cfg(target_arch = "riscv64")]
+#[derive(Copy, Clone)]
+pub struct CpuFeatures {
+    pub v: bool,
 }
 
-#[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")))]
+#[cfg(target_arch = "wasm32")]
+#[derive(Copy, Clone)]
+pub struct CpuFeatures {
+    /// wasm has no runtime detection, this is set if the crate is compiled with `target_feature = "simd128"`
+    pub simd128: bool,
+}
+
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "wasm32"
+)))]
 #[derive(Copy, Clone)]
 pub struct CpuFeatures {
     pub dummy: bool,
@@ -190,10 +270,14 @@ fn detect_hw_config() -> HWConfig {
         let fma = feature_info.has_fma();
         let avx2 = extended_feature_info.has_avx2();
         let avx512f16 = extended_feature_info.has_avx512_fp16();
-        // let avx512bf16 = extended_feature_info.has_avx512_bf16();
+        let avx512bf16 = extended_feature_info.has_avx512_bf16();
         let avx512f = extended_feature_info.has_avx512f();
         let avx512bw = extended_feature_info.has_avx512bw();
         let avx512_vnni = extended_feature_info.has_avx512vnni();
+        let avx_vnni = extended_feature_info.has_avx_vnni();
+        let amx_tile = extended_feature_info.has_amx_tile() && amx::request_amx_permission();
+        let amx_int8 = amx_tile && extended_feature_info.has_amx_int8();
+        let amx_bf16 = amx_tile && extended_feature_info.has_amx_bf16();
         let f16c = feature_info.has_f16c();
         let extended_processor_info = cpuid.get_extended_processor_and_feature_identifiers().unwrap();
         let fma4 = extended_processor_info.has_fma4();
@@ -206,8 +290,13 @@ fn detect_hw_config() -> HWConfig {
             avx2,
             avx512f,
             avx512f16,
+            avx512bf16,
             avx512bw,
             avx512_vnni,
+            avx_vnni,
+            amx_tile,
+            amx_int8,
+            amx_bf16,
             fma,
             fma4,
             f16c,
@@ -240,18 +329,49 @@ fn detect_hw_config() -> HWConfig {
         let sve = is_aarch64_feature_detected!("sve");
         let fp16 = is_aarch64_feature_detected!("fp16");
         let f32mm = is_aarch64_feature_detected!("f32mm");
+        let f64mm = is_aarch64_feature_detected!("f64mm");
         let fcma = is_aarch64_feature_detected!("fcma");
         let i8mm = is_aarch64_feature_detected!("i8mm");
+        let dotprod = is_aarch64_feature_detected!("dotprod");
+        let bf16 = is_aarch64_feature_detected!("bf16");
 
         return HWConfig {
-            cpu_ft: CpuFeatures { neon, sve, fp16, f32mm, fcma, i8mm },
+            cpu_ft: CpuFeatures { neon, sve, fp16, f32mm, f64mm, fcma, i8mm, dotprod, bf16 },
+            hw_model: HWModel::Reference,
+            is_l1_shared: false,
+            is_l2_shared: false,
+            is_l3_shared: true,
+        };
+    }
+    #[cfg(target_arch = "riscv64")]
+    {
+        let v = detect_riscv_v();
+        return HWConfig {
+            cpu_ft: CpuFeatures { v },
+            hw_model: HWModel::Reference,
+            is_l1_shared: false,
+            is_l2_shared: false,
+            is_l3_shared: true,
+        };
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let simd128 = cfg!(target_feature = "simd128");
+        return HWConfig {
+            cpu_ft: CpuFeatures { simd128 },
             hw_model: HWModel::Reference,
             is_l1_shared: false,
             is_l2_shared: false,
             is_l3_shared: true,
         };
     }
-    #[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")))]
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        target_arch = "wasm32"
+    )))]
     {
         return HWConfig {
             cpu_ft: CpuFeatures { dummy: false },
@@ -263,6 +383,21 @@ fn detect_hw_config() -> HWConfig {
     }
 }
 
+// std has no stable runtime detection for riscv yet, read the isa bits of hwcap
+// the kernel only sets V if it also saves the vector state on context switch
+#[cfg(target_arch = "riscv64")]
+fn detect_riscv_v() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        let hwcap = unsafe { libc::getauxval(libc::AT_HWCAP) };
+        hwcap & (1 << (b'V' - b'A')) != 0
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        false
+    }
+}
+
 #[cfg(feature = "debug_cpu_features")]
 #[allow(unused)]
 fn apply_debug_cpu_features(cpu_ft: &mut CpuFeatures) {
@@ -276,8 +411,13 @@ fn apply_debug_cpu_features(cpu_ft: &mut CpuFeatures) {
         let avx2_turn_off = std::env::var("PIRE_AVX2_OFF").is_ok();
         let avx512f_turn_off = std::env::var("PIRE_AVX512F_OFF").is_ok();
         let avx512f16_turn_off = std::env::var("PIRE_AVX512F16_OFF").is_ok();
+        let avx512bf16_turn_off = std::env::var("PIRE_AVX512BF16_OFF").is_ok();
         let avx512bw_turn_off = std::env::var("PIRE_AVX512BW_OFF").is_ok();
         let avx512_vnni_turn_off = std::env::var("PIRE_AVX512_VNNI_OFF").is_ok();
+        let avx_vnni_turn_off = std::env::var("PIRE_AVX_VNNI_OFF").is_ok();
+        let amx_tile_turn_off = std::env::var("PIRE_AMX_TILE_OFF").is_ok();
+        let amx_int8_turn_off = std::env::var("PIRE_AMX_INT8_OFF").is_ok();
+        let amx_bf16_turn_off = std::env::var("PIRE_AMX_BF16_OFF").is_ok();
         let fma_turn_off = std::env::var("PIRE_FMA_OFF").is_ok();
         let fma4_turn_off = std::env::var("PIRE_FMA4_OFF").is_ok();
         let f16c_turn_off = std::env::var("PIRE_F16C_OFF").is_ok();
@@ -290,8 +430,13 @@ fn apply_debug_cpu_features(cpu_ft: &mut CpuFeatures) {
         cpu_ft.avx2 = cpu_ft.avx2 && !avx2_turn_off;
         cpu_ft.avx512f = cpu_ft.avx512f && !avx512f_turn_off;
         cpu_ft.avx512f16 = cpu_ft.avx512f16 && !avx512f16_turn_off;
+        cpu_ft.avx512bf16 = cpu_ft.avx512bf16 && !avx512bf16_turn_off;
         cpu_ft.avx512bw = cpu_ft.avx512bw && !avx512bw_turn_off;
         cpu_ft.avx512_vnni = cpu_ft.avx512_vnni && !avx512_vnni_turn_off;
+        cpu_ft.avx_vnni = cpu_ft.avx_vnni && !avx_vnni_turn_off;
+        cpu_ft.amx_tile = cpu_ft.amx_tile && !amx_tile_turn_off;
+        cpu_ft.amx_int8 = cpu_ft.amx_int8 && cpu_ft.amx_tile && !amx_int8_turn_off;
+        cpu_ft.amx_bf16 = cpu_ft.amx_bf16 && cpu_ft.amx_tile && !amx_bf16_turn_off;
         cpu_ft.fma = cpu_ft.fma && !fma_turn_off;
         cpu_ft.fma4 = cpu_ft.fma4 && !fma4_turn_off;
         cpu_ft.f16c = cpu_ft.f16c && !f16c_turn_off;
@@ -314,15 +459,33 @@ fn apply_debug_cpu_features(cpu_ft: &mut CpuFeatures) {
         let sve_turn_off = std::env::var("PIRE_SVE_OFF").is_ok();
         let fp16_turn_off = std::env::var("PIRE_FP16_OFF").is_ok();
         let f32mm_turn_off = std::env::var("PIRE_F32MM_OFF").is_ok();
+        let f64mm_turn_off = std::env::var("PIRE_F64MM_OFF").is_ok();
         let fcma_turn_off = std::env::var("PIRE_FCMA_OFF").is_ok();
         let i8mm_turn_off = std::env::var("PIRE_I8MM_OFF").is_ok();
+        let dotprod_turn_off = std::env::var("PIRE_DOTPROD_OFF").is_ok();
+        let bf16_turn_off = std::env::var("PIRE_BF16_OFF").is_ok();
 
         cpu_ft.neon = cpu_ft.neon && !neon_turn_off;
         cpu_ft.sve = cpu_ft.sve && !sve_turn_off;
         cpu_ft.fp16 = cpu_ft.fp16 && !fp16_turn_off;
         cpu_ft.f32mm = cpu_ft.f32mm && !f32mm_turn_off;
+        cpu_ft.f64mm = cpu_ft.f64mm && !f64mm_turn_off;
         cpu_ft.fcma = cpu_ft.fcma && !fcma_turn_off;
         cpu_ft.i8mm = cpu_ft.i8mm && !i8mm_turn_off;
+        cpu_ft.dotprod = cpu_ft.dotprod && !dotprod_turn_off;
+        cpu_ft.bf16 = cpu_ft.bf16 && !bf16_turn_off;
+    }
+    #[cfg(target_arch = "riscv64")]
+    {
+        let v_turn_off = std::env::var("PIRE_V_OFF").is_ok();
+
+        cpu_ft.v = cpu_ft.v && !v_turn_off;
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let simd128_turn_off = std::env::var("PIRE_SIMD128_OFF").is_ok();
+
+        cpu_ft.simd128 = cpu_ft.simd128 && !simd128_turn_off;
     }
 }
 
@@ -336,7 +499,8 @@ pub static RUNTIME_HW_CONFIG: Lazy<HWConfig> = Lazy::new(|| {
 });
 
 pub static PIRE_NUM_THREADS: Lazy<usize> = Lazy::new(|| {
-    let n_core = std::thread::available_parallelism().unwrap().get();
+    // e.g. wasm has no threads, available_parallelism returns an error there
+    let n_core = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
     // PIRE_NUM_THREADS or the number of logical cores
     let x = std::env::var("PIRE_NUM_THREADS").unwrap_or(n_core.to_string());
     x.parse::<usize>().unwrap()
@@ -379,6 +543,17 @@ pub(crate) mod cpu_features {
             && RUNTIME_HW_CONFIG.cpu_ft.f16c
             && RUNTIME_HW_CONFIG.cpu_ft.fma
     }
+    pub fn has_bf16_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.avx512bf16 && RUNTIME_HW_CONFIG.cpu_ft.avx512f && RUNTIME_HW_CONFIG.cpu_ft.avx
+    }
+    // bf16 inputs widened to f32 during packing and computed with f32 kernels
+    pub fn has_bf16f32_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.avx2 && RUNTIME_HW_CONFIG.cpu_ft.avx && RUNTIME_HW_CONFIG.cpu_ft.fma
+    }
+    // fp8 inputs widened to f32 during packing and computed with f32 kernels
+    pub fn has_f8f32_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.avx2 && RUNTIME_HW_CONFIG.cpu_ft.avx && RUNTIME_HW_CONFIG.cpu_ft.fma
+    }
     pub fn has_i16i32_compute() -> bool {
         (RUNTIME_HW_CONFIG.cpu_ft.avx2 && RUNTIME_HW_CONFIG.cpu_ft.avx)
             || (RUNTIME_HW_CONFIG.cpu_ft.sse && RUNTIME_HW_CONFIG.cpu_ft.sse2)
@@ -427,6 +602,15 @@ pub(crate) mod cpu_features {
     pub fn has_f16_compute() -> bool {
         false
     }
+    pub fn has_bf16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f8f32_compute() -> bool {
+        false
+    }
     pub fn has_i16i32_compute() -> bool {
         RUNTIME_HW_CONFIG.cpu_ft.sse && RUNTIME_HW_CONFIG.cpu_ft.sse2
     }
@@ -465,7 +649,8 @@ pub(crate) mod cpu_features {
     }
 
     pub fn has_f16f32_compute() -> bool {
-        false
+        // f16 <-> f32 conversion (fcvtl/fcvtn) is part of base neon
+        RUNTIME_HW_CONFIG.cpu_ft.neon
     }
     pub fn has_f64_compute() -> bool {
         RUNTIME_HW_CONFIG.cpu_ft.neon
@@ -478,13 +663,124 @@ pub(crate) mod cpu_features {
     pub fn has_f16_compute() -> bool {
         RUNTIME_HW_CONFIG.cpu_ft.fp16 && RUNTIME_HW_CONFIG.cpu_ft.neon
     }
+    pub fn has_bf16_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.bf16 && RUNTIME_HW_CONFIG.cpu_ft.neon
+    }
+    pub fn has_bf16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f8f32_compute() -> bool {
+        false
+    }
+    pub fn has_i16i32_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.neon
+    }
+    pub fn has_i8i32_compute() -> bool {
+        (RUNTIME_HW_CONFIG.cpu_ft.i8mm || RUNTIME_HW_CONFIG.cpu_ft.dotprod) && RUNTIME_HW_CONFIG.cpu_ft.neon
+    }
+    // TODO: Use actual info from hardware
+    pub fn get_cache_params() -> (usize, usize, usize) {
+        (4800, 256, 128)
+    }
+}
+
+#[cfg(target_arch = "riscv64")]
+pub(crate) mod cpu_features {
+    use super::HWModel;
+    use super::RUNTIME_HW_CONFIG;
+
+    pub fn hw_model() -> HWModel {
+        RUNTIME_HW_CONFIG.hw_model
+    }
+
+    pub fn has_f32_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.v
+    }
+
+    pub fn has_c32_compute() -> bool {
+        false
+    }
+
+    pub fn has_f16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f64_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.v
+    }
+
+    pub fn has_c64_compute() -> bool {
+        false
+    }
+
+    pub fn has_f16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f8f32_compute() -> bool {
+        false
+    }
+    pub fn has_i16i32_compute() -> bool {
+        false
+    }
+    pub fn has_i8i32_compute() -> bool {
+        false
+    }
+    // TODO: Use actual info from hardware
+    pub fn get_cache_params() -> (usize, usize, usize) {
+        (4800, 256, 128)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod cpu_features {
+    use super::HWModel;
+    use super::RUNTIME_HW_CONFIG;
+
+    pub fn hw_model() -> HWModel {
+        RUNTIME_HW_CONFIG.hw_model
+    }
+
+    pub fn has_f32_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.simd128
+    }
+
+    pub fn has_c32_compute() -> bool {
+        false
+    }
+
+    pub fn has_f16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f64_compute() -> bool {
+        RUNTIME_HW_CONFIG.cpu_ft.simd128
+    }
+
+    pub fn has_c64_compute() -> bool {
+        false
+    }
+
+    pub fn has_f16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f8f32_compute() -> bool {
+        false
+    }
     pub fn has_i16i32_compute() -> bool {
-        // currenty we do not support this
-        // since the only insturction is smlal, whose throupout is not high enough
         false
     }
     pub fn has_i8i32_compute() -> bool {
-        RUNTIME_HW_CONFIG.cpu_ft.i8mm && RUNTIME_HW_CONFIG.cpu_ft.neon
+        false
     }
     // TODO: Use actual info from hardware
     pub fn get_cache_params() -> (usize, usize, usize) {
@@ -492,7 +788,13 @@ pub(crate) mod cpu_features {
     }
 }
 
-#[cfg(not(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64")))]
+#[cfg(not(any(
+    target_arch = "x86_64",
+    target_arch = "x86",
+    target_arch = "aarch64",
+    target_arch = "riscv64",
+    target_arch = "wasm32"
+)))]
 pub(crate) mod cpu_features {
     use super::HWModel;
     use super::RUNTIME_HW_CONFIG;
@@ -523,6 +825,15 @@ pub(crate) mod cpu_features {
     pub fn has_f16_compute() -> bool {
         false
     }
+    pub fn has_bf16_compute() -> bool {
+        false
+    }
+    pub fn has_bf16f32_compute() -> bool {
+        false
+    }
+    pub fn has_f8f32_compute() -> bool {
+        false
+    }
     pub fn has_i16i32_compute() -> bool {
         false
     }
@@ -570,6 +881,28 @@ pub fn extend<'a>(pool_vec: Vec<u8>) {
     pool_guard.push(Mutex::new(pool_vec));
 }
 
+// takes a buffer of at least pack_size bytes out of the pool, for callers that run gemms while using it
+// (holding the pool guard across them would block extend), give it back with put_pool_buf
+pub fn take_pool_buf(pack_size: usize) -> (Option<usize>, Vec<u8>) {
+    let pool_guard = PACK_POOL.buffer.read().unwrap();
+    for (i, x) in pool_guard.iter().enumerate() {
+        if let Ok(mut pool_vec) = x.try_lock() {
+            if pool_vec.len() >= pack_size {
+                return (Some(i), std::mem::take(&mut *pool_vec));
+            }
+        }
+    }
+    (None, vec![0_u8; pack_size])
+}
+
+// buffers of the pool are never removed, so the slot index of take_pool_buf stays valid
+pub fn put_pool_buf(slot: Option<usize>, pool_vec: Vec<u8>) {
+    match slot {
+        Some(i) => *PACK_POOL.buffer.read().unwrap()[i].lock().unwrap() = pool_vec,
+        None => extend(pool_vec),
+    }
+}
+
 pub struct PireThreadConfig<'a> {
     pub ic_id: usize,
     // pc_id: usize,
@@ -662,7 +995,7 @@ pub fn pire_num_threads() -> usize {
     return *PIRE_NUM_THREADS;
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq)]
 pub struct PirePar {
     pub num_threads: usize,
     pub ic_par: usize,
@@ -767,6 +1100,10 @@ impl PirePar {
         let num_threads = pire_num_threads();
         Self::from_num_threads(num_threads, m, n)
     }
+    // runs entirely on the calling thread, no worker thread is spawned
+    pub fn single_thread() -> Self {
+        Self::new(1, 1, 1, 1, 1, 1)
+    }
     #[inline]
     fn get_ic_id(&self, t_id: usize) -> usize {
         (t_id / (self.pc_par * self.jc_par * self.ir_par * self.jr_par)) % self.ic_par
@@ -1041,6 +1378,7 @@ impl<T> StridedMatrix<T> {
 }
 
 unsafe impl<T> Send for StridedMatrix<T> {}
+unsafe impl<T> Sync for StridedMatrix<T> {}
 
 #[derive(Clone, Copy)]
 pub struct StridedMatrixMut<T> {
@@ -1050,6 +1388,7 @@ pub struct StridedMatrixMut<T> {
 }
 
 unsafe impl<T> Send for StridedMatrixMut<T> {}
+unsafe impl<T> Sync for StridedMatrixMut<T> {}
 
 impl<T> StridedMatrixMut<T> {
     pub fn new(src: *mut T, rs: usize, cs: usize) -> Self {
@@ -1098,6 +1437,7 @@ pub struct PackedMatrix<T> {
 }
 
 unsafe impl<T> Send for PackedMatrix<T> {}
+unsafe impl<T> Sync for PackedMatrix<T> {}
 
 impl<T> PackedMatrix<T> {
     pub fn src(&self) -> *const T {
@@ -1255,6 +1595,20 @@ impl<X> Array<X> {
             _ => false,
         }
     }
+
+    // packed layout depends on dims (e.g. mc, kc blocking), so packed buffer must be used with the same dims
+    pub fn assert_packed_dims(&self, m: usize, k: usize) {
+        if let Array::PackedMatrix(x) = self {
+            assert!(
+                x.m == m && x.k == k,
+                "packed matrix dims ({}, {}) do not match gemm dims ({}, {})",
+                x.m,
+                x.k,
+                m,
+                k
+            );
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -1427,19 +1781,30 @@ pub fn matrix_size_strided(m: usize, n: usize, rs: usize, cs: usize) -> usize {
 #[macro_export]
 macro_rules! packing_api {
     ($ta:ty, $tb:ty) => {
-        fn a_size_packed(m: usize, k: usize) -> usize {
+        /// Number of elements the buffer given to `pack_a` must hold
+        pub fn a_size_packed(m: usize, k: usize) -> usize {
             let round_m_fn = dispatch_round_m();
-            let round_k_fn = dispatch_round_k();
             let m_round = round_m_fn(m);
-            let k_round = round_k_fn(k);
+            let k_round = k_size_packed(k);
             return m_round * k_round;
         }
 
-        fn b_size_packed(n: usize, k: usize) -> usize {
-            let round_k_fn = dispatch_round_k();
-            let k_round = round_k_fn(k);
+        /// Number of elements the buffer given to `pack_b` must hold
+        pub fn b_size_packed(n: usize, k: usize) -> usize {
+            let k_round = k_size_packed(k);
             return n * k_round;
         }
+
+        // k is packed in blocks of kc, each block is rounded on its own, so the packed size of k is the
+        // sum of round_k over the kc blocks and not round_k(k). The two differ when round_k is not additive
+        // over blocks, e.g. the vnni kernels of s8s8s32 add a group of column sums to every block, or when
+        // kc is not a multiple of the k granularity. def_pire_gemm finds the packed kc block kc_i at
+        // kc_i / kc * round_k(kc), as all blocks before it are full
+        fn k_size_packed(k: usize) -> usize {
+            let round_k_fn = dispatch_round_k();
+            let (_, _, kc) = dispatch_get_mcnckc();
+            (0..k).step_by(kc).map(|p| round_k_fn(kc.min(k - p))).sum()
+        }
         // block idx for packa and packb is s.t.
         // m dim for block idx is contiguous and n dim is contiguous
         // this is to ensure that indexing for parallelization over these dims are easy  (otherwise ranges would have to be in the same mc, nc range)
@@ -1575,7 +1940,7 @@ macro_rules! def_pire_gemm {
         $packa_name:ident, $packb_name:ident,
         $packa_name0:ident, $packb_name0:ident,
         $run_small_m:expr, $run_small_n:expr,
-        $pack_fn:tt, $include_flag:tt,
+        $pack_fn:tt, $include_flag:tt, $conj_flag:tt, $src_b_flag:tt,
     ) => {
         def_pa!($packa_ty,$include_flag,$ta,$tap);
         def_pa!($packb_ty,$include_flag,$tb,$tbp);
@@ -1592,6 +1957,9 @@ macro_rules! def_pire_gemm {
         {
             let a_need_pool = a.is_strided() || !hw_config.is_compute_native();
             let b_need_pool = b.is_strided() || !hw_config.is_compute_native();
+            // conjugation is applied during packing and inside gemv kernels,
+            // small m (n) paths read b (a) without packing so they must not be conjugated
+            let (conj_a, conj_b) = $crate::is_conj!($conj_flag, (hw_config.conj_a, hw_config.conj_b), (false, false));
             if n == 1 && a.is_strided() {
                 let alpha = &alpha as *const $t_as;
                 let beta = &beta as *const $t_bs;
@@ -1617,9 +1985,9 @@ macro_rules! def_pire_gemm {
                 ),
                 PoolSize
             )
-             = if run_small_m(m) && $run_small_m && b.is_strided() {
+             = if run_small_m(m) && $run_small_m && b.is_strided() && !conj_b {
                 (GemmPool::SmallM, $small_m_name, get_mem_pool_size_small_m::<$tap,$tbp,$t_dispatcher::<F>>(hw_config, par, a_need_pool))
-            } else if run_small_n(n) && $run_small_n && a.is_strided() {
+            } else if run_small_n(n) && $run_small_n && a.is_strided() && !conj_a {
                 (GemmPool::SmallN, $small_n_name, get_mem_pool_size_small_n::<$tap,$tbp,$t_dispatcher::<F>>(hw_config, par, b_need_pool))
             } else {
                 (GemmPool::Goto, $goto_name, get_mem_pool_size_goto::<$tap,$tbp,$t_dispatcher::<F>>(hw_config, par, a_need_pool, b_need_pool))
@@ -1676,7 +2044,6 @@ macro_rules! def_pire_gemm {
             let mc_eff = <$t_dispatcher::<F> as GemmCache>::get_mc_eff(hw_config, par.ic_par);
             let nc_eff = <$t_dispatcher::<F> as GemmCache>::get_nc_eff(hw_config, par.jc_par);
             let kc_eff = <$t_dispatcher::<F> as GemmCache>::get_kc_eff(hw_config);
-            let (pa_br_vec_ref, pb_br_vec_ref) = get_apbp_barrier(par);
 
             let (i_load_par, j_load_par) = par.get_load_par(&gemm_mode, m, n, mc_eff, nc_eff);
             let (ap_pool_vec, bp_pool_vec) = pool_info.slice_mut_from_pool::<$tap,$tbp>(
@@ -1684,43 +2051,24 @@ macro_rules! def_pire_gemm {
             );
             let (ap_pool, bp_pool) = (&ap_pool_vec, &bp_pool_vec);
 
-            // remove par.clone
-            std::thread::scope(|s| {
-                for t_id in 1..par.num_threads {
-                    let t_cfg = PireThreadConfig::new(
-                        par.clone(), &pa_br_vec_ref, &pb_br_vec_ref, t_id, mc_eff, nc_eff, kc_eff
-                    );
-                    let ic_id = t_cfg.ic_id;
-                    let jc_id = t_cfg.jc_id;
-                    let ap_id = match gemm_mode {
-                        GemmPool::Goto => ic_id,
-                        GemmPool::SmallM => ic_id,
-                        GemmPool::SmallN => t_id,
-                    };
-                    let bp_id = match gemm_mode {
-                        GemmPool::Goto => jc_id,
-                        GemmPool::SmallM => 0,
-                        GemmPool::SmallN => jc_id,
-                    };
-                    let ap_cur = a.$pack_fn(ap_pool, ap_id);
-                    let bp_cur = b.$pack_fn(bp_pool, bp_id);
-                    let g = hw_config;
-                    s.spawn(move || {
-                            let alpha = &alpha as *const $t_as;
-                            let beta = &beta as *const $t_bs;
-                            gemm_fn(g, m, n, k, alpha, ap_cur, bp_cur, beta, c, &t_cfg);
-                        }
-                    );
-                }
-                {
-                    let ap = a.$pack_fn(ap_pool, 0);
-                    let bp = b.$pack_fn(bp_pool, 0);
-                    let t_id: usize = 0;
-                    let t_cfg = PireThreadConfig::new(par.clone(), &pa_br_vec_ref, &pb_br_vec_ref, t_id, mc_eff, nc_eff, kc_eff);
-                    let alpha = &alpha as *const $t_as;
-                    let beta = &beta as *const $t_bs;
-                    gemm_fn(hw_config, m, n, k, alpha, ap, bp, beta, c, &t_cfg);
-                }
+            // threads come from the persistent pool (or the executor set by the user)
+            run_par(par, |t_id, pa_br, pb_br| {
+                let t_cfg = PireThreadConfig::new(*par, pa_br, pb_br, t_id, mc_eff, nc_eff, kc_eff);
+                let ap_id = match gemm_mode {
+                    GemmPool::Goto => t_cfg.ic_id,
+                    GemmPool::SmallM => t_cfg.ic_id,
+                    GemmPool::SmallN => t_id,
+                };
+                let bp_id = match gemm_mode {
+                    GemmPool::Goto => t_cfg.jc_id,
+                    GemmPool::SmallM => 0,
+                    GemmPool::SmallN => t_cfg.jc_id,
+                };
+                let ap = a.$pack_fn(ap_pool, ap_id);
+                let bp = b.$pack_fn(bp_pool, bp_id);
+                let alpha = &alpha as *const $t_as;
+                let beta = &beta as *const $t_bs;
+                gemm_fn(hw_config, m, n, k, alpha, ap, bp, beta, c, &t_cfg);
             });
         }
 
@@ -1781,7 +2129,7 @@ macro_rules! def_pire_gemm {
                         $goto_kernel(
                             hw_cfg, mr_len, nr_len, kc_len, alpha, beta_t, c_ij, c_rs, c_cs,
                             ap, bp,
-                            kc_last,
+                            mc_i + mr_start, nc_i + nr_start, kc_last,
                         );
 
                         nc_i += nc;
@@ -1875,7 +2223,7 @@ macro_rules! def_pire_gemm {
                             b_cur, b_rs, b_cs,
                             c_ij, c_rs, c_cs,
                             ap,
-                            kc_last,
+                            mc_i + mr_start, nc_i + nr_start, kc_last,
                         );
                         nc_i += nc;
                     }
@@ -1956,7 +2304,7 @@ macro_rules! def_pire_gemm {
                             a_cur, a_rs, a_cs,
                             a_dst_ptr, bp,
                             c_ij, c_rs, c_cs,
-                            kc_last,
+                            mc_i + mr_start, nc_i + nr_start, kc_last,
                         );
                         nc_i += nc;
                     }
@@ -2006,7 +2354,15 @@ macro_rules! def_pire_gemm {
                         let dst = x_i.dst_write(t_cfg.i_load_p_idx, kc_len_ro);
                         let dst_ref = dst.get();
                         let dst_ptr = dst_ref.as_mut_ptr();
-                        $packa_name0(src_ptr, dst_ptr, mc_len_x, kc_len, rs, cs);
+                        $crate::is_conj!(
+                            $conj_flag,
+                            if hw_cfg.conj_a {
+                                $packa_name0::<true>(src_ptr, dst_ptr, mc_len_x, kc_len, rs, cs)
+                            } else {
+                                $packa_name0::<false>(src_ptr, dst_ptr, mc_len_x, kc_len, rs, cs)
+                            },
+                            $packa_name0(src_ptr, dst_ptr, mc_len_x, kc_len, rs, cs)
+                        );
                     }
                     t_cfg.wait_packa();
                     PtrData::RefData(x_i.dst_read())
@@ -2014,6 +2370,8 @@ macro_rules! def_pire_gemm {
                 $packa_ty::PackedMatrix(x_i) => {
                     let m_ro = hw_cfg.round_m(x_i.m());
                     let kc_len_ro = hw_cfg.round_k(kc_len);
+                    // all kc blocks before kc_i are full
+                    let kc_offset = kc_i / t_cfg.kc_eff * hw_cfg.round_k(t_cfg.kc_eff) * m_ro;
                     let res = is_mixed!(
                         $include_flag,
                         {
@@ -2022,7 +2380,7 @@ macro_rules! def_pire_gemm {
                             if mc_len > mc_offset {
                                 let mc_len_x = (mc_len - mc_offset).min(mc_par);
                                 let mc_i = mc_i + mc_offset;
-                                let src_ptr = x_i.src().add(mc_i*kc_len_ro + kc_i*m_ro);
+                                let src_ptr = x_i.src().add(mc_i*kc_len_ro + kc_offset);
                                 let dst = x_i.dst_write(t_cfg.i_load_p_idx, kc_len_ro);
                                 let dst_ref = dst.get();
                                 let dst_ptr = dst_ref.as_mut_ptr();
@@ -2033,7 +2391,7 @@ macro_rules! def_pire_gemm {
                             PtrData::RefData(x_i.dst_read())
                         },
                         {
-                            let src_ptr = x_i.src().add(mc_i*kc_len_ro + kc_i*m_ro);
+                            let src_ptr = x_i.src().add(mc_i*kc_len_ro + kc_offset);
                             t_cfg.wait_packa();
                             PtrData::PtrData(src_ptr)
                         }
@@ -2062,7 +2420,22 @@ macro_rules! def_pire_gemm {
                         let dst = x_i.dst_write(t_cfg.j_load_p_idx, kc_len_ro);
                         let dst_ref = dst.get();
                         let dst_ptr = dst_ref.as_mut_ptr();
-                        $packb_name0(src_ptr, dst_ptr, nc_len_x, kc_len, rs, cs);
+                        $crate::is_src_b!(
+                            $src_b_flag,
+                            // b is a SrcB given with zero strides, so src_ptr points to it for every block
+                            (*(src_ptr as *const $crate::src_b::SrcB<$tbp>)).pack(
+                                dst_ptr, nc_i, kc_i, nc_len_x, kc_len, kc_len_ro, hw_cfg.nr, $packb_name0
+                            ),
+                            $crate::is_conj!(
+                                $conj_flag,
+                                if hw_cfg.conj_b {
+                                    $packb_name0::<true>(src_ptr, dst_ptr, nc_len_x, kc_len, rs, cs)
+                                } else {
+                                    $packb_name0::<false>(src_ptr, dst_ptr, nc_len_x, kc_len, rs, cs)
+                                },
+                                $packb_name0(src_ptr, dst_ptr, nc_len_x, kc_len, rs, cs)
+                            )
+                        );
                     }
                     t_cfg.wait_packb();
                     PtrData::RefData(x_i.dst_read())
@@ -2070,6 +2443,7 @@ macro_rules! def_pire_gemm {
                 $packb_ty::PackedMatrix(x_i) => {
                     let kc_len_ro = hw_cfg.round_k(kc_len);
                     let n_ro = x_i.m();
+                    let kc_offset = kc_i / t_cfg.kc_eff * hw_cfg.round_k(t_cfg.kc_eff) * n_ro;
                     let res = is_mixed!(
                         $include_flag,
                         {
@@ -2078,7 +2452,7 @@ macro_rules! def_pire_gemm {
                             if nc_len > nc_offset {
                                 let nc_len_x = (nc_len - nc_offset).min(nc_par);
                                 let nc_i = nc_i + nc_offset;
-                                let src_ptr = x_i.src().add(nc_i*kc_len_ro + kc_i*n_ro);
+                                let src_ptr = x_i.src().add(nc_i*kc_len_ro + kc_offset);
                                 let dst = x_i.dst_write(t_cfg.j_load_p_idx, kc_len_ro);
                                 let dst_ref = dst.get();
                                 let dst_ptr = dst_ref.as_mut_ptr();
@@ -2088,7 +2462,7 @@ macro_rules! def_pire_gemm {
                             PtrData::RefData(x_i.dst_read())
                         },
                         {
-                            let src_ptr = x_i.src().add(nc_i*kc_len_ro + kc_i*n_ro);
+                            let src_ptr = x_i.src().add(nc_i*kc_len_ro + kc_offset);
                             t_cfg.wait_packb();
                             PtrData::PtrData(src_ptr)
                         }
@@ -2099,7 +2473,32 @@ macro_rules! def_pire_gemm {
             };
             xp_ptr
         }
-    }
+    };
+    // without conj (src_b) flag, for dispatchers that do not support conjugated operands (b given as SrcB),
+    // the missing flags are filled with F one at a time
+    ($($args:tt)*) => {
+        $crate::def_pire_gemm!($($args)* F,);
+    };
+}
+
+#[macro_export]
+macro_rules! is_conj {
+    (T, $st1:expr, $st2:expr) => {
+        $st1
+    };
+    (F, $st1:expr, $st2:expr) => {
+        $st2
+    };
+}
+
+#[macro_export]
+macro_rules! is_src_b {
+    (T, $st1:expr, $st2:expr) => {
+        $st1
+    };
+    (F, $st1:expr, $st2:expr) => {
+        $st2
+    };
 }
 
 #[macro_export]
@@ -2155,7 +2554,7 @@ macro_rules! def_kernel_bb_v0 {
                     };
                     ukernel_bbc(ap_cur, bp_cur, c_cur1_f, alpha, beta, k, d_arr, c_cs_f, mr, nr);
                     for j in 0..nr {
-                        f.call(c_cur1_f.add(j*c_cs_f), mr);
+                        f.call_tile(c_cur1_f.add(j*c_cs_f), mr, m_i, n_i + j);
                     }
                     if STRIDED {
                         pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, mr, nr, mr);
@@ -2188,7 +2587,7 @@ macro_rules! def_kernel_bb_v0 {
                             [<ukernel_ mr_vs _bbp>](ap_cur, bp_cur, c_cur1_f, alpha, beta, k, d_arr, c_cs_f, m_left, nr);
                         }
                         for j in 0..nr {
-                            f.call(c_cur1_f.add(j*c_cs_f), m_left);
+                            f.call_tile(c_cur1_f.add(j*c_cs_f), m_left, m_i, n_i + j);
                         }
                         if STRIDED || $no_partial {
                             pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, m_left, nr, mr_left);
@@ -2228,7 +2627,7 @@ macro_rules! def_kernel_sb_v0 {
     (
         $t_a:ty, $t_ap:ty, $t_bp:ty, $t_c:ty, $t_s:ty,
         $no_partial:tt, $l2_prefetch:tt,
-        $pack_fn:tt,
+        $pack_fn:expr,
         $RS:tt,
         $MR:tt, $NR:tt, $pf1_0:tt, $pf_step:tt
     ) => {
@@ -2270,7 +2669,7 @@ macro_rules! def_kernel_sb_v0 {
                     };
                     ukernel_bbc(ap, bp_cur, c_cur1_f, alpha, beta, k_eff, d_arr, c_cs_f, mr, nr);
                     for j in 0..nr {
-                        f.call(c_cur1_f.add(j*c_cs_f), mr);
+                        f.call_tile(c_cur1_f.add(j*c_cs_f), mr, m_i, n_i + j);
                     }
                     if STRIDED {
                         pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, mr, nr, mr);
@@ -2303,7 +2702,7 @@ macro_rules! def_kernel_sb_v0 {
                             [<ukernel_ mr_vs _bbp>](ap, bp_cur, c_cur1_f, alpha, beta, k_eff, d_arr, c_cs_f, m_left, nr);
                         }
                         for j in 0..nr {
-                            f.call(c_cur1_f.add(j*c_cs_f), m_left);
+                            f.call_tile(c_cur1_f.add(j*c_cs_f), m_left, m_i, n_i + j);
                         }
                         if STRIDED || $no_partial {
                             pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, m_left, nr, mr_left);
@@ -2379,7 +2778,7 @@ macro_rules! def_kernel_bs {
                     };
                     ukernel_bsc(ap_cur, b_cur, c_cur1_f, alpha, beta, k, d_arr, c_cs_f, MR, nr);
                     for j in 0..nr {
-                        f.call(c_cur1_f.add(j*c_cs_f), MR);
+                        f.call_tile(c_cur1_f.add(j*c_cs_f), MR, m_i, n_i + j);
                     }
                     if STRIDED {
                         pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, MR, nr, MR);
@@ -2409,7 +2808,7 @@ macro_rules! def_kernel_bs {
                             [<ukernel_ mr_left _bsp>](ap_cur, b_cur, c_cur1_f, alpha, beta, k, d_arr, c_cs_f, m_left, nr);
                         }
                         for j in 0..nr {
-                            f.call(c_cur1_f.add(j*c_cs_f), m_left);
+                            f.call_tile(c_cur1_f.add(j*c_cs_f), m_left, m_i, n_i + j);
                         }
                         if STRIDED {
                             pire_base::store_buf(c_cur1, c_rs, c_cs, &c_buf, m_left, nr, MR_LEFT);
@@ -2490,7 +2889,7 @@ macro_rules! load_a {
     };
 }
 
-#[cfg(target_arch = "aarch64")]
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
 #[macro_export]
 macro_rules! load_a {
     ($mr:tt) => {
@@ -2518,6 +2917,9 @@ macro_rules! init_ab {
             "mov 24({dim_arrx}),{x0}\n",
         )
     };
+    (Bsum) => {
+        $crate::init_ab!(B)
+    };
     (S) => {
         concat!(
             // mov cs_b to reg
@@ -2683,7 +3085,7 @@ macro_rules! c_mem {
     };
 }
 
-#[cfg(target_arch = "aarch64")]
+#[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
 #[macro_export]
 macro_rules! c_mem {
     (0) => {
@@ -3012,6 +3414,20 @@ macro_rules! cum_seq {
     };
 }
 
+// packed b panels with the Bsum layout end with the i32 sum of each column (see s8s8s32 vnni kernels),
+// b_sum_corr_col! of the kernel applies it to the accumulators of one column once the k loop is done
+#[macro_export]
+macro_rules! b_sum_corr {
+    (Bsum, $mr:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(#(b_sum_corr_col!($mr, n, $nr),)*)
+        })
+    };
+    ($b_layout:tt, $mr:tt, $nr:tt) => {
+        ""
+    };
+}
+
 #[cfg(target_arch = "x86_64")]
 #[macro_export]
 macro_rules! prefetch_c_avx512 {
@@ -3114,6 +3530,9 @@ macro_rules! prefetch_b {
     (B) => {
         concat!("prefetcht0 192({bx}) \n",)
     };
+    (Bsum) => {
+        $crate::prefetch_b!(B)
+    };
 }
 
 #[cfg(target_arch = "x86")]
@@ -3245,6 +3664,7 @@ macro_rules! asm_body_avx {
             "dec {x0}", "jne 4b", // KLEFT
 
             "5:", // POSTACCUM
+            pire_base::b_sum_corr!($b_layout, $mr, $nr),
             c_load!(),
 
             "cmpw $0, ({alpha_st})",
@@ -3442,6 +3862,7 @@ macro_rules! asm_body_avx512 {
             "dec {x0}", "jne 4b", // KLEFT
 
             "5:", // POSTACCUM
+            pire_base::b_sum_corr!($b_layout, $mr, $nr),
             c_load!(),
 
             "cmpw $0, ({alpha_st})",
@@ -4403,25 +4824,123 @@ macro_rules! asm_body_sve {
 }
 
 #[macro_export]
-macro_rules! def_ukernel_neon {
+macro_rules! asm_body_rvv {
     (
-        $step_macro:tt,
-        $acc_macro:tt,
-        $store_macro:tt,
-        $mr:tt, $nr:tt,
-        $b_layout:tt,
-        $is_partial:tt,
-        $func_name:ident
+        $step_macro:tt, $acc_macro:tt, $store_macro:tt,
+        $mr:tt, $nr:tt, $b_layout:tt, $is_partial:tt,
+        $a:tt, $b:tt, $c:tt, $alpha:tt, $beta:tt, $alpha_st:tt, $beta_st:tt,
+        $vs:tt, $m_left:tt, $vlb:tt,
+        $dim_arr:tt,
+        | $($xreg:ident,)* |,
+        [$($vreg:tt,)*],
+        [$($freg:tt,)*]
     ) => {
-        #[target_feature(enable="neon")]
-        pub(crate) unsafe fn $func_name(
-            a: *const TA, b: *const TB, c: *mut TC,
-            alpha: *const TA, beta: *const TB,
-            k: usize,
-            d_arr: [usize; 2], c_cs: usize,
-            m: usize, n: usize,
-        ) {
-            use core::mem::size_of;
+        core::arch::asm!(
+            set_vl!(C),
+            vzero_kernel!(),
+
+            init_ab!($b_layout),
+
+            // 3 -> CONSIDKLEFT
+            "beqz {x0}, 3f",
+
+            // 2 -> KITER
+            "2:",
+            pire_base::load_a!($mr),
+            $step_macro!($b_layout, $nr),
+            inc_b!($b_layout, $nr),
+            pire_base::load_a!($mr),
+            $step_macro!($b_layout, $nr),
+            inc_b!($b_layout, $nr),
+            pire_base::load_a!($mr),
+            $step_macro!($b_layout, $nr),
+            inc_b!($b_layout, $nr),
+            pire_base::load_a!($mr),
+            $step_macro!($b_layout, $nr),
+            inc_b!($b_layout, $nr),
+
+            "addi {x0}, {x0}, -1",
+            // 2 -> KITER
+            "bnez {x0}, 2b",
+
+            // 3 -> CONSIDKLEFT
+            "3:",
+            "ld {x0}, 32({dim_arrx})",
+
+            // 5 -> POSTACCUM
+            "beqz {x0}, 5f",
+            // 4 -> KLEFT
+            "4:",
+            pire_base::load_a!($mr),
+            $step_macro!($b_layout, $nr),
+            inc_b!($b_layout, $nr),
+
+            "addi {x0}, {x0}, -1",
+
+            // 4 -> KLEFT
+            "bnez {x0}, 4b",
+
+            // 5 -> POSTACCUM
+            "5:",
+            c_load!(),
+            "beqz {alpha_st}, 13f",
+            alpha_scale!(),
+            "13:",
+
+            "beqz {beta_st}, 6f",
+
+            "addi {x0}, {beta_st}, -1",
+            "beqz {x0}, 9f",
+
+            load_beta!(),
+            pire_base::cum_seq!($acc_macro,$is_partial,$nr,2),
+            "j 6f",
+
+            "9:",
+            // 9 -> BETAONE
+            pire_base::cum_seq!($acc_macro,$is_partial,$nr,1),
+
+            // 6 -> BETAZERO
+            "6:",
+            pire_base::cum_seq!($store_macro,$is_partial,$nr),
+            ax = inout(reg) $a => _,
+            bx = inout(reg) $b => _,
+            cx = inout(reg) $c => _,
+            dim_arrx = inout(reg) $dim_arr.as_ptr() => _,
+            alphax = inout(reg) $alpha => _,
+            betax = inout(reg) $beta => _,
+            beta_st = in(reg) $beta_st,
+            alpha_st = in(reg) $alpha_st,
+            vs = in(reg) $vs,
+            m_e = in(reg) $m_left,
+            vlb = in(reg) $vlb,
+            $($xreg = out(reg) _,)*
+            $(out($vreg) _,)*
+            $(out($freg) _,)*
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! def_ukernel_neon {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="neon")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TA, beta: *const TB,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
             let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 4, k % 4];
             let alpha_st = if *alpha == ONE_SCALAR {
                 0i32
@@ -4722,7 +5241,7 @@ macro_rules! def_ukernel_neon_i8mm {
 }
 
 #[macro_export]
-macro_rules! def_ukernel_sve {
+macro_rules! def_ukernel_neon_dotprod {
     (
         $step_macro:tt,
         $acc_macro:tt,
@@ -4732,19 +5251,96 @@ macro_rules! def_ukernel_sve {
         $is_partial:tt,
         $func_name:ident
     ) => {
-        #[target_feature(enable="neon,sve")]
+        #[target_feature(enable="neon,dotprod")]
         pub(crate) unsafe fn $func_name(
             a: *const TA, b: *const TB, c: *mut TC,
-            alpha: *const TA, beta: *const TB,
+            alpha: *const TS, beta: *const TS,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 32, (k % 32) / 8];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0i32
+            } else {
+                1i32
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0i32
+            } else if *beta == ONE_SCALAR {
+                1i32
+            } else {
+                2i32
+            };
+            if n == $nr {
+                pire_base::asm_body_neon!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    dim_arr, | |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni {
+                            pire_base::asm_body_neon!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                dim_arr, | |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_neon_i16 {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="neon")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TS, beta: *const TS,
             k: usize,
             d_arr: [usize; 2], c_cs: usize,
             m: usize, n: usize,
         ) {
             use core::mem::size_of;
-            let vs = sve_vs();
-            let m_left = if m % vs == 0 {vs} else {m%vs};
-            let inc_a = vs * $mr * size_of::<TA>();
-            let mr = $mr * vs;
             let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 4, k % 4];
             let alpha_st = if *alpha == ONE_SCALAR {
                 0i32
@@ -4759,13 +5355,12 @@ macro_rules! def_ukernel_sve {
                 2i32
             };
             if n == $nr {
-                pire_base::asm_body_sve!(
+                pire_base::asm_body_neon!(
                     $step_macro, $acc_macro, $store_macro,
                     $mr, $nr, $b_layout, $is_partial,
                     a, b, c, alpha, beta, alpha_st, beta_st,
-                    m_left, inc_a,
                     dim_arr, | |,
-                    | x0, x1, x2, x3, x4, x5, x6, x7, |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
                     [
                         "v0", "v1", "v2", "v3",
                         "v4", "v5", "v6", "v7",
@@ -4775,20 +5370,18 @@ macro_rules! def_ukernel_sve {
                         "v20", "v21", "v22", "v23",
                         "v24", "v25", "v26", "v27",
                         "v28", "v29", "v30", "v31",
-                        "p0", "p1", "p2", "p3",
                     ]
                 );
             } else {
                 let _ = 'blk: {
                     seq!(ni in 1..$nr {
-                        if n == ni{
-                            pire_base::asm_body_sve!(
+                        if n == ni {
+                            pire_base::asm_body_neon!(
                                 $step_macro, $acc_macro, $store_macro,
                                 $mr, ni, $b_layout, $is_partial,
                                 a, b, c, alpha, beta, alpha_st, beta_st,
-                                m_left, inc_a,
                                 dim_arr, | |,
-                                | x0, x1, x2, x3, x4, x5, x6, x7, |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
                                 [
                                     "v0", "v1", "v2", "v3",
                                     "v4", "v5", "v6", "v7",
@@ -4798,7 +5391,6 @@ macro_rules! def_ukernel_sve {
                                     "v20", "v21", "v22", "v23",
                                     "v24", "v25", "v26", "v27",
                                     "v28", "v29", "v30", "v31",
-                                    "p0", "p1", "p2", "p3",
                                 ]
                             );
                             break 'blk;
@@ -4811,7 +5403,7 @@ macro_rules! def_ukernel_sve {
 }
 
 #[macro_export]
-macro_rules! def_ukernel_sve_i8mm {
+macro_rules! def_ukernel_neon_bf16 {
     (
         $step_macro:tt,
         $acc_macro:tt,
@@ -4819,10 +5411,9 @@ macro_rules! def_ukernel_sve_i8mm {
         $mr:tt, $nr:tt,
         $b_layout:tt,
         $is_partial:tt,
-        // $feature_enable:tt,
         $func_name:ident
     ) => {
-        #[target_feature(enable="neon,sve,i8mm")]
+        #[target_feature(enable="neon,bf16")]
         pub(crate) unsafe fn $func_name(
             a: *const TA, b: *const TB, c: *mut TC,
             alpha: *const TS, beta: *const TS,
@@ -4831,11 +5422,7 @@ macro_rules! def_ukernel_sve_i8mm {
             m: usize, n: usize,
         ) {
             use core::mem::size_of;
-            let vs = sve_vs();
-            let m_left = if m % vs == 0 {vs} else {m%vs};
-            let inc_a = $mr * vs * size_of::<TA>() * 8;
-            let mr = $mr * vs;
-            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 32, (k % 32) / 8];
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 16, (k % 16) / 4];
             let alpha_st = if *alpha == ONE_SCALAR {
                 0i32
             } else {
@@ -4849,11 +5436,10 @@ macro_rules! def_ukernel_sve_i8mm {
                 2i32
             };
             if n == $nr {
-                pire_base::asm_body_sve!(
+                pire_base::asm_body_neon!(
                     $step_macro, $acc_macro, $store_macro,
                     $mr, $nr, $b_layout, $is_partial,
                     a, b, c, alpha, beta, alpha_st, beta_st,
-                    m_left, inc_a,
                     dim_arr, | |,
                     | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
                     [
@@ -4865,18 +5451,16 @@ macro_rules! def_ukernel_sve_i8mm {
                         "v20", "v21", "v22", "v23",
                         "v24", "v25", "v26", "v27",
                         "v28", "v29", "v30", "v31",
-                        "p0", "p1", "p2", "p3",
                     ]
                 );
             } else {
                 let _ = 'blk: {
                     seq!(ni in 1..$nr {
                         if n == ni {
-                            pire_base::asm_body_sve!(
+                            pire_base::asm_body_neon!(
                                 $step_macro, $acc_macro, $store_macro,
                                 $mr, ni, $b_layout, $is_partial,
                                 a, b, c, alpha, beta, alpha_st, beta_st,
-                                m_left, inc_a,
                                 dim_arr, | |,
                                 | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
                                 [
@@ -4888,7 +5472,462 @@ macro_rules! def_ukernel_sve_i8mm {
                                     "v20", "v21", "v22", "v23",
                                     "v24", "v25", "v26", "v27",
                                     "v28", "v29", "v30", "v31",
-                                    "p0", "p1", "p2", "p3",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_sve {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="neon,sve")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TA, beta: *const TB,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            let vs = sve_vs();
+            let m_left = if m % vs == 0 {vs} else {m%vs};
+            let inc_a = vs * $mr * size_of::<TA>();
+            let mr = $mr * vs;
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 4, k % 4];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0i32
+            } else {
+                1i32
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0i32
+            } else if *beta == ONE_SCALAR {
+                1i32
+            } else {
+                2i32
+            };
+            if n == $nr {
+                pire_base::asm_body_sve!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    m_left, inc_a,
+                    dim_arr, | |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                        "p0", "p1", "p2", "p3",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni{
+                            pire_base::asm_body_sve!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                m_left, inc_a,
+                                dim_arr, | |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                    "p0", "p1", "p2", "p3",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_sve_i8mm {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        // $feature_enable:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="neon,sve,i8mm")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TS, beta: *const TS,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            let vs = sve_vs();
+            let m_left = if m % vs == 0 {vs} else {m%vs};
+            let inc_a = $mr * vs * size_of::<TA>() * 8;
+            let mr = $mr * vs;
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 32, (k % 32) / 8];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0i32
+            } else {
+                1i32
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0i32
+            } else if *beta == ONE_SCALAR {
+                1i32
+            } else {
+                2i32
+            };
+            if n == $nr {
+                pire_base::asm_body_sve!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    m_left, inc_a,
+                    dim_arr, | |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                        "p0", "p1", "p2", "p3",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni {
+                            pire_base::asm_body_sve!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                m_left, inc_a,
+                                dim_arr, | |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                    "p0", "p1", "p2", "p3",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_sve_fmmla {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        $feature_enable:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable=$feature_enable)]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TA, beta: *const TB,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            let vs = sve_vs();
+            let m_left = if m % vs == 0 {vs} else {m%vs};
+            let inc_a = $mr * vs * size_of::<TA>() * 2;
+            let mr = $mr * vs;
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 8, (k % 8) / 2];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0i32
+            } else {
+                1i32
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0i32
+            } else if *beta == ONE_SCALAR {
+                1i32
+            } else {
+                2i32
+            };
+            if n == $nr {
+                pire_base::asm_body_sve!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    m_left, inc_a,
+                    dim_arr, | |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                        "p0", "p1", "p2", "p3",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni {
+                            pire_base::asm_body_sve!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                m_left, inc_a,
+                                dim_arr, | |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                    "p0", "p1", "p2", "p3",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_sve_i16 {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        // $feature_enable:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="neon,sve")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TS, beta: *const TS,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            let vs = sve_vs();
+            let m_left = if m % vs == 0 {vs} else {m%vs};
+            let inc_a = $mr * vs * size_of::<TA>() * 4;
+            let mr = $mr * vs;
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 16, (k % 16) / 4];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0i32
+            } else {
+                1i32
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0i32
+            } else if *beta == ONE_SCALAR {
+                1i32
+            } else {
+                2i32
+            };
+            if n == $nr {
+                pire_base::asm_body_sve!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    m_left, inc_a,
+                    dim_arr, | |,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                        "p0", "p1", "p2", "p3",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni {
+                            pire_base::asm_body_sve!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                m_left, inc_a,
+                                dim_arr, | |,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, x9, x10, x11, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                    "p0", "p1", "p2", "p3",
+                                ]
+                            );
+                            break 'blk;
+                        }
+                    });
+                };
+            };
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! def_ukernel_rvv {
+    (
+        $step_macro:tt,
+        $acc_macro:tt,
+        $store_macro:tt,
+        $mr:tt, $nr:tt,
+        $b_layout:tt,
+        $is_partial:tt,
+        $func_name:ident
+    ) => {
+        #[target_feature(enable="v")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA, b: *const TB, c: *mut TC,
+            alpha: *const TA, beta: *const TB,
+            k: usize,
+            d_arr: [usize; 2], c_cs: usize,
+            m: usize, n: usize,
+        ) {
+            use core::mem::size_of;
+            // vl is always set explicitly (never vlmax) so that vs may be capped below the hardware vlen
+            let vs = rvv_vs();
+            let m_left = if m % vs == 0 {vs} else {m%vs};
+            let vlb = vs * size_of::<TA>();
+            let dim_arr = [d_arr[0]*size_of::<TB>(), d_arr[1]*size_of::<TB>(), c_cs*TC_SIZE, k / 4, k % 4];
+            let alpha_st = if *alpha == ONE_SCALAR {
+                0usize
+            } else {
+                1usize
+            };
+            let beta_st = if *beta == ZERO_SCALAR {
+                0usize
+            } else if *beta == ONE_SCALAR {
+                1usize
+            } else {
+                2usize
+            };
+            if n == $nr {
+                pire_base::asm_body_rvv!(
+                    $step_macro, $acc_macro, $store_macro,
+                    $mr, $nr, $b_layout, $is_partial,
+                    a, b, c, alpha, beta, alpha_st, beta_st,
+                    vs, m_left, vlb,
+                    dim_arr,
+                    | x0, x1, x2, x3, x4, x5, x6, x7, x8, |,
+                    [
+                        "v0", "v1", "v2", "v3",
+                        "v4", "v5", "v6", "v7",
+                        "v8", "v9", "v10", "v11",
+                        "v12", "v13", "v14", "v15",
+                        "v16", "v17", "v18", "v19",
+                        "v20", "v21", "v22", "v23",
+                        "v24", "v25", "v26", "v27",
+                        "v28", "v29", "v30", "v31",
+                    ],
+                    [
+                        "ft0", "ft1", "ft2", "ft3",
+                        "ft4", "ft5", "ft6", "ft7",
+                        "fa0", "fa1",
+                    ]
+                );
+            } else {
+                let _ = 'blk: {
+                    seq!(ni in 1..$nr {
+                        if n == ni {
+                            pire_base::asm_body_rvv!(
+                                $step_macro, $acc_macro, $store_macro,
+                                $mr, ni, $b_layout, $is_partial,
+                                a, b, c, alpha, beta, alpha_st, beta_st,
+                                vs, m_left, vlb,
+                                dim_arr,
+                                | x0, x1, x2, x3, x4, x5, x6, x7, x8, |,
+                                [
+                                    "v0", "v1", "v2", "v3",
+                                    "v4", "v5", "v6", "v7",
+                                    "v8", "v9", "v10", "v11",
+                                    "v12", "v13", "v14", "v15",
+                                    "v16", "v17", "v18", "v19",
+                                    "v20", "v21", "v22", "v23",
+                                    "v24", "v25", "v26", "v27",
+                                    "v28", "v29", "v30", "v31",
+                                ],
+                                [
+                                    "ft0", "ft1", "ft2", "ft3",
+                                    "ft4", "ft5", "ft6", "ft7",
+                                    "fa0", "fa1",
                                 ]
                             );
                             break 'blk;
@@ -0,0 +1,76 @@
+/// `b` of a gemm that is not a strided matrix of the gemm type, e.g. block quantized weights.
+/// Each `kc x nc` block is loaded as the gemm type right before it is packed, into the packing buffer of the gemm,
+/// so `b` is never converted as a whole
+#[derive(Copy, Clone)]
+pub struct SrcB<T> {
+    src: *const u8,
+    ld: usize,
+    load: unsafe fn(src: *const u8, ld: usize, kc_i: usize, kc_len: usize, j: usize, dst: *mut T),
+}
+
+// elements of the stack buffer that SrcB::pack loads b into
+const SRC_B_BUF_LEN: usize = 4096;
+
+// src is only read, every thread packs its own part of b
+unsafe impl<T> Sync for SrcB<T> {}
+
+impl<T: Copy + Default> SrcB<T> {
+    /// `load(src, ld, kc_i, kc_len, j, dst)` must write `b[kc_i..kc_i + kc_len, j]` into `kc_len` contiguous
+    /// elements of `dst`, `src` and `ld` are passed through as given here
+    pub fn new(
+        src: *const u8,
+        ld: usize,
+        load: unsafe fn(src: *const u8, ld: usize, kc_i: usize, kc_len: usize, j: usize, dst: *mut T),
+    ) -> Self {
+        Self { src, ld, load }
+    }
+
+    /// Writes `b[kc_i..kc_i + kc_len, j]` into `dst`
+    ///
+    /// # Safety
+    ///
+    /// `dst` must be valid for `kc_len` elements and the rows and column inside b
+    pub unsafe fn load_col(&self, kc_i: usize, kc_len: usize, j: usize, dst: *mut T) {
+        (self.load)(self.src, self.ld, kc_i, kc_len, j, dst);
+    }
+
+    /// Packs `b[kc_i..kc_i + kc_len, nc_i..nc_i + nc_len]` into `bp` as `pack_fn` does for a strided block,
+    /// panels of `nr` columns start at `j * kc_len_eff`. Each panel is loaded a few rows at a time into a stack
+    /// buffer and packed from there, so b goes into the packing buffer of the gemm without a copy on the heap
+    ///
+    /// # Safety
+    ///
+    /// `bp` must be valid for the packed block and the block inside b, `pack_fn` must store a panel of `w` columns
+    /// with its rows `p..` at `p * w` whenever `p` is a multiple of 8, as the packing with or without `round_k` does
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn pack(
+        &self,
+        bp: *mut T,
+        nc_i: usize,
+        kc_i: usize,
+        nc_len: usize,
+        kc_len: usize,
+        kc_len_eff: usize,
+        nr: usize,
+        pack_fn: unsafe fn(*const T, *mut T, usize, usize, usize, usize),
+    ) {
+        let mut buf = [T::default(); SRC_B_BUF_LEN];
+        // rows of a chunk, a multiple of 8 so that every chunk starts at a whole k group of the packed panel
+        let kb = SRC_B_BUF_LEN / nr / 8 * 8;
+        let mut j = 0;
+        while j < nc_len {
+            let nr_cur = nr.min(nc_len - j);
+            let bp_j = bp.add(j * kc_len_eff);
+            let mut p = 0;
+            while p < kc_len {
+                let kb_cur = kb.min(kc_len - p);
+                for jj in 0..nr_cur {
+                    self.load_col(kc_i + p, kb_cur, nc_i + j + jj, buf.as_mut_ptr().add(jj * kb_cur));
+                }
+                pack_fn(buf.as_ptr(), bp_j.add(p * nr_cur), nr_cur, kb_cur, 1, kb_cur);
+                p += kb_cur;
+            }
+            j += nr;
+        }
+    }
+}
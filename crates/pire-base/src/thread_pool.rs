@@ -0,0 +1,387 @@
+use crate::{get_apbp_barrier, PirePar};
+use once_cell::sync::Lazy;
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Condvar, Mutex, RwLock};
+
+/// Runs the per thread work of a multithreaded gemm call
+///
+/// `execute` must call `f(t_id)` for every `t_id` in `0..num_tasks` and return only after all of them finished.
+/// The tasks wait on each other through barriers, so all of them must be able to run at the same time,
+/// e.g. with rayon use `rayon::scope` on a pool that has at least `num_tasks` threads:
+///
+/// ```ignore
+/// struct RayonExecutor;
+/// impl pire_base::thread_pool::Executor for RayonExecutor {
+///     fn execute(&self, num_tasks: usize, f: &(dyn Fn(usize) + Sync)) {
+///         rayon::scope(|s| {
+///             for t_id in 1..num_tasks {
+///                 s.spawn(move |_| f(t_id));
+///             }
+///             f(0);
+///         });
+///     }
+/// }
+/// pire_base::thread_pool::set_executor(Some(std::sync::Arc::new(RayonExecutor)));
+/// ```
+pub trait Executor: Send + Sync {
+    fn execute(&self, num_tasks: usize, f: &(dyn Fn(usize) + Sync));
+}
+
+static EXECUTOR: RwLock<Option<Arc<dyn Executor>>> = RwLock::new(None);
+
+/// Replaces the builtin thread pool with `executor`, `None` switches back to the builtin pool
+pub fn set_executor(executor: Option<Arc<dyn Executor>>) {
+    *EXECUTOR.write().unwrap() = executor;
+}
+
+// pinning is opt-in with PIRE_PIN_THREADS=1, pinned workers would fight the placement of other runtimes in the process
+static PIRE_PIN_THREADS: Lazy<bool> = Lazy::new(|| std::env::var("PIRE_PIN_THREADS").map_or(false, |x| x == "1"));
+
+// lifetime of the closure is erased, ThreadPool::execute waits for all tasks before the closure goes out of scope
+struct Task {
+    f: *const (dyn Fn(usize) + Sync + 'static),
+    t_id: usize,
+    latch: Arc<Latch>,
+}
+
+unsafe impl Send for Task {}
+
+struct Worker {
+    task: Mutex<Option<Task>>,
+    cv: Condvar,
+}
+
+// number of workers of one call that have not finished their task yet
+struct Latch {
+    count: Mutex<usize>,
+    cv: Condvar,
+    panicked: AtomicBool,
+}
+
+// packing barriers built for par
+struct CachedBarriers {
+    par: PirePar,
+    pa_br: Vec<Barrier>,
+    pb_br: Vec<Barrier>,
+}
+
+struct ThreadPool {
+    // workers not running a task, each call takes the workers it needs and puts them back when done,
+    // so concurrent and nested calls (e.g. a multithreaded gemm per batch item) all run on pool workers
+    idle: Mutex<Vec<Arc<Worker>>>,
+    num_workers: AtomicUsize,
+    // barriers of the last par, reused as long as the thread layout does not change
+    barrier_cache: Mutex<Option<CachedBarriers>>,
+}
+
+static THREAD_POOL: Lazy<ThreadPool> = Lazy::new(|| ThreadPool {
+    idle: Mutex::new(vec![]),
+    num_workers: AtomicUsize::new(0),
+    barrier_cache: Mutex::new(None),
+});
+
+static ALLOWED_CPUS: Lazy<Vec<usize>> = Lazy::new(|| if *PIRE_PIN_THREADS { allowed_cpus() } else { vec![] });
+
+#[cfg(target_os = "linux")]
+fn allowed_cpus() -> Vec<usize> {
+    unsafe {
+        let mut set: libc::cpu_set_t = core::mem::zeroed();
+        if libc::sched_getaffinity(0, core::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return vec![];
+        }
+        (0..libc::CPU_SETSIZE as usize).filter(|&i| libc::CPU_ISSET(i, &set)).collect()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pin_current_thread(cpu: usize) {
+    unsafe {
+        let mut set: libc::cpu_set_t = core::mem::zeroed();
+        libc::CPU_SET(cpu, &mut set);
+        // failing to pin is not an error, the thread just stays movable
+        libc::sched_setaffinity(0, core::mem::size_of::<libc::cpu_set_t>(), &set);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn allowed_cpus() -> Vec<usize> {
+    vec![]
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pin_current_thread(_cpu: usize) {}
+
+fn worker_loop(worker: Arc<Worker>) {
+    loop {
+        let task = {
+            let mut guard = worker.task.lock().unwrap();
+            loop {
+                if let Some(task) = guard.take() {
+                    break task;
+                }
+                guard = worker.cv.wait(guard).unwrap();
+            }
+        };
+        let f = unsafe { &*task.f };
+        if catch_unwind(AssertUnwindSafe(|| f(task.t_id))).is_err() {
+            task.latch.panicked.store(true, Ordering::Relaxed);
+        }
+        let mut count = task.latch.count.lock().unwrap();
+        *count -= 1;
+        if *count == 0 {
+            task.latch.cv.notify_one();
+        }
+    }
+}
+
+impl ThreadPool {
+    fn spawn_worker(&self) -> Arc<Worker> {
+        let worker = Arc::new(Worker { task: Mutex::new(None), cv: Condvar::new() });
+        let w = worker.clone();
+        // t_id 0 runs on the calling thread, so workers start at the second allowed cpu
+        let w_id = self.num_workers.fetch_add(1, Ordering::Relaxed) + 1;
+        let cpus = &*ALLOWED_CPUS;
+        let cpu = if cpus.is_empty() { None } else { Some(cpus[w_id % cpus.len()]) };
+        std::thread::Builder::new()
+            .name(format!("pire-worker-{}", w_id))
+            .spawn(move || {
+                if let Some(cpu) = cpu {
+                    pin_current_thread(cpu);
+                }
+                worker_loop(w)
+            })
+            .unwrap();
+        worker
+    }
+
+    fn take_workers(&self, num_workers: usize) -> Vec<Arc<Worker>> {
+        let mut workers = {
+            let mut idle = self.idle.lock().unwrap();
+            let len = idle.len();
+            idle.split_off(len - num_workers.min(len))
+        };
+        while workers.len() < num_workers {
+            workers.push(self.spawn_worker());
+        }
+        workers
+    }
+
+    fn execute(&self, num_tasks: usize, f: &(dyn Fn(usize) + Sync)) {
+        let workers = self.take_workers(num_tasks - 1);
+        let latch =
+            Arc::new(Latch { count: Mutex::new(num_tasks - 1), cv: Condvar::new(), panicked: AtomicBool::new(false) });
+        let f: *const (dyn Fn(usize) + Sync + 'static) = unsafe { core::mem::transmute(f) };
+        for (w_id, worker) in workers.iter().enumerate() {
+            *worker.task.lock().unwrap() = Some(Task { f, t_id: w_id + 1, latch: latch.clone() });
+            worker.cv.notify_one();
+        }
+        let res = catch_unwind(AssertUnwindSafe(|| unsafe { (*f)(0) }));
+        // workers borrow f, wait for all of them even if t_id 0 panicked
+        {
+            let mut count = latch.count.lock().unwrap();
+            while *count > 0 {
+                count = latch.cv.wait(count).unwrap();
+            }
+        }
+        // workers catch panics of their task, so they are ready for the next call in any case
+        self.idle.lock().unwrap().extend(workers);
+        if let Err(e) = res {
+            resume_unwind(e);
+        }
+        if latch.panicked.load(Ordering::Relaxed) {
+            panic!("pire worker thread panicked");
+        }
+    }
+}
+
+/// Runs `f(t_id)` for every `t_id` in `0..num_tasks` concurrently, t_id 0 runs on the calling thread
+///
+/// Uses the executor set by [`set_executor`] if any, otherwise the persistent worker pool of pire.
+/// Calls from several threads at once or from inside a task take distinct workers, the pool grows as needed.
+pub fn run_tasks<G: Fn(usize) + Sync>(num_tasks: usize, f: G) {
+    if num_tasks <= 1 {
+        f(0);
+        return;
+    }
+    let executor = EXECUTOR.read().unwrap().clone();
+    if let Some(executor) = executor {
+        executor.execute(num_tasks, &f);
+        return;
+    }
+    THREAD_POOL.execute(num_tasks, &f);
+}
+
+/// Same as [`run_tasks`] with one task per thread of `par`, each task also gets the packing barriers of `par`
+///
+/// Barriers are reused across calls on the worker pool as long as `par` does not change.
+pub fn run_par<G: Fn(usize, &[Barrier], &[Barrier]) + Sync>(par: &PirePar, f: G) {
+    if par.num_threads <= 1 || EXECUTOR.read().unwrap().is_some() {
+        let (pa_br, pb_br) = get_apbp_barrier(par);
+        run_tasks(par.num_threads, |t_id| f(t_id, &pa_br, &pb_br));
+        return;
+    }
+    // concurrent or nested calls with the same par find the cache empty and build their own barriers
+    let cached = {
+        let mut cache = THREAD_POOL.barrier_cache.lock().unwrap();
+        match cache.take() {
+            Some(x) if x.par == *par => Some((x.pa_br, x.pb_br)),
+            other => {
+                *cache = other;
+                None
+            }
+        }
+    };
+    let (pa_br, pb_br) = cached.unwrap_or_else(|| get_apbp_barrier(par));
+    THREAD_POOL.execute(par.num_threads, &|t_id| f(t_id, &pa_br, &pb_br));
+    // not reached if a task panicked, its barriers may be left in an inconsistent state
+    *THREAD_POOL.barrier_cache.lock().unwrap() = Some(CachedBarriers { par: *par, pa_br, pb_br });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::MutexGuard;
+
+    // executor and pool are process wide, tests that touch them must not overlap
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn serial() -> MutexGuard<'static, ()> {
+        SERIAL.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // every t_id runs exactly once and all tasks are alive at the same time
+    fn check_run_tasks(num_tasks: usize) {
+        let hits: Vec<AtomicUsize> = (0..num_tasks).map(|_| AtomicUsize::new(0)).collect();
+        let barrier = Barrier::new(num_tasks);
+        run_tasks(num_tasks, |t_id| {
+            hits[t_id].fetch_add(1, Ordering::Relaxed);
+            barrier.wait();
+        });
+        assert!(hits.iter().all(|h| h.load(Ordering::Relaxed) == 1));
+    }
+
+    #[test]
+    fn test_run_tasks() {
+        let _g = serial();
+        for num_tasks in [1, 2, 5, 3, 8] {
+            check_run_tasks(num_tasks);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_callers() {
+        let _g = serial();
+        // every caller takes its own workers from the pool
+        std::thread::scope(|s| {
+            for i in 0..8 {
+                s.spawn(move || {
+                    for _ in 0..20 {
+                        check_run_tasks(2 + i % 4);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        let _g = serial();
+        // inner calls run on pool workers next to the outer ones instead of spawning threads
+        let outer_barrier = Barrier::new(3);
+        run_tasks(3, |_| {
+            outer_barrier.wait();
+            let hits = AtomicUsize::new(0);
+            run_tasks(4, |t_id| {
+                let name = std::thread::current().name().map(|x| x.to_string());
+                assert!(t_id == 0 || name.unwrap().starts_with("pire-worker-"));
+                hits.fetch_add(1, Ordering::Relaxed);
+            });
+            assert_eq!(hits.load(Ordering::Relaxed), 4);
+        });
+        let num_workers = THREAD_POOL.num_workers.load(Ordering::Relaxed);
+        // 2 outer workers, 3 inner workers for each of the 3 outer tasks
+        assert!(num_workers >= 11);
+        // idle workers are reused
+        run_tasks(3, |_| run_tasks(4, |_| {}));
+        assert_eq!(THREAD_POOL.num_workers.load(Ordering::Relaxed), num_workers);
+    }
+
+    #[test]
+    fn test_run_par_barrier_cache() {
+        let _g = serial();
+        let pars = [PirePar::new(4, 2, 1, 2, 1, 1), PirePar::new(4, 2, 1, 2, 1, 1), PirePar::new(6, 3, 1, 2, 1, 1)];
+        for par in pars.iter() {
+            let sum = AtomicUsize::new(0);
+            run_par(par, |t_id, pa_br, pb_br| {
+                assert_eq!((pa_br.len(), pb_br.len()), (par.ic_par, par.jc_par));
+                sum.fetch_add(t_id, Ordering::Relaxed);
+                pa_br[t_id % par.ic_par].wait();
+                pb_br[t_id / par.ic_par % par.jc_par].wait();
+            });
+            let n = par.num_threads;
+            assert_eq!(sum.load(Ordering::Relaxed), n * (n - 1) / 2);
+            let cache = THREAD_POOL.barrier_cache.lock().unwrap();
+            assert!(matches!(&*cache, Some(x) if x.par == *par));
+        }
+    }
+
+    struct CountingExecutor {
+        calls: AtomicUsize,
+    }
+
+    impl Executor for CountingExecutor {
+        fn execute(&self, num_tasks: usize, f: &(dyn Fn(usize) + Sync)) {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            std::thread::scope(|s| {
+                for t_id in 1..num_tasks {
+                    s.spawn(move || f(t_id));
+                }
+                f(0);
+            });
+        }
+    }
+
+    #[test]
+    fn test_custom_executor() {
+        let _g = serial();
+        let executor = Arc::new(CountingExecutor { calls: AtomicUsize::new(0) });
+        set_executor(Some(executor.clone()));
+        check_run_tasks(3);
+        let par = PirePar::new(4, 2, 1, 2, 1, 1);
+        run_par(&par, |t_id, pa_br, _| {
+            pa_br[t_id % 2].wait();
+        });
+        // single task calls do not go through the executor
+        check_run_tasks(1);
+        set_executor(None);
+        check_run_tasks(3);
+        assert_eq!(executor.calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_worker_panic() {
+        let _g = serial();
+        let res = catch_unwind(|| run_tasks(4, |t_id| assert_ne!(t_id, 2)));
+        assert!(res.is_err());
+        check_run_tasks(4);
+
+        // a panic in t_id 0 still waits for the workers
+        let res = catch_unwind(|| run_tasks(4, |t_id| assert_ne!(t_id, 0)));
+        assert!(res.is_err());
+        check_run_tasks(4);
+
+        // barriers of a panicked run_par are not cached
+        let par = PirePar::new(2, 1, 1, 2, 1, 1);
+        run_par(&par, |_, _, _| {});
+        let res = catch_unwind(|| run_par(&par, |t_id, _, _| assert_ne!(t_id, 1)));
+        assert!(res.is_err());
+        assert!(THREAD_POOL.barrier_cache.lock().unwrap().is_none());
+        let sum = AtomicUsize::new(0);
+        run_par(&par, |t_id, _, pb_br| {
+            sum.fetch_add(t_id + 1, Ordering::Relaxed);
+            pb_br[t_id].wait();
+        });
+        assert_eq!(sum.load(Ordering::Relaxed), 3);
+    }
+}
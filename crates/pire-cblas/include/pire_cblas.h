@@ -0,0 +1,42 @@
+/* C declarations of the symbols exported by libpire_cblas, enum values are the same as cblas.h and mkl */
+#ifndef PIRE_CBLAS_H
+#define PIRE_CBLAS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#ifndef CBLAS_H
+typedef enum CBLAS_LAYOUT { CblasRowMajor = 101, CblasColMajor = 102 } CBLAS_LAYOUT;
+typedef enum CBLAS_TRANSPOSE { CblasNoTrans = 111, CblasTrans = 112, CblasConjTrans = 113 } CBLAS_TRANSPOSE;
+#endif
+typedef enum CBLAS_OFFSET { CblasRowOffset = 171, CblasColOffset = 172, CblasFixOffset = 173 } CBLAS_OFFSET;
+
+void cblas_sgemm(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, int m, int n, int k,
+                 float alpha, const float *a, int lda, const float *b, int ldb, float beta, float *c, int ldc);
+void cblas_dgemm(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, int m, int n, int k,
+                 double alpha, const double *a, int lda, const double *b, int ldb, double beta, double *c, int ldc);
+void cblas_cgemm(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, int m, int n, int k,
+                 const void *alpha, const void *a, int lda, const void *b, int ldb, const void *beta, void *c, int ldc);
+void cblas_zgemm(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, int m, int n, int k,
+                 const void *alpha, const void *a, int lda, const void *b, int ldb, const void *beta, void *c, int ldc);
+/* f16 values are given by their bits */
+void cblas_hgemm(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, int m, int n, int k,
+                 uint16_t alpha, const uint16_t *a, int lda, const uint16_t *b, int ldb, uint16_t beta, uint16_t *c,
+                 int ldc);
+/* a is int8 and b uint8 for CblasColMajor, the other way around for CblasRowMajor */
+void cblas_gemm_s8u8s32(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, CBLAS_OFFSET offsetc,
+                        int m, int n, int k, float alpha, const void *a, int lda, int8_t ao, const void *b, int ldb,
+                        int8_t bo, float beta, int32_t *c, int ldc, const int32_t *co);
+/* ao and bo must be 0 */
+void cblas_gemm_s16s16s32(CBLAS_LAYOUT layout, CBLAS_TRANSPOSE transa, CBLAS_TRANSPOSE transb, CBLAS_OFFSET offsetc,
+                          int m, int n, int k, float alpha, const int16_t *a, int lda, int16_t ao, const int16_t *b,
+                          int ldb, int16_t bo, float beta, int32_t *c, int ldc, const int32_t *co);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
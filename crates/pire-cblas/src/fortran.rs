@@ -0,0 +1,203 @@
+//! Fortran BLAS symbols (sgemm_, dgemm_, ...) so that pire can replace the gemm of a blas library,
+//! e.g. with LD_PRELOAD. Matrices are column major, every argument is passed by pointer and
+//! the hidden lengths of the character arguments are ignored
+
+use core::ffi::{c_char, c_int, c_void};
+
+use crate::{
+    cblas_cgemm, cblas_dgemm, cblas_sgemm, cblas_zgemm, CblasColMajor, CblasConjTrans, CblasNoTrans, CblasTrans,
+    CBLAS_TRANSPOSE,
+};
+
+// same message as reference xerbla, without stopping the program
+fn xerbla(srname: &str, info: c_int) {
+    eprintln!(" ** On entry to {:<6} parameter number {:>2} had an illegal value", srname, info);
+}
+
+fn to_cblas_trans(trans: c_char) -> Option<CBLAS_TRANSPOSE> {
+    match trans as u8 {
+        b'N' | b'n' => Some(CblasNoTrans),
+        b'T' | b't' => Some(CblasTrans),
+        b'C' | b'c' => Some(CblasConjTrans),
+        _ => None,
+    }
+}
+
+// validates arguments with the info numbers of reference blas, returns cblas transpose values
+#[allow(clippy::too_many_arguments)]
+fn fortran_args(
+    srname: &str,
+    transa: c_char,
+    transb: c_char,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    lda: c_int,
+    ldb: c_int,
+    ldc: c_int,
+) -> Option<(CBLAS_TRANSPOSE, CBLAS_TRANSPOSE)> {
+    let (Some(ta), Some(tb)) = (to_cblas_trans(transa), to_cblas_trans(transb)) else {
+        xerbla(srname, if to_cblas_trans(transa).is_none() { 1 } else { 2 });
+        return None;
+    };
+    let nrowa = if ta == CblasNoTrans { m } else { k };
+    let nrowb = if tb == CblasNoTrans { k } else { n };
+    let info = if m < 0 {
+        3
+    } else if n < 0 {
+        4
+    } else if k < 0 {
+        5
+    } else if lda < nrowa.max(1) {
+        8
+    } else if ldb < nrowb.max(1) {
+        10
+    } else if ldc < m.max(1) {
+        13
+    } else {
+        0
+    };
+    if info != 0 {
+        xerbla(srname, info);
+        return None;
+    }
+    Some((ta, tb))
+}
+
+macro_rules! def_fortran_gemm {
+    ($name:ident, $srname:literal, $t:ty, $cblas_fn:ident, by_value) => {
+        def_fortran_gemm!(@def $name, $srname, $t, $cblas_fn, |x: *const $t| *x);
+    };
+    ($name:ident, $srname:literal, $t:ty, $cblas_fn:ident, by_ptr) => {
+        def_fortran_gemm!(@def $name, $srname, $t, $cblas_fn, |x: *const $t| x as *const c_void);
+    };
+    (@def $name:ident, $srname:literal, $t:ty, $cblas_fn:ident, $scalar:expr) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $name(
+            transa: *const c_char,
+            transb: *const c_char,
+            m: *const c_int,
+            n: *const c_int,
+            k: *const c_int,
+            alpha: *const $t,
+            a: *const $t,
+            lda: *const c_int,
+            b: *const $t,
+            ldb: *const c_int,
+            beta: *const $t,
+            c: *mut $t,
+            ldc: *const c_int,
+        ) {
+            let (m, n, k, lda, ldb, ldc) = (*m, *n, *k, *lda, *ldb, *ldc);
+            let Some((ta, tb)) = fortran_args($srname, *transa, *transb, m, n, k, lda, ldb, ldc) else {
+                return;
+            };
+            let scalar = $scalar;
+            $cblas_fn(
+                CblasColMajor,
+                ta,
+                tb,
+                m,
+                n,
+                k,
+                scalar(alpha),
+                a as _,
+                lda,
+                b as _,
+                ldb,
+                scalar(beta),
+                c as _,
+                ldc,
+            );
+        }
+    };
+}
+
+def_fortran_gemm!(sgemm_, "SGEMM", f32, cblas_sgemm, by_value);
+def_fortran_gemm!(dgemm_, "DGEMM", f64, cblas_dgemm, by_value);
+// complex values are passed as pairs of real numbers
+def_fortran_gemm!(cgemm_, "CGEMM", [f32; 2], cblas_cgemm, by_ptr);
+def_fortran_gemm!(zgemm_, "ZGEMM", [f64; 2], cblas_zgemm, by_ptr);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sgemm_trans() {
+        // c = a^t * b with a 3 x 2, b 3 x 2, column major
+        let a = [1f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let b = [1f32, 0.0, 1.0, 0.0, 1.0, 1.0];
+        let mut c = [1f32; 4];
+        let (m, n, k, lda, ldb, ldc) = (2, 2, 3, 3, 3, 2);
+        let (alpha, beta) = (1f32, 1f32);
+        unsafe {
+            sgemm_(
+                &(b't' as c_char),
+                &(b'N' as c_char),
+                &m,
+                &n,
+                &k,
+                &alpha,
+                a.as_ptr(),
+                &lda,
+                b.as_ptr(),
+                &ldb,
+                &beta,
+                c.as_mut_ptr(),
+                &ldc,
+            );
+        }
+        assert_eq!(c, [5.0, 11.0, 6.0, 12.0]);
+
+        // invalid ldc leaves c as is
+        let ldc = 1;
+        unsafe {
+            sgemm_(
+                &(b'N' as c_char),
+                &(b'N' as c_char),
+                &m,
+                &n,
+                &k,
+                &alpha,
+                a.as_ptr(),
+                &lda,
+                b.as_ptr(),
+                &ldb,
+                &beta,
+                c.as_mut_ptr(),
+                &ldc,
+            );
+        }
+        assert_eq!(c, [5.0, 11.0, 6.0, 12.0]);
+    }
+
+    #[test]
+    fn test_zgemm_conj() {
+        // c = a^h * b with 1 x 1 matrices
+        let a = [[1f64, 2.0]];
+        let b = [[3f64, 4.0]];
+        let mut c = [[0f64; 2]];
+        let (alpha, beta) = ([1f64, 0.0], [0f64, 0.0]);
+        let one = 1;
+        unsafe {
+            zgemm_(
+                &(b'C' as c_char),
+                &(b'N' as c_char),
+                &one,
+                &one,
+                &one,
+                &alpha,
+                a.as_ptr(),
+                &one,
+                b.as_ptr(),
+                &one,
+                &beta,
+                c.as_mut_ptr(),
+                &one,
+            );
+        }
+        // (1 - 2i) * (3 + 4i)
+        assert_eq!(c, [[11.0, -2.0]]);
+    }
+}
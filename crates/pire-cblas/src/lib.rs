@@ -0,0 +1,725 @@
+//! CBLAS compatible C ABI on top of pire gemm kernels
+//! Signatures follow cblas.h, and mkl for cblas_hgemm and the integer gemms (include/pire_cblas.h)
+//! Nonzero ao/bo offsets are supported by cblas_gemm_s8u8s32 and cblas_gemm_s16s16s32
+//! Fortran BLAS symbols (sgemm_, dgemm_, cgemm_, zgemm_) are exported as well, see [`fortran`]
+//!
+//! # Safety
+//!
+//! All functions have the contract of their cblas counterpart, pointers must be valid for the given dims and leading dimensions
+
+#![allow(non_camel_case_types)]
+#![allow(clippy::missing_safety_doc)]
+#![allow(non_upper_case_globals)]
+#![allow(clippy::too_many_arguments)]
+
+pub mod fortran;
+
+use core::ffi::{c_int, c_short, c_void};
+use half::f16;
+use num_complex::Complex;
+
+use pire_gemm_c32::pire_cgemm_conj;
+use pire_gemm_c64::pire_zgemm_conj;
+use pire_gemm_f16::pire_hgemm;
+use pire_gemm_f32::pire_sgemm;
+use pire_gemm_f64::pire_dgemm;
+use pire_gemm_s16s16s32::{pire_gemm_s16s16s32_offset, OffsetC as OffsetC16};
+use pire_gemm_s8u8s32::{pire_gemm_s8u8s32_offset, OffsetC};
+
+// c enums are passed as int, values are not trusted to be valid enum variants
+pub type CBLAS_LAYOUT = c_int;
+pub const CblasRowMajor: CBLAS_LAYOUT = 101;
+pub const CblasColMajor: CBLAS_LAYOUT = 102;
+
+pub type CBLAS_TRANSPOSE = c_int;
+pub const CblasNoTrans: CBLAS_TRANSPOSE = 111;
+pub const CblasTrans: CBLAS_TRANSPOSE = 112;
+pub const CblasConjTrans: CBLAS_TRANSPOSE = 113;
+
+pub type CBLAS_OFFSET = c_int;
+pub const CblasRowOffset: CBLAS_OFFSET = 171;
+pub const CblasColOffset: CBLAS_OFFSET = 172;
+pub const CblasFixOffset: CBLAS_OFFSET = 173;
+
+// same message as reference cblas, p is the 1-based position of the invalid argument
+fn cblas_xerbla(p: usize, rout: &str) {
+    eprintln!("Parameter {} to routine {} was incorrect", p, rout);
+}
+
+// positions of m, n, k, lda, ldb, ldc in the argument list
+const FLOAT_POS: [usize; 6] = [4, 5, 6, 9, 11, 14];
+const INT_POS: [usize; 6] = [5, 6, 7, 10, 13, 17];
+
+// dims and strides of op(a), op(b), c in the convention of the gemm crates
+struct GemmArgs {
+    m: usize,
+    n: usize,
+    k: usize,
+    a_rs: usize,
+    a_cs: usize,
+    b_rs: usize,
+    b_cs: usize,
+    c_rs: usize,
+    c_cs: usize,
+}
+
+// strides of op(x) with rows x cols dims and the smallest valid leading dimension
+fn op_strides(
+    layout: CBLAS_LAYOUT,
+    trans: CBLAS_TRANSPOSE,
+    ld: usize,
+    rows: usize,
+    cols: usize,
+) -> (usize, usize, usize) {
+    let is_col_major = (layout == CblasColMajor) == (trans == CblasNoTrans);
+    if is_col_major {
+        (1, ld, rows)
+    } else {
+        (ld, 1, cols)
+    }
+}
+
+fn is_trans_valid(trans: CBLAS_TRANSPOSE) -> bool {
+    trans == CblasNoTrans || trans == CblasTrans || trans == CblasConjTrans
+}
+
+// validates arguments in the order of reference cblas, None if any of them is invalid
+fn gemm_args(
+    rout: &str,
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    lda: c_int,
+    ldb: c_int,
+    ldc: c_int,
+    pos: [usize; 6],
+) -> Option<GemmArgs> {
+    if layout != CblasRowMajor && layout != CblasColMajor {
+        cblas_xerbla(1, rout);
+        return None;
+    }
+    if !is_trans_valid(transa) {
+        cblas_xerbla(2, rout);
+        return None;
+    }
+    if !is_trans_valid(transb) {
+        cblas_xerbla(3, rout);
+        return None;
+    }
+    for (i, x) in [m, n, k].into_iter().enumerate() {
+        if x < 0 {
+            cblas_xerbla(pos[i], rout);
+            return None;
+        }
+    }
+    for (i, x) in [lda, ldb, ldc].into_iter().enumerate() {
+        if x < 1 {
+            cblas_xerbla(pos[i + 3], rout);
+            return None;
+        }
+    }
+    let (m, n, k) = (m as usize, n as usize, k as usize);
+    let (a_rs, a_cs, min_lda) = op_strides(layout, transa, lda as usize, m, k);
+    let (b_rs, b_cs, min_ldb) = op_strides(layout, transb, ldb as usize, k, n);
+    let (c_rs, c_cs, min_ldc) = op_strides(layout, CblasNoTrans, ldc as usize, m, n);
+    for (i, (ld, min_ld)) in [(lda, min_lda), (ldb, min_ldb), (ldc, min_ldc)].into_iter().enumerate() {
+        if (ld as usize) < min_ld {
+            cblas_xerbla(pos[i + 3], rout);
+            return None;
+        }
+    }
+    Some(GemmArgs { m, n, k, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs })
+}
+
+// c = f(c), used when there is no a*b term to compute (k == 0)
+unsafe fn scale_c<T: Copy>(x: &GemmArgs, c: *mut T, f: impl Fn(T) -> T) {
+    for j in 0..x.n {
+        for i in 0..x.m {
+            let c_cur = c.add(i * x.c_rs + j * x.c_cs);
+            *c_cur = f(*c_cur);
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cblas_sgemm(
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f32,
+    a: *const f32,
+    lda: c_int,
+    b: *const f32,
+    ldb: c_int,
+    beta: f32,
+    c: *mut f32,
+    ldc: c_int,
+) {
+    let Some(x) = gemm_args("cblas_sgemm", layout, transa, transb, m, n, k, lda, ldb, ldc, FLOAT_POS) else {
+        return;
+    };
+    if x.m == 0 || x.n == 0 {
+        return;
+    }
+    if x.k == 0 {
+        scale_c(&x, c, |v| if beta == 0.0 { 0.0 } else { beta * v });
+        return;
+    }
+    pire_sgemm(x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, b, x.b_rs, x.b_cs, beta, c, x.c_rs, x.c_cs);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cblas_dgemm(
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f64,
+    a: *const f64,
+    lda: c_int,
+    b: *const f64,
+    ldb: c_int,
+    beta: f64,
+    c: *mut f64,
+    ldc: c_int,
+) {
+    let Some(x) = gemm_args("cblas_dgemm", layout, transa, transb, m, n, k, lda, ldb, ldc, FLOAT_POS) else {
+        return;
+    };
+    if x.m == 0 || x.n == 0 {
+        return;
+    }
+    if x.k == 0 {
+        scale_c(&x, c, |v| if beta == 0.0 { 0.0 } else { beta * v });
+        return;
+    }
+    pire_dgemm(x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, b, x.b_rs, x.b_cs, beta, c, x.c_rs, x.c_cs);
+}
+
+/// f16 values are passed as their bits, as MKL_F16 of mkl
+#[no_mangle]
+pub unsafe extern "C" fn cblas_hgemm(
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: u16,
+    a: *const u16,
+    lda: c_int,
+    b: *const u16,
+    ldb: c_int,
+    beta: u16,
+    c: *mut u16,
+    ldc: c_int,
+) {
+    let Some(x) = gemm_args("cblas_hgemm", layout, transa, transb, m, n, k, lda, ldb, ldc, FLOAT_POS) else {
+        return;
+    };
+    if x.m == 0 || x.n == 0 {
+        return;
+    }
+    let (alpha, beta) = (f16::from_bits(alpha), f16::from_bits(beta));
+    let (a, b, c) = (a as *const f16, b as *const f16, c as *mut f16);
+    if x.k == 0 {
+        scale_c(&x, c, |v| if beta == f16::ZERO { f16::ZERO } else { beta * v });
+        return;
+    }
+    pire_hgemm(x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, b, x.b_rs, x.b_cs, beta, c, x.c_rs, x.c_cs);
+}
+
+macro_rules! def_cblas_complex_gemm {
+    ($name:ident, $t:ty, $pire_fn:ident) => {
+        #[no_mangle]
+        pub unsafe extern "C" fn $name(
+            layout: CBLAS_LAYOUT,
+            transa: CBLAS_TRANSPOSE,
+            transb: CBLAS_TRANSPOSE,
+            m: c_int,
+            n: c_int,
+            k: c_int,
+            alpha: *const c_void,
+            a: *const c_void,
+            lda: c_int,
+            b: *const c_void,
+            ldb: c_int,
+            beta: *const c_void,
+            c: *mut c_void,
+            ldc: c_int,
+        ) {
+            let Some(x) = gemm_args(stringify!($name), layout, transa, transb, m, n, k, lda, ldb, ldc, FLOAT_POS)
+            else {
+                return;
+            };
+            if x.m == 0 || x.n == 0 {
+                return;
+            }
+            let alpha = *(alpha as *const Complex<$t>);
+            let beta = *(beta as *const Complex<$t>);
+            let (a, b, c) = (a as *const Complex<$t>, b as *const Complex<$t>, c as *mut Complex<$t>);
+            let zero = Complex::<$t>::new(0.0, 0.0);
+            if x.k == 0 {
+                scale_c(&x, c, |v| if beta == zero { zero } else { beta * v });
+                return;
+            }
+            let (conj_a, conj_b) = (transa == CblasConjTrans, transb == CblasConjTrans);
+            $pire_fn(
+                x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, conj_a, b, x.b_rs, x.b_cs, conj_b, beta, c, x.c_rs, x.c_cs,
+            );
+        }
+    };
+}
+
+def_cblas_complex_gemm!(cblas_cgemm, f32, pire_cgemm_conj);
+def_cblas_complex_gemm!(cblas_zgemm, f64, pire_zgemm_conj);
+
+// adds co to c as selected by offsetc, c += co[0] (fix), c[i, j] += co[j] (row), c[i, j] += co[i] (col)
+unsafe fn add_c_offset(x: &GemmArgs, offsetc: CBLAS_OFFSET, c: *mut i32, co: *const i32) {
+    for j in 0..x.n {
+        for i in 0..x.m {
+            let offset = match offsetc {
+                CblasRowOffset => *co.add(j),
+                CblasColOffset => *co.add(i),
+                _ => *co,
+            };
+            let c_cur = c.add(i * x.c_rs + j * x.c_cs);
+            *c_cur = (*c_cur).wrapping_add(offset);
+        }
+    }
+}
+
+fn is_offsetc_valid(rout: &str, offsetc: CBLAS_OFFSET) -> bool {
+    if offsetc != CblasRowOffset && offsetc != CblasColOffset && offsetc != CblasFixOffset {
+        cblas_xerbla(4, rout);
+        return false;
+    }
+    true
+}
+
+/// Integer types follow mkl, the signed matrix is a for CblasColMajor and b for CblasRowMajor
+#[no_mangle]
+pub unsafe extern "C" fn cblas_gemm_s8u8s32(
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    offsetc: CBLAS_OFFSET,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f32,
+    a: *const c_void,
+    lda: c_int,
+    ao: i8,
+    b: *const c_void,
+    ldb: c_int,
+    bo: i8,
+    beta: f32,
+    c: *mut i32,
+    ldc: c_int,
+    co: *const i32,
+) {
+    let rout = "cblas_gemm_s8u8s32";
+    let Some(x) = gemm_args(rout, layout, transa, transb, m, n, k, lda, ldb, ldc, INT_POS) else {
+        return;
+    };
+    if !is_offsetc_valid(rout, offsetc) {
+        return;
+    }
+    if x.m == 0 || x.n == 0 {
+        return;
+    }
+    if x.k == 0 {
+        scale_c(&x, c, |v| (beta * v as f32).round() as i32);
+        add_c_offset(&x, offsetc, c, co);
+    } else if layout == CblasColMajor {
+        let (a, b) = (a as *const i8, b as *const u8);
+        let offsetc = match offsetc {
+            CblasRowOffset => OffsetC::Row,
+            CblasColOffset => OffsetC::Col,
+            _ => OffsetC::Fix,
+        };
+        pire_gemm_s8u8s32_offset(
+            x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, ao, b, x.b_rs, x.b_cs, bo, beta, c, x.c_rs, x.c_cs, offsetc, co,
+        );
+    } else {
+        // c^t = op(b)^t * op(a)^t so that the signed matrix comes first, rows of c are columns of c^t
+        let (a, b) = (a as *const u8, b as *const i8);
+        let offsetc = match offsetc {
+            CblasRowOffset => OffsetC::Col,
+            CblasColOffset => OffsetC::Row,
+            _ => OffsetC::Fix,
+        };
+        pire_gemm_s8u8s32_offset(
+            x.n, x.m, x.k, alpha, b, x.b_cs, x.b_rs, bo, a, x.a_cs, x.a_rs, ao, beta, c, x.c_cs, x.c_rs, offsetc, co,
+        );
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cblas_gemm_s16s16s32(
+    layout: CBLAS_LAYOUT,
+    transa: CBLAS_TRANSPOSE,
+    transb: CBLAS_TRANSPOSE,
+    offsetc: CBLAS_OFFSET,
+    m: c_int,
+    n: c_int,
+    k: c_int,
+    alpha: f32,
+    a: *const c_short,
+    lda: c_int,
+    ao: c_short,
+    b: *const c_short,
+    ldb: c_int,
+    bo: c_short,
+    beta: f32,
+    c: *mut i32,
+    ldc: c_int,
+    co: *const i32,
+) {
+    let rout = "cblas_gemm_s16s16s32";
+    let Some(x) = gemm_args(rout, layout, transa, transb, m, n, k, lda, ldb, ldc, INT_POS) else {
+        return;
+    };
+    if !is_offsetc_valid(rout, offsetc) {
+        return;
+    }
+    if x.m == 0 || x.n == 0 {
+        return;
+    }
+    if x.k == 0 {
+        scale_c(&x, c, |v| (beta * v as f32).round() as i32);
+        add_c_offset(&x, offsetc, c, co);
+    } else {
+        let offsetc = match offsetc {
+            CblasRowOffset => OffsetC16::Row,
+            CblasColOffset => OffsetC16::Col,
+            _ => OffsetC16::Fix,
+        };
+        pire_gemm_s16s16s32_offset(
+            x.m, x.n, x.k, alpha, a, x.a_rs, x.a_cs, ao, b, x.b_rs, x.b_cs, bo, beta, c, x.c_rs, x.c_cs, offsetc, co,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // column major m x n result of op(a) * op(b) for matrices given with gemm crate strides
+    fn naive_gemm<T: Copy + Default + core::ops::Mul<Output = T> + core::ops::Add<Output = T>>(
+        x: &GemmArgs,
+        a: &[T],
+        b: &[T],
+    ) -> Vec<T> {
+        let mut c = vec![T::default(); x.m * x.n];
+        for j in 0..x.n {
+            for i in 0..x.m {
+                let mut acc = T::default();
+                for p in 0..x.k {
+                    acc = acc + a[i * x.a_rs + p * x.a_cs] * b[p * x.b_rs + j * x.b_cs];
+                }
+                c[i + j * x.m] = acc;
+            }
+        }
+        c
+    }
+
+    fn test_values(len: usize, seed: usize) -> Vec<f64> {
+        (0..len).map(|i| ((i * 7 + seed * 13) % 17) as f64 - 8.0).collect()
+    }
+
+    #[test]
+    fn test_sgemm_layouts() {
+        let (m, n, k) = (23, 17, 9);
+        let a = test_values(m * k, 1).into_iter().map(|x| x as f32).collect::<Vec<_>>();
+        let b = test_values(k * n, 2).into_iter().map(|x| x as f32).collect::<Vec<_>>();
+        for layout in [CblasRowMajor, CblasColMajor] {
+            for transa in [CblasNoTrans, CblasTrans, CblasConjTrans] {
+                for transb in [CblasNoTrans, CblasTrans] {
+                    let lda = op_strides(layout, transa, 0, m, k).2 as c_int;
+                    let ldb = op_strides(layout, transb, 0, k, n).2 as c_int;
+                    let ldc = op_strides(layout, CblasNoTrans, 0, m, n).2 as c_int;
+                    let x = gemm_args("test", layout, transa, transb, 23, 17, 9, lda, ldb, ldc, FLOAT_POS).unwrap();
+                    let mut c = vec![0f32; m * n];
+                    unsafe {
+                        cblas_sgemm(
+                            layout,
+                            transa,
+                            transb,
+                            23,
+                            17,
+                            9,
+                            1.0,
+                            a.as_ptr(),
+                            lda,
+                            b.as_ptr(),
+                            ldb,
+                            0.0,
+                            c.as_mut_ptr(),
+                            ldc,
+                        );
+                    }
+                    let c_ref = naive_gemm(&x, &a, &b);
+                    for j in 0..n {
+                        for i in 0..m {
+                            assert_eq!(c[i * x.c_rs + j * x.c_cs], c_ref[i + j * m]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_args() {
+        let mut c = vec![1f32; 4];
+        let a = [1f32; 4];
+        unsafe {
+            // lda smaller than m is rejected, c is not touched
+            cblas_sgemm(
+                CblasColMajor,
+                CblasNoTrans,
+                CblasNoTrans,
+                2,
+                2,
+                2,
+                1.0,
+                a.as_ptr(),
+                1,
+                a.as_ptr(),
+                2,
+                0.0,
+                c.as_mut_ptr(),
+                2,
+            );
+            cblas_sgemm(
+                CblasColMajor,
+                0,
+                CblasNoTrans,
+                2,
+                2,
+                2,
+                1.0,
+                a.as_ptr(),
+                2,
+                a.as_ptr(),
+                2,
+                0.0,
+                c.as_mut_ptr(),
+                2,
+            );
+        }
+        assert_eq!(c, vec![1f32; 4]);
+        unsafe {
+            cblas_sgemm(
+                CblasColMajor,
+                CblasNoTrans,
+                CblasNoTrans,
+                2,
+                2,
+                0,
+                1.0,
+                a.as_ptr(),
+                2,
+                a.as_ptr(),
+                2,
+                3.0,
+                c.as_mut_ptr(),
+                2,
+            );
+        }
+        assert_eq!(c, vec![3f32; 4]);
+    }
+
+    #[test]
+    fn test_zgemm_conj() {
+        let (m, n, k) = (5, 4, 3);
+        let a = test_values(2 * m * k, 3);
+        let b = test_values(2 * k * n, 4);
+        let a = a.chunks(2).map(|x| Complex::new(x[0], x[1])).collect::<Vec<_>>();
+        let b = b.chunks(2).map(|x| Complex::new(x[0], x[1])).collect::<Vec<_>>();
+        let (alpha, beta) = (Complex::new(1.0, 0.0), Complex::new(0.0, 0.0));
+        let mut c = vec![Complex::new(0.0, 0.0); m * n];
+        // a is k x m col major, b is n x k col major
+        unsafe {
+            cblas_zgemm(
+                CblasColMajor,
+                CblasConjTrans,
+                CblasConjTrans,
+                5,
+                4,
+                3,
+                &alpha as *const Complex<f64> as *const c_void,
+                a.as_ptr() as *const c_void,
+                3,
+                b.as_ptr() as *const c_void,
+                4,
+                &beta as *const Complex<f64> as *const c_void,
+                c.as_mut_ptr() as *mut c_void,
+                5,
+            );
+        }
+        let x = gemm_args("test", CblasColMajor, CblasConjTrans, CblasConjTrans, 5, 4, 3, 3, 4, 5, FLOAT_POS).unwrap();
+        let a_conj = a.iter().map(|x| x.conj()).collect::<Vec<_>>();
+        let b_conj = b.iter().map(|x| x.conj()).collect::<Vec<_>>();
+        assert_eq!(c, naive_gemm(&x, &a_conj, &b_conj));
+    }
+
+    #[test]
+    fn test_gemm_s8u8s32_offset() {
+        let (m, n, k) = (7, 6, 19);
+        let a = test_values(m * k, 5).into_iter().map(|x| x as i8).collect::<Vec<_>>();
+        let b = test_values(k * n, 6).into_iter().map(|x| (x + 8.0) as u8).collect::<Vec<_>>();
+        let co = (0..n as i32).collect::<Vec<_>>();
+        let (ao, bo) = (-3i8, 4i8);
+        // signed a in col major
+        let mut c_col = vec![0i32; m * n];
+        unsafe {
+            let (a_ptr, b_ptr) = (a.as_ptr() as *const c_void, b.as_ptr() as *const c_void);
+            let c_ptr = c_col.as_mut_ptr();
+            cblas_gemm_s8u8s32(
+                CblasColMajor,
+                CblasNoTrans,
+                CblasNoTrans,
+                CblasRowOffset,
+                7,
+                6,
+                19,
+                1.0,
+                a_ptr,
+                7,
+                ao,
+                b_ptr,
+                19,
+                bo,
+                0.0,
+                c_ptr,
+                7,
+                co.as_ptr(),
+            );
+        }
+        let x = gemm_args("test", CblasColMajor, CblasNoTrans, CblasNoTrans, 7, 6, 19, 7, 19, 7, INT_POS).unwrap();
+        let a_i32 = a.iter().map(|&x| x as i32 + ao as i32).collect::<Vec<_>>();
+        let b_i32 = b.iter().map(|&x| x as i32 + bo as i32).collect::<Vec<_>>();
+        let mut c_ref = naive_gemm(&x, &a_i32, &b_i32);
+        for j in 0..n {
+            for i in 0..m {
+                c_ref[i + j * m] += co[j];
+            }
+        }
+        assert_eq!(c_col, c_ref);
+
+        // same product in row major, c^t = b^t * a^t with signed b^t
+        let mut c_row = vec![0i32; m * n];
+        unsafe {
+            let (a_ptr, b_ptr) = (b.as_ptr() as *const c_void, a.as_ptr() as *const c_void);
+            let c_ptr = c_row.as_mut_ptr();
+            cblas_gemm_s8u8s32(
+                CblasRowMajor,
+                CblasNoTrans,
+                CblasNoTrans,
+                CblasColOffset,
+                6,
+                7,
+                19,
+                1.0,
+                a_ptr,
+                19,
+                bo,
+                b_ptr,
+                7,
+                ao,
+                0.0,
+                c_ptr,
+                7,
+                co.as_ptr(),
+            );
+        }
+        assert_eq!(c_row, c_ref);
+    }
+
+    #[test]
+    fn test_gemm_s16s16s32_offset() {
+        let (m, n, k) = (7, 6, 19);
+        let a = test_values(m * k, 7).into_iter().map(|x| x as i16 * 300).collect::<Vec<_>>();
+        let b = test_values(k * n, 8).into_iter().map(|x| x as i16).collect::<Vec<_>>();
+        let co = (0..m as i32).collect::<Vec<_>>();
+        let (ao, bo) = (5i16, -2i16);
+        let x = gemm_args("test", CblasColMajor, CblasNoTrans, CblasNoTrans, 7, 6, 19, 7, 19, 7, INT_POS).unwrap();
+        let a_i32 = a.iter().map(|&x| x as i32 + ao as i32).collect::<Vec<_>>();
+        let b_i32 = b.iter().map(|&x| x as i32 + bo as i32).collect::<Vec<_>>();
+        let mut c_ref = naive_gemm(&x, &a_i32, &b_i32);
+        for j in 0..n {
+            for i in 0..m {
+                c_ref[i + j * m] += co[i];
+            }
+        }
+        // col major c, and row major c with a and b read transposed from the same col major storage
+        for (layout, trans, ldc) in [(CblasColMajor, CblasNoTrans, 7), (CblasRowMajor, CblasTrans, 6)] {
+            let mut c = vec![0i32; m * n];
+            unsafe {
+                cblas_gemm_s16s16s32(
+                    layout,
+                    trans,
+                    trans,
+                    CblasColOffset,
+                    7,
+                    6,
+                    19,
+                    1.0,
+                    a.as_ptr(),
+                    7,
+                    ao,
+                    b.as_ptr(),
+                    19,
+                    bo,
+                    0.0,
+                    c.as_mut_ptr(),
+                    ldc,
+                    co.as_ptr(),
+                );
+            }
+            let (c_rs, c_cs) = if layout == CblasColMajor { (1, m) } else { (n, 1) };
+            for j in 0..n {
+                for i in 0..m {
+                    assert_eq!(c[i * c_rs + j * c_cs], c_ref[i + j * m], "layout: {:?}", layout);
+                }
+            }
+        }
+
+        // k == 0 rounds beta * c to the nearest integer before adding the offset
+        let mut c = vec![3i32; m * n];
+        unsafe {
+            cblas_gemm_s16s16s32(
+                CblasColMajor,
+                CblasNoTrans,
+                CblasNoTrans,
+                CblasFixOffset,
+                7,
+                6,
+                0,
+                1.0,
+                a.as_ptr(),
+                7,
+                ao,
+                b.as_ptr(),
+                1,
+                bo,
+                0.5,
+                c.as_mut_ptr(),
+                7,
+                co.as_ptr(),
+            );
+        }
+        assert!(c.iter().all(|&v| v == 2));
+    }
+}
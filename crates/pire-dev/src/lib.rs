@@ -9,7 +9,7 @@ use libc::{c_double, c_float, c_int, c_schar, c_short, c_ushort, c_void};
 
 use num_complex::{c32, c64, Complex32, Complex64};
 
-use half::f16;
+use half::{bf16, f16};
 use once_cell::sync::Lazy;
 
 #[repr(C)]
@@ -584,6 +584,19 @@ impl Bound for f16 {
     }
 }
 
+impl Bound for bf16 {
+    type X = bf16;
+    fn min_value() -> Self {
+        bf16::from_f32(-1.0)
+    }
+    fn max_value() -> Self {
+        bf16::from_f32(1.0)
+    }
+    fn my_sample(dist: &Uniform<Self>, rng: &mut StdRng) -> Self {
+        dist.sample(rng)
+    }
+}
+
 impl Bound for Complex<f32> {
     type X = f32;
     fn min_value() -> f32 {
@@ -636,6 +649,38 @@ where
     arr.iter_mut().for_each(|p| *p = T::my_sample(&un_dist, &mut x));
 }
 
+// values over the whole range of t, random_matrix_uniform keeps integers in a small range
+// that does not reach the overflow and saturation cases of the integer kernels
+pub fn random_matrix_full_range<T>(arr: &mut [T], seed: u64)
+where
+    rand::distributions::Standard: rand::prelude::Distribution<T>,
+{
+    let mut x = StdRng::seed_from_u64(seed);
+    arr.iter_mut().for_each(|p| *p = x.gen::<T>());
+}
+
+/// Runs the test `name` of the current test executable again in a child process with the
+/// variables of `env` set. The `PIRE_*_OFF` switches of pire-base (feature `debug_cpu_features`)
+/// are read once per process, this is how tests reach the kernels of the other cpu features
+pub fn run_test_with_env(name: &str, env: &[&str]) {
+    let exe = std::env::current_exe().unwrap();
+    let mut cmd = std::process::Command::new(exe);
+    cmd.args([name, "--exact", "--test-threads=1"]);
+    for var in env {
+        cmd.env(var, "1");
+    }
+    let out = cmd.output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(
+        out.status.success() && stdout.contains("1 passed"),
+        "{} with {:?} failed:\n{}\n{}",
+        name,
+        env,
+        stdout,
+        String::from_utf8_lossy(&out.stderr)
+    );
+}
+
 pub trait Diff {
     fn diff(&self, other: &Self) -> f64;
 }
@@ -840,6 +885,111 @@ pub unsafe fn gemm_fallback_s8u8s32(
     }
 }
 
+pub unsafe fn gemm_fallback_s8u8s32_offset(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i8,
+    a_rs: usize,
+    a_cs: usize,
+    ao: i8,
+    b: *const u8,
+    b_rs: usize,
+    b_cs: usize,
+    bo: i8,
+    beta: f32,
+    c: *mut i32,
+    c_rs: usize,
+    c_cs: usize,
+    offsetc: CBLAS_OFFSET,
+    co: *const i32,
+) {
+    for i in 0..m {
+        for j in 0..n {
+            let mut dx = 0i32;
+            for p in 0..k {
+                let a_ip = *a.add(a_rs * i + a_cs * p) as i32 + ao as i32;
+                let b_pj = *b.add(b_rs * p + b_cs * j) as i32 + bo as i32;
+                dx += a_ip * b_pj;
+            }
+            let co_ij = match offsetc {
+                CblasFixOffset => *co,
+                CblasRowOffset => *co.add(j),
+                CblasColOffset => *co.add(i),
+            };
+            let c_ij = c.add(c_rs * i + c_cs * j);
+            *c_ij = (alpha * dx as f32 + beta * *c_ij as f32) as i32 + co_ij;
+        }
+    }
+}
+
+pub unsafe fn gemm_fallback_s16s16s32_offset(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i16,
+    a_rs: usize,
+    a_cs: usize,
+    ao: i16,
+    b: *const i16,
+    b_rs: usize,
+    b_cs: usize,
+    bo: i16,
+    beta: f32,
+    c: *mut i32,
+    c_rs: usize,
+    c_cs: usize,
+    offsetc: CBLAS_OFFSET,
+    co: *const i32,
+) {
+    for i in 0..m {
+        for j in 0..n {
+            let mut dx = 0i32;
+            for p in 0..k {
+                let a_ip = *a.add(a_rs * i + a_cs * p) as i32 + ao as i32;
+                let b_pj = *b.add(b_rs * p + b_cs * j) as i32 + bo as i32;
+                dx += a_ip * b_pj;
+            }
+            let co_ij = match offsetc {
+                CblasFixOffset => *co,
+                CblasRowOffset => *co.add(j),
+                CblasColOffset => *co.add(i),
+            };
+            let c_ij = c.add(c_rs * i + c_cs * j);
+            *c_ij = (alpha * dx as f32 + beta * *c_ij as f32) as i32 + co_ij;
+        }
+    }
+}
+
+pub unsafe fn gemm_fallback_s8s8s32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i8,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const i8,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut i32,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    for i in 0..m {
+        for j in 0..n {
+            let mut dx = 0i32;
+            for p in 0..k {
+                dx += *a.add(a_rs * i + a_cs * p) as i32 * *b.add(b_rs * p + b_cs * j) as i32;
+            }
+            *c.add(c_rs * i + c_cs * j) = (alpha * dx as f32 + beta * *c.add(c_rs * i + c_cs * j) as f32) as i32;
+        }
+    }
+}
+
 pub unsafe fn gemm_fallback_c32(
     m: usize,
     n: usize,
@@ -921,6 +1071,33 @@ pub unsafe fn gemm_fallback_f16(
     }
 }
 
+pub unsafe fn gemm_fallback_bf16bf16f32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const bf16,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const bf16,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut f32,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    for i in 0..m {
+        for j in 0..n {
+            let mut dx = 0_f32;
+            for p in 0..k {
+                dx += (*a.add(a_rs * i + a_cs * p)).to_f32() * (*b.add(b_rs * p + b_cs * j)).to_f32();
+            }
+            *c.add(c_rs * i + c_cs * j) = alpha * dx + beta * *c.add(c_rs * i + c_cs * j);
+        }
+    }
+}
+
 pub fn stride_to_cblas(
     m: usize,
     n: usize,
@@ -1124,6 +1301,203 @@ pub unsafe fn check_gemm_s8u8s32(
     return diff;
 }
 
+pub unsafe fn check_gemm_s8u8s32_offset(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i8,
+    a_rs: usize,
+    a_cs: usize,
+    ao: i8,
+    b: *const u8,
+    b_rs: usize,
+    b_cs: usize,
+    bo: i8,
+    beta: f32,
+    c: &[i32],
+    c_rs: usize,
+    c_cs: usize,
+    c_ref: &mut [i32],
+    offsetc: CBLAS_OFFSET,
+    co: *const i32,
+    eps: f64,
+) -> f64 {
+    #[cfg(feature = "mkl")]
+    {
+        let (layout, transa, transb, m, n, lda, ldb, ldc) =
+            stride_to_cblas(m, n, k, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs);
+        let a = a as *const c_void;
+        let b = b as *const c_void;
+        cblas_gemm_s8u8s32(
+            layout,
+            transa,
+            transb,
+            offsetc,
+            m as c_int,
+            n as c_int,
+            k as c_int,
+            alpha,
+            a,
+            lda,
+            ao,
+            b,
+            ldb,
+            bo,
+            beta,
+            c_ref.as_mut_ptr(),
+            ldc,
+            co,
+            CBlasBackend::Mkl,
+        );
+    }
+    #[cfg(not(feature = "mkl"))]
+    {
+        gemm_fallback_s8u8s32_offset(
+            m,
+            n,
+            k,
+            alpha,
+            a,
+            a_rs,
+            a_cs,
+            ao,
+            b,
+            b_rs,
+            b_cs,
+            bo,
+            beta,
+            c_ref.as_mut_ptr(),
+            c_rs,
+            c_cs,
+            offsetc,
+            co,
+        );
+    }
+
+    let diff = max_abs_diff(&c, &c_ref, eps);
+    return diff;
+}
+
+pub unsafe fn check_gemm_s16s16s32_offset(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i16,
+    a_rs: usize,
+    a_cs: usize,
+    ao: i16,
+    b: *const i16,
+    b_rs: usize,
+    b_cs: usize,
+    bo: i16,
+    beta: f32,
+    c: &[i32],
+    c_rs: usize,
+    c_cs: usize,
+    c_ref: &mut [i32],
+    offsetc: CBLAS_OFFSET,
+    co: *const i32,
+    eps: f64,
+) -> f64 {
+    #[cfg(feature = "mkl")]
+    {
+        let (layout, transa, transb, m, n, lda, ldb, ldc) =
+            stride_to_cblas(m, n, k, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs);
+        cblas_gemm_s16s16s32(
+            layout,
+            transa,
+            transb,
+            offsetc,
+            m as c_int,
+            n as c_int,
+            k as c_int,
+            alpha,
+            a,
+            lda,
+            ao,
+            b,
+            ldb,
+            bo,
+            beta,
+            c_ref.as_mut_ptr(),
+            ldc,
+            co,
+            CBlasBackend::Mkl,
+        );
+    }
+    #[cfg(not(feature = "mkl"))]
+    {
+        gemm_fallback_s16s16s32_offset(
+            m,
+            n,
+            k,
+            alpha,
+            a,
+            a_rs,
+            a_cs,
+            ao,
+            b,
+            b_rs,
+            b_cs,
+            bo,
+            beta,
+            c_ref.as_mut_ptr(),
+            c_rs,
+            c_cs,
+            offsetc,
+            co,
+        );
+    }
+
+    let diff = max_abs_diff(&c, &c_ref, eps);
+    return diff;
+}
+
+pub unsafe fn check_gemm_s8s8s32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const i8,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const i8,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: &[i32],
+    c_rs: usize,
+    c_cs: usize,
+    c_ref: &mut [i32],
+    unary: unsafe fn(*mut i32, m: usize),
+    eps: f64,
+) -> f64 {
+    // mkl has no signed x signed int8 gemm, use the fallback as reference
+    gemm_fallback_s8s8s32(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c_ref.as_mut_ptr(), c_rs, c_cs);
+
+    let c_ref_ptr = c_ref.as_mut_ptr();
+    if c_rs == 1 {
+        for j in 0..n {
+            unary(c_ref_ptr.add(j * c_cs), m);
+        }
+    } else if c_cs == 1 {
+        for i in 0..m {
+            unary(c_ref_ptr.add(i * c_rs), n);
+        }
+    } else {
+        for i in 0..m {
+            for j in 0..n {
+                unary(c_ref_ptr.add(i * c_rs + j * c_cs), 1);
+            }
+        }
+    }
+
+    let diff = max_abs_diff(&c, &c_ref, eps);
+    return diff;
+}
+
 pub unsafe fn check_gemm_f16(
     m: usize,
     n: usize,
@@ -1197,6 +1571,49 @@ pub unsafe fn check_gemm_f16(
     return diff;
 }
 
+pub unsafe fn check_gemm_bf16bf16f32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const bf16,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const bf16,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: &[f32],
+    c_rs: usize,
+    c_cs: usize,
+    c_ref: &mut [f32],
+    unary: unsafe fn(*mut f32, m: usize),
+    eps: f64,
+) -> f64 {
+    // products of bf16 values are exact in f32, so the fallback is accurate enough as reference
+    gemm_fallback_bf16bf16f32(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c_ref.as_mut_ptr(), c_rs, c_cs);
+
+    let c_ref_ptr = c_ref.as_mut_ptr();
+    if c_rs == 1 {
+        for j in 0..n {
+            unary(c_ref_ptr.add(j * c_cs), m);
+        }
+    } else if c_cs == 1 {
+        for i in 0..m {
+            unary(c_ref_ptr.add(i * c_rs), n);
+        }
+    } else {
+        for i in 0..m {
+            for j in 0..n {
+                unary(c_ref_ptr.add(i * c_rs + j * c_cs), 1);
+            }
+        }
+    }
+
+    let diff = max_abs_diff(&c, &c_ref, eps);
+    return diff;
+}
+
 pub unsafe fn check_gemm_f64(
     m: usize,
     n: usize,
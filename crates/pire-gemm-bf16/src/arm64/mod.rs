@@ -0,0 +1,219 @@
+pub(crate) mod neon;
+pub(crate) mod pack_neon;
+
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+};
+
+use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+
+// bfmmla multiplies 2x4 by 4x2 bf16 blocks into 2x2 f32, so k is packed in groups of 4
+const NEON_VS: usize = 4;
+const NEON_MR: usize = 8;
+const NEON_NR: usize = 12;
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    let (mr, nr) = (NEON_MR, NEON_NR);
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        _ => (4800, 192, 512),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    pack_neon::packa_panel_8(m, k, x, rs, cs, y, NEON_VS);
+}
+
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    pack_neon::packb_panel_12(n, k, x, cs, rs, y);
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    (m + NEON_VS - 1) / NEON_VS * NEON_VS
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    (k + 3) / 4 * 4
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        Self { mc, nc, kc, mr: NEON_MR, nr: NEON_NR, vs: NEON_VS, is_l2_shared, is_l3_shared, func: f }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        (k + 3) / 4 * 4
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f)
+    } else {
+        let null_fn = IdentityFn {};
+        neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn)
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+unsafe fn kernel_n<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    b: *const TB,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f)
+    } else {
+        let null_fn = IdentityFn {};
+        neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
+    }
+}
+
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    neon::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+}
+
+def_pire_gemm!(
+    KernelDispatcher,
+    TA,
+    TA,
+    TB,
+    TB,
+    TC,
+    f32,
+    f32,
+    PackArrTypeA,
+    PackArrTypeB,
+    1_f32,
+    pire_gemm,
+    gemm_mt,
+    gemm_goto_serial,
+    kernel,
+    gemm_small_m_serial,
+    kernel_m,
+    gemm_small_n_serial,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0,
+    packb0,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    true,
+    into_pack_array,
+    F,
+);
@@ -0,0 +1,51 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+const VS: usize = 4;
+const VS_MAX: usize = VS;
+
+const ZERO: TC = 0.0;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn axpy<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const f32,
+    y: *mut TC,
+    incy: usize,
+    f: F,
+) {
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = 0f32;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = x.add(j * incx);
+            acc += (*a_cur).to_f32() * (*x_cur).to_f32();
+        }
+        *y_cur = *beta * *y_cur + *alpha * acc;
+        f.call(y_cur, 1);
+    }
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(TA, TB, TC, f32, true, F, 4, 2, 12, 0, 0);
+
+use super::pack_neon::packa_panel_8;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(TA, TA, TB, TC, f32, true, F, packa_panel_8, 4, 2, 12, 0, 0);
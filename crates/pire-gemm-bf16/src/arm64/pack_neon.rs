@@ -0,0 +1,120 @@
+use crate::{TA, TB};
+
+use paste::paste;
+
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn pack_scalar_k(
+    m_left: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    vs: usize,
+) {
+    let mr = (m_left + vs - 1) / vs * vs;
+    let k4 = k / 4 * 4;
+    let kl = k % 4;
+    let kl_4 = if kl == 0 { 0 } else { 4 };
+    for i in 0..m_left {
+        let mut j = 0;
+        while j < k4 {
+            *ap.add(j * mr + i * 4) = *a.add(j * a_cs + i * a_rs);
+            *ap.add(j * mr + i * 4 + 1) = *a.add((j + 1) * a_cs + i * a_rs);
+            *ap.add(j * mr + i * 4 + 2) = *a.add((j + 2) * a_cs + i * a_rs);
+            *ap.add(j * mr + i * 4 + 3) = *a.add((j + 3) * a_cs + i * a_rs);
+            j += 4;
+        }
+        let mut jl = 0;
+        while jl < kl {
+            *ap.add(j * mr + i * 4 + jl) = *a.add((j + jl) * a_cs + i * a_rs);
+            jl += 1;
+        }
+        while jl < kl_4 {
+            *ap.add(j * mr + i * 4 + jl) = TA::ZERO;
+            jl += 1;
+        }
+    }
+}
+
+macro_rules! def_packa {
+    ($mr:tt) => {
+        paste! {
+            #[target_feature(enable = "neon")]
+            pub(crate) unsafe fn [<packa_panel_ $mr>](
+                m: usize, k: usize,
+                a: *const TA, a_rs: usize, a_cs: usize,
+                ap: *mut TA, vs: usize,
+            ) {
+                let mr = $mr;
+                let k_eff = (k+3) / 4 * 4;
+                let ap0 = ap;
+                let a0 = a;
+                let m_rounded = m / mr * mr;
+                let mut m_idx = 0;
+                if a_rs == 1 {
+                    let lda = a_cs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx);
+                        let ap = ap0.add(m_idx*k_eff);
+                        pack_scalar_k(
+                            mr, k,
+                            a, 1, lda,
+                            ap, vs
+                        );
+                        m_idx += mr;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        pack_scalar_k(
+                            m_left, k,
+                            a0.add(m_idx), a_rs, a_cs,
+                            ap0.add(m_idx*k_eff), vs
+                        );
+                    }
+
+                } else if a_cs == 1 {
+                    let lda = a_rs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx*lda);
+                        let ap = ap0.add(m_idx*k_eff);
+                        pack_scalar_k(
+                            mr, k,
+                            a, a_rs, a_cs,
+                            ap, vs
+                        );
+                        m_idx += mr;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        pack_scalar_k(
+                            m_left, k,
+                            a0.add(m_idx*lda), a_rs, a_cs,
+                            ap0.add(m_idx*k_eff), vs
+                        );
+                    }
+                }
+            }
+        }
+    };
+}
+
+def_packa!(8);
+
+// called with the strides of b swapped, b_rs is the stride along n and b_cs along k
+// b is packed as n rows of k in the same layout as a, with unit vs
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn packb_panel_12(n: usize, k: usize, b: *const TB, b_rs: usize, b_cs: usize, bp: *mut TB) {
+    const NR: usize = 12;
+    let k_eff = (k + 3) / 4 * 4;
+    let n_rounded = n / NR * NR;
+    let mut n_idx = 0;
+    while n_idx < n_rounded {
+        pack_scalar_k(NR, k, b.add(n_idx * b_rs), b_rs, b_cs, bp.add(n_idx * k_eff), 1);
+        n_idx += NR;
+    }
+    let n_left = n - n_idx;
+    if n_left > 0 {
+        pack_scalar_k(n_left, k, b.add(n_idx * b_rs), b_rs, b_cs, bp.add(n_idx * k_eff), 1);
+    }
+}
@@ -0,0 +1,812 @@
+#[cfg(target_arch = "aarch64")]
+pub(crate) mod arm64;
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86_64_arch;
+
+#[cfg(target_arch = "x86_64")]
+use x86_64_arch::{
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_f32, round_k_simd, round_m_simd,
+    KernelDispatcher, KernelDispatcherF32,
+};
+
+use core::mem::size_of;
+
+#[cfg(target_arch = "aarch64")]
+use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+pub(crate) mod reference;
+
+pub(crate) type TA = bf16;
+pub(crate) type TB = bf16;
+pub(crate) type TC = f32;
+#[allow(unused)]
+const TC_SIZE: usize = size_of::<TC>();
+
+pub use half::bf16;
+
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::thread_pool::run_tasks;
+use pire_base::{
+    get_cache_params, has_bf16_compute, has_bf16f32_compute, put_pool_buf, split_range, take_pool_buf, GemmCache,
+    UnaryFn, AB_ALIGN,
+};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
+use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
+
+pub trait UnaryFnC: UnaryFn<TC> {}
+impl<F: UnaryFn<TC>> UnaryFnC for F {}
+
+pub(crate) unsafe fn pire_gemm_bf16bf16f32_fused<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    let par = PirePar::default(m, n);
+    pire_gemm_bf16bf16f32_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_gemm_bf16bf16f32_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    if has_bf16_compute() {
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+        {
+            let hw_config = KernelDispatcher::new(f);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            return;
+        }
+    }
+    if has_bf16f32_compute() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            let hw_config = KernelDispatcherF32::new(f);
+            pire_gemm_f32(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            return;
+        }
+    }
+
+    // if none of the optimized paths are available, use reference implementation
+    let hw_config = RefGemm::new(f);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+}
+
+pub unsafe fn pire_gemm_bf16bf16f32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    // do not exchange if transa && transb
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_gemm_bf16bf16f32_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
+}
+
+/// Same as [`pire_gemm_bf16bf16f32`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_gemm_bf16bf16f32_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // do not exchange if transa && transb
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_gemm_bf16bf16f32_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_gemm_bf16bf16f32`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_gemm_bf16bf16f32_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_gemm_bf16bf16f32_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_gemm_bf16bf16f32_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_gemm_bf16bf16f32_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_gemm_bf16bf16f32_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_gemm_bf16bf16f32`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_gemm_bf16bf16f32_grouped(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+) {
+    pire_gemm_bf16bf16f32_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_gemm_bf16bf16f32_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_gemm_bf16bf16f32_grouped_par(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_gemm_bf16bf16f32_par(
+            g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par,
+        );
+    });
+}
+
+/// bf16 output variant of [`pire_gemm_bf16bf16f32`], `c = alpha * a * b + beta * c` with c in bf16
+///
+/// c is widened into an f32 buffer, computed with f32 accumulation and rounded back to bf16 once at the end,
+/// so the result is rounded only once regardless of k
+pub unsafe fn pire_gemm_bf16bf16bf16(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut bf16,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    let par = PirePar::default(m, n);
+    pire_gemm_bf16bf16bf16_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, &par);
+}
+
+/// Same as [`pire_gemm_bf16bf16bf16`] with thread configuration given by `par`
+pub unsafe fn pire_gemm_bf16bf16bf16_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut bf16,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    if m == 0 || n == 0 {
+        return;
+    }
+    // column major f32 scratch of the pack pool, for one mc x nc block of c at a time
+    let (mc, nc, _) = dispatch_get_mcnckc();
+    let (mc, nc) = (mc.min(m), nc.min(n));
+    let (slot, mut pool_vec) = take_pool_buf(mc * nc * size_of::<f32>() + AB_ALIGN);
+    let c_f32 = pool_vec.as_mut_ptr();
+    let c_f32 = c_f32.add(c_f32.align_offset(AB_ALIGN)) as *mut f32;
+    let mut j = 0;
+    while j < n {
+        let nc_len = nc.min(n - j);
+        let mut i = 0;
+        while i < m {
+            let mc_len = mc.min(m - i);
+            let c_ij = c.add(i * c_rs + j * c_cs);
+            if beta != 0.0 {
+                convert_c_block(mc_len, nc_len, c_ij, c_rs, c_cs, c_f32, true, par.num_threads);
+            }
+            let (a_i, b_j) = (a.add(i * a_rs), b.add(j * b_cs));
+            pire_gemm_bf16bf16f32_par(
+                mc_len, nc_len, k, alpha, a_i, a_rs, a_cs, b_j, b_rs, b_cs, beta, c_f32, 1, mc_len, par,
+            );
+            convert_c_block(mc_len, nc_len, c_ij, c_rs, c_cs, c_f32, false, par.num_threads);
+            i += mc_len;
+        }
+        j += nc_len;
+    }
+    put_pool_buf(slot, pool_vec);
+}
+
+// copies an m x n block of c into the column major f32 scratch (to_f32) or back, columns split over threads
+#[allow(clippy::too_many_arguments)]
+unsafe fn convert_c_block(
+    m: usize,
+    n: usize,
+    c: *mut bf16,
+    c_rs: usize,
+    c_cs: usize,
+    c_f32: *mut f32,
+    to_f32: bool,
+    num_threads: usize,
+) {
+    let num_tasks = num_threads.min(n).max(1);
+    // pointers as addresses so that the closure is Sync, columns of different threads are disjoint
+    let (c_addr, c_f32_addr) = (c as usize, c_f32 as usize);
+    run_tasks(num_tasks, |t_id| {
+        let (c, c_f32) = (c_addr as *mut bf16, c_f32_addr as *mut f32);
+        let (start, end) = split_range(n, 1, t_id, num_tasks);
+        for j in start..end {
+            for i in 0..m {
+                let (x, y) = (c.add(i * c_rs + j * c_cs), c_f32.add(i + j * m));
+                if to_f32 {
+                    *y = (*x).to_f32();
+                } else {
+                    *x = bf16::from_f32(*y);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(feature = "fuse")]
+pub unsafe fn pire_gemm_bf16bf16f32_fn_ptr(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    unary: unsafe fn(*mut TC, usize),
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    pire_gemm_bf16bf16f32_fused(m, n, k, alpha, a, b, beta, c, unary);
+}
+
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_gemm_bf16bf16f32_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_bf16bf16f32_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_gemm_bf16bf16f32_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_gemm_bf16bf16f32_packed`]
+pub unsafe fn pire_gemm_bf16bf16f32_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_bf16bf16f32_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Safe counterpart of [`pire_gemm_bf16bf16f32`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_gemm_bf16bf16f32_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: f32,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| if beta == 0.0 { 0.0 } else { beta * v });
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_gemm_bf16bf16f32(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
+fn dispatch_round_m() -> fn(usize) -> usize {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_bf16_compute() || has_bf16f32_compute() {
+            return round_m_simd;
+        }
+    }
+    round_m_ref
+}
+fn dispatch_round_k() -> fn(usize) -> usize {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_bf16_compute() || has_bf16f32_compute() {
+            return round_k_simd;
+        }
+    }
+    round_k_ref
+}
+
+fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_bf16_compute() || has_bf16f32_compute() {
+            return packa_fn_simd;
+        }
+    }
+    packa_fn_ref
+}
+
+fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_bf16_compute() || has_bf16f32_compute() {
+            return packb_fn_simd;
+        }
+    }
+    packb_fn_ref
+}
+
+fn dispatch_get_mcnckc() -> (usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_bf16_compute() || has_bf16f32_compute() {
+            return get_mcnckc_simd();
+        }
+    }
+    get_cache_params()
+}
+
+pire_base::packing_api!(TA, TB);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aligned_vec::avec;
+    use pire_base::{get_cache_params, matrix_size};
+    use pire_dev::{
+        check_gemm_bf16bf16f32, generate_k_dims, generate_m_dims, generate_n_dims, layout_to_strides,
+        random_matrix_uniform, ABLayout,
+    };
+    #[test]
+    fn test_pack_a() {
+        let a_stride_scale = 1;
+        let (mc, _, kc) = get_mcnckc();
+        let (mr, _, kr) = (48, 8, 8);
+        let m_dims = generate_m_dims(mc, mr);
+        let k_dims = generate_k_dims(kc, kr);
+
+        for &m in &m_dims {
+            for &k in &k_dims {
+                let a_rs = 1 * a_stride_scale;
+                let a_cs = m * a_stride_scale;
+                let a_size = a_size_packed(m, k);
+                let a = vec![TA::ZERO; m * k * a_stride_scale];
+                let mut ap = avec![[AB_ALIGN]| TA::ZERO; a_size];
+                let ap_array = pack_a(m, k, &a, a_rs, a_cs, &mut ap);
+                assert!(!ap_array.is_strided() || m == 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_b() {
+        let b_stride_scale = 1;
+        let (_, nc, kc) = get_mcnckc();
+        let (_, nr, kr) = (48, 8, 8);
+        let n_dims = generate_n_dims(nc, nr);
+        let k_dims = generate_k_dims(kc, kr);
+
+        for &n in &n_dims {
+            for &k in &k_dims {
+                let b_rs = 1 * b_stride_scale;
+                let b_cs = k * b_stride_scale;
+                let b_size = b_size_packed(n, k);
+                let b = vec![TB::ZERO; n * k * b_stride_scale];
+                let mut bp = avec![[AB_ALIGN]| TA::ZERO; b_size];
+                let bp_array = pack_b(n, k, &b, b_rs, b_cs, &mut bp);
+                assert!(!bp_array.is_strided() || n == 1);
+            }
+        }
+    }
+
+    #[allow(unreachable_code)]
+    pub(crate) fn get_mcnckc() -> (usize, usize, usize) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            return x86_64_arch::get_mcnckc_simd();
+        }
+        get_cache_params()
+    }
+
+    unsafe fn unary_fn_test(c: *mut TC, m: usize) {
+        for i in 0..m {
+            *c.add(i) *= 2.0;
+        }
+    }
+
+    const EPS: f64 = 2e-2;
+
+    static ALPHA_ARR: [f32; 1] = [1.23];
+    static BETA_ARR: [f32; 1] = [1.17];
+
+    fn test_gemm(layout: &ABLayout, is_a_packed: bool, is_b_packed: bool) {
+        let a_stride_scale = 1;
+        let b_stride_scale = 1;
+        let c_stride_scale = 2;
+        let (mc, nc, kc) = get_mcnckc();
+        let (mr, nr, kr) = (48, 8, 8);
+        let m_dims = generate_m_dims(mc, mr);
+        let n_dims = generate_n_dims(nc, nr);
+        let k_dims = generate_k_dims(kc, kr);
+        let unary_fn: unsafe fn(*mut TC, usize) = unary_fn_test;
+        for &m in &m_dims {
+            for &n in &n_dims {
+                let c_size = matrix_size(m, n) * c_stride_scale;
+                let mut c = vec![0.0; c_size];
+                let mut c_ref = vec![0.0; c_size];
+                for &k in &k_dims {
+                    let a_size = matrix_size(m, k) * a_stride_scale;
+                    let b_size = matrix_size(k, n) * b_stride_scale;
+                    let mut a = vec![TA::ZERO; a_size];
+                    let mut b = vec![TB::ZERO; b_size];
+                    random_matrix_uniform(&mut a);
+                    random_matrix_uniform(&mut b);
+                    let ap_size = if is_a_packed { a_size_packed(m, k) } else { 0 };
+                    let mut ap = avec![[AB_ALIGN]| TA::ZERO; ap_size];
+
+                    let bp_size = if is_b_packed { b_size_packed(n, k) } else { 0 };
+                    let mut bp = avec![[AB_ALIGN]| TB::ZERO; bp_size];
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = layout_to_strides(&layout, m, n, k);
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = (
+                        a_rs * a_stride_scale,
+                        a_cs * a_stride_scale,
+                        b_rs * b_stride_scale,
+                        b_cs * b_stride_scale,
+                        c_rs * c_stride_scale,
+                        c_cs * c_stride_scale,
+                    );
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for alpha in ALPHA_ARR {
+                        for beta in BETA_ARR {
+                            random_matrix_uniform(&mut c);
+                            c_ref.copy_from_slice(&c);
+                            let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), c_rs, c_cs);
+                            unsafe {
+                                pire_gemm_bf16bf16f32_fused(
+                                    m, n, k, alpha, a_matrix, b_matrix, beta, c_matrix, unary_fn,
+                                );
+                            }
+                            let diff_max = unsafe {
+                                check_gemm_bf16bf16f32(
+                                    m,
+                                    n,
+                                    k,
+                                    alpha,
+                                    a.as_ptr(),
+                                    a_rs,
+                                    a_cs,
+                                    b.as_ptr(),
+                                    b_rs,
+                                    b_cs,
+                                    beta,
+                                    &mut c,
+                                    c_rs,
+                                    c_cs,
+                                    &mut c_ref,
+                                    unary_fn,
+                                    EPS,
+                                )
+                            };
+                            // if diff_max >= EPS {
+                            // 	println!("a: {:?}", a);
+                            // 	println!("b: {:?}", b);
+                            // 	println!("c:     {:?}", c);
+                            // 	println!("c_ref: {:?}", c_ref);
+                            // }
+                            assert!(
+                                diff_max < EPS,
+                                "diff_max: {}, m: {}, n: {}, k: {}, alpha: {}, beta: {}",
+                                diff_max,
+                                m,
+                                n,
+                                k,
+                                alpha,
+                                beta
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_nn_col_ap() {
+        test_gemm(&ABLayout::NN, true, false);
+    }
+    #[test]
+    fn test_nt_col_ap() {
+        test_gemm(&ABLayout::NT, true, false);
+    }
+    #[test]
+    fn test_tn_col_ap() {
+        test_gemm(&ABLayout::TN, true, false);
+    }
+    #[test]
+    fn test_tt_col_ap() {
+        test_gemm(&ABLayout::TT, true, false);
+    }
+    #[test]
+    fn test_nn_col_bp() {
+        test_gemm(&ABLayout::NN, false, true);
+    }
+    #[test]
+    fn test_nt_col_bp() {
+        test_gemm(&ABLayout::NT, false, true);
+    }
+    #[test]
+    fn test_tn_col_bp() {
+        test_gemm(&ABLayout::TN, false, true);
+    }
+    #[test]
+    fn test_tt_col_bp() {
+        test_gemm(&ABLayout::TT, false, true);
+    }
+    #[test]
+    fn test_nn_col() {
+        test_gemm(&ABLayout::NN, false, false);
+    }
+    #[test]
+    fn test_nt_col() {
+        test_gemm(&ABLayout::NT, false, false);
+    }
+    #[test]
+    fn test_tn_col() {
+        test_gemm(&ABLayout::TN, false, false);
+    }
+    #[test]
+    fn test_tt_col() {
+        test_gemm(&ABLayout::TT, false, false);
+    }
+    #[test]
+    fn test_nn_col_apbp() {
+        test_gemm(&ABLayout::NN, true, true);
+    }
+    #[test]
+    fn test_nt_col_apbp() {
+        test_gemm(&ABLayout::NT, true, true);
+    }
+    #[test]
+    fn test_tn_col_apbp() {
+        test_gemm(&ABLayout::TN, true, true);
+    }
+    #[test]
+    fn test_tt_col_apbp() {
+        test_gemm(&ABLayout::TT, true, true);
+    }
+
+    #[test]
+    fn test_gemm_bf16_out() {
+        let (mc, nc, _) = get_mcnckc();
+        // m and n past mc and nc span several blocks of the f32 scratch of c
+        for &m in &[1, 37, mc + 5] {
+            for &n in &[1, 9, nc + 3] {
+                let k = 29;
+                let mut a = vec![TA::ZERO; m * k];
+                let mut b = vec![TB::ZERO; k * n];
+                let mut c = vec![bf16::ZERO; m * n];
+                random_matrix_uniform(&mut a);
+                random_matrix_uniform(&mut b);
+                random_matrix_uniform(&mut c);
+                // col and row major c
+                for (c_rs, c_cs) in [(1, m), (n, 1)] {
+                    for beta in [0.0, 1.17] {
+                        let alpha = 1.23;
+                        let mut c_out = c.clone();
+                        let mut c_ref = c.iter().map(|x| x.to_f32()).collect::<Vec<_>>();
+                        unsafe {
+                            pire_gemm_bf16bf16bf16(
+                                m,
+                                n,
+                                k,
+                                alpha,
+                                a.as_ptr(),
+                                1,
+                                m,
+                                b.as_ptr(),
+                                1,
+                                k,
+                                beta,
+                                c_out.as_mut_ptr(),
+                                c_rs,
+                                c_cs,
+                            );
+                            pire_gemm_bf16bf16f32(
+                                m,
+                                n,
+                                k,
+                                alpha,
+                                a.as_ptr(),
+                                1,
+                                m,
+                                b.as_ptr(),
+                                1,
+                                k,
+                                beta,
+                                c_ref.as_mut_ptr(),
+                                c_rs,
+                                c_cs,
+                            );
+                        }
+                        for (x, y) in c_out.iter().zip(c_ref.iter()) {
+                            let diff = (x.to_f32() - y).abs();
+                            assert!(diff <= 1e-2 * (1.0 + y.abs()), "m: {}, n: {}, {} vs {}", m, n, x, y);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use core::arch::asm;
+use super::{MR, NR};
+use crate::{TA, TB, TC};
+
+// tiles: tmm0-3 c, tmm4-5 b, tmm6-7 a
+// b is the row operand of tdpbf16ps, so the tiles hold c transposed, nr rows of mr f32
+
+macro_rules! tile_zero {
+    (1, 1) => { "tilezero %tmm0\n" };
+    (2, 1) => { "tilezero %tmm0\n tilezero %tmm1\n" };
+    (1, 2) => { "tilezero %tmm0\n tilezero %tmm2\n" };
+    (2, 2) => { "tilezero %tmm0\n tilezero %tmm1\n tilezero %tmm2\n tilezero %tmm3\n" };
+}
+
+macro_rules! load_a {
+    (1) => { "tileloadd ({ap},{lda},1), %tmm6\n" };
+    (2) => { "tileloadd ({ap},{lda},1), %tmm6\n tileloadd 64({ap},{lda},1), %tmm7\n" };
+}
+
+macro_rules! load_b {
+    (1) => { "tileloadd ({bp0},{ldb},1), %tmm4\n" };
+    (2) => { "tileloadd ({bp0},{ldb},1), %tmm4\n tileloadd ({bp1},{ldb},1), %tmm5\n" };
+}
+
+macro_rules! dp {
+    (1, 1) => { "tdpbf16ps %tmm6, %tmm4, %tmm0\n" };
+    (2, 1) => { "tdpbf16ps %tmm6, %tmm4, %tmm0\n tdpbf16ps %tmm7, %tmm4, %tmm1\n" };
+    (1, 2) => { "tdpbf16ps %tmm6, %tmm4, %tmm0\n tdpbf16ps %tmm6, %tmm5, %tmm2\n" };
+    (2, 2) => {
+        concat!(
+            "tdpbf16ps %tmm6, %tmm4, %tmm0\n tdpbf16ps %tmm7, %tmm4, %tmm1\n",
+            "tdpbf16ps %tmm6, %tmm5, %tmm2\n tdpbf16ps %tmm7, %tmm5, %tmm3\n",
+        )
+    };
+}
+
+// c tile (j, i) goes to c_buf + (16*j*MR + 16*i) * 4 bytes, MR = 32
+macro_rules! store_c {
+    (1, 1) => { "tilestored %tmm0, ({cb},{ldc},1)\n" };
+    (2, 1) => { "tilestored %tmm0, ({cb},{ldc},1)\n tilestored %tmm1, 64({cb},{ldc},1)\n" };
+    (1, 2) => { "tilestored %tmm0, ({cb},{ldc},1)\n tilestored %tmm2, 2048({cb},{ldc},1)\n" };
+    (2, 2) => {
+        concat!(
+            "tilestored %tmm0, ({cb},{ldc},1)\n tilestored %tmm1, 64({cb},{ldc},1)\n",
+            "tilestored %tmm2, 2048({cb},{ldc},1)\n tilestored %tmm3, 2112({cb},{ldc},1)\n",
+        )
+    };
+}
+
+macro_rules! def_ukernel_amx {
+    ($mt:tt, $nt:tt, $name:ident) => {
+        // k_eff is a multiple of 32, a is packed with lda bytes per 2 k, b with 2 * k_eff bytes per column
+        pub(crate) unsafe fn $name(ap: *const TA, bp: *const TB, c_buf: *mut TC, k_eff: usize, lda: usize) {
+            let ldb = k_eff * 2;
+            let bp1 = bp.add(16 * k_eff);
+            asm!(
+                tile_zero!($mt, $nt),
+                "2:",
+                load_b!($nt),
+                load_a!($mt),
+                dp!($mt, $nt),
+                "add $64, {bp0}",
+                "add $64, {bp1}",
+                "add {a_step}, {ap}",
+                "dec {kt}",
+                "jnz 2b",
+                store_c!($mt, $nt),
+                ap = inout(reg) ap => _,
+                bp0 = inout(reg) bp => _,
+                bp1 = inout(reg) bp1 => _,
+                lda = in(reg) lda,
+                ldb = in(reg) ldb,
+                a_step = in(reg) lda * 16,
+                kt = inout(reg) k_eff / 32 => _,
+                cb = in(reg) c_buf,
+                ldc = in(reg) MR * 4,
+                options(att_syntax, nostack)
+            );
+        }
+    };
+}
+
+def_ukernel_amx!(1, 1, ukernel_1x1);
+def_ukernel_amx!(2, 1, ukernel_2x1);
+def_ukernel_amx!(1, 2, ukernel_1x2);
+def_ukernel_amx!(2, 2, ukernel_2x2);
+
+const _: () = assert!(MR == 32 && NR == 32);
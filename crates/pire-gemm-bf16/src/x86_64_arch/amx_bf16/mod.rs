@@ -0,0 +1,194 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use core::arch::x86_64::*;
+use std::ptr::{copy_nonoverlapping, write_bytes};
+
+use pire_base::amx::{tile_release, TileConfig};
+
+use super::pack_amx::{packa_panel, round_k};
+use crate::{UnaryFnC, TA, TB, TC};
+
+pub(crate) const MR: usize = 32;
+pub(crate) const NR: usize = 32;
+
+#[repr(C, align(64))]
+struct CBuf([TC; MR * NR]);
+
+// the k loop only depends on the number of b rows (columns of c) of the tile,
+// tile config is reloaded only when it changes, i.e. at the n edge
+struct Tiles {
+    nr_cfg: usize,
+}
+
+impl Tiles {
+    fn new() -> Self {
+        Self { nr_cfg: 0 }
+    }
+
+    unsafe fn configure(&mut self, nr: usize) {
+        if nr == self.nr_cfg {
+            return;
+        }
+        let r0 = nr.min(16);
+        let r1 = if nr > 16 { nr - 16 } else { 16 };
+        let mut cfg = TileConfig::new();
+        cfg.set_tile(0, r0, 64);
+        cfg.set_tile(1, r0, 64);
+        cfg.set_tile(2, r1, 64);
+        cfg.set_tile(3, r1, 64);
+        cfg.set_tile(4, r0, 64);
+        cfg.set_tile(5, r1, 64);
+        cfg.set_tile(6, 16, 64);
+        cfg.set_tile(7, 16, 64);
+        cfg.load();
+        self.nr_cfg = nr;
+    }
+}
+
+// c_buf holds the tile column major with leading dim MR, lanes past m are never stored
+#[target_feature(enable = "avx,avx2,fma")]
+unsafe fn store_tile<F: UnaryFnC>(
+    c_buf: *mut TC,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    f: F,
+) {
+    let alpha_v = _mm256_set1_ps(*alpha);
+    let beta_v = _mm256_set1_ps(*beta);
+    let mut c_col = [0.0; MR];
+    for j in 0..n {
+        let acc = c_buf.add(j * MR);
+        let c_cur = c.add(j * c_cs);
+        if *beta != 0.0 {
+            if c_rs == 1 {
+                copy_nonoverlapping(c_cur, c_col.as_mut_ptr(), m);
+            } else {
+                for i in 0..m {
+                    c_col[i] = *c_cur.add(i * c_rs);
+                }
+            }
+        }
+        for v in 0..(m + 7) / 8 {
+            let acc_v = acc.add(v * 8);
+            let mut x = _mm256_loadu_ps(acc_v);
+            if *alpha != 1.0 {
+                x = _mm256_mul_ps(x, alpha_v);
+            }
+            if *beta != 0.0 {
+                x = _mm256_fmadd_ps(_mm256_loadu_ps(c_col.as_ptr().add(v * 8)), beta_v, x);
+            }
+            _mm256_storeu_ps(acc_v, x);
+        }
+        f.call_tile(acc, m, 0, j);
+        if c_rs == 1 {
+            copy_nonoverlapping(acc, c_cur, m);
+        } else {
+            for i in 0..m {
+                *c_cur.add(i * c_rs) = *acc.add(i);
+            }
+        }
+    }
+}
+
+// one packed panel of a (m <= MR) against all of the packed b
+unsafe fn kernel_panel<F: UnaryFnC>(
+    tiles: &mut Tiles,
+    m: usize,
+    n: usize,
+    k_eff: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    f: F,
+) {
+    let mut c_buf = CBuf([0.0; MR * NR]);
+    let c_buf = c_buf.0.as_mut_ptr();
+    let m_tiles = (m + 15) / 16;
+    let lda = m_tiles * 16 * 4;
+    let mut n_i = 0;
+    while n_i < n {
+        let nr = NR.min(n - n_i);
+        let bp_cur = bp.add(n_i * k_eff);
+        if k_eff == 0 {
+            write_bytes(c_buf, 0, MR * NR);
+        } else {
+            tiles.configure(nr);
+            match (m_tiles, nr > 16) {
+                (1, false) => ukernel_1x1(ap, bp_cur, c_buf, k_eff, lda),
+                (2, false) => ukernel_2x1(ap, bp_cur, c_buf, k_eff, lda),
+                (1, true) => ukernel_1x2(ap, bp_cur, c_buf, k_eff, lda),
+                _ => ukernel_2x2(ap, bp_cur, c_buf, k_eff, lda),
+            }
+        }
+        store_tile(c_buf, m, nr, alpha, beta, c.add(n_i * c_cs), c_rs, c_cs, f.at(0, n_i));
+        n_i += NR;
+    }
+}
+
+pub unsafe fn kernel<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    f: F,
+) {
+    let k_eff = round_k(k);
+    let mut tiles = Tiles::new();
+    let mut m_i = 0;
+    while m_i < m {
+        let mr = MR.min(m - m_i);
+        let ap_cur = ap.add(m_i * k_eff);
+        let c_cur = c.add(m_i * c_rs);
+        kernel_panel(&mut tiles, mr, n, k_eff, alpha, beta, c_cur, c_rs, c_cs, ap_cur, bp, f.at(m_i, 0));
+        m_i += MR;
+    }
+    tile_release();
+}
+
+// strided a, packed b, a is packed one panel at a time into ap
+pub unsafe fn kernel_sb<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    bp: *const TB,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *mut TA,
+    f: F,
+) {
+    let k_eff = round_k(k);
+    let mut tiles = Tiles::new();
+    let mut m_i = 0;
+    while m_i < m {
+        let mr = MR.min(m - m_i);
+        packa_panel(mr, k, a.add(m_i * a_rs), a_rs, a_cs, ap, MR);
+        let c_cur = c.add(m_i * c_rs);
+        kernel_panel(&mut tiles, mr, n, k_eff, alpha, beta, c_cur, c_rs, c_cs, ap, bp, f.at(m_i, 0));
+        m_i += MR;
+    }
+    tile_release();
+}
@@ -0,0 +1,23 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+const VS: usize = 16;
+const VS_MAX: usize = VS;
+
+const ZERO: f32 = 0.0;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(TA, TB, TC, f32, false, T, 2, 3, 8, 64, 8);
+
+use super::pack_avx::packa_panel_48;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(TA, TA, TB, TC, f32, false, T, packa_panel_48, 2, 3, 8, 64, 8);
@@ -0,0 +1,633 @@
+pub(crate) mod amx_bf16;
+pub(crate) mod avx512_bf16;
+pub(crate) mod avx512f;
+pub(crate) mod avx_fma;
+pub(crate) mod pack_amx;
+pub(crate) mod pack_avx;
+pub(crate) mod pack_avx_f32;
+
+const AVX512F_VS: usize = 16;
+const AVX_VS: usize = 8;
+
+const AMX_BF16_MR: usize = amx_bf16::MR;
+const AVX512_BF16_MR: usize = 48;
+const AVX512F_MR: usize = 48;
+const AVXFMA_MR: usize = 24;
+
+const AMX_BF16_NR: usize = amx_bf16::NR;
+const AVX512_BF16_NR: usize = 8;
+const AVX512F_NR: usize = 8;
+const AVXFMA_NR: usize = 4;
+
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, has_bf16_compute, is_mixed, run_par, run_small_m, run_small_n, split_c_range,
+    split_range, Array, ArrayMut, CpuFeatures, GemmPool, HWModel, PArray, PArrayMixed, PirePar, PireThreadConfig,
+    PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+};
+
+use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd_f32() -> (usize, usize, usize) {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    let (mr, nr) = if features.avx512f { (AVX512F_MR, AVX512F_NR) } else { (AVXFMA_MR, AVXFMA_NR) };
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        HWModel::Skylake => (4800, 384, 1024),
+        HWModel::Haswell => (4800, 320, 192),
+        _ => get_cache_params(),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+// bf16 kernel used for the given features, amx only if the os granted it (amx_bf16 is off otherwise)
+pub(crate) fn select_kernel_bf16(features: &CpuFeatures) -> (usize, usize, RegDimBf16) {
+    if features.amx_bf16 {
+        (AMX_BF16_MR, AMX_BF16_NR, RegDimBf16::Amx32x32)
+    } else {
+        (AVX512_BF16_MR, AVX512_BF16_NR, RegDimBf16::Reg48x8)
+    }
+}
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd_bf16() -> (usize, usize, usize) {
+    let amx = (*RUNTIME_HW_CONFIG).cpu_ft.amx_bf16;
+    let (mr, nr, _) = select_kernel_bf16(&(*RUNTIME_HW_CONFIG).cpu_ft());
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        HWModel::Skylake => (4800, 192, 1440),
+        HWModel::Haswell => (4800, 384, 384),
+        _ => get_cache_params(),
+    };
+    // kc is a whole number of amx k steps so that the packed size of k is the sum over kc blocks
+    let kc = if amx { (kc / 32).max(1) * 32 } else { kc };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    if has_bf16_compute() {
+        get_mcnckc_simd_bf16()
+    } else {
+        get_mcnckc_simd_f32()
+    }
+}
+
+pub(crate) unsafe fn packa_fn_simd_f32(x: *const TA, y: *mut f32, m: usize, k: usize, rs: usize, cs: usize) {
+    if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
+        pack_avx_f32::packa_panel_48(m, k, x, rs, cs, y, AVX512F_VS);
+    } else {
+        pack_avx_f32::packa_panel_24(m, k, x, rs, cs, y, AVX_VS);
+    }
+}
+pub(crate) unsafe fn packb_fn_simd_f32(x: *const TB, y: *mut f32, n: usize, k: usize, rs: usize, cs: usize) {
+    if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
+        pack_avx_f32::packb_panel_8(n, k, x, cs, rs, y);
+    } else {
+        pack_avx_f32::packb_panel_4(n, k, x, cs, rs, y);
+    }
+}
+
+pub(crate) unsafe fn packa_fn_simd_bf16(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    if (*RUNTIME_HW_CONFIG).cpu_ft.amx_bf16 {
+        pack_amx::packa_panel(m, k, x, rs, cs, y, AMX_BF16_MR);
+    } else {
+        pack_avx::packa_panel_48(m, k, x, rs, cs, y, AVX512F_VS);
+    }
+}
+pub(crate) unsafe fn packb_fn_simd_bf16(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    if (*RUNTIME_HW_CONFIG).cpu_ft.amx_bf16 {
+        pack_amx::packb(n, k, x, rs, cs, y);
+    } else {
+        pack_avx::packb_panel_8(n, k, x, cs, rs, y);
+    }
+}
+
+// without native bf16 compute, packed api stores bf16 in the layout of f32 panels
+// and it is widened to f32 when the packed matrix is used
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    if has_bf16_compute() {
+        packa_fn_simd_bf16(x, y, m, k, rs, cs);
+    } else if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
+        pack_avx_f32::packa_panel_48_same(m, k, x, rs, cs, y, AVX512F_VS);
+    } else {
+        pack_avx_f32::packa_panel_24_same(m, k, x, rs, cs, y, AVX_VS);
+    }
+}
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    if has_bf16_compute() {
+        packb_fn_simd_bf16(x, y, n, k, rs, cs);
+    } else if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
+        pack_avx_f32::packb_panel_8_same(n, k, x, cs, rs, y);
+    } else {
+        pack_avx_f32::packb_panel_4_same(n, k, x, cs, rs, y);
+    }
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    let vs = if has_bf16_compute() || (*RUNTIME_HW_CONFIG).cpu_ft.avx512f { AVX512F_VS } else { AVX_VS };
+    (m + vs - 1) / vs * vs
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    if has_bf16_compute() && (*RUNTIME_HW_CONFIG).cpu_ft.amx_bf16 {
+        pack_amx::round_k(k)
+    } else if has_bf16_compute() {
+        (k + 1) / 2 * 2
+    } else {
+        k
+    }
+}
+
+pub(crate) enum RegDim {
+    Reg48x8,
+    Reg24x4,
+}
+
+pub(crate) enum RegDimBf16 {
+    Amx32x32,
+    Reg48x8,
+}
+
+pub(crate) struct KernelDispatcherF32<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDim,
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcherF32<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd_f32();
+        let features = hw_config.cpu_ft();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, reg_dim, vs) = if features.avx512f {
+            (AVX512F_MR, AVX512F_NR, RegDim::Reg48x8, AVX512F_VS)
+        } else {
+            (AVXFMA_MR, AVXFMA_NR, RegDim::Reg24x4, AVX_VS)
+        };
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        false
+    }
+
+    #[target_feature(enable = "avx,avx2")]
+    unsafe fn cvt_mixed(&self, x: *const TA, y: *mut f32, m: usize) {
+        use core::arch::x86_64::*;
+        let m_iter = m / 8;
+        let m_rem = m % 8;
+        for i in 0..m_iter {
+            let x_ptr = x.add(i * 8);
+            let y_ptr = y.add(i * 8);
+            let v_u32 = _mm256_cvtepu16_epi32(_mm_loadu_si128(x_ptr as *const __m128i));
+            _mm256_storeu_ps(y_ptr, _mm256_castsi256_ps(_mm256_slli_epi32(v_u32, 16)));
+        }
+        for i in 0..m_rem {
+            let x_ptr = x.add(m_iter * 8 + i);
+            let y_ptr = y.add(m_iter * 8 + i);
+            *y_ptr = (*x_ptr).to_f32();
+        }
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        k
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDimBf16,
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd_bf16();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, reg_dim) = select_kernel_bf16(&hw_config.cpu_ft());
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs: AVX512F_VS,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        match self.reg_dim {
+            RegDimBf16::Amx32x32 => pack_amx::round_k(k),
+            RegDimBf16::Reg48x8 => (k + 1) / 2 * 2,
+        }
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcherF32<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    bp: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+#[allow(unused)]
+unsafe fn kernel_n<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut f32,
+    b: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    avx_fma::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func);
+}
+
+def_pire_gemm!(
+    KernelDispatcherF32,
+    TA,
+    f32,
+    TB,
+    f32,
+    TC,
+    f32,
+    f32,
+    PackArrTypeAM,
+    PackArrTypeBM,
+    1_f32,
+    pire_gemm_f32,
+    gemm_mt,
+    gemm_goto_serial,
+    kernel,
+    gemm_small_m_serial,
+    kernel_m,
+    gemm_small_n_serial,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0,
+    packb0,
+    packa_fn_simd_f32,
+    packb_fn_simd_f32,
+    false,
+    false,
+    into_pack_array2,
+    T,
+);
+
+unsafe fn kernel_native<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDimBf16::Amx32x32 => amx_bf16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDimBf16::Reg48x8 => avx512_bf16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDimBf16::Amx32x32 => amx_bf16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDimBf16::Reg48x8 => avx512_bf16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m_native<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    panic!("Not implemented");
+}
+
+unsafe fn kernel_n_native<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    b: *const TB,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDimBf16::Amx32x32 => amx_bf16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDimBf16::Reg48x8 => avx512_bf16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDimBf16::Amx32x32 => {
+                amx_bf16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
+            }
+            RegDimBf16::Reg48x8 => {
+                avx512_bf16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
+            }
+        }
+    }
+}
+
+unsafe fn pire_gemv_native<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    avx_fma::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func);
+}
+
+def_pire_gemm!(
+    KernelDispatcher,
+    TA,
+    TA,
+    TB,
+    TB,
+    TC,
+    f32,
+    f32,
+    PackArrTypeA,
+    PackArrTypeB,
+    1_f32,
+    pire_gemm,
+    gemm_mt_native,
+    gemm_goto_serial_native,
+    kernel_native,
+    gemm_small_m_serial_native,
+    kernel_m_native,
+    gemm_small_n_serial_native,
+    kernel_n_native,
+    pire_gemv_native,
+    pire_gemv_native,
+    packa0bf16,
+    packb0bf16,
+    packa_fn_simd_bf16,
+    packb_fn_simd_bf16,
+    false,
+    true,
+    into_pack_array,
+    F,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every feature of the isa set to x, amx as given
+    fn features(x: bool, amx: bool) -> CpuFeatures {
+        CpuFeatures {
+            sse: x,
+            sse2: x,
+            sse3: x,
+            ssse3: x,
+            avx: x,
+            avx2: x,
+            avx512f: x,
+            avx512f16: x,
+            avx512bf16: x,
+            avx512bw: x,
+            avx512_vnni: x,
+            avx_vnni: x,
+            amx_tile: amx,
+            amx_int8: amx,
+            amx_bf16: amx,
+            fma: x,
+            fma4: x,
+            f16c: x,
+        }
+    }
+
+    #[test]
+    fn test_select_kernel_amx() {
+        for x in [false, true] {
+            let (mr, nr, reg_dim) = select_kernel_bf16(&features(x, false));
+            assert!(matches!(reg_dim, RegDimBf16::Reg48x8));
+            assert_eq!((mr, nr), (AVX512_BF16_MR, AVX512_BF16_NR));
+        }
+        let (mr, nr, reg_dim) = select_kernel_bf16(&features(true, true));
+        assert!(matches!(reg_dim, RegDimBf16::Amx32x32));
+        assert_eq!((mr, nr), (AMX_BF16_MR, AMX_BF16_NR));
+    }
+
+    #[test]
+    fn test_runtime_kernel_amx() {
+        // amx is only reported (and so dispatched to) together with the os permission for the tile state
+        let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+        assert!(!features.amx_bf16 || features.amx_tile);
+        if has_bf16_compute() {
+            let hw_config = KernelDispatcher::new(IdentityFn {});
+            assert_eq!(matches!(hw_config.reg_dim, RegDimBf16::Amx32x32), features.amx_bf16);
+        }
+        assert_eq!(round_k_simd(1) == pack_amx::round_k(1), has_bf16_compute() && features.amx_bf16);
+    }
+}
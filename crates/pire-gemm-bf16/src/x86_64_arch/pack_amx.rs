@@ -0,0 +1,150 @@
+use crate::{TA, TB};
+
+use std::ptr::{copy_nonoverlapping, write_bytes};
+
+// amx kernels consume k in steps of one tile row (64 bytes, 32 bf16), the padding is zero
+pub(crate) fn round_k(k: usize) -> usize {
+    (k + 31) / 32 * 32
+}
+
+// panels of mr rows with 2 consecutive k of a row next to each other (layout of the vnni operand of tdpbf16ps),
+// rows of the last panel are rounded up to 16, padding rows and k are zero
+pub(crate) unsafe fn packa_panel(m: usize, k: usize, a: *const TA, a_rs: usize, a_cs: usize, ap: *mut TA, mr: usize) {
+    let k_eff = round_k(k);
+    let mut m_idx = 0;
+    while m_idx < m {
+        let mr_cur = mr.min(m - m_idx);
+        let mr_eff = (mr_cur + 15) / 16 * 16;
+        let a_cur = a.add(m_idx * a_rs);
+        let ap_cur = ap.add(m_idx * k_eff);
+        // zero bits are bf16 zero
+        write_bytes(ap_cur, 0, mr_eff * k_eff);
+        if a_cs == 1 {
+            for i in 0..mr_cur {
+                for p in 0..k {
+                    *ap_cur.add(p / 2 * mr_eff * 2 + i * 2 + p % 2) = *a_cur.add(i * a_rs + p);
+                }
+            }
+        } else {
+            for p in 0..k {
+                let ap_p = ap_cur.add(p / 2 * mr_eff * 2 + p % 2);
+                for i in 0..mr_cur {
+                    *ap_p.add(i * 2) = *a_cur.add(i * a_rs + p * a_cs);
+                }
+            }
+        }
+        m_idx += mr;
+    }
+}
+
+// column j of b is stored in k_eff contiguous elements (row of the first operand of tdpbf16ps)
+pub(crate) unsafe fn packb(n: usize, k: usize, b: *const TB, b_rs: usize, b_cs: usize, bp: *mut TB) {
+    let k_eff = round_k(k);
+    if b_rs == 1 {
+        for j in 0..n {
+            copy_nonoverlapping(b.add(j * b_cs), bp.add(j * k_eff), k);
+        }
+    } else {
+        for p in 0..k {
+            for j in 0..n {
+                *bp.add(j * k_eff + p) = *b.add(p * b_rs + j * b_cs);
+            }
+        }
+    }
+    for j in 0..n {
+        write_bytes(bp.add(j * k_eff + k), 0, k_eff - k);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::x86_64_arch::amx_bf16::{MR, NR};
+    use half::bf16;
+
+    // tileloadd / tdpbf16ps / tilestored in software with the addressing of the amx_bf16 ukernels (in elements),
+    // c_buf gets the tile column major with leading dim MR as the kernel stores it
+    unsafe fn ukernel_emu(ap: *const TA, bp: *const TB, m: usize, nr: usize, k_eff: usize, c_buf: &mut [f32]) {
+        let m_tiles = (m + 15) / 16;
+        let lda = m_tiles * 16 * 2;
+        let ldb = k_eff;
+        c_buf.fill(0.0);
+        for s in 0..k_eff / 32 {
+            for it in 0..m_tiles {
+                // 16 rows of 2 k each, a row holds 2 consecutive k of 16 rows of a
+                let a_tile = ap.add(s * 16 * lda + it * 32);
+                for jt in 0..(nr + 15) / 16 {
+                    let rows = if jt == 0 { nr.min(16) } else { nr - 16 };
+                    // a row holds 32 consecutive k of a column of b
+                    let b_tile = bp.add(jt * 16 * ldb + s * 32);
+                    for r in 0..rows {
+                        for i in 0..16 {
+                            let mut acc = 0.0;
+                            for kk in 0..16 {
+                                for t in 0..2 {
+                                    let b_v = (*b_tile.add(r * ldb + kk * 2 + t)).to_f32();
+                                    let a_v = (*a_tile.add(kk * lda + i * 2 + t)).to_f32();
+                                    acc += b_v * a_v;
+                                }
+                            }
+                            c_buf[(jt * 16 + r) * MR + it * 16 + i] += acc;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_amx_tiles() {
+        // small integers, so that bf16 values and the f32 sums are exact whatever the order
+        let value = |x: usize| bf16::from_f32((x * 7 % 9) as f32 - 4.0);
+        for (m, n, k) in [(1, 1, 1), (13, 17, 32), (32, 32, 33), (45, 40, 100), (70, 33, 65)] {
+            let a = (0..m * k).map(value).collect::<Vec<_>>();
+            let b = (0..k * n).map(|x| value(x * 3 + 1)).collect::<Vec<_>>();
+            let k_eff = round_k(k);
+            for (a_rs, a_cs) in [(k, 1), (1, m)] {
+                for (b_rs, b_cs) in [(1, k), (n, 1)] {
+                    // padding of the packed buffers is garbage before packing, it must be zero after
+                    let mut ap = vec![bf16::NAN; (m + 15) / 16 * 16 * k_eff];
+                    let mut bp = vec![bf16::NAN; n * k_eff];
+                    let mut c_buf = vec![0f32; MR * NR];
+                    unsafe {
+                        packa_panel(m, k, a.as_ptr(), a_rs, a_cs, ap.as_mut_ptr(), MR);
+                        packb(n, k, b.as_ptr(), b_rs, b_cs, bp.as_mut_ptr());
+                    }
+                    assert!((0..n).all(|j| bp[j * k_eff + k..(j + 1) * k_eff].iter().all(|&x| x == bf16::ZERO)));
+                    for m_i in (0..m).step_by(MR) {
+                        let mr = MR.min(m - m_i);
+                        for n_i in (0..n).step_by(NR) {
+                            let nr = NR.min(n - n_i);
+                            unsafe {
+                                let ap_cur = ap.as_ptr().add(m_i * k_eff);
+                                let bp_cur = bp.as_ptr().add(n_i * k_eff);
+                                ukernel_emu(ap_cur, bp_cur, mr, nr, k_eff, &mut c_buf);
+                            }
+                            for j in 0..nr {
+                                for i in 0..mr {
+                                    let (i_g, j_g) = (m_i + i, n_i + j);
+                                    let c_ref = (0..k)
+                                        .map(|p| a[i_g * a_rs + p * a_cs].to_f32() * b[p * b_rs + j_g * b_cs].to_f32())
+                                        .sum::<f32>();
+                                    assert_eq!(
+                                        c_buf[i + j * MR],
+                                        c_ref,
+                                        "m: {}, n: {}, k: {}, i: {}, j: {}",
+                                        m,
+                                        n,
+                                        k,
+                                        i_g,
+                                        j_g
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
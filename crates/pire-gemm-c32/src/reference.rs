@@ -1,16 +1,22 @@
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
-    RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
-use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+use crate::{conj_if, GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
 
 const MR: usize = 24;
 const NR: usize = 4;
 
-pub(crate) unsafe fn packa_fn_ref(a: *const TA, ap: *mut TA, m: usize, k: usize, a_rs: usize, a_cs: usize) {
+pub(crate) unsafe fn packa_fn_ref<const CONJ: bool>(
+    a: *const TA,
+    ap: *mut TA,
+    m: usize,
+    k: usize,
+    a_rs: usize,
+    a_cs: usize,
+) {
     let mut a_cur = a;
     let mut ap_cur = ap;
     let mr = MR;
@@ -19,7 +25,7 @@ pub(crate) unsafe fn packa_fn_ref(a: *const TA, ap: *mut TA, m: usize, k: usize,
         let mut j = 0;
         while j < k {
             for ix in 0..mr {
-                *ap_cur.add(ix + j * mr) = *a_cur.add(ix * a_rs + j * a_cs);
+                *ap_cur.add(ix + j * mr) = conj_if::<CONJ>(*a_cur.add(ix * a_rs + j * a_cs));
             }
             j += 1;
         }
@@ -32,13 +38,20 @@ pub(crate) unsafe fn packa_fn_ref(a: *const TA, ap: *mut TA, m: usize, k: usize,
     let mr_left = m % mr;
     while j < k {
         for ix in 0..mr_left {
-            *ap_cur.add(ix + j * mr_left) = *a_cur.add(ix * a_rs + j * a_cs);
+            *ap_cur.add(ix + j * mr_left) = conj_if::<CONJ>(*a_cur.add(ix * a_rs + j * a_cs));
         }
         j += 1;
     }
 }
 
-pub(crate) unsafe fn packb_fn_ref(b: *const TB, bp: *mut TB, n: usize, k: usize, b_rs: usize, b_cs: usize) {
+pub(crate) unsafe fn packb_fn_ref<const CONJ: bool>(
+    b: *const TB,
+    bp: *mut TB,
+    n: usize,
+    k: usize,
+    b_rs: usize,
+    b_cs: usize,
+) {
     let mut b_cur = b;
     let mut bp_cur = bp;
     let nr = NR;
@@ -47,7 +60,7 @@ pub(crate) unsafe fn packb_fn_ref(b: *const TB, bp: *mut TB, n: usize, k: usize,
         let mut j = 0;
         while j < k {
             for ix in 0..nr {
-                *bp_cur.add(ix + j * nr) = *b_cur.add(ix * b_cs + j * b_rs);
+                *bp_cur.add(ix + j * nr) = conj_if::<CONJ>(*b_cur.add(ix * b_cs + j * b_rs));
             }
             j += 1;
         }
@@ -60,7 +73,7 @@ pub(crate) unsafe fn packb_fn_ref(b: *const TB, bp: *mut TB, n: usize, k: usize,
     let n_left = n % nr;
     while j < k {
         for ix in 0..n_left {
-            *bp_cur.add(ix + j * n_left) = *b_cur.add(ix * b_cs + j * b_rs);
+            *bp_cur.add(ix + j * n_left) = conj_if::<CONJ>(*b_cur.add(ix * b_cs + j * b_rs));
         }
         j += 1;
     }
@@ -85,6 +98,8 @@ pub(crate) struct RefGemm<T: UnaryFnC = IdentityFn> {
     is_l2_shared: bool,
     is_l3_shared: bool,
     func: T,
+    pub(crate) conj_a: bool,
+    pub(crate) conj_b: bool,
 }
 
 impl<F: UnaryFnC> RefGemm<F> {
@@ -103,9 +118,17 @@ impl<F: UnaryFnC> RefGemm<F> {
             is_l2_shared,
             is_l3_shared,
             func: f,
+            conj_a: false,
+            conj_b: false,
         }
     }
 
+    pub(crate) fn with_conj(mut self, conj_a: bool, conj_b: bool) -> Self {
+        self.conj_a = conj_a;
+        self.conj_b = conj_b;
+        self
+    }
+
     pub(crate) fn is_compute_native(&self) -> bool {
         true
     }
@@ -152,6 +175,8 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     let mut i = 0;
@@ -185,7 +210,7 @@ unsafe fn kernel<F: UnaryFnC>(
                     let c_cur = c.add(i * c_rs + j * c_cs + ii * c_rs + jj * c_cs);
                     *c_cur = *c_cur * *beta + acc[ii * nr_eff + jj] * *alpha;
                     if kc_last {
-                        hw_cfg.func.call(c_cur, 1);
+                        hw_cfg.func.at(i0, j0).call_tile(c_cur, 1, i + ii, j + jj);
                     }
                     acc[ii * nr_eff + jj] = TC::ZERO;
                     jj += 1;
@@ -214,6 +239,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -234,6 +261,8 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -247,6 +276,35 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     x: Array<TB>,
     beta: *const TC,
     y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_a, hw_cfg.conj_b);
+}
+
+// m == 1 case, a and x are the transposed b and a of the gemm
+unsafe fn pire_gemv2<F: UnaryFnC>(
+    hw_cfg: &RefGemm<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_b, hw_cfg.conj_a);
+}
+
+unsafe fn gemv_conj<F: UnaryFnC>(
+    hw_cfg: &RefGemm<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+    conj_a: bool,
+    conj_x: bool,
 ) {
     let mut i = 0;
     let a_rs = a.rs();
@@ -261,9 +319,15 @@ unsafe fn pire_gemv<F: UnaryFnC>(
         let mut j = 0;
         let mut acc = TC::ZERO;
         while j < n {
-            acc += *a_ptr.add(i * a_rs + j * a_cs) * *x_ptr.add(j * inc_x);
+            let x_cur = *x_ptr.add(j * inc_x);
+            let x_cur = if conj_a != conj_x { x_cur.conj() } else { x_cur };
+            acc += *a_ptr.add(i * a_rs + j * a_cs) * x_cur;
             j += 1;
         }
+        // conj(a) * x = conj(a * conj(x))
+        if conj_a {
+            acc = acc.conj();
+        }
         *y_ptr.add(i * incy) = *y_ptr.add(i * incy) * *beta + acc * *alpha;
         hw_cfg.func.call(y_ptr.add(i * incy), 1);
         i += 1;
@@ -291,7 +355,7 @@ def_pire_gemm!(
     gemm_small_n_serial,
     kernel_n,
     pire_gemv,
-    pire_gemv,
+    pire_gemv2,
     packa0,
     packb0,
     packa_fn_ref,
@@ -300,4 +364,5 @@ def_pire_gemm!(
     false,
     into_pack_array,
     F,
+    T,
 );
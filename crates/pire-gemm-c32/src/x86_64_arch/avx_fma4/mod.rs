@@ -0,0 +1,61 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+const VS: usize = 4;
+const VS_MAX: usize = VS;
+
+const ZERO: TC = TC::ZERO;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+#[target_feature(enable = "avx")]
+pub unsafe fn axpy<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const TC,
+    y: *mut TC,
+    incy: usize,
+    conj_a: bool,
+    conj_x: bool,
+    f: F,
+) {
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = TC::ZERO;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = *x.add(j * incx);
+            let x_cur = if conj_a != conj_x { x_cur.conj() } else { x_cur };
+            acc += *a_cur * x_cur;
+        }
+        // conj(a) * x = conj(a * conj(x))
+        if conj_a {
+            acc = acc.conj();
+        }
+        *y_cur = *beta * *y_cur + *alpha * acc;
+        f.call(y_cur, 1);
+    }
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(TA, TB, TC, TC, false, T, 1, 3, 2, 96, 4);
+
+use pire_base::def_kernel_bs;
+def_kernel_bs!(TA, TB, TC, TC, 3, 2);
+
+use super::pack_avx::packa_panel_12;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(TA, TA, TB, TC, TC, false, F, packa_panel_12::<false>, 1, 3, 2, 0, 0);
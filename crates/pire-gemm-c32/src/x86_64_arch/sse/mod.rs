@@ -29,6 +29,8 @@ pub unsafe fn axpy<F: UnaryFnC>(
     beta: *const TC,
     y: *mut TC,
     incy: usize,
+    conj_a: bool,
+    conj_x: bool,
     f: F,
 ) {
     // if a_cs == 1 && incx == 1 {
@@ -49,8 +51,13 @@ pub unsafe fn axpy<F: UnaryFnC>(
         let mut acc = TC::ZERO;
         for j in 0..n {
             let a_cur = a.add(i * a_rs + j * a_cs);
-            let x_cur = x.add(j * incx);
-            acc += *a_cur * *x_cur;
+            let x_cur = *x.add(j * incx);
+            let x_cur = if conj_a != conj_x { x_cur.conj() } else { x_cur };
+            acc += *a_cur * x_cur;
+        }
+        // conj(a) * x = conj(a * conj(x))
+        if conj_a {
+            acc = acc.conj();
         }
         *y_cur = *beta * *y_cur + *alpha * acc;
         f.call(y_cur, 1);
@@ -66,4 +73,4 @@ def_kernel_bs!(TA, TB, TC, TC, 2, 2);
 use super::pack_sse::packa_panel_4;
 
 use pire_base::def_kernel_sb_v0;
-def_kernel_sb_v0!(TA, TA, TB, TC, TC, true, F, packa_panel_4, 1, 2, 2, 0, 0);
+def_kernel_sb_v0!(TA, TA, TB, TC, TC, true, F, packa_panel_4::<false>, 1, 2, 2, 0, 0);
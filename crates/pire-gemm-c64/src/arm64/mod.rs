@@ -4,9 +4,9 @@ pub(crate) mod pack_sve;
 pub(crate) mod sve;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_mem_pool_size_goto, get_mem_pool_size_small_m,
-    get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range, split_range, Array, ArrayMut,
-    GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -31,22 +31,36 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     (mc / mr * mr, nc / nr * nr, kc)
 }
 
-pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+pub(crate) unsafe fn packa_fn_simd<const CONJ: bool>(
+    x: *const TA,
+    y: *mut TA,
+    m: usize,
+    k: usize,
+    rs: usize,
+    cs: usize,
+) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     if features.sve && features.fcma {
         let vs = unsafe { sve_vs() };
-        pack_sve::packa_panel(m, k, x, rs, cs, y, vs);
+        pack_sve::packa_panel::<CONJ>(m, k, x, rs, cs, y, vs);
     } else {
-        pack_neon::packa_panel_3(m, k, x, rs, cs, y, NEON_VS);
+        pack_neon::packa_panel_3::<CONJ>(m, k, x, rs, cs, y, NEON_VS);
     }
 }
 
-pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+pub(crate) unsafe fn packb_fn_simd<const CONJ: bool>(
+    x: *const TB,
+    y: *mut TB,
+    n: usize,
+    k: usize,
+    rs: usize,
+    cs: usize,
+) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     if features.sve && features.fcma {
-        pack_sve::packb_panel_8(n, k, x, cs, rs, y);
+        pack_sve::packb_panel_8::<CONJ>(n, k, x, cs, rs, y);
     } else {
-        pack_neon::packb_panel_4(n, k, x, cs, rs, y);
+        pack_neon::packb_panel_4::<CONJ>(n, k, x, cs, rs, y);
     }
 }
 
@@ -89,6 +103,8 @@ pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
     is_l2_shared: bool,
     is_l3_shared: bool,
     func: T,
+    pub(crate) conj_a: bool,
+    pub(crate) conj_b: bool,
 }
 
 impl<F: UnaryFnC> KernelDispatcher<F> {
@@ -117,9 +133,17 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
             is_l3_shared,
             // features,
             func: f,
+            conj_a: false,
+            conj_b: false,
         }
     }
 
+    pub(crate) fn with_conj(mut self, conj_a: bool, conj_b: bool) -> Self {
+        self.conj_a = conj_a;
+        self.conj_b = conj_b;
+        self
+    }
+
     pub(crate) fn is_compute_native(&self) -> bool {
         true
     }
@@ -168,12 +192,15 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
@@ -199,6 +226,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -218,12 +247,15 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
@@ -243,14 +275,73 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     x: Array<TB>,
     beta: *const TC,
     y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_a, hw_cfg.conj_b);
+}
+
+// m == 1 case, a and x are the transposed b and a of the gemm
+unsafe fn pire_gemv2<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TC,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_b, hw_cfg.conj_a);
+}
+
+unsafe fn gemv_conj<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TC,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+    conj_a: bool,
+    conj_x: bool,
 ) {
     let x_ptr = x.src();
     let inc_x = x.rs();
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Neon => neon::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
-        RegDim::Sve => sve::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Neon => neon::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
+        RegDim::Sve => sve::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
     }
 }
 
@@ -275,7 +366,7 @@ def_pire_gemm!(
     gemm_small_n_serial,
     kernel_n,
     pire_gemv,
-    pire_gemv,
+    pire_gemv2,
     packa,
     packb,
     packa_fn_simd,
@@ -284,4 +375,5 @@ def_pire_gemm!(
     true,
     into_pack_array,
     F,
+    T,
 );
@@ -28,16 +28,18 @@ pub unsafe fn axpy<F: UnaryFnC>(
     beta: *const TC,
     y: *mut TC,
     incy: usize,
+    conj_a: bool,
+    conj_x: bool,
     f: F,
 ) {
-    if a_cs == 1 && incx == 1 {
+    if !conj_a && !conj_x && a_cs == 1 && incx == 1 {
         axpy_d(m, n, alpha, a, a_rs, x, beta, y, incy);
         for i in 0..m {
             f.call(y.add(i * incy), 1);
         }
         return;
     }
-    if a_rs == 1 && incy == 1 {
+    if !conj_a && !conj_x && a_rs == 1 && incy == 1 {
         axpy_v(m, n, alpha, a, a_cs, x, incx, beta, y);
         f.call(y, m);
         return;
@@ -48,8 +50,13 @@ pub unsafe fn axpy<F: UnaryFnC>(
         let mut acc = TC::ZERO;
         for j in 0..n {
             let a_cur = a.add(i * a_rs + j * a_cs);
-            let x_cur = x.add(j * incx);
-            acc += *a_cur * *x_cur;
+            let x_cur = *x.add(j * incx);
+            let x_cur = if conj_a != conj_x { x_cur.conj() } else { x_cur };
+            acc += *a_cur * x_cur;
+        }
+        // conj(a) * x = conj(a * conj(x))
+        if conj_a {
+            acc = acc.conj();
         }
         *y_cur = *beta * *y_cur + *alpha * acc;
         f.call(y_cur, 1);
@@ -62,4 +69,4 @@ def_kernel_bb_v0!(TC, TC, TC, TC, true, F, 1, 3, 4, 0, 0);
 use super::pack_neon::packa_panel_3;
 
 use pire_base::def_kernel_sb_v0;
-def_kernel_sb_v0!(TA, TB, TC, TA, TC, true, F, packa_panel_3, 1, 3, 4, 0, 0);
+def_kernel_sb_v0!(TA, TB, TC, TA, TC, true, F, packa_panel_3::<false>, 1, 3, 4, 0, 0);
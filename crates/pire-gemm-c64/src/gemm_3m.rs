@@ -0,0 +1,181 @@
+// 3M (Gauss) algorithm, complex gemm computed with three real gemms instead of one complex gemm.
+// With a = ar + i * ai and b = br + i * bi:
+//   t1 = ar * br, t2 = ai * bi, t3 = (ar + ai) * (br + bi)
+//   re(a * b) = t1 - t2, im(a * b) = t3 - t1 - t2
+// This saves 1/4 of the flops, at the cost of a larger rounding error in the imaginary part
+// (bounded by |a| * |b| + |beta * c| rather than |a * b|)
+//
+// The product is computed per mc x nc block of c and kc block of k. For each block, a (scaled by
+// alpha) and b are split into their re, im and re + im planes in one pass, into a buffer of
+// PACK_POOL of the size of the usual packed blocks. The three products go directly into the real
+// and imaginary parts of c, read in place as real matrices with doubled strides. t1 and t2 feed
+// both of them, which is done by passes im += s * re over c in between:
+//   (re, im) = (x, y + x)     with x + i * y = beta * c, or c for kc blocks after the first
+//   re += t1, im += t3        -> (x + t1, y + x + t3)
+//   im -= 2 * re              -> (x + t1, y - x + t3 - 2 * t1)
+//   re -= t2, im += re        -> (x + t1 - t2, y + t3 - t1 - t2)
+
+use core::mem::size_of;
+
+use pire_base::thread_pool::run_tasks;
+use pire_base::{put_pool_buf, split_range, take_pool_buf, PirePar, AB_ALIGN};
+use pire_gemm_f64::pire_dgemm_par;
+
+use crate::{dispatch_get_mcnckc, TA, TB, TC};
+
+type TR = f64;
+
+// runs f(j) for every column j in 0..cols, columns are split over num_threads threads
+fn for_each_col<G: Fn(usize) + Sync>(cols: usize, num_threads: usize, f: G) {
+    let num_tasks = num_threads.min(cols).max(1);
+    run_tasks(num_tasks, |t_id| {
+        let (start, end) = split_range(cols, 1, t_id, num_tasks);
+        for j in start..end {
+            f(j);
+        }
+    });
+}
+
+// applies f to every element of the m x n matrix c, columns are split over num_threads threads
+unsafe fn for_each_c<G: Fn(&mut TC) + Sync>(
+    m: usize,
+    n: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    num_threads: usize,
+    f: G,
+) {
+    // pointers as addresses so that the closure is Sync, columns written by different threads are disjoint
+    let c_addr = c as usize;
+    for_each_col(n, num_threads, |j| {
+        let c = c_addr as *mut TC;
+        for i in 0..m {
+            f(&mut *c.add(i * c_rs + j * c_cs));
+        }
+    });
+}
+
+// re, im and re + im planes of scale * x for a rows x cols complex matrix, each stored column major
+unsafe fn split_planes(
+    x: *const TA,
+    rs: usize,
+    cs: usize,
+    rows: usize,
+    cols: usize,
+    scale: TA,
+    planes: [*mut TR; 3],
+    num_threads: usize,
+) {
+    // pointers as addresses so that the closure is Sync, columns written by different threads are disjoint
+    let x_addr = x as usize;
+    let dst = planes.map(|p| p as usize);
+    for_each_col(cols, num_threads, |j| {
+        let x = x_addr as *const TA;
+        let [xr, xi, xs] = dst.map(|p| (p as *mut TR).add(j * rows));
+        for i in 0..rows {
+            let v = scale * *x.add(i * rs + j * cs);
+            *xr.add(i) = v.re;
+            *xi.add(i) = v.im;
+            *xs.add(i) = v.re + v.im;
+        }
+    });
+}
+
+// c = beta * c + a * b for one block, with the re, im and re + im planes of a (m x k) and b (k x n)
+#[allow(clippy::too_many_arguments)]
+unsafe fn gemm_3m_block(
+    m: usize,
+    n: usize,
+    k: usize,
+    a_planes: [*mut TR; 3],
+    b_planes: [*mut TR; 3],
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    let num_threads = par.num_threads;
+    let [ar, ai, a_sum] = a_planes;
+    let [br, bi, b_sum] = b_planes;
+
+    // real and imaginary parts of c as real matrices
+    let (c_re, c_im) = (c as *mut TR, (c as *mut TR).add(1));
+    let (cr_rs, cr_cs) = (2 * c_rs, 2 * c_cs);
+    let beta_re = if beta == TC::ZERO {
+        0.0
+    } else {
+        for_each_c(m, n, c, c_rs, c_cs, num_threads, |x| {
+            let v = beta * *x;
+            *x = TC::new(v.re, v.im + v.re);
+        });
+        1.0
+    };
+    pire_dgemm_par(m, n, k, 1.0, ar, 1, m, br, 1, k, beta_re, c_re, cr_rs, cr_cs, par);
+    pire_dgemm_par(m, n, k, 1.0, a_sum, 1, m, b_sum, 1, k, beta_re, c_im, cr_rs, cr_cs, par);
+    for_each_c(m, n, c, c_rs, c_cs, num_threads, |x| x.im -= 2.0 * x.re);
+    pire_dgemm_par(m, n, k, -1.0, ai, 1, m, bi, 1, k, 1.0, c_re, cr_rs, cr_cs, par);
+    for_each_c(m, n, c, c_rs, c_cs, num_threads, |x| x.im += x.re);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) unsafe fn gemm_3m(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    if m == 0 || n == 0 {
+        return;
+    }
+    let num_threads = par.num_threads;
+    if k == 0 {
+        // beta == 0 overwrites c, as in blas
+        for_each_c(m, n, c, c_rs, c_cs, num_threads, |x| *x = if beta == TC::ZERO { TC::ZERO } else { beta * *x });
+        return;
+    }
+    let (mc, nc, kc) = dispatch_get_mcnckc();
+    let (mc, nc, kc) = (mc.min(m), nc.min(n), kc.min(k));
+    let pool_size = 3 * (mc * kc + kc * nc) * size_of::<TR>() + AB_ALIGN;
+    let (slot, mut pool_vec) = take_pool_buf(pool_size);
+    let p = pool_vec.as_mut_ptr();
+    let p = p.add(p.align_offset(AB_ALIGN)) as *mut TR;
+
+    let mut p0 = 0;
+    while p0 < k {
+        let kc_len = kc.min(k - p0);
+        // c holds the sum over the previous kc blocks, which is the beta = 1 input of this one
+        let beta_p = if p0 == 0 { beta } else { TC::ONE };
+        let mut j = 0;
+        while j < n {
+            let nc_len = nc.min(n - j);
+            let b_planes = [0, 1, 2].map(|l| p.add(3 * mc * kc + l * kc_len * nc_len));
+            split_planes(b.add(p0 * b_rs + j * b_cs), b_rs, b_cs, kc_len, nc_len, TB::ONE, b_planes, num_threads);
+            let mut i = 0;
+            while i < m {
+                let mc_len = mc.min(m - i);
+                let a_planes = [0, 1, 2].map(|l| p.add(l * mc_len * kc_len));
+                split_planes(a.add(i * a_rs + p0 * a_cs), a_rs, a_cs, mc_len, kc_len, alpha, a_planes, num_threads);
+                let c_ij = c.add(i * c_rs + j * c_cs);
+                gemm_3m_block(mc_len, nc_len, kc_len, a_planes, b_planes, beta_p, c_ij, c_rs, c_cs, par);
+                i += mc_len;
+            }
+            j += nc_len;
+        }
+        p0 += kc_len;
+    }
+
+    put_pool_buf(slot, pool_vec);
+}
@@ -18,6 +18,7 @@ use x86_arch::{
 #[cfg(target_arch = "aarch64")]
 use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
 
+pub(crate) mod gemm_3m;
 pub(crate) mod reference;
 
 use core::mem::size_of;
@@ -31,9 +32,12 @@ const TC_SIZE: usize = size_of::<TC>();
 
 use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
 
-use pire_base::{
-    get_cache_params, has_c64_compute, Array, ArrayMut, GemmCache, IdentityFn, PirePar, UnaryFn, AB_ALIGN,
-};
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::{get_cache_params, has_c64_compute, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
 
 pub trait UnaryFnC: UnaryFn<TC> {}
 impl<F: UnaryFn<TC>> UnaryFnC for F {}
@@ -50,17 +54,68 @@ pub(crate) unsafe fn pire_zgemm_fused<F: UnaryFnC>(
     f: F,
 ) {
     let par = PirePar::default(m, n);
+    pire_zgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_zgemm_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    pire_zgemm_fused_conj_par(m, n, k, alpha, a, false, b, false, beta, c, f, par);
+}
+
+// conj_a (conj_b) conjugates strided a (b) while it is packed, packed arrays are used as they are
+pub(crate) unsafe fn pire_zgemm_fused_conj_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    conj_a: bool,
+    b: Array<TB>,
+    conj_b: bool,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
     if has_c64_compute() {
         #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
         {
-            let hw_config = KernelDispatcher::new(f);
-            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+            let hw_config = KernelDispatcher::new(f).with_conj(conj_a, conj_b);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
             return;
         }
     }
     // if none of the optimized paths are available, use reference implementation
-    let hw_config = RefGemm::new(f);
-    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+    let hw_config = RefGemm::new(f).with_conj(conj_a, conj_b);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+}
+
+// conjugation selected at compile time, pack routines are instantiated for both
+#[inline(always)]
+pub(crate) fn conj_if<const CONJ: bool>(x: TA) -> TA {
+    if CONJ {
+        x.conj()
+    } else {
+        x
+    }
+}
+
+// negates the imaginary parts of len elements, conjugation of packed panels
+pub(crate) unsafe fn conj_packed(x: *mut TA, len: usize) {
+    let x = x as *mut f64;
+    for i in 0..len {
+        *x.add(2 * i + 1) = -*x.add(2 * i + 1);
+    }
 }
 
 pub unsafe fn pire_zgemm(
@@ -92,6 +147,200 @@ pub unsafe fn pire_zgemm(
     pire_zgemm_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
 }
 
+/// Same as [`pire_zgemm`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_zgemm_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_zgemm_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Same as [`pire_zgemm`] with `a` replaced by `conj(a)` if `conj_a` and `b` replaced by `conj(b)` if `conj_b`,
+/// conjugation is applied while packing so it does not cost extra passes over a and b
+pub unsafe fn pire_zgemm_conj(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    conj_a: bool,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    conj_b: bool,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b, conj_a, conj_b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a, conj_b, conj_a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b, conj_a, conj_b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    let par = PirePar::default(m, n);
+    pire_zgemm_fused_conj_par(m, n, k, alpha, a, conj_a, b, conj_b, beta, c, identity_fn, &par);
+}
+
+/// Same as [`pire_zgemm`] computed with the 3M (Gauss) algorithm, i.e. three real gemms of
+/// pire-gemm-f64 on the real and imaginary parts instead of one complex gemm.
+///
+/// It needs 25% fewer flops but extra memory for the real parts of a and b, and the error of
+/// the imaginary part of the result scales with `|a| * |b| + |beta * c|` instead of `|a * b|`
+pub unsafe fn pire_zgemm_3m(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    let par = PirePar::default(m, n);
+    gemm_3m::gemm_3m(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, &par);
+}
+
+/// Same as [`pire_zgemm_3m`] with thread configuration given by `par`
+pub unsafe fn pire_zgemm_3m_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    gemm_3m::gemm_3m(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+}
+
+/// Strided batched gemm, runs [`pire_zgemm`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_zgemm_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_zgemm_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_zgemm_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_zgemm_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_zgemm_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_zgemm`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_zgemm_grouped(groups: &[GemmGroup<TA, TC>], a: &[*const TA], b: &[*const TB], c: &[*mut TC]) {
+    pire_zgemm_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_zgemm_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_zgemm_grouped_par(
+    groups: &[GemmGroup<TA, TC>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_zgemm_par(g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par);
+    });
+}
+
 #[cfg(feature = "fuse")]
 pub unsafe fn pire_zgemm_fn_ptr(
     m: usize,
@@ -122,6 +371,123 @@ pub unsafe fn pire_zgemm_fn_ptr(
     pire_zgemm_fused(m, n, k, alpha, a, b, beta, c, unary);
 }
 
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_zgemm_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_zgemm_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_zgemm_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_zgemm_packed`]
+pub unsafe fn pire_zgemm_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_zgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Same as [`pire_zgemm_packed`] with strided `a` (`b`) conjugated if `conj_a` (`conj_b`),
+/// conjugated packed matrices are made with [`pack_a_conj`] / [`pack_b_conj`]
+///
+/// # Safety
+///
+/// See [`pire_zgemm_packed`]
+pub unsafe fn pire_zgemm_packed_conj<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    conj_a: bool,
+    b: Array<TB>,
+    conj_b: bool,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    assert!(!conj_a || a.is_strided(), "packed a is conjugated by pack_a_conj");
+    assert!(!conj_b || b.is_strided(), "packed b is conjugated by pack_b_conj");
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    let par = PirePar::default(m, n);
+    pire_zgemm_fused_conj_par(m, n, k, alpha, a, conj_a, b, conj_b, beta, c, f, &par);
+}
+
+/// Safe counterpart of [`pire_zgemm`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_zgemm_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: TC,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| if beta == TC::new(0.0, 0.0) { TC::new(0.0, 0.0) } else { beta * v });
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_zgemm(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
 fn dispatch_round_m() -> fn(usize) -> usize {
     #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
     {
@@ -145,20 +511,20 @@ fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize
     #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
     {
         if has_c64_compute() {
-            return packa_fn_simd;
+            return packa_fn_simd::<false>;
         }
     }
-    packa_fn_ref
+    packa_fn_ref::<false>
 }
 
 fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
     #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
     {
         if has_c64_compute() {
-            return packb_fn_simd;
+            return packb_fn_simd::<false>;
         }
     }
-    packb_fn_ref
+    packb_fn_ref::<false>
 }
 
 fn dispatch_get_mcnckc() -> (usize, usize, usize) {
@@ -173,6 +539,22 @@ fn dispatch_get_mcnckc() -> (usize, usize, usize) {
 
 pire_base::packing_api!(TA, TB);
 
+/// Same as [`pack_a`] with `a` conjugated
+pub fn pack_a_conj(m: usize, k: usize, a: &[TA], a_rs: usize, a_cs: usize, ap: &mut [TA]) -> Array<TA> {
+    let ap_array = pack_a(m, k, a, a_rs, a_cs, ap);
+    // safety: pack_a checks that ap holds a_size_packed(m, k) elements
+    unsafe { conj_packed(ap.as_mut_ptr(), a_size_packed(m, k)) };
+    ap_array
+}
+
+/// Same as [`pack_b`] with `b` conjugated
+pub fn pack_b_conj(n: usize, k: usize, b: &[TB], b_rs: usize, b_cs: usize, bp: &mut [TB]) -> Array<TB> {
+    let bp_array = pack_b(n, k, b, b_rs, b_cs, bp);
+    // safety: pack_b checks that bp holds b_size_packed(n, k) elements
+    unsafe { conj_packed(bp.as_mut_ptr(), b_size_packed(n, k)) };
+    bp_array
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,4 +792,191 @@ mod tests {
     fn test_tt_col_apbp() {
         test_gemm(&ABLayout::TT, true, true);
     }
+
+    fn conj_vec(x: &[TA]) -> Vec<TA> {
+        x.iter().map(|v| Complex::new(v.re, -v.im)).collect()
+    }
+
+    fn max_diff(x: &[TC], y: &[TC]) -> f64 {
+        x.iter().zip(y).map(|(x, y)| (x - y).norm()).fold(0.0, f64::max)
+    }
+
+    #[test]
+    fn test_conj() {
+        // gemv, small m, small n and goto paths
+        let dims = [(37, 1, 19), (1, 29, 19), (5, 300, 67), (300, 7, 67), (211, 190, 150)];
+        let alpha = ALPHA_ARR[0];
+        let beta = BETA_ARR[0];
+        for (m, n, k) in dims {
+            let mut a = vec![TA::ZERO; m * k];
+            let mut b = vec![TB::ZERO; k * n];
+            let mut c0 = vec![TC::ZERO; m * n];
+            random_matrix_uniform(&mut a);
+            random_matrix_uniform(&mut b);
+            random_matrix_uniform(&mut c0);
+            let mut ap = avec![[AB_ALIGN]| TA::ZERO; a_size_packed(m, k)];
+            let mut bp = avec![[AB_ALIGN]| TB::ZERO; b_size_packed(n, k)];
+            for (conj_a, conj_b) in [(false, true), (true, false), (true, true)] {
+                let a_ref = if conj_a { conj_vec(&a) } else { a.clone() };
+                let b_ref = if conj_b { conj_vec(&b) } else { b.clone() };
+                let mut c_ref = c0.clone();
+                let mut c = c0.clone();
+                let mut c_row = c0.clone();
+                let mut c_packed = c0.clone();
+                unsafe {
+                    pire_zgemm(
+                        m,
+                        n,
+                        k,
+                        alpha,
+                        a_ref.as_ptr(),
+                        1,
+                        m,
+                        b_ref.as_ptr(),
+                        1,
+                        k,
+                        beta,
+                        c_ref.as_mut_ptr(),
+                        1,
+                        m,
+                    );
+                    pire_zgemm_conj(
+                        m,
+                        n,
+                        k,
+                        alpha,
+                        a.as_ptr(),
+                        1,
+                        m,
+                        conj_a,
+                        b.as_ptr(),
+                        1,
+                        k,
+                        conj_b,
+                        beta,
+                        c.as_mut_ptr(),
+                        1,
+                        m,
+                    );
+                    // row major c, conj flags follow a and b through the transpose
+                    let mut c_t = vec![TC::ZERO; m * n];
+                    for i in 0..m {
+                        for j in 0..n {
+                            c_t[i * n + j] = c_row[i + j * m];
+                        }
+                    }
+                    pire_zgemm_conj(
+                        m,
+                        n,
+                        k,
+                        alpha,
+                        a.as_ptr(),
+                        1,
+                        m,
+                        conj_a,
+                        b.as_ptr(),
+                        1,
+                        k,
+                        conj_b,
+                        beta,
+                        c_t.as_mut_ptr(),
+                        n,
+                        1,
+                    );
+                    for i in 0..m {
+                        for j in 0..n {
+                            c_row[i + j * m] = c_t[i * n + j];
+                        }
+                    }
+                    let a_matrix =
+                        if conj_a { pack_a_conj(m, k, &a, 1, m, &mut ap) } else { pack_a(m, k, &a, 1, m, &mut ap) };
+                    let b_matrix = Array::strided_matrix(b.as_ptr(), 1, k);
+                    let c_matrix = ArrayMut::strided_matrix(c_packed.as_mut_ptr(), 1, m);
+                    pire_zgemm_packed_conj(
+                        m,
+                        n,
+                        k,
+                        alpha,
+                        a_matrix,
+                        false,
+                        b_matrix,
+                        conj_b,
+                        beta,
+                        c_matrix,
+                        IdentityFn {},
+                    );
+                }
+                let tol = EPS as f64 * 1e-2;
+                for x in [&c, &c_row, &c_packed] {
+                    let diff = max_diff(x, &c_ref);
+                    assert!(diff < tol, "diff: {}, m: {}, n: {}, k: {}, conj: {} {}", diff, m, n, k, conj_a, conj_b);
+                }
+            }
+            let b_matrix = pack_b_conj(n, k, &b, 1, k, &mut bp);
+            let b_ref = conj_vec(&b);
+            let mut c_ref = c0.clone();
+            let mut c = c0.clone();
+            unsafe {
+                pire_zgemm(m, n, k, alpha, a.as_ptr(), 1, m, b_ref.as_ptr(), 1, k, beta, c_ref.as_mut_ptr(), 1, m);
+                let a_matrix = Array::strided_matrix(a.as_ptr(), 1, m);
+                let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+                pire_zgemm_packed(m, n, k, alpha, a_matrix, b_matrix, beta, c_matrix, IdentityFn {});
+            }
+            assert!(max_diff(&c, &c_ref) < EPS as f64 * 1e-2);
+        }
+    }
+
+    #[test]
+    fn test_3m() {
+        // the last but one spans two nc and two kc blocks of the split planes
+        let (_, nc, kc) = dispatch_get_mcnckc();
+        let dims = [(37, 1, 19), (1, 29, 19), (5, 300, 67), (211, 190, 150), (13, nc + 7, kc + 11), (17, 9, 0)];
+        let alpha = ALPHA_ARR[0];
+        // beta == 0 skips the scaling pass over c
+        for ((m, n, k), beta) in dims.into_iter().flat_map(|d| [(d, BETA_ARR[0]), (d, TC::ZERO)]) {
+            let mut a = vec![TA::ZERO; m * k];
+            let mut b = vec![TB::ZERO; k * n];
+            let mut c = vec![TC::ZERO; m * n];
+            random_matrix_uniform(&mut a);
+            random_matrix_uniform(&mut b);
+            random_matrix_uniform(&mut c);
+            // reference with beta applied explicitly, gemm leaves c untouched for k == 0
+            let mut c_ref = c.iter().map(|x| x * beta).collect::<Vec<_>>();
+            let mut c_row = vec![TC::ZERO; m * n];
+            for i in 0..m {
+                for j in 0..n {
+                    c_row[i * n + j] = c[i + j * m];
+                }
+            }
+            unsafe {
+                pire_zgemm(m, n, k, alpha, a.as_ptr(), k, 1, b.as_ptr(), 1, k, TC::ONE, c_ref.as_mut_ptr(), 1, m);
+                pire_zgemm_3m(m, n, k, alpha, a.as_ptr(), k, 1, b.as_ptr(), 1, k, beta, c.as_mut_ptr(), 1, m);
+                let par = PirePar::single_thread();
+                pire_zgemm_3m_par(
+                    m,
+                    n,
+                    k,
+                    alpha,
+                    a.as_ptr(),
+                    k,
+                    1,
+                    b.as_ptr(),
+                    1,
+                    k,
+                    beta,
+                    c_row.as_mut_ptr(),
+                    n,
+                    1,
+                    &par,
+                );
+            }
+            for i in 0..m {
+                for j in 0..n {
+                    assert!(c_row[i * n + j] == c[i + j * m]);
+                }
+            }
+            let diff = max_diff(&c, &c_ref);
+            assert!(diff < EPS as f64 * 1e-2, "diff: {}, m: {}, n: {}, k: {}", diff, m, n, k);
+        }
+    }
 }
@@ -22,4 +22,4 @@ def_kernel_bs!(TA, TB, TC, TC, 3, 4);
 
 use super::pack_avx::packa_panel_12;
 use pire_base::def_kernel_sb_v0;
-def_kernel_sb_v0!(TA, TA, TB, TC, TC, false, T, packa_panel_12, 1, 3, 4, 96, 8);
+def_kernel_sb_v0!(TA, TA, TB, TC, TC, false, T, packa_panel_12::<false>, 1, 3, 4, 96, 8);
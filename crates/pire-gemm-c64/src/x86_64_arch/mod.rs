@@ -1,15 +1,15 @@
 pub(crate) mod avx;
 pub(crate) mod avx512f;
 pub(crate) mod avx_fma;
+pub(crate) mod avx_fma4;
 pub(crate) mod pack_avx;
 pub(crate) mod pack_sse;
 pub(crate) mod sse;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
-    RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 const AVX512F_VS: usize = 4;
@@ -33,7 +33,7 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     let (mr, nr) = if features.avx512f {
         (AVX512F_MR, AVX512F_NR)
-    } else if features.avx && features.fma {
+    } else if features.avx && (features.fma || features.fma4) {
         (AVXFMA_MR, AVXFMA_NR)
     } else if features.avx {
         (AVX_MR, AVX_NR)
@@ -51,26 +51,40 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     (mc / mr * mr, nc / nr * nr, kc)
 }
 
-pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+pub(crate) unsafe fn packa_fn_simd<const CONJ: bool>(
+    x: *const TA,
+    y: *mut TA,
+    m: usize,
+    k: usize,
+    rs: usize,
+    cs: usize,
+) {
     let hw_config = &*RUNTIME_HW_CONFIG;
     if hw_config.cpu_ft.avx512f {
-        pack_avx::packa_panel_12(m, k, x, rs, cs, y, AVX512F_VS);
-    } else if hw_config.cpu_ft.avx && hw_config.cpu_ft.fma {
-        pack_avx::packa_panel_6(m, k, x, rs, cs, y, AVX_VS);
+        pack_avx::packa_panel_12::<CONJ>(m, k, x, rs, cs, y, AVX512F_VS);
+    } else if hw_config.cpu_ft.avx && (hw_config.cpu_ft.fma || hw_config.cpu_ft.fma4) {
+        pack_avx::packa_panel_6::<CONJ>(m, k, x, rs, cs, y, AVX_VS);
     } else if hw_config.cpu_ft.avx {
-        pack_avx::packa_panel_4(m, k, x, rs, cs, y, AVX_VS);
+        pack_avx::packa_panel_4::<CONJ>(m, k, x, rs, cs, y, AVX_VS);
     } else {
-        pack_sse::packa_panel_2(m, k, x, rs, cs, y, SSE_VS);
+        pack_sse::packa_panel_2::<CONJ>(m, k, x, rs, cs, y, SSE_VS);
     }
 }
 
-pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+pub(crate) unsafe fn packb_fn_simd<const CONJ: bool>(
+    x: *const TB,
+    y: *mut TB,
+    n: usize,
+    k: usize,
+    rs: usize,
+    cs: usize,
+) {
     if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
-        pack_avx::packb_panel_4(n, k, x, cs, rs, y);
+        pack_avx::packb_panel_4::<CONJ>(n, k, x, cs, rs, y);
     } else if (*RUNTIME_HW_CONFIG).cpu_ft.avx {
-        pack_avx::packb_panel_2(n, k, x, cs, rs, y);
+        pack_avx::packb_panel_2::<CONJ>(n, k, x, cs, rs, y);
     } else {
-        pack_sse::packb_panel_2(n, k, x, cs, rs, y);
+        pack_sse::packb_panel_2::<CONJ>(n, k, x, cs, rs, y);
     }
 }
 
@@ -93,6 +107,7 @@ pub(crate) fn round_k_simd(k: usize) -> usize {
 pub(crate) enum RegDim {
     Avx512f,
     AvxFma,
+    AvxFma4,
     Avx,
     Sse,
 }
@@ -111,6 +126,8 @@ pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
     is_l3_shared: bool,
     // features: CpuFeatures,
     func: T,
+    pub(crate) conj_a: bool,
+    pub(crate) conj_b: bool,
 }
 
 impl<F: UnaryFnC> KernelDispatcher<F> {
@@ -123,6 +140,8 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
             (AVX512F_MR, AVX512F_NR, RegDim::Avx512f)
         } else if features.avx && features.fma {
             (AVXFMA_MR, AVXFMA_NR, RegDim::AvxFma)
+        } else if features.avx && features.fma4 {
+            (AVXFMA_MR, AVXFMA_NR, RegDim::AvxFma4)
         } else if features.avx {
             (AVX_MR, AVX_NR, RegDim::Avx)
         } else {
@@ -148,9 +167,17 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
             is_l3_shared,
             // features,
             func: f,
+            conj_a: false,
+            conj_b: false,
         }
     }
 
+    pub(crate) fn with_conj(mut self, conj_a: bool, conj_b: bool) -> Self {
+        self.conj_a = conj_a;
+        self.conj_b = conj_b;
+        self
+    }
+
     pub(crate) fn is_compute_native(&self) -> bool {
         true
     }
@@ -199,20 +226,25 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512f => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::AvxFma => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Avx => avx::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Avx512f => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::AvxFma => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::AvxFma4 => avx_fma4::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx => avx::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Avx512f => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::AvxFma => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::AvxFma4 => avx_fma4::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx => avx::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
         }
@@ -233,20 +265,25 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512f => avx512f::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::AvxFma => avx_fma::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Avx => avx::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sse => sse::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Avx512f => avx512f::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f),
+            RegDim::AvxFma => avx_fma::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f),
+            RegDim::AvxFma4 => avx_fma4::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f),
+            RegDim::Avx => avx::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f),
+            RegDim::Sse => sse::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Avx512f => avx512f::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn),
             RegDim::AvxFma => avx_fma::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn),
+            RegDim::AvxFma4 => avx_fma4::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn),
             RegDim::Avx => avx::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn),
             RegDim::Sse => sse::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn),
         }
@@ -268,24 +305,25 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512f => {
-                avx512f::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::AvxFma => {
-                avx_fma::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::Avx => avx::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Avx512f => avx512f::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::AvxFma => avx_fma::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::AvxFma4 => avx_fma4::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx => avx::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Avx512f => avx512f::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::AvxFma => avx_fma::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+            RegDim::AvxFma4 => avx_fma4::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Avx => avx::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
         }
@@ -301,17 +339,105 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     x: Array<TB>,
     beta: *const TC,
     y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_a, hw_cfg.conj_b);
+}
+
+// m == 1 case, a and x are the transposed b and a of the gemm
+unsafe fn pire_gemv2<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+) {
+    gemv_conj(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.conj_b, hw_cfg.conj_a);
+}
+
+unsafe fn gemv_conj<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const TC,
+    y: ArrayMut<TC>,
+    conj_a: bool,
+    conj_x: bool,
 ) {
     let x_ptr = x.src();
     let inc_x = x.rs();
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Avx512f | RegDim::AvxFma => {
-            avx_fma::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
-        }
-        RegDim::Avx => avx::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
-        RegDim::Sse => sse::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Avx512f | RegDim::AvxFma => avx_fma::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
+        RegDim::AvxFma4 => avx_fma4::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
+        RegDim::Avx => avx::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
+        RegDim::Sse => sse::axpy(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            conj_a,
+            conj_x,
+            hw_cfg.func,
+        ),
     }
 }
 
@@ -336,7 +462,7 @@ def_pire_gemm!(
     gemm_small_n_serial,
     kernel_n,
     pire_gemv,
-    pire_gemv,
+    pire_gemv2,
     packa0,
     packb0,
     packa_fn_simd,
@@ -345,4 +471,5 @@ def_pire_gemm!(
     true,
     into_pack_array,
     F,
+    T,
 );
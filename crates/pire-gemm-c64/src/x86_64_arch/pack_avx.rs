@@ -1,11 +1,11 @@
-use crate::{TA, TB};
+use crate::{conj_if, TA, TB};
 use seq_macro::seq;
 use std::ptr::copy_nonoverlapping;
 
 use paste::paste;
 
 #[target_feature(enable = "avx")]
-pub(crate) unsafe fn pack_scalar_k(
+pub(crate) unsafe fn pack_scalar_k<const CONJ: bool>(
     m_left: usize,
     k: usize,
     a: *const TA,
@@ -17,29 +17,45 @@ pub(crate) unsafe fn pack_scalar_k(
     let mr = (m_left + vs - 1) / vs * vs;
     for i in 0..m_left {
         for j in 0..k {
-            *ap.add(j * mr + i) = *a.add(j * a_cs + i * a_rs);
+            *ap.add(j * mr + i) = conj_if::<CONJ>(*a.add(j * a_cs + i * a_rs));
         }
     }
 }
 
 #[target_feature(enable = "avx")]
-pub(crate) unsafe fn pack_k_v1<const M: usize, const MR: usize>(k: usize, a: *const TA, lda: usize, ap: *mut TA) {
+pub(crate) unsafe fn pack_k_v1<const M: usize, const MR: usize, const CONJ: bool>(
+    k: usize,
+    a: *const TA,
+    lda: usize,
+    ap: *mut TA,
+) {
     for i in 0..M {
         for j in 0..k {
-            *ap.add(j * MR + i) = *a.add(j + i * lda);
+            *ap.add(j * MR + i) = conj_if::<CONJ>(*a.add(j + i * lda));
         }
     }
 }
 
 #[target_feature(enable = "avx")]
-pub(crate) unsafe fn copy_packed<const M: usize>(a: *const TA, b: *mut TA) {
+pub(crate) unsafe fn copy_packed<const M: usize, const CONJ: bool>(a: *const TA, b: *mut TA) {
+    if CONJ {
+        for i in 0..M {
+            *b.add(i) = (*a.add(i)).conj();
+        }
+        return;
+    }
     let a = a as *const f64;
     let b = b as *mut f64;
     copy_nonoverlapping(a, b, M * 2);
 }
 
 #[target_feature(enable = "avx")]
-pub(crate) unsafe fn pack_k_v0<const M: usize, const MR: usize>(k: usize, a: *const TA, lda: usize, ap: *mut TA) {
+pub(crate) unsafe fn pack_k_v0<const M: usize, const MR: usize, const CONJ: bool>(
+    k: usize,
+    a: *const TA,
+    lda: usize,
+    ap: *mut TA,
+) {
     let k8 = k / 8 * 8;
     let a0 = a;
     let ap0 = ap;
@@ -47,14 +63,14 @@ pub(crate) unsafe fn pack_k_v0<const M: usize, const MR: usize>(k: usize, a: *co
     while k_i < k8 {
         let a = a0.add(k_i * lda);
         let ap = ap0.add(k_i * MR);
-        copy_packed::<M>(a, ap);
-        copy_packed::<M>(a.add(lda), ap.add(MR));
-        copy_packed::<M>(a.add(lda * 2), ap.add(MR * 2));
-        copy_packed::<M>(a.add(lda * 3), ap.add(MR * 3));
-        copy_packed::<M>(a.add(lda * 4), ap.add(MR * 4));
-        copy_packed::<M>(a.add(lda * 5), ap.add(MR * 5));
-        copy_packed::<M>(a.add(lda * 6), ap.add(MR * 6));
-        copy_packed::<M>(a.add(lda * 7), ap.add(MR * 7));
+        copy_packed::<M, CONJ>(a, ap);
+        copy_packed::<M, CONJ>(a.add(lda), ap.add(MR));
+        copy_packed::<M, CONJ>(a.add(lda * 2), ap.add(MR * 2));
+        copy_packed::<M, CONJ>(a.add(lda * 3), ap.add(MR * 3));
+        copy_packed::<M, CONJ>(a.add(lda * 4), ap.add(MR * 4));
+        copy_packed::<M, CONJ>(a.add(lda * 5), ap.add(MR * 5));
+        copy_packed::<M, CONJ>(a.add(lda * 6), ap.add(MR * 6));
+        copy_packed::<M, CONJ>(a.add(lda * 7), ap.add(MR * 7));
 
         k_i += 8;
     }
@@ -62,7 +78,7 @@ pub(crate) unsafe fn pack_k_v0<const M: usize, const MR: usize>(k: usize, a: *co
     while k_i < k {
         let a = a0.add(k_i * lda);
         let ap = ap0.add(k_i * MR);
-        copy_packed::<M>(a, ap);
+        copy_packed::<M, CONJ>(a, ap);
 
         k_i += 1;
     }
@@ -72,7 +88,7 @@ macro_rules! def_packb {
     ($nr:tt) => {
         paste! {
         // #[target_feature(enable = "avx")]
-        pub(crate) unsafe fn [<packb_panel_ $nr>](
+        pub(crate) unsafe fn [<packb_panel_ $nr>]<const CONJ: bool>(
                 n: usize, k: usize,
                 b: *const TB, b_rs: usize, b_cs: usize,
                 bp: *mut TB,
@@ -87,7 +103,7 @@ macro_rules! def_packb {
                     while n_idx < n_rounded {
                         let b = b0.add(n_idx);
                         let bp = bp0.add(n_idx*k);
-                        pack_k_v0::<NR,NR>(k, b, ldb, bp);
+                        pack_k_v0::<NR,NR,CONJ>(k, b, ldb, bp);
                         n_idx += NR;
                     }
                     let n_left = n - n_idx;
@@ -95,7 +111,7 @@ macro_rules! def_packb {
                         if n_left == NL {
                             let b = b0.add(n_idx);
                             let bp = bp0.add(n_idx*k);
-                            pack_k_v0::<NL,NL>(k, b, ldb, bp);
+                            pack_k_v0::<NL,NL,CONJ>(k, b, ldb, bp);
                             return;
                         }
                     });
@@ -104,7 +120,7 @@ macro_rules! def_packb {
                     while n_idx < n_rounded {
                         let b = b0.add(n_idx*ldb);
                         let bp = bp0.add(n_idx*k);
-                        pack_k_v1::<NR,NR>(k, b, ldb, bp);
+                        pack_k_v1::<NR,NR,CONJ>(k, b, ldb, bp);
                         // [<pack_kx$nr _v1>](k, b, ldb, bp);
                         n_idx += NR;
                     }
@@ -113,7 +129,7 @@ macro_rules! def_packb {
                         if n_left == NL {
                             let b = b0.add(n_idx*ldb);
                             let bp = bp0.add(n_idx*k);
-                            pack_k_v1::<NL,NL>(k, b, ldb, bp);
+                            pack_k_v1::<NL,NL,CONJ>(k, b, ldb, bp);
                             return;
                         }
                     });
@@ -132,7 +148,7 @@ macro_rules! def_packa {
     ($mr:tt) => {
         paste! {
             // #[target_feature(enable = "avx")]
-            pub(crate) unsafe fn [<packa_panel_ $mr>](
+            pub(crate) unsafe fn [<packa_panel_ $mr>]<const CONJ: bool>(
                 m: usize, k: usize,
                 a: *const TA, a_rs: usize, a_cs: usize,
                 ap: *mut TA, vs: usize
@@ -147,12 +163,12 @@ macro_rules! def_packa {
                     while m_idx < m_rounded {
                         let a = a0.add(m_idx);
                         let ap = ap0.add(m_idx*k);
-                        pack_k_v0::<MR,MR>(k, a, lda, ap);
+                        pack_k_v0::<MR,MR,CONJ>(k, a, lda, ap);
                         m_idx += MR;
                     }
                     let m_left = m - m_idx;
                     if m_left > 0 {
-                        pack_scalar_k(
+                        pack_scalar_k::<CONJ>(
                             m_left, k,
                             a0.add(m_idx), a_rs, a_cs,
                             ap0.add(m_idx*k), vs
@@ -164,12 +180,12 @@ macro_rules! def_packa {
                     while m_idx < m_rounded {
                         let a = a0.add(m_idx*lda);
                         let ap = ap0.add(m_idx*k);
-                        pack_k_v1::<MR,MR>(k, a, lda, ap);
+                        pack_k_v1::<MR,MR,CONJ>(k, a, lda, ap);
                         m_idx += MR;
                     }
                     let m_left = m - m_idx;
                     if m_left > 0 {
-                        pack_scalar_k(
+                        pack_scalar_k::<CONJ>(
                             m_left, k,
                             a0.add(m_idx*lda), a_rs, a_cs,
                             ap0.add(m_idx*k), vs
@@ -1,12 +1,14 @@
 pub(crate) mod neon;
+pub(crate) mod neon_f32;
 pub(crate) mod pack_neon;
 pub(crate) mod pack_sve;
 pub(crate) mod sve;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_mem_pool_size_goto, get_mem_pool_size_small_m,
-    get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range, split_range, Array, ArrayMut,
-    GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, has_f16_compute, is_mixed, run_par, run_small_m, run_small_n, split_c_range,
+    split_range, Array, ArrayMut, GemmPool, PArray, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData,
+    PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use half::f16;
@@ -17,10 +19,23 @@ const NEON_NR: usize = 4;
 
 const SVE_NR: usize = 8;
 
+const NEON_F32_VS: usize = 4;
+const NEON_F32_MR: usize = 12;
+const NEON_F32_NR: usize = 8;
+
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
 
 #[inline(always)]
-pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+pub(crate) fn get_mcnckc_simd_f32() -> (usize, usize, usize) {
+    let (mr, nr) = (NEON_F32_MR, NEON_F32_NR);
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        _ => (4800, 192, 384),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd_f16() -> (usize, usize, usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     let (mr, nr) = if features.sve { (unsafe { sve_vs() * 3 }, SVE_NR) } else { (NEON_MR, NEON_NR) };
     // let mc = std::env::var("GLAR_MC").unwrap_or("4800".to_string()).parse::<usize>().unwrap();
@@ -33,7 +48,23 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     (mc / mr * mr, nc / nr * nr, kc)
 }
 
-pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut f16, m: usize, k: usize, rs: usize, cs: usize) {
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    if has_f16_compute() {
+        get_mcnckc_simd_f16()
+    } else {
+        get_mcnckc_simd_f32()
+    }
+}
+
+pub(crate) unsafe fn packa_fn_simd_f32(x: *const TA, y: *mut f32, m: usize, k: usize, rs: usize, cs: usize) {
+    pack_neon::packa_panel_12_f32(m, k, x, rs, cs, y, NEON_F32_VS);
+}
+pub(crate) unsafe fn packb_fn_simd_f32(x: *const TB, y: *mut f32, n: usize, k: usize, rs: usize, cs: usize) {
+    pack_neon::packb_panel_8_f32(n, k, x, cs, rs, y);
+}
+
+pub(crate) unsafe fn packa_fn_simd_f16(x: *const TA, y: *mut f16, m: usize, k: usize, rs: usize, cs: usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     if features.sve {
         let vs = unsafe { sve_vs() };
@@ -42,7 +73,7 @@ pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut f16, m: usize, k: usize
         pack_neon::packa_panel_24(m, k, x, rs, cs, y, NEON_VS);
     }
 }
-pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut f16, n: usize, k: usize, rs: usize, cs: usize) {
+pub(crate) unsafe fn packb_fn_simd_f16(x: *const TB, y: *mut f16, n: usize, k: usize, rs: usize, cs: usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
     if features.sve {
         pack_sve::packb_panel_8(n, k, x, cs, rs, y);
@@ -51,9 +82,32 @@ pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut f16, n: usize, k: usize
     }
 }
 
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut f16, m: usize, k: usize, rs: usize, cs: usize) {
+    if has_f16_compute() {
+        packa_fn_simd_f16(x, y, m, k, rs, cs);
+    } else {
+        pack_neon::packa_panel_12(m, k, x, rs, cs, y, NEON_F32_VS);
+    }
+}
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut f16, n: usize, k: usize, rs: usize, cs: usize) {
+    if has_f16_compute() {
+        packb_fn_simd_f16(x, y, n, k, rs, cs);
+    } else {
+        pack_neon::packb_panel_8(n, k, x, cs, rs, y);
+    }
+}
+
 pub(crate) fn round_m_simd(m: usize) -> usize {
-    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
-    let vs = if features.sve { unsafe { sve_vs() } } else { NEON_VS };
+    let vs = if has_f16_compute() {
+        let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+        if features.sve {
+            unsafe { sve_vs() }
+        } else {
+            NEON_VS
+        }
+    } else {
+        NEON_F32_VS
+    };
     (m + vs - 1) / vs * vs
 }
 
@@ -77,6 +131,66 @@ pub(crate) unsafe fn sve_vs() -> usize {
     (sve_vs / core::mem::size_of::<TC>() as u64) as usize
 }
 
+pub(crate) struct KernelDispatcherF32<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcherF32<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd_f32();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, vs) = (NEON_F32_MR, NEON_F32_NR, NEON_F32_VS);
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        false
+    }
+
+    #[target_feature(enable = "neon")]
+    unsafe fn cvt_mixed(&self, x: *const f16, y: *mut f32, m: usize) {
+        let m_iter = m / 4;
+        let m_rem = m % 4;
+        for i in 0..m_iter {
+            pack_neon::cvt_f16_f32_4(x.add(i * 4), y.add(i * 4));
+        }
+        for i in 0..m_rem {
+            let x_ptr = x.add(m_iter * 4 + i);
+            let y_ptr = y.add(m_iter * 4 + i);
+            *y_ptr = (*x_ptr).to_f32();
+        }
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        k
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
 pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
     mc: usize,
     nc: usize,
@@ -95,7 +209,7 @@ pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
 impl<F: UnaryFnC> KernelDispatcher<F> {
     pub(crate) fn new(f: F) -> Self {
         let hw_config = &*RUNTIME_HW_CONFIG;
-        let (mc, nc, kc) = get_mcnckc_simd();
+        let (mc, nc, kc) = get_mcnckc_simd_f16();
         let features = hw_config.cpu_ft();
         let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
 
@@ -136,6 +250,29 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
     }
 }
 
+impl<T: UnaryFnC> GemmCache for KernelDispatcherF32<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
 impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
     fn mr(&self) -> usize {
         self.mr
@@ -159,6 +296,123 @@ impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
     }
 }
 
+unsafe fn kernel_f32<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut f16,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    bp: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        neon_f32::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f);
+    } else {
+        let null_fn = IdentityFn {};
+        neon_f32::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn);
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m_f32<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const f16,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut f16,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+#[allow(unused)]
+unsafe fn kernel_n_f32<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const f16,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut f32,
+    b: *const f32,
+    c: *mut f16,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+unsafe fn pire_gemv_f32<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherF32<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    neon_f32::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func);
+}
+
+def_pire_gemm!(
+    KernelDispatcherF32,
+    f16,
+    f32,
+    f16,
+    f32,
+    f16,
+    f32,
+    f32,
+    PackArrTypeAM,
+    PackArrTypeBM,
+    1_f32,
+    pire_gemm_f32,
+    gemm_mt_f32,
+    gemm_goto_serial_f32,
+    kernel_f32,
+    gemm_small_m_serial_f32,
+    kernel_m_f32,
+    gemm_small_n_serial_f32,
+    kernel_n_f32,
+    pire_gemv_f32,
+    pire_gemv_f32,
+    packa0,
+    packb0,
+    packa_fn_simd_f32,
+    packb_fn_simd_f32,
+    false,
+    false,
+    into_pack_array2,
+    T,
+);
+
 unsafe fn kernel<F: UnaryFnC>(
     hw_cfg: &KernelDispatcher<F>,
     m: usize,
@@ -171,12 +425,15 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
@@ -202,6 +459,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -221,12 +480,15 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
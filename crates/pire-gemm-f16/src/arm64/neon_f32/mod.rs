@@ -0,0 +1,48 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use half::f16;
+
+use crate::UnaryFnC;
+
+const VS: usize = 4;
+const VS_MAX: usize = VS;
+
+const ZERO: f16 = f16::ZERO;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn axpy<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: *const f16,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const f16,
+    incx: usize,
+    beta: *const f32,
+    y: *mut f16,
+    incy: usize,
+    f: F,
+) {
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = 0f32;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = x.add(j * incx);
+            acc += (*a_cur).to_f32() * (*x_cur).to_f32();
+        }
+        *y_cur = f16::from_f32(*beta * (*y_cur).to_f32() + *alpha * acc);
+        f.call(y_cur, 1);
+    }
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(f32, f32, f16, f32, true, F, 1, 3, 8, 0, 0);
@@ -12,7 +12,10 @@ use x86_64_arch::{
 use core::mem::size_of;
 
 #[cfg(target_arch = "aarch64")]
-use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+use arm64::{
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_f32, round_k_simd, round_m_simd,
+    KernelDispatcher, KernelDispatcherF32,
+};
 pub(crate) mod reference;
 
 pub(crate) type TA = f16;
@@ -23,10 +26,12 @@ const TC_SIZE: usize = size_of::<TC>();
 
 pub use half::f16;
 
-use pire_base::{
-    get_cache_params, has_f16_compute, has_f16f32_compute, Array, ArrayMut, GemmCache, IdentityFn, PirePar, UnaryFn,
-    AB_ALIGN,
-};
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::{get_cache_params, has_f16_compute, has_f16f32_compute, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
 use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
 
 pub trait UnaryFnC: UnaryFn<TC> {}
@@ -44,26 +49,41 @@ pub(crate) unsafe fn pire_hgemm_fused<F: UnaryFnC>(
     f: F,
 ) {
     let par = PirePar::default(m, n);
+    pire_hgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_hgemm_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
     if has_f16_compute() {
         #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
         {
             let hw_config = KernelDispatcher::new(f);
-            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
             return;
         }
     }
     if has_f16f32_compute() {
-        #[cfg(target_arch = "x86_64")]
+        #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
         {
             let hw_config = KernelDispatcherF32::new(f);
-            pire_gemm_f32(&hw_config, m, n, k, alpha.to_f32(), a, b, beta.to_f32(), c, &par);
+            pire_gemm_f32(&hw_config, m, n, k, alpha.to_f32(), a, b, beta.to_f32(), c, par);
             return;
         }
     }
 
     // if none of the optimized paths are available, use reference implementation
     let hw_config = RefGemm::new(f);
-    reference::pire_gemm(&hw_config, m, n, k, alpha.to_f32(), a, b, beta.to_f32(), c, &par);
+    reference::pire_gemm(&hw_config, m, n, k, alpha.to_f32(), a, b, beta.to_f32(), c, par);
 }
 
 pub unsafe fn pire_hgemm(
@@ -95,6 +115,120 @@ pub unsafe fn pire_hgemm(
     pire_hgemm_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
 }
 
+/// Same as [`pire_hgemm`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_hgemm_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f16,
+    a: *const f16,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const f16,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f16,
+    c: *mut f16,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // do not exchange if transa && transb
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_hgemm_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_hgemm`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_hgemm_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f16,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f16,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_hgemm_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_hgemm_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_hgemm_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f16,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f16,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_hgemm_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_hgemm`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_hgemm_grouped(groups: &[GemmGroup<f16, f16>], a: &[*const TA], b: &[*const TB], c: &[*mut TC]) {
+    pire_hgemm_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_hgemm_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_hgemm_grouped_par(
+    groups: &[GemmGroup<f16, f16>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_hgemm_par(g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par);
+    });
+}
+
 #[cfg(feature = "fuse")]
 pub unsafe fn pire_hgemm_fn_ptr(
     m: usize,
@@ -125,6 +259,96 @@ pub unsafe fn pire_hgemm_fn_ptr(
     pire_hgemm_fused(m, n, k, alpha, a, b, beta, c, unary);
 }
 
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_hgemm_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_hgemm_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_hgemm_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_hgemm_packed`]
+pub unsafe fn pire_hgemm_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_hgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Safe counterpart of [`pire_hgemm`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_hgemm_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: TC,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| if beta == f16::ZERO { f16::ZERO } else { beta * v });
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_hgemm(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
 fn dispatch_round_m() -> fn(usize) -> usize {
     #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
     {
@@ -233,6 +457,10 @@ mod tests {
         {
             return x86_64_arch::get_mcnckc_simd();
         }
+        #[cfg(target_arch = "aarch64")]
+        {
+            return arm64::get_mcnckc_simd();
+        }
         get_cache_params()
     }
 
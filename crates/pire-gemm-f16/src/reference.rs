@@ -1,8 +1,7 @@
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
-    RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use half::f16;
@@ -218,6 +217,8 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const f32,
     bp: *const f32,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     let mut i = 0;
@@ -251,7 +252,7 @@ unsafe fn kernel<F: UnaryFnC>(
                     let c_cur = c.add(i * c_rs + j * c_cs + ii * c_rs + jj * c_cs);
                     *c_cur = f16::from_f32((*c_cur).to_f32() * *beta + acc[ii * nr_eff + jj] * *alpha);
                     if kc_last {
-                        hw_cfg.func.call(c_cur, 1);
+                        hw_cfg.func.at(i0, j0).call_tile(c_cur, 1, i + ii, j + jj);
                     }
                     acc[ii * nr_eff + jj] = 0.0;
                     jj += 1;
@@ -280,6 +281,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const f32,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -300,6 +303,8 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut f16,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -21,10 +21,10 @@ const AVX_NR: usize = 4;
 const AVX512_F16_NR: usize = 15;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, has_f16_compute, is_mixed, run_small_m, run_small_n,
-    split_c_range, split_range, Array, ArrayMut, GemmPool, HWModel, PArray, PArrayMixed, PirePar, PireThreadConfig,
-    PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, has_f16_compute, is_mixed, run_par, run_small_m, run_small_n, split_c_range,
+    split_range, Array, ArrayMut, GemmPool, HWModel, PArray, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData,
+    PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -327,13 +327,16 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const f32,
     bp: *const f32,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Reg16x4 => avx::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Reg16x4 => avx::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
@@ -360,6 +363,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const f32,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -380,6 +385,8 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut f16,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -453,10 +460,13 @@ unsafe fn kernel_native<F: UnaryFnC>(
     c_cs: usize,
     ap: *const f16,
     bp: *const f16,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
-        avx512_f16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func);
+        let f = hw_cfg.func.at(i0, j0);
+        avx512_f16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f);
     } else {
         let null_fn = IdentityFn {};
         avx512_f16::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn);
@@ -477,10 +487,13 @@ unsafe fn kernel_m_native<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const f16,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
-        avx512_f16::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, hw_cfg.func);
+        let f = hw_cfg.func.at(i0, j0);
+        avx512_f16::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, f);
     } else {
         let null_fn = IdentityFn {};
         avx512_f16::kernel_bs(m, n, k, alpha, beta, b, b_rs, b_cs, c, c_rs, c_cs, ap, null_fn);
@@ -502,10 +515,13 @@ unsafe fn kernel_n_native<F: UnaryFnC>(
     c: *mut f16,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
-        avx512_f16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func);
+        let f = hw_cfg.func.at(i0, j0);
+        avx512_f16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f);
     } else {
         let null_fn = IdentityFn {};
         avx512_f16::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn);
@@ -0,0 +1,428 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_sve_fmmla,
+    acc_2, acc_1,
+    store_2, store_1,
+};
+use super::super::sve_vs;
+
+const ONE_SCALAR: TC = 1.0;
+const ZERO_SCALAR: TC = 0.0;
+
+// fmmla multiplies a 2x2 block of b (two columns, two k) with a 2x2 block of a (two rows, two k)
+// in every 128-bit segment, the result segment holds rows 2s,2s+1 of column n followed by column n+1,
+// the columns are separated with uzp1/uzp2 on 64-bit lanes before the store
+// z0-z3: a, z4-z7: b, z8-z31: c
+
+macro_rules! v_i {
+    ($m0:tt, $ni:tt) => {
+        concat!("[", $m0, ", #", $ni, ", MUL VL]")
+    }
+}
+
+macro_rules! set_predicate {
+    (M) => { "mov {m_s}, #0 \n whilelo p1.s, {m_s}, {m_e} \n" };
+    (C) => { "/* {m_s}, {m_e} */" }
+}
+
+macro_rules! inc_a {
+    ($mr:tt) => {
+        concat!("add {ax}, {ax}, {incax} \n")
+    };
+}
+
+macro_rules! unzip_tuple {
+    ($r1:tt, $r2:tt,$rt1:tt,$rt2:tt) => {
+        concat!(
+            "uzp1 z", $rt1, ".d, z", $r1, ".d, z", $r2, ".d\n",
+            "uzp2 z", $rt2, ".d, z", $r1, ".d, z", $r2, ".d\n",
+            "orr z", $r1, ".d, z", $rt1, ".d, z", $rt1, ".d\n",
+            "orr z", $r2, ".d, z", $rt2, ".d, z", $rt2, ".d\n",
+        )
+    };
+}
+
+macro_rules! unzip_c {
+    () => {
+        concat!(
+            unzip_tuple!(8, 9, 1, 2),
+            unzip_tuple!(10, 11, 3, 4),
+
+            unzip_tuple!(12, 13, 5, 6),
+            unzip_tuple!(14, 15, 7, 1),
+
+            unzip_tuple!(16, 17, 2, 3),
+            unzip_tuple!(18, 19, 4, 5),
+
+            unzip_tuple!(20, 21, 6, 7),
+            unzip_tuple!(22, 23, 1, 2),
+
+            unzip_tuple!(24, 25, 3, 4),
+            unzip_tuple!(26, 27, 5, 6),
+
+            unzip_tuple!(28, 29, 7, 1),
+            unzip_tuple!(30, 31, 2, 3),
+        )
+    }
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ld1w {{ z1.s }}, p0/z, ", $m0, "\n",
+            "fmla z", $r1, ".s, z1.s, z0.s[0]\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ld1w {{ z1.s }}, p1/z, ", $m0, "\n",
+            "fmla z", $r1, ".s, z1.s, z0.s[0]\n",
+        )
+    };
+    (C, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ld1w {{ z1.s }}, p0/z, ", $m0, "\n",
+            "fadd z", $r1, ".s, z", $r1, ".s, z1.s\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ld1w {{ z1.s }}, p1/z, ", $m0, "\n",
+            "fadd z", $r1, ".s, z", $r1, ".s, z1.s\n",
+        )
+    };
+}
+
+macro_rules! vzeroall {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(#("dup z", r, ".s, #0 \n",)*)
+        })
+    }
+}
+
+macro_rules! vfmadd {
+    ($r1:expr, $r2:expr, $r3:expr) => {
+        concat!(
+            "fmmla z", $r3, ".s", ", z", $r2,".s, z", $r1, ".s\n",
+        )
+    };
+}
+
+macro_rules! loadp_unit {
+    ($m0:expr, 0) => {
+        concat!(
+            "ld1w {{ z0.s }}, p0/z, [", $m0, "]\n",
+            "ld1w {{ z1.s }}, p0/z, [", $m0, ", #1, MUL VL]\n",
+        )
+    };
+    ($m0:expr, 1) => {
+        concat!(
+            "ld1w {{ z2.s }}, p0/z, [", $m0, ", #2, MUL VL]\n",
+            "ld1w {{ z3.s }}, p0/z, [", $m0, ", #3, MUL VL]\n",
+        )
+    };
+}
+
+macro_rules! storep_unit {
+    (C, $r1:expr, $m0:expr) => {
+        concat!(
+            "st1w {{ z", $r1, ".s }}, p0, ", $m0, "\n",
+        )
+    };
+    (M, $r1:expr, $m0:expr) => {
+        concat!(
+            "st1w {{ z", $r1, ".s }}, p1, ", $m0, "\n",
+        )
+    };
+}
+
+macro_rules! alpha_scale_0 {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(
+                "ld1rqw {{ z1.s }}, p0/z, [{alphax}]", "\n",
+                #(
+                    "fmul  z", r, ".s, z", r, ".s, z1.s[0]\n",
+                )*
+            )
+        })
+    }
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            "ld1rqw {{ z0.s }}, p0/z, [{betax}]", "\n",
+        )
+    }
+}
+
+macro_rules! init_ab {
+    (B) => {
+        concat!(
+            "/* {x11} */", "\n",
+            "/* {x10} */", "\n",
+            "/* {x9} */", "\n",
+            "/* {x8} */", "\n",
+            "/* {x7} */", "\n",
+            "/* {x6} */", "\n",
+            "/* {x5} */", "\n",
+            "/* {x4} */", "\n",
+            "/* {x3} */", "\n",
+            "/* {x2} */", "\n",
+            "/* {x1} */", "\n",
+
+            "ldr {x0}, [{dim_arrx}, #24]", "\n",
+        )
+    };
+}
+
+
+macro_rules! c_load {
+    () => {
+        concat!(
+            unzip_c!(),
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0} \n",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+            "add {x8}, {x7}, {x0} \n",
+            "add {x9}, {x8}, {x0} \n",
+            "add {x10}, {x9}, {x0} \n",
+            "add {x11}, {x10}, {x0} \n",
+        )
+    };
+}
+
+
+macro_rules! vzero_kernel {
+    () => {
+        concat!(
+            vzeroall!(8,31),
+            "ptrue p2.s, vl2\n",
+        )
+    };
+}
+
+macro_rules! inc_b {
+    (B,$nr:tt) => {
+        concat!(
+            "add {bx}, {bx}, #", $nr, "*8 \n",
+        )
+    };
+}
+
+macro_rules! alpha_scale {
+    () => {
+        alpha_scale_0!(8,31)
+    };
+}
+
+macro_rules! br {
+    (0) => { 4 };
+    (2) => { 5 };
+    (4) => { 6 };
+    (6) => { 7 };
+    (8) => { 4 };
+    (10) => { 5 };
+}
+
+macro_rules! cr {
+    (0,0) => { 8 };
+    (0,1) => { 9 };
+
+    (1,0) => { 10 };
+    (1,1) => { 11 };
+
+    (0,2) => { 12 };
+    (0,3) => { 13 };
+
+    (1,2) => { 14 };
+    (1,3) => { 15 };
+
+    (0,4) => { 16 };
+    (0,5) => { 17 };
+
+    (1,4) => { 18 };
+    (1,5) => { 19 };
+
+    (0,6) => { 20 };
+    (0,7) => { 21 };
+
+    (1,6) => { 22 };
+    (1,7) => { 23 };
+
+    (0,8) => { 24 };
+    (0,9) => { 25 };
+
+    (1,8) => { 26 };
+    (1,9) => { 27 };
+
+    (0,10) => { 28 };
+    (0,11) => { 29 };
+
+    (1,10) => { 30 };
+    (1,11) => { 31 };
+}
+
+macro_rules! dr {
+    (0,0) => { 8 };
+    (1,0) => { 9 };
+    (2,0) => { 10 };
+    (3,0) => { 11 };
+
+    (0,2) => { 12 };
+    (1,2) => { 13 };
+    (2,2) => { 14 };
+    (3,2) => { 15 };
+
+    (0,4) => { 16 };
+    (1,4) => { 17 };
+    (2,4) => { 18 };
+    (3,4) => { 19 };
+
+    (0,6) => { 20 };
+    (1,6) => { 21 };
+    (2,6) => { 22 };
+    (3,6) => { 23 };
+
+    (0,8) => { 24 };
+    (1,8) => { 25 };
+    (2,8) => { 26 };
+    (3,8) => { 27 };
+
+    (0,10) => { 28 };
+    (1,10) => { 29 };
+    (2,10) => { 30 };
+    (3,10) => { 31 };
+}
+
+// b of a 2 k block is 2 consecutive k of each column, ld1rqw brings two columns into every 128-bit segment.
+// the last column of an odd panel is loaded with p2 (2 lanes) to not read past the panel
+macro_rules! load_b {
+    (B, 1, 0) => { "ld1rqw {{ z4.s }}, p2/z, [{bx}]\n" };
+    (B, 3, 2) => { "ld1rqw {{ z5.s }}, p2/z, [{bx}, #0x10]\n" };
+    (B, 5, 4) => { "ld1rqw {{ z6.s }}, p2/z, [{bx}, #0x20]\n" };
+    (B, 7, 6) => { "ld1rqw {{ z7.s }}, p2/z, [{bx}, #0x30]\n" };
+    (B, 9, 8) => { "ld1rqw {{ z4.s }}, p2/z, [{bx}, #0x40]\n" };
+    (B, 11, 10) => { "ld1rqw {{ z5.s }}, p2/z, [{bx}, #0x50]\n" };
+    (B, $nr:tt, 0) => { "ld1rqw {{ z4.s }}, p0/z, [{bx}]\n" };
+    (B, $nr:tt, 2) => { "ld1rqw {{ z5.s }}, p0/z, [{bx}, #0x10]\n" };
+    (B, $nr:tt, 4) => { "ld1rqw {{ z6.s }}, p0/z, [{bx}, #0x20]\n" };
+    (B, $nr:tt, 6) => { "ld1rqw {{ z7.s }}, p0/z, [{bx}, #0x30]\n" };
+    (B, $nr:tt, 8) => { "ld1rqw {{ z4.s }}, p0/z, [{bx}, #0x40]\n" };
+    (B, $nr:tt, 10) => { "ld1rqw {{ z5.s }}, p0/z, [{bx}, #0x50]\n" };
+    (B, $nr:tt, $ni:tt) => { "" };
+}
+
+macro_rules! fmadd_2 {
+    (1) => {""};
+    (3) => {""};
+    (5) => {""};
+    (7) => {""};
+    (9) => {""};
+    (11) => {""};
+    ($ni:tt) => {
+        concat!(
+            vfmadd!(0, br!($ni), dr!(0,$ni)),
+            vfmadd!(1, br!($ni), dr!(1,$ni)),
+            vfmadd!(2, br!($ni), dr!(2,$ni)),
+            vfmadd!(3, br!($ni), dr!(3,$ni)),
+        )
+    };
+}
+
+macro_rules! fmadd_1 {
+    (1) => {""};
+    (3) => {""};
+    (5) => {""};
+    (7) => {""};
+    (9) => {""};
+    (11) => {""};
+    ($ni:tt) => {
+        concat!(
+            vfmadd!(0, br!($ni), dr!(0,$ni)),
+            vfmadd!(1, br!($ni), dr!(1,$ni)),
+        )
+    };
+}
+
+macro_rules! step_2 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, $nr, n),
+                    fmadd_2!(n),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! step_1 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, $nr, n),
+                    fmadd_1!(n),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! prefetch_c {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0}\n ",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+            "add {x8}, {x7}, {x0} \n",
+            "add {x9}, {x8}, {x0} \n",
+            "add {x10}, {x9}, {x0} \n",
+            "add {x11}, {x10}, {x0} \n",
+            "prfm pldl1keep, [{cx}] \n",
+            "prfm pldl1keep, [{cx},#64]\n",
+            "prfm pldl1keep, [{x1}] \n",
+            "prfm pldl1keep, [{x1},#64]\n",
+            "prfm pldl1keep, [{x2}] \n",
+            "prfm pldl1keep, [{x2},#64]\n",
+            "prfm pldl1keep, [{x3}] \n",
+            "prfm pldl1keep, [{x3},#64]\n",
+            "prfm pldl1keep, [{x4}] \n",
+            "prfm pldl1keep, [{x4},#64]\n",
+            "prfm pldl1keep, [{x5}] \n",
+            "prfm pldl1keep, [{x5},#64]\n",
+            "prfm pldl1keep, [{x6}] \n",
+            "prfm pldl1keep, [{x6},#64]\n",
+            "prfm pldl1keep, [{x7}] \n",
+            "prfm pldl1keep, [{x7},#64]\n",
+            "prfm pldl1keep, [{x8}] \n",
+            "prfm pldl1keep, [{x8},#64]\n",
+            "prfm pldl1keep, [{x9}] \n",
+            "prfm pldl1keep, [{x9},#64]\n",
+            "prfm pldl1keep, [{x10}] \n",
+            "prfm pldl1keep, [{x10},#64]\n",
+            "prfm pldl1keep, [{x11}] \n",
+            "prfm pldl1keep, [{x11},#64]\n",
+        )
+    };
+}
+
+def_ukernel_sve_fmmla!(step_1, acc_1, store_1, 1, 12, B, M, "neon,sve,f32mm", ukernel_1_bbp);
+def_ukernel_sve_fmmla!(step_2, acc_2, store_2, 2, 12, B, M, "neon,sve,f32mm", ukernel_2_bbp);
+
+def_ukernel_sve_fmmla!(step_2, acc_2, store_2, 2, 12, B, C, "neon,sve,f32mm", ukernel_bbc);
@@ -0,0 +1,21 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+unsafe fn simd_vector_length() -> usize {
+    super::sve_vs()
+}
+
+const VS_MAX: usize = 2048 / (8 * core::mem::size_of::<TC>());
+const ZERO: TC = 0.0;
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(TC, TC, TC, TC, false, F, 2, 2, 12, 0, 0);
+
+use super::pack_sve_mmla::packa_panel;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(TC, TC, TC, TC, TC, false, F, packa_panel, 2, 2, 12, 0, 0);
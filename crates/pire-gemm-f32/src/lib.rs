@@ -1,5 +1,9 @@
 #[cfg(target_arch = "aarch64")]
 pub(crate) mod arm64;
+#[cfg(target_arch = "riscv64")]
+pub(crate) mod riscv64;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub(crate) mod wasm32;
 #[cfg(target_arch = "x86_64")]
 pub(crate) mod x86_64_arch;
 #[cfg(target_arch = "x86")]
@@ -7,16 +11,33 @@ pub(crate) mod x86_arch;
 
 #[cfg(target_arch = "x86_64")]
 use x86_64_arch::{
-    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher,
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_src_b, round_k_simd, round_m_simd,
+    KernelDispatcher,
 };
 
 #[cfg(target_arch = "x86")]
 use x86_arch::{
-    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher,
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_src_b, round_k_simd, round_m_simd,
+    KernelDispatcher,
 };
 
 #[cfg(target_arch = "aarch64")]
-use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+use arm64::{
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_src_b, round_k_simd, round_m_simd,
+    KernelDispatcher,
+};
+
+#[cfg(target_arch = "riscv64")]
+use riscv64::{
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_src_b, round_k_simd, round_m_simd,
+    KernelDispatcher,
+};
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use wasm32::{
+    get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_src_b, round_k_simd, round_m_simd,
+    KernelDispatcher,
+};
 
 pub(crate) mod reference;
 
@@ -30,9 +51,15 @@ const TC_SIZE: usize = size_of::<TC>();
 
 use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
 
-use pire_base::{
-    get_cache_params, has_f32_compute, Array, ArrayMut, GemmCache, IdentityFn, PirePar, UnaryFn, AB_ALIGN,
-};
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::split_range;
+pub use pire_base::src_b::SrcB;
+use pire_base::thread_pool::run_tasks;
+use pire_base::{get_cache_params, has_f32_compute, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
 
 pub trait UnaryFnC: UnaryFn<TC> {}
 impl<F: UnaryFn<TC>> UnaryFnC for F {}
@@ -49,17 +76,38 @@ pub(crate) unsafe fn pire_sgemm_fused<F: UnaryFnC>(
     f: F,
 ) {
     let par = PirePar::default(m, n);
+    pire_sgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_sgemm_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
     if has_f32_compute() {
-        #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+        #[cfg(any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "aarch64",
+            target_arch = "riscv64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
         {
             let hw_config = KernelDispatcher::new(f);
-            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
             return;
         }
     }
     // if none of the optimized paths are available, use reference implementation
     let hw_config = RefGemm::new(f);
-    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
 }
 
 pub unsafe fn pire_sgemm(
@@ -91,6 +139,120 @@ pub unsafe fn pire_sgemm(
     pire_sgemm_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
 }
 
+/// Same as [`pire_sgemm`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_sgemm_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_sgemm_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_sgemm`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_sgemm_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_sgemm_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_sgemm_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_sgemm_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_sgemm_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_sgemm`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_sgemm_grouped(groups: &[GemmGroup<TA, TC>], a: &[*const TA], b: &[*const TB], c: &[*mut TC]) {
+    pire_sgemm_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_sgemm_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_sgemm_grouped_par(
+    groups: &[GemmGroup<TA, TC>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_sgemm_par(g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par);
+    });
+}
+
 #[cfg(feature = "fuse")]
 pub unsafe fn pire_sgemm_fn_ptr(
     m: usize,
@@ -121,8 +283,222 @@ pub unsafe fn pire_sgemm_fn_ptr(
     pire_sgemm_fused(m, n, k, alpha, a, b, beta, c, unary);
 }
 
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_sgemm_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_sgemm_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_sgemm_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_sgemm_packed`]
+pub unsafe fn pire_sgemm_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_sgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+// rows and columns of b loaded at a time by src_b_gemv_t, so that a tile of b^t fits a stack buffer
+const SRC_B_GEMV_KB: usize = 256;
+const SRC_B_GEMV_NB: usize = 16;
+
+// c[0, j] = alpha * a[0, :] * b[:, j] + beta * c[0, j] followed by f, as c^t = b^t * a^t through the gemv kernel
+// on tiles of b^t loaded into a stack buffer, accumulated over the k blocks
+#[allow(clippy::too_many_arguments)]
+unsafe fn src_b_gemv_t<F: UnaryFnC>(
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_cs: usize,
+    b: &SrcB<TB>,
+    beta: TC,
+    c: *mut TC,
+    c_cs: usize,
+    f: F,
+    par: &PirePar,
+) {
+    let num_tasks = par.num_threads.min(n).max(1);
+    // pointers as addresses so that the closure is Sync, columns written by different threads are disjoint
+    let (a_addr, c_addr) = (a as usize, c as usize);
+    run_tasks(num_tasks, |t_id| {
+        let (a, c) = (a_addr as *const TA, c_addr as *mut TC);
+        let (start, end) = split_range(n, 1, t_id, num_tasks);
+        let par_serial = PirePar::new(1, 1, 1, 1, 1, 1);
+        let mut bt = [0 as TB; SRC_B_GEMV_KB * SRC_B_GEMV_NB];
+        let mut j = start;
+        while j < end {
+            let nb = SRC_B_GEMV_NB.min(end - j);
+            let c_j = c.add(j * c_cs);
+            let mut p = 0;
+            while p < k {
+                let kb = SRC_B_GEMV_KB.min(k - p);
+                // row jj of the tile is b[p..p + kb, j + jj]
+                for jj in 0..nb {
+                    b.load_col(p, kb, j + jj, bt.as_mut_ptr().add(jj * kb));
+                }
+                let bt_tile = Array::strided_matrix(bt.as_ptr(), kb, 1);
+                let a_p = Array::strided_matrix(a.add(p * a_cs), a_cs, 1);
+                let c_tile = ArrayMut::strided_matrix(c_j, c_cs, 1);
+                let beta_p = if p == 0 { beta } else { 1.0 };
+                pire_sgemm_fused_par(nb, 1, kb, alpha, bt_tile, a_p, beta_p, c_tile, IdentityFn {}, &par_serial);
+                p += kb;
+            }
+            for jj in 0..nb {
+                f.call_tile(c_j.add(jj * c_cs), 1, 0, j + jj);
+            }
+            j += nb;
+        }
+    });
+}
+
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `b` is given as a [`SrcB`],
+/// each `kc x nc` block of `b` is loaded right before it is packed, into the packing buffers of the gemm
+///
+/// # Safety
+///
+/// `a` and `c` must point to valid memory for `m x k` and `m x n` matrices with their strides,
+/// `b` must load valid values for every `kc_i + kc_len <= k` and `j < n`
+pub unsafe fn pire_sgemm_src_b_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: &SrcB<TB>,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    f: F,
+    par: &PirePar,
+) {
+    if m == 0 || n == 0 {
+        return;
+    }
+    // gemv paths read b in place, so the columns of b they need are loaded here
+    if m == 1 && n > 1 && k > 0 {
+        src_b_gemv_t(n, k, alpha, a, a_cs, b, beta, c, c_cs, f, par);
+        return;
+    }
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    if n == 1 || k == 0 {
+        // b is a single column, or not read at all for k == 0
+        let mut b_col = vec![TB::default(); k];
+        if k > 0 {
+            b.load_col(0, k, 0, b_col.as_mut_ptr());
+        }
+        let b = Array::strided_matrix(b_col.as_ptr(), 1, k);
+        pire_sgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+        return;
+    }
+    // b is given with zero strides, so that the packing of every block gets the SrcB itself
+    let b = Array::strided_matrix(b as *const SrcB<TB> as *const TB, 0, 0);
+    if has_f32_compute() {
+        #[cfg(any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "aarch64",
+            target_arch = "riscv64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
+        {
+            let hw_config = KernelDispatcher::new(f);
+            pire_gemm_src_b(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            return;
+        }
+    }
+    // if none of the optimized paths are available, use reference implementation
+    let hw_config = RefGemm::new(f);
+    reference::pire_gemm_src_b(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+}
+
+/// Safe counterpart of [`pire_sgemm`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_sgemm_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: TC,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| if beta == 0.0 { 0.0 } else { beta * v });
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_sgemm(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
 fn dispatch_round_m() -> fn(usize) -> usize {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f32_compute() {
             return round_m_simd;
@@ -131,7 +507,13 @@ fn dispatch_round_m() -> fn(usize) -> usize {
     round_m_ref
 }
 fn dispatch_round_k() -> fn(usize) -> usize {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f32_compute() {
             return round_k_simd;
@@ -141,7 +523,13 @@ fn dispatch_round_k() -> fn(usize) -> usize {
 }
 
 fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f32_compute() {
             return packa_fn_simd;
@@ -151,7 +539,13 @@ fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize
 }
 
 fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f32_compute() {
             return packb_fn_simd;
@@ -161,7 +555,13 @@ fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize
 }
 
 fn dispatch_get_mcnckc() -> (usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f32_compute() {
             return get_mcnckc_simd();
@@ -176,7 +576,7 @@ pire_base::packing_api!(TA, TB);
 mod tests {
     use super::*;
     use aligned_vec::avec;
-    use pire_base::{get_cache_params, matrix_size};
+    use pire_base::{checked::GemmOperand, get_cache_params, matrix_size};
     use pire_dev::{
         check_gemm_f32, generate_k_dims, generate_m_dims, generate_n_dims, layout_to_strides, random_matrix_uniform,
         ABLayout,
@@ -409,4 +809,304 @@ mod tests {
     fn test_tt_col_apbp() {
         test_gemm(&ABLayout::TT, true, true);
     }
+
+    #[test]
+    fn test_checked() {
+        let (m, n, k) = (7, 5, 3);
+        let a = vec![1f32; m * k];
+        let b = vec![2f32; k * n];
+        let mut c = vec![1f32; m * n];
+        let (a_l, b_l, c_l) = (MatLayout::col_major(m), MatLayout::col_major(k), MatLayout::col_major(m));
+        pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b, b_l, 2.0, &mut c, c_l).unwrap();
+        assert!(c.iter().all(|&x| x == 8.0));
+
+        let res = pire_sgemm_checked(m, n, k, 1.0, &a[1..], a_l, &b, b_l, 2.0, &mut c, c_l);
+        assert_eq!(res, Err(GemmError::BufferTooSmall { operand: GemmOperand::A, required: m * k, len: m * k - 1 }));
+        let res = pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b, MatLayout::strided(0, k), 2.0, &mut c, c_l);
+        assert_eq!(res, Err(GemmError::ZeroStride(GemmOperand::B)));
+        let res = pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b, b_l, 2.0, &mut c, MatLayout::strided(1, 1));
+        assert_eq!(res, Err(GemmError::OverlappingStride));
+        let res = pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b, b_l, 2.0, &mut c[..m * n - 1], c_l);
+        assert_eq!(res, Err(GemmError::BufferTooSmall { operand: GemmOperand::C, required: m * n, len: m * n - 1 }));
+        // extents that wrap around usize must not pass as small
+        let huge = MatLayout::strided(1, usize::MAX / 2 + 1);
+        let res =
+            pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b[..1], MatLayout::strided(1, usize::MAX / 2), 2.0, &mut c, c_l);
+        assert_eq!(res, Err(GemmError::SizeOverflow(GemmOperand::B)));
+        let res = pire_sgemm_checked(m, n, k, 1.0, &a, a_l, &b, b_l, 2.0, &mut c[..1], huge);
+        assert_eq!(res, Err(GemmError::SizeOverflow(GemmOperand::C)));
+
+        // empty product, c = beta * c
+        let mut c = vec![4f32; m * n];
+        pire_sgemm_checked(m, n, 0, 1.0, &[], a_l, &[], b_l, 0.5, &mut c, c_l).unwrap();
+        assert!(c.iter().all(|&x| x == 2.0));
+        let mut c = vec![f32::NAN; m * n];
+        pire_sgemm_checked(m, n, 0, 1.0, &[], a_l, &[], b_l, 0.0, &mut c, c_l).unwrap();
+        assert!(c.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn test_packed_api() {
+        let (m, n, k) = (197, 83, 613);
+        let mut a = vec![0f32; m * k];
+        let mut b = vec![0f32; k * n];
+        random_matrix_uniform(&mut a);
+        random_matrix_uniform(&mut b);
+        let mut ap = avec![[AB_ALIGN]| 0f32; a_size_packed(m, k)];
+        let mut bp = avec![[AB_ALIGN]| 0f32; b_size_packed(n, k)];
+        let ap_array = pack_a(m, k, &a, 1, m, &mut ap);
+        let bp_array = pack_b(n, k, &b, n, 1, &mut bp);
+        let mut c = vec![1f32; m * n];
+        let mut c_ref = c.clone();
+        unsafe {
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+            pire_sgemm_packed(m, n, k, 1.5, ap_array, bp_array, 0.5, c_array, IdentityFn {});
+            pire_sgemm(m, n, k, 1.5, a.as_ptr(), 1, m, b.as_ptr(), n, 1, 0.5, c_ref.as_mut_ptr(), 1, m);
+        }
+        assert_eq!(c, c_ref);
+    }
+
+    #[test]
+    #[should_panic(expected = "packed matrix dims")]
+    fn test_packed_dims_mismatch() {
+        let (m, n, k) = (17, 9, 33);
+        let a = vec![1f32; m * k];
+        let b = vec![1f32; k * n];
+        let mut ap = avec![[AB_ALIGN]| 0f32; a_size_packed(m, k)];
+        let ap_array = pack_a(m, k, &a, 1, m, &mut ap);
+        let mut c = vec![0f32; m * n];
+        unsafe {
+            let b_array = Array::strided_matrix(b.as_ptr(), 1, k);
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m - 1);
+            pire_sgemm_packed(m - 1, n, k, 1.0, ap_array, b_array, 0.0, c_array, IdentityFn {});
+        }
+    }
+
+    #[test]
+    fn test_par() {
+        let (m, n, k) = (97, 53, 41);
+        let a = vec![1f32; m * k];
+        let b = vec![2f32; k * n];
+        for par in [PirePar::single_thread(), PirePar::new(2, 2, 1, 1, 1, 1), PirePar::new(4, 2, 1, 2, 1, 1)] {
+            let mut c = vec![1f32; m * n];
+            unsafe {
+                pire_sgemm_par(m, n, k, 1.0, a.as_ptr(), 1, m, b.as_ptr(), 1, k, 2.0, c.as_mut_ptr(), 1, m, &par);
+            }
+            assert!(c.iter().all(|&x| x == 2.0 * k as f32 + 2.0));
+        }
+    }
+
+    #[test]
+    fn test_batch_strided() {
+        let (batch, m, n, k) = (13, 17, 9, 33);
+        let (stride_a, stride_b, stride_c) = (m * k + 3, k * n, m * n + 1);
+        let mut a = vec![0f32; stride_a * batch];
+        let mut b = vec![0f32; stride_b * batch];
+        random_matrix_uniform(&mut a);
+        random_matrix_uniform(&mut b);
+        let mut c = vec![1f32; stride_c * batch];
+        let mut c_ref = c.clone();
+        unsafe {
+            pire_sgemm_batch_strided(
+                batch,
+                m,
+                n,
+                k,
+                1.0,
+                a.as_ptr(),
+                1,
+                m,
+                stride_a,
+                b.as_ptr(),
+                n,
+                1,
+                stride_b,
+                2.0,
+                c.as_mut_ptr(),
+                1,
+                m,
+                stride_c,
+            );
+            for i in 0..batch {
+                pire_sgemm(
+                    m,
+                    n,
+                    k,
+                    1.0,
+                    a.as_ptr().add(i * stride_a),
+                    1,
+                    m,
+                    b.as_ptr().add(i * stride_b),
+                    n,
+                    1,
+                    2.0,
+                    c_ref.as_mut_ptr().add(i * stride_c),
+                    1,
+                    m,
+                );
+            }
+        }
+        assert_eq!(c, c_ref);
+    }
+
+    #[test]
+    fn test_batch_strided_par() {
+        unsafe fn unary_fn_identity(_c: *mut TC, _m: usize) {}
+        let (m, n, k) = (211, 157, 45);
+        let (stride_a, stride_b, stride_c) = (m * k, k * n, m * n);
+        // one item with a caller layout, fewer items than threads (nested multithreaded gemms), more items than threads
+        let pars = [
+            (1, PirePar::new(4, 2, 1, 2, 1, 1)),
+            (3, PirePar::new(8, 2, 1, 2, 1, 2)),
+            (11, PirePar::new(4, 1, 1, 4, 1, 1)),
+        ];
+        for (batch, par) in pars {
+            let mut a = vec![0f32; stride_a * batch];
+            let mut b = vec![0f32; stride_b * batch];
+            let mut c = vec![0f32; stride_c * batch];
+            random_matrix_uniform(&mut a);
+            random_matrix_uniform(&mut b);
+            random_matrix_uniform(&mut c);
+            let mut c_ref = c.clone();
+            unsafe {
+                pire_sgemm_batch_strided_par(
+                    batch,
+                    m,
+                    n,
+                    k,
+                    1.5,
+                    a.as_ptr(),
+                    1,
+                    m,
+                    stride_a,
+                    b.as_ptr(),
+                    n,
+                    1,
+                    stride_b,
+                    0.5,
+                    c.as_mut_ptr(),
+                    1,
+                    m,
+                    stride_c,
+                    &par,
+                );
+                for i in 0..batch {
+                    let diff_max = check_gemm_f32(
+                        m,
+                        n,
+                        k,
+                        1.5,
+                        a.as_ptr().add(i * stride_a),
+                        1,
+                        m,
+                        b.as_ptr().add(i * stride_b),
+                        n,
+                        1,
+                        0.5,
+                        &c[i * stride_c..(i + 1) * stride_c],
+                        1,
+                        m,
+                        &mut c_ref[i * stride_c..(i + 1) * stride_c],
+                        unary_fn_identity,
+                        EPS,
+                    );
+                    assert!(diff_max < EPS, "batch: {}, item: {}, diff_max: {}", batch, i, diff_max);
+                }
+            }
+        }
+    }
+
+    fn check_grouped(par: Option<&PirePar>) {
+        unsafe fn unary_fn_identity(_c: *mut TC, _m: usize) {}
+        // (m, n, k, size), a and c col major, b row major
+        let dims = [(37, 21, 64, 3), (5, 3, 2, 7), (0, 4, 4, 2), (129, 65, 17, 1)];
+        let groups = dims
+            .iter()
+            .map(|&(m, n, k, size)| GemmGroup {
+                m,
+                n,
+                k,
+                alpha: 1.5,
+                a_rs: 1,
+                a_cs: m,
+                b_rs: n,
+                b_cs: 1,
+                beta: 0.5,
+                c_rs: 1,
+                c_cs: m,
+                size,
+            })
+            .collect::<Vec<_>>();
+        let mut a_vecs = vec![];
+        let mut b_vecs = vec![];
+        let mut c_vecs = vec![];
+        for &(m, n, k, size) in dims.iter() {
+            for _ in 0..size {
+                let mut a = vec![0f32; m * k];
+                let mut b = vec![0f32; k * n];
+                let mut c = vec![0f32; m * n];
+                random_matrix_uniform(&mut a);
+                random_matrix_uniform(&mut b);
+                random_matrix_uniform(&mut c);
+                a_vecs.push(a);
+                b_vecs.push(b);
+                c_vecs.push(c);
+            }
+        }
+        let mut c_ref_vecs = c_vecs.clone();
+        let a_ptrs = a_vecs.iter().map(|x| x.as_ptr()).collect::<Vec<_>>();
+        let b_ptrs = b_vecs.iter().map(|x| x.as_ptr()).collect::<Vec<_>>();
+        let c_ptrs = c_vecs.iter_mut().map(|x| x.as_mut_ptr()).collect::<Vec<_>>();
+        unsafe {
+            match par {
+                Some(par) => pire_sgemm_grouped_par(&groups, &a_ptrs, &b_ptrs, &c_ptrs, par),
+                None => pire_sgemm_grouped(&groups, &a_ptrs, &b_ptrs, &c_ptrs),
+            }
+        }
+        let mut p_id = 0;
+        for g in groups.iter() {
+            for _ in 0..g.size {
+                if g.m != 0 {
+                    let diff_max = unsafe {
+                        check_gemm_f32(
+                            g.m,
+                            g.n,
+                            g.k,
+                            g.alpha,
+                            a_ptrs[p_id],
+                            g.a_rs,
+                            g.a_cs,
+                            b_ptrs[p_id],
+                            g.b_rs,
+                            g.b_cs,
+                            g.beta,
+                            &c_vecs[p_id],
+                            g.c_rs,
+                            g.c_cs,
+                            &mut c_ref_vecs[p_id],
+                            unary_fn_identity,
+                            EPS,
+                        )
+                    };
+                    assert!(diff_max < EPS, "problem: {}, diff_max: {}", p_id, diff_max);
+                } else {
+                    assert_eq!(c_vecs[p_id], c_ref_vecs[p_id]);
+                }
+                p_id += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn test_grouped() {
+        check_grouped(None);
+    }
+
+    #[test]
+    fn test_grouped_par() {
+        // a single thread for all problems, fewer problems than threads (nested multithreaded gemms)
+        for par in [PirePar::new(1, 1, 1, 1, 1, 1), PirePar::new(24, 24, 1, 1, 1, 1)] {
+            check_grouped(Some(&par));
+        }
+    }
 }
@@ -1,8 +1,7 @@
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
-    RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -152,6 +151,8 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     let mut i = 0;
@@ -185,7 +186,7 @@ unsafe fn kernel<F: UnaryFnC>(
                     let c_cur = c.add(i * c_rs + j * c_cs + ii * c_rs + jj * c_cs);
                     *c_cur = *c_cur * *beta + acc[ii * nr_eff + jj] * *alpha;
                     if kc_last {
-                        hw_cfg.func.call(c_cur, 1);
+                        hw_cfg.func.at(i0, j0).call_tile(c_cur, 1, i + ii, j + jj);
                     }
                     acc[ii * nr_eff + jj] = 0.0;
                     jj += 1;
@@ -214,6 +215,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -234,6 +237,8 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -302,3 +307,38 @@ def_pire_gemm!(
     into_pack_array,
     F,
 );
+
+// b given as SrcB, the small m path is off since it reads b without packing
+def_pire_gemm!(
+    RefGemm,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    PackArrTypeASrcB,
+    PackArrTypeBSrcB,
+    1_f32,
+    pire_gemm_src_b,
+    gemm_mt_src_b,
+    gemm_goto_serial_src_b,
+    kernel,
+    gemm_small_m_serial_src_b,
+    kernel_m,
+    gemm_small_n_serial_src_b,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0_src_b,
+    packb0_src_b,
+    packa_fn_ref,
+    packb_fn_ref,
+    false,
+    false,
+    into_pack_array,
+    F,
+    F,
+    T,
+);
@@ -0,0 +1,304 @@
+pub(crate) mod pack_rvv;
+pub(crate) mod rvv;
+
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+};
+
+use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+
+const RVV_NR: usize = 8;
+// kernels set vl explicitly, anything beyond this is left unused
+const RVV_VLEN_MAX: usize = 2048;
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    let (mr, nr) = (unsafe { rvv_vs() * 3 }, RVV_NR);
+    // let mc = std::env::var("PIRE_MC").unwrap_or("4800".to_string()).parse::<usize>().unwrap();
+    // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
+    // let kc = std::env::var("PIRE_KC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
+    // return (mc, nc, kc);
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        _ => (4800, 192, 384),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    let vs = rvv_vs();
+    pack_rvv::packa_panel(m, k, x, rs, cs, y, vs);
+}
+
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    pack_rvv::packb_panel_8(n, k, x, cs, rs, y);
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    let vs = unsafe { rvv_vs() };
+    (m + vs - 1) / vs * vs
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    k
+}
+
+pub(crate) enum RegDim {
+    Rvv,
+}
+
+#[target_feature(enable = "v")]
+pub(crate) unsafe fn rvv_vs() -> usize {
+    // use vlenb csr to get the vector length in bytes
+    let vlenb: u64;
+    core::arch::asm!(
+        "csrr {x0}, vlenb",
+        x0 = out(reg) vlenb,
+    );
+    let vlenb = vlenb.min(RVV_VLEN_MAX as u64 / 8);
+    (vlenb / core::mem::size_of::<TC>() as u64) as usize
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDim,
+    // TODO: Cech jr parallelism is beneificial for perf
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+
+        let vs = unsafe { rvv_vs() };
+        let (mr, nr, reg_dim) = (vs * 3, RVV_NR, RegDim::Rvv);
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            // features,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        k
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Rvv => rvv::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Rvv => rvv::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+unsafe fn kernel_n<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    b: *const TB,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Rvv => rvv::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Rvv => rvv::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+        }
+    }
+}
+
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    match hw_cfg.reg_dim {
+        RegDim::Rvv => rvv::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+    }
+}
+
+def_pire_gemm!(
+    KernelDispatcher,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    PackArrTypeA,
+    PackArrTypeB,
+    1_f32,
+    pire_gemm,
+    gemm_mt,
+    gemm_goto_serial,
+    kernel,
+    gemm_small_m_serial,
+    kernel_m,
+    gemm_small_n_serial,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0,
+    packb0,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    true,
+    into_pack_array,
+    F,
+);
+
+// b given as SrcB, the small m path is off since it reads b without packing
+def_pire_gemm!(
+    KernelDispatcher,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    PackArrTypeASrcB,
+    PackArrTypeBSrcB,
+    1_f32,
+    pire_gemm_src_b,
+    gemm_mt_src_b,
+    gemm_goto_serial_src_b,
+    kernel,
+    gemm_small_m_serial_src_b,
+    kernel_m,
+    gemm_small_n_serial_src_b,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0_src_b,
+    packb0_src_b,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    true,
+    into_pack_array,
+    F,
+    F,
+    T,
+);
@@ -0,0 +1,254 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_rvv,
+    acc_3, acc_2, acc_1,
+    store_3, store_2, store_1,
+    step_3, step_2, step_1,
+};
+use super::super::rvv_vs;
+
+const ONE_SCALAR: TC = 1.0;
+const ZERO_SCALAR: TC = 0.0;
+
+macro_rules! cr {
+    (0,0) => { 8 };
+    (1,0) => { 9 };
+    (2,0) => { 10 };
+
+    (0,1) => { 11 };
+    (1,1) => { 12 };
+    (2,1) => { 13 };
+
+    (0,2) => { 14 };
+    (1,2) => { 15 };
+    (2,2) => { 16 };
+
+    (0,3) => { 17 };
+    (1,3) => { 18 };
+    (2,3) => { 19 };
+
+    (0,4) => { 20 };
+    (1,4) => { 21 };
+    (2,4) => { 22 };
+
+    (0,5) => { 23 };
+    (1,5) => { 24 };
+    (2,5) => { 25 };
+
+    (0,6) => { 26 };
+    (1,6) => { 27 };
+    (2,6) => { 28 };
+
+    (0,7) => { 29 };
+    (1,7) => { 30 };
+    (2,7) => { 31 };
+}
+
+macro_rules! br_3 {
+    (0) => { "ft0" };
+    (1) => { "ft1" };
+    (2) => { "ft2" };
+    (3) => { "ft3" };
+    (4) => { "ft4" };
+    (5) => { "ft5" };
+    (6) => { "ft6" };
+    (7) => { "ft7" };
+}
+
+macro_rules! br_2 {
+    (0) => { "ft0" };
+    (1) => { "ft1" };
+    (2) => { "ft2" };
+    (3) => { "ft3" };
+    (4) => { "ft4" };
+    (5) => { "ft5" };
+    (6) => { "ft6" };
+    (7) => { "ft7" };
+}
+
+macro_rules! br_1 {
+    (0) => { "ft0" };
+    (1) => { "ft1" };
+    (2) => { "ft2" };
+    (3) => { "ft3" };
+    (4) => { "ft4" };
+    (5) => { "ft5" };
+    (6) => { "ft6" };
+    (7) => { "ft7" };
+}
+
+// rvv loads/stores have no offset, so v_i computes the address of the i-th vector into {x8}
+macro_rules! v_i {
+    ($m0:tt, 0) => {
+        concat!("mv {x8}, ", $m0, "\n")
+    };
+    ($m0:tt, 1) => {
+        concat!("add {x8}, ", $m0, ", {vlb}\n")
+    };
+    ($m0:tt, 2) => {
+        concat!(
+            "add {x8}, ", $m0, ", {vlb}\n",
+            "add {x8}, {x8}, {vlb}\n",
+        )
+    };
+}
+
+macro_rules! set_vl {
+    (C) => { "vsetvli zero, {vs}, e32, m1, ta, ma\n /* {m_e} */\n" };
+    (M) => { "vsetvli zero, {m_e}, e32, m1, ta, ma\n" };
+}
+
+macro_rules! inc_a {
+    ($mr:tt) => {
+        ""
+    };
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr,2) => {
+        concat!(
+            $m0,
+            "vle32.v v4, ({x8})\n",
+            "vfmacc.vf v", $r1, ", fa1, v4\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,2) => {
+        concat!(
+            set_vl!(M),
+            beta_fmadd!(C, $m0, $r1, 2),
+            set_vl!(C),
+        )
+    };
+    (C, $m0:expr, $r1:expr,1) => {
+        concat!(
+            $m0,
+            "vle32.v v4, ({x8})\n",
+            "vfadd.vv v", $r1, ", v", $r1, ", v4\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,1) => {
+        concat!(
+            set_vl!(M),
+            beta_fmadd!(C, $m0, $r1, 1),
+            set_vl!(C),
+        )
+    };
+}
+
+macro_rules! vzeroall {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(#("vmv.v.i v", r, ", 0\n",)*)
+        })
+    }
+}
+
+macro_rules! vfmadd {
+    ($i:tt, $j:tt, $b_macro:tt) => {
+        concat!(
+            "vfmacc.vf v", cr!($i,$j), ", ", $b_macro!($j), ", v", $i, "\n",
+        )
+    };
+}
+
+macro_rules! loadp_unit {
+    ($m0:expr, $r1:expr) => {
+        concat!(
+            "vle32.v v", $r1, ", (", $m0, ")\n",
+            "add ", $m0, ", ", $m0, ", {vlb}\n",
+        )
+    };
+}
+
+macro_rules! storep_unit {
+    (C, $r1:expr, $m0:expr) => {
+        concat!(
+            $m0,
+            "vse32.v v", $r1, ", ({x8})\n",
+        )
+    };
+    (M, $r1:expr, $m0:expr) => {
+        concat!(
+            set_vl!(M),
+            storep_unit!(C, $r1, $m0),
+            set_vl!(C),
+        )
+    };
+}
+
+macro_rules! alpha_scale_0 {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(
+                "flw fa0, ({alphax})", "\n",
+                #(
+                    "vfmul.vf v", r, ", v", r, ", fa0\n",
+                )*
+            )
+        })
+    }
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            "flw fa1, ({betax})", "\n",
+        )
+    }
+}
+
+macro_rules! init_ab {
+    (B) => {
+        concat!(
+            "ld {x0}, 24({dim_arrx})", "\n",
+        )
+    };
+}
+
+macro_rules! c_load {
+    () => {
+        concat!(
+            "ld {x0}, 16({dim_arrx})\n",
+            "add {x1}, {cx}, {x0} \n",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+        )
+    };
+}
+
+macro_rules! vzero_kernel {
+    () => {vzeroall!(8,31)};
+}
+
+macro_rules! inc_b {
+    (B,$nr:tt) => {
+        concat!(
+            "addi {bx}, {bx}, ", $nr, "*4 \n",
+        )
+    };
+}
+
+macro_rules! alpha_scale {
+    () => {
+        alpha_scale_0!(8,31)
+    };
+}
+
+macro_rules! load_b {
+    (B, $ni:tt, $b_macro:tt) => {
+        concat!(
+            "flw ", $b_macro!($ni), ", ", $ni, "*4({bx})", "\n",
+        )
+    };
+}
+
+def_ukernel_rvv!(step_1, acc_1, store_1, 1, 8, B, M, ukernel_1_bbp);
+def_ukernel_rvv!(step_2, acc_2, store_2, 2, 8, B, M, ukernel_2_bbp);
+def_ukernel_rvv!(step_3, acc_3, store_3, 3, 8, B, M, ukernel_3_bbp);
+
+def_ukernel_rvv!(step_3, acc_3, store_3, 3, 8, B, C, ukernel_bbc);
@@ -0,0 +1,293 @@
+pub(crate) mod pack_simd128;
+pub(crate) mod simd128;
+
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+};
+
+use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+
+const VS: usize = 4;
+const MR: usize = 8;
+const NR: usize = 4;
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    // let mc = std::env::var("PIRE_MC").unwrap_or("4800".to_string()).parse::<usize>().unwrap();
+    // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
+    // let kc = std::env::var("PIRE_KC").unwrap_or("768".to_string()).parse::<usize>().unwrap();
+    // return (mc, nc, kc);
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        _ => (4800, 320, 192),
+    };
+    (mc, nc, kc)
+}
+
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    pack_simd128::packa_panel_8(m, k, x, rs, cs, y, VS);
+}
+
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    pack_simd128::packb_panel_4(n, k, x, cs, rs, y);
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    let vs = VS;
+    (m + vs - 1) / vs * vs
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    k
+}
+
+pub(crate) enum RegDim {
+    Simd128,
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDim,
+    // TODO: Cech jr parallelism is beneificial for perf
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+
+        let (mr, nr, reg_dim) = (MR, NR, RegDim::Simd128);
+        let vs = VS;
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            // features,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        k
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Simd128 => simd128::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Simd128 => simd128::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    panic!("Not implemented");
+}
+
+unsafe fn kernel_n<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const TA,
+    beta: *const TC,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    b: *const TB,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Simd128 => simd128::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Simd128 => simd128::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+        }
+    }
+}
+
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    match hw_cfg.reg_dim {
+        RegDim::Simd128 => {
+            simd128::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
+    }
+}
+
+def_pire_gemm!(
+    KernelDispatcher,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    PackArrTypeA,
+    PackArrTypeB,
+    1_f32,
+    pire_gemm,
+    gemm_mt,
+    gemm_goto_serial,
+    kernel,
+    gemm_small_m_serial,
+    kernel_m,
+    gemm_small_n_serial,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0,
+    packb0,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    true,
+    into_pack_array,
+    F,
+);
+
+// b given as SrcB, the small m path is off since it reads b without packing
+def_pire_gemm!(
+    KernelDispatcher,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    f32,
+    PackArrTypeASrcB,
+    PackArrTypeBSrcB,
+    1_f32,
+    pire_gemm_src_b,
+    gemm_mt_src_b,
+    gemm_goto_serial_src_b,
+    kernel,
+    gemm_small_m_serial_src_b,
+    kernel_m,
+    gemm_small_n_serial_src_b,
+    kernel_n,
+    pire_gemv,
+    pire_gemv,
+    packa0_src_b,
+    packb0_src_b,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    true,
+    into_pack_array,
+    F,
+    F,
+    T,
+);
@@ -0,0 +1,105 @@
+use core::arch::wasm32::*;
+
+use super::{v_fmadd, v_hsum, v_loadu_n, v_scale_store, v_splat, VS, ZERO};
+use crate::{TA, TB, TC};
+
+const M_UNROLL: usize = 4;
+
+// y[0..ml] = alpha * a[0..ml, 0..n] * x + beta * y[0..ml], ml <= MV * VS
+#[target_feature(enable = "simd128")]
+unsafe fn axpy_v_strip<const MV: usize>(
+    ml: usize,
+    n: usize,
+    alpha_t: TC,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    incx: usize,
+    beta_t: TC,
+    y: *mut TC,
+) {
+    let mut acc = [v_splat(ZERO); MV];
+    for j in 0..n {
+        let xv = v_splat(*x.add(j * incx));
+        let a_j = a.add(j * lda);
+        for i in 0..MV {
+            acc[i] = v_fmadd(v_loadu_n(a_j.add(i * VS), VS.min(ml - i * VS)), xv, acc[i]);
+        }
+    }
+    let alpha_v = v_splat(alpha_t);
+    for i in 0..MV {
+        v_scale_store(y.add(i * VS), acc[i], alpha_v, beta_t, VS.min(ml - i * VS));
+    }
+}
+
+#[target_feature(enable = "simd128")]
+pub(crate) unsafe fn axpy_v(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const TC,
+    y: *mut TC,
+) {
+    let alpha_t = *alpha;
+    let beta_t = *beta;
+    const STRIP: usize = M_UNROLL * VS;
+    let mut mi = 0;
+    while mi + STRIP <= m {
+        axpy_v_strip::<M_UNROLL>(STRIP, n, alpha_t, a.add(mi), lda, x, incx, beta_t, y.add(mi));
+        mi += STRIP;
+    }
+    while mi < m {
+        let ml = VS.min(m - mi);
+        axpy_v_strip::<1>(ml, n, alpha_t, a.add(mi), lda, x, incx, beta_t, y.add(mi));
+        mi += ml;
+    }
+}
+
+// sum(a[0..n] * x[0..n])
+#[target_feature(enable = "simd128")]
+unsafe fn dot(n: usize, a: *const TA, x: *const TB) -> TC {
+    let n_v = n / VS * VS;
+    let mut acc_v = v_splat(ZERO);
+    let mut j = 0;
+    while j < n_v {
+        acc_v = v_fmadd(v128_load(a.add(j) as *const v128), v128_load(x.add(j) as *const v128), acc_v);
+        j += VS;
+    }
+    let mut acc = v_hsum(acc_v);
+    while j < n {
+        acc += *a.add(j) * *x.add(j);
+        j += 1;
+    }
+    acc
+}
+
+#[target_feature(enable = "simd128")]
+pub(crate) unsafe fn axpy_d(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    beta: *const TC,
+    y: *mut TC,
+    incy: usize,
+) {
+    let alpha_t = *alpha;
+    let beta_t = *beta;
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let acc = dot(n, a.add(i * lda), x) * alpha_t;
+        if beta_t == 0.0 {
+            *y_cur = acc;
+        } else if beta_t == 1.0 {
+            *y_cur = *y_cur + acc;
+        } else {
+            *y_cur = *y_cur * beta_t + acc;
+        }
+    }
+}
@@ -1,12 +1,14 @@
 pub(crate) mod neon;
 pub(crate) mod pack_neon;
 pub(crate) mod pack_sve;
+pub(crate) mod pack_sve_mmla;
 pub(crate) mod sve;
+pub(crate) mod sve_mmla;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_mem_pool_size_goto, get_mem_pool_size_small_m,
-    get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range, split_range, Array, ArrayMut,
-    GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -16,11 +18,25 @@ const NEON_MR: usize = 6;
 const NEON_NR: usize = 4;
 
 const SVE_NR: usize = 8;
+const SVE_MMLA_NR: usize = 12;
+
+// the fmmla kernels read a and b in 2x2 blocks, k is padded to even for them.
+// fmmla on f64 works on 256-bit segments, shorter vector lengths stay on the fmla kernels
+fn use_sve_mmla() -> bool {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    features.sve && features.f64mm && unsafe { sve_vs() } % 4 == 0
+}
 
 #[inline(always)]
 pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
-    let (mr, nr) = if features.sve { (unsafe { sve_vs() * 3 }, SVE_NR) } else { (NEON_MR, NEON_NR) };
+    let (mr, nr) = if use_sve_mmla() {
+        (unsafe { sve_vs() * 2 }, SVE_MMLA_NR)
+    } else if features.sve {
+        (unsafe { sve_vs() * 3 }, SVE_NR)
+    } else {
+        (NEON_MR, NEON_NR)
+    };
     // let mc = std::env::var("PIRE_MC").unwrap_or("4800".to_string()).parse::<usize>().unwrap();
     // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
     // let kc = std::env::var("PIRE_KC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
@@ -33,7 +49,10 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
 
 pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
     let hw_config = &*RUNTIME_HW_CONFIG;
-    if hw_config.cpu_ft.sve {
+    if use_sve_mmla() {
+        let vs = unsafe { sve_vs() };
+        pack_sve_mmla::packa_panel(m, k, x, rs, cs, y, vs);
+    } else if hw_config.cpu_ft.sve {
         let vs = unsafe { sve_vs() };
         pack_sve::packa_panel(m, k, x, rs, cs, y, vs);
     } else {
@@ -43,7 +62,9 @@ pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize,
 
 pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
     let hw_config = &*RUNTIME_HW_CONFIG;
-    if hw_config.cpu_ft.sve {
+    if use_sve_mmla() {
+        pack_sve_mmla::packb_panel_12(n, k, x, cs, rs, y);
+    } else if hw_config.cpu_ft.sve {
         pack_sve::packb_panel_8(n, k, x, cs, rs, y);
     } else {
         pack_neon::packb_panel_8(n, k, x, cs, rs, y);
@@ -57,12 +78,17 @@ pub(crate) fn round_m_simd(m: usize) -> usize {
 }
 
 pub(crate) fn round_k_simd(k: usize) -> usize {
-    k
+    if use_sve_mmla() {
+        (k + 1) / 2 * 2
+    } else {
+        k
+    }
 }
 
 pub(crate) enum RegDim {
     Neon,
     Sve,
+    SveMmla,
 }
 
 #[target_feature(enable = "neon,sve")]
@@ -98,7 +124,9 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
         let features = hw_config.cpu_ft();
         let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
 
-        let (mr, nr, reg_dim) = if features.sve {
+        let (mr, nr, reg_dim) = if use_sve_mmla() {
+            (unsafe { sve_vs() * 2 }, SVE_MMLA_NR, RegDim::SveMmla)
+        } else if features.sve {
             (unsafe { sve_vs() * 3 }, SVE_NR, RegDim::Sve)
         } else {
             (NEON_MR, NEON_NR, RegDim::Neon)
@@ -125,7 +153,10 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
     }
 
     pub(crate) fn round_k(&self, k: usize) -> usize {
-        k
+        match self.reg_dim {
+            RegDim::SveMmla => (k + 1) / 2 * 2,
+            _ => k,
+        }
     }
 
     pub(crate) fn round_m(&self, m: usize) -> usize {
@@ -168,18 +199,23 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::SveMmla => sve_mmla::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::SveMmla => sve_mmla::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
         }
     }
 }
@@ -199,6 +235,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -218,18 +256,23 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::SveMmla => sve_mmla::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+            RegDim::SveMmla => sve_mmla::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
         }
     }
 }
@@ -251,7 +294,9 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     match hw_cfg.reg_dim {
         RegDim::Neon => neon::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
 
-        RegDim::Sve => sve::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Sve | RegDim::SveMmla => {
+            sve::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
     }
 }
 
@@ -1,5 +1,9 @@
 #[cfg(target_arch = "aarch64")]
 pub(crate) mod arm64;
+#[cfg(target_arch = "riscv64")]
+pub(crate) mod riscv64;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub(crate) mod wasm32;
 #[cfg(target_arch = "x86_64")]
 pub(crate) mod x86_64_arch;
 #[cfg(target_arch = "x86")]
@@ -18,6 +22,12 @@ use x86_arch::{
 #[cfg(target_arch = "aarch64")]
 use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
 
+#[cfg(target_arch = "riscv64")]
+use riscv64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+use wasm32::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+
 pub(crate) mod reference;
 use core::mem::size_of;
 
@@ -27,9 +37,12 @@ pub(crate) type TC = f64;
 #[allow(unused)]
 const TC_SIZE: usize = size_of::<TC>();
 
-use pire_base::{
-    get_cache_params, has_f64_compute, Array, ArrayMut, GemmCache, IdentityFn, PirePar, UnaryFn, AB_ALIGN,
-};
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::{get_cache_params, has_f64_compute, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
 use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
 
 pub trait UnaryFnC: UnaryFn<TC> {}
@@ -47,17 +60,38 @@ pub(crate) unsafe fn pire_dgemm_fused<F: UnaryFnC>(
     f: F,
 ) {
     let par = PirePar::default(m, n);
+    pire_dgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_dgemm_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
     if has_f64_compute() {
-        #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+        #[cfg(any(
+            target_arch = "x86_64",
+            target_arch = "x86",
+            target_arch = "aarch64",
+            target_arch = "riscv64",
+            all(target_arch = "wasm32", target_feature = "simd128")
+        ))]
         {
             let hw_config = KernelDispatcher::new(f);
-            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
             return;
         }
     }
     // if none of the optimized paths are available, use reference implementation
     let hw_config = RefGemm::new(f);
-    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
 }
 pub unsafe fn pire_dgemm(
     m: usize,
@@ -88,6 +122,120 @@ pub unsafe fn pire_dgemm(
     pire_dgemm_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
 }
 
+/// Same as [`pire_dgemm`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_dgemm_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+        (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    } else {
+        (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_dgemm_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_dgemm`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_dgemm_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_dgemm_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_dgemm_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_dgemm_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: TC,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_dgemm_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_dgemm`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_dgemm_grouped(groups: &[GemmGroup<TA, TC>], a: &[*const TA], b: &[*const TB], c: &[*mut TC]) {
+    pire_dgemm_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_dgemm_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_dgemm_grouped_par(
+    groups: &[GemmGroup<TA, TC>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_dgemm_par(g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par);
+    });
+}
+
 #[cfg(feature = "fuse")]
 pub unsafe fn pire_dgemm_fn_ptr(
     m: usize,
@@ -118,8 +266,104 @@ pub unsafe fn pire_dgemm_fn_ptr(
     pire_dgemm_fused(m, n, k, alpha, a, b, beta, c, unary);
 }
 
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_dgemm_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_dgemm_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_dgemm_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_dgemm_packed`]
+pub unsafe fn pire_dgemm_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: TC,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_dgemm_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Safe counterpart of [`pire_dgemm`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_dgemm_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: TA,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: TC,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| if beta == 0.0 { 0.0 } else { beta * v });
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_dgemm(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
 fn dispatch_round_m() -> fn(usize) -> usize {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f64_compute() {
             return round_m_simd;
@@ -128,7 +372,13 @@ fn dispatch_round_m() -> fn(usize) -> usize {
     round_m_ref
 }
 fn dispatch_round_k() -> fn(usize) -> usize {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f64_compute() {
             return round_k_simd;
@@ -138,7 +388,13 @@ fn dispatch_round_k() -> fn(usize) -> usize {
 }
 
 fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f64_compute() {
             return packa_fn_simd;
@@ -148,7 +404,13 @@ fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize
 }
 
 fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f64_compute() {
             return packb_fn_simd;
@@ -158,7 +420,13 @@ fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize
 }
 
 fn dispatch_get_mcnckc() -> (usize, usize, usize) {
-    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "riscv64",
+        all(target_arch = "wasm32", target_feature = "simd128")
+    ))]
     {
         if has_f64_compute() {
             return get_mcnckc_simd();
@@ -406,4 +674,120 @@ mod tests {
     fn test_tt_col_apbp() {
         test_gemm(&ABLayout::TT, true, true);
     }
+
+    #[test]
+    fn test_packed_api() {
+        let (m, n, k) = (197, 83, 613);
+        let mut a = vec![0f64; m * k];
+        let mut b = vec![0f64; k * n];
+        random_matrix_uniform(&mut a);
+        random_matrix_uniform(&mut b);
+        let mut ap = avec![[AB_ALIGN]| 0f64; a_size_packed(m, k)];
+        let mut bp = avec![[AB_ALIGN]| 0f64; b_size_packed(n, k)];
+        let ap_array = pack_a(m, k, &a, 1, m, &mut ap);
+        let bp_array = pack_b(n, k, &b, n, 1, &mut bp);
+        let mut c = vec![1f64; m * n];
+        let mut c_ref = c.clone();
+        unsafe {
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+            pire_dgemm_packed(m, n, k, 1.5, ap_array, bp_array, 0.5, c_array, IdentityFn {});
+            pire_dgemm(m, n, k, 1.5, a.as_ptr(), 1, m, b.as_ptr(), n, 1, 0.5, c_ref.as_mut_ptr(), 1, m);
+        }
+        assert_eq!(c, c_ref);
+    }
+
+    #[test]
+    #[should_panic(expected = "packed matrix dims")]
+    fn test_packed_dims_mismatch() {
+        let (m, n, k) = (17, 9, 33);
+        let a = vec![1f64; m * k];
+        let b = vec![1f64; k * n];
+        let mut ap = avec![[AB_ALIGN]| 0f64; a_size_packed(m, k)];
+        let ap_array = pack_a(m, k, &a, 1, m, &mut ap);
+        let mut c = vec![0f64; m * n];
+        unsafe {
+            let b_array = Array::strided_matrix(b.as_ptr(), 1, k);
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m - 1);
+            pire_dgemm_packed(m - 1, n, k, 1.0, ap_array, b_array, 0.0, c_array, IdentityFn {});
+        }
+    }
+
+    #[test]
+    fn test_grouped() {
+        unsafe fn unary_fn_identity(_c: *mut TC, _m: usize) {}
+        // (m, n, k, size), a and c col major, b row major
+        let dims = [(37, 21, 64, 3), (5, 3, 2, 7), (0, 4, 4, 2), (129, 65, 17, 1)];
+        let groups = dims
+            .iter()
+            .map(|&(m, n, k, size)| GemmGroup {
+                m,
+                n,
+                k,
+                alpha: 1.5,
+                a_rs: 1,
+                a_cs: m,
+                b_rs: n,
+                b_cs: 1,
+                beta: 0.5,
+                c_rs: 1,
+                c_cs: m,
+                size,
+            })
+            .collect::<Vec<_>>();
+        let mut a_vecs = vec![];
+        let mut b_vecs = vec![];
+        let mut c_vecs = vec![];
+        for &(m, n, k, size) in dims.iter() {
+            for _ in 0..size {
+                let mut a = vec![0f64; m * k];
+                let mut b = vec![0f64; k * n];
+                let mut c = vec![0f64; m * n];
+                random_matrix_uniform(&mut a);
+                random_matrix_uniform(&mut b);
+                random_matrix_uniform(&mut c);
+                a_vecs.push(a);
+                b_vecs.push(b);
+                c_vecs.push(c);
+            }
+        }
+        let mut c_ref_vecs = c_vecs.clone();
+        let a_ptrs = a_vecs.iter().map(|x| x.as_ptr()).collect::<Vec<_>>();
+        let b_ptrs = b_vecs.iter().map(|x| x.as_ptr()).collect::<Vec<_>>();
+        let c_ptrs = c_vecs.iter_mut().map(|x| x.as_mut_ptr()).collect::<Vec<_>>();
+        unsafe {
+            pire_dgemm_grouped(&groups, &a_ptrs, &b_ptrs, &c_ptrs);
+        }
+        let mut p_id = 0;
+        for g in groups.iter() {
+            for _ in 0..g.size {
+                if g.m != 0 {
+                    let diff_max = unsafe {
+                        check_gemm_f64(
+                            g.m,
+                            g.n,
+                            g.k,
+                            g.alpha,
+                            a_ptrs[p_id],
+                            g.a_rs,
+                            g.a_cs,
+                            b_ptrs[p_id],
+                            g.b_rs,
+                            g.b_cs,
+                            g.beta,
+                            &c_vecs[p_id],
+                            g.c_rs,
+                            g.c_cs,
+                            &mut c_ref_vecs[p_id],
+                            unary_fn_identity,
+                            EPS,
+                        )
+                    };
+                    assert!(diff_max < EPS, "problem: {}, diff_max: {}", p_id, diff_max);
+                } else {
+                    assert_eq!(c_vecs[p_id], c_ref_vecs[p_id]);
+                }
+                p_id += 1;
+            }
+        }
+    }
 }
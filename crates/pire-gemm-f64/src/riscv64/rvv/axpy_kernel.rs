@@ -0,0 +1,145 @@
+use core::arch::asm;
+use core::mem::size_of;
+
+use super::super::rvv_vs;
+use crate::{TA, TB, TC};
+
+// no stable rvv intrinsics in core::arch, kernels below are strip-mined loops in asm with lmul=4
+
+// y[0..vl] = alpha * a[0..vl, 0..n] * x + beta * y[0..vl]
+#[target_feature(enable = "v")]
+unsafe fn axpy_v_strip(
+    vl: usize,
+    n: usize,
+    alpha_t: TC,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    incx: usize,
+    beta_t: TC,
+    y: *mut TC,
+) {
+    let beta_st = if beta_t == 0.0 { 0usize } else { 1usize };
+    asm!(
+        "vsetvli zero, {vl}, e64, m4, ta, ma",
+        "vmv.v.i v8, 0",
+        "beqz {n}, 3f",
+        "2:",
+        "fld ft0, ({x})",
+        "vle64.v v16, ({a})",
+        "vfmacc.vf v8, ft0, v16",
+        "add {a}, {a}, {lda}",
+        "add {x}, {x}, {incx}",
+        "addi {n}, {n}, -1",
+        "bnez {n}, 2b",
+        "3:",
+        "vfmul.vf v8, v8, {alpha}",
+        "beqz {beta_st}, 4f",
+        "vle64.v v16, ({y})",
+        "vfmacc.vf v8, {beta}, v16",
+        "4:",
+        "vse64.v v8, ({y})",
+        vl = in(reg) vl,
+        n = inout(reg) n => _,
+        a = inout(reg) a => _,
+        lda = in(reg) lda * size_of::<TA>(),
+        x = inout(reg) x => _,
+        incx = in(reg) incx * size_of::<TB>(),
+        y = in(reg) y,
+        alpha = in(freg) alpha_t,
+        beta = in(freg) beta_t,
+        beta_st = in(reg) beta_st,
+        out("ft0") _,
+        out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+        out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+        options(nostack),
+    );
+}
+
+// sum(a[0..n] * x[0..n])
+#[target_feature(enable = "v")]
+unsafe fn dot(n: usize, a: *const TA, x: *const TB) -> TC {
+    let acc: TC;
+    // tail undisturbed inside the loop so that lanes past the last (shorter) strip keep their partial sums
+    asm!(
+        "vsetvli {t}, zero, e64, m4, ta, ma",
+        "vmv.v.i v8, 0",
+        "beqz {n}, 3f",
+        "2:",
+        "vsetvli {t}, {n}, e64, m4, tu, ma",
+        "vle64.v v16, ({a})",
+        "vle64.v v24, ({x})",
+        "vfmacc.vv v8, v16, v24",
+        "sub {n}, {n}, {t}",
+        "slli {t}, {t}, 3",
+        "add {a}, {a}, {t}",
+        "add {x}, {x}, {t}",
+        "bnez {n}, 2b",
+        "3:",
+        "vsetvli {t}, zero, e64, m4, ta, ma",
+        "vmv.s.x v0, zero",
+        "vfredusum.vs v0, v8, v0",
+        "vfmv.f.s {acc}, v0",
+        n = inout(reg) n => _,
+        a = inout(reg) a => _,
+        x = inout(reg) x => _,
+        t = out(reg) _,
+        acc = out(freg) acc,
+        out("v0") _,
+        out("v8") _, out("v9") _, out("v10") _, out("v11") _,
+        out("v16") _, out("v17") _, out("v18") _, out("v19") _,
+        out("v24") _, out("v25") _, out("v26") _, out("v27") _,
+        options(nostack, readonly),
+    );
+    acc
+}
+
+#[target_feature(enable = "v")]
+pub(crate) unsafe fn axpy_v(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const TC,
+    y: *mut TC,
+) {
+    let alpha_t = *alpha;
+    let beta_t = *beta;
+    let vl_max = rvv_vs() * 4;
+    let mut mi = 0;
+    while mi < m {
+        let vl = vl_max.min(m - mi);
+        axpy_v_strip(vl, n, alpha_t, a.add(mi), lda, x, incx, beta_t, y.add(mi));
+        mi += vl;
+    }
+}
+
+#[target_feature(enable = "v")]
+pub(crate) unsafe fn axpy_d(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    lda: usize,
+    x: *const TB,
+    beta: *const TC,
+    y: *mut TC,
+    incy: usize,
+) {
+    let alpha_t = *alpha;
+    let beta_t = *beta;
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let acc = dot(n, a.add(i * lda), x) * alpha_t;
+        if beta_t == 0.0 {
+            *y_cur = acc;
+        } else if beta_t == 1.0 {
+            *y_cur = *y_cur + acc;
+        } else {
+            *y_cur = *y_cur * beta_t + acc;
+        }
+    }
+}
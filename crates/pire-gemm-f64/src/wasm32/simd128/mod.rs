@@ -0,0 +1,117 @@
+mod axpy_kernel;
+mod ukernel;
+
+use axpy_kernel::*;
+use ukernel::*;
+
+use core::arch::wasm32::*;
+use core::ptr::copy_nonoverlapping;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+const VS: usize = 2;
+const VS_MAX: usize = VS;
+
+const ZERO: f64 = 0.0;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+// wasm simd has no fma in the stable instruction set, so mul + add
+#[inline(always)]
+unsafe fn v_fmadd(a: v128, b: v128, c: v128) -> v128 {
+    f64x2_add(f64x2_mul(a, b), c)
+}
+
+#[inline(always)]
+unsafe fn v_loadu_n(mem_addr: *const TC, n: usize) -> v128 {
+    if n == VS {
+        return v128_load(mem_addr as *const v128);
+    }
+    let mut a_arr = [ZERO; VS];
+    copy_nonoverlapping(mem_addr, a_arr.as_mut_ptr(), n);
+    v128_load(a_arr.as_ptr() as *const v128)
+}
+
+#[inline(always)]
+unsafe fn v_storeu_n(mem_addr: *mut TC, a: v128, n: usize) {
+    if n == VS {
+        v128_store(mem_addr as *mut v128, a);
+        return;
+    }
+    let mut a_arr = [ZERO; VS];
+    v128_store(a_arr.as_mut_ptr() as *mut v128, a);
+    copy_nonoverlapping(a_arr.as_ptr(), mem_addr, n);
+}
+
+// c[0..n] = alpha * acc + beta * c[0..n], c is not read when beta is zero
+#[inline(always)]
+unsafe fn v_scale_store(c: *mut TC, acc: v128, alpha_v: v128, beta_t: TC, n: usize) {
+    let mut cv = f64x2_mul(acc, alpha_v);
+    if beta_t == 1.0 {
+        cv = f64x2_add(v_loadu_n(c, n), cv);
+    } else if beta_t != 0.0 {
+        cv = v_fmadd(v_loadu_n(c, n), f64x2_splat(beta_t), cv);
+    }
+    v_storeu_n(c, cv, n);
+}
+
+#[inline(always)]
+unsafe fn v_splat(a: TC) -> v128 {
+    f64x2_splat(a)
+}
+
+#[inline(always)]
+unsafe fn v_hsum(a: v128) -> TC {
+    f64x2_extract_lane::<0>(a) + f64x2_extract_lane::<1>(a)
+}
+
+#[target_feature(enable = "simd128")]
+pub unsafe fn axpy<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const TA,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const TC,
+    y: *mut TC,
+    incy: usize,
+    f: F,
+) {
+    if a_cs == 1 && incx == 1 {
+        axpy_d(m, n, alpha, a, a_rs, x, beta, y, incy);
+        for i in 0..m {
+            f.call(y.add(i * incy), 1);
+        }
+        return;
+    }
+    if a_rs == 1 && incy == 1 {
+        axpy_v(m, n, alpha, a, a_cs, x, incx, beta, y);
+        f.call(y, m);
+        return;
+    }
+
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = 0.0;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = x.add(j * incx);
+            acc += *a_cur * *x_cur;
+        }
+        *y_cur = *beta * *y_cur + *alpha * acc;
+        f.call(y_cur, 1);
+    }
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(TA, TB, TC, TC, false, F, 1, 2, 4, 0, 0);
+
+use super::pack_simd128::packa_panel_4;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(TA, TA, TB, TC, TC, false, F, packa_panel_4, 1, 2, 4, 0, 0);
@@ -0,0 +1,82 @@
+use core::arch::wasm32::*;
+
+use seq_macro::seq;
+
+use super::{v_fmadd, v_scale_store, v_splat, VS, ZERO};
+use crate::{TA, TB, TC};
+
+// no inline asm on wasm, the micro kernel is written with intrinsics and left to the wasm runtime to
+// allocate registers, MR_V x N accumulators fit into the 16 xmm registers of x86 hosts
+
+#[target_feature(enable = "simd128")]
+unsafe fn ukernel_v<const MR_V: usize, const N: usize>(
+    a: *const TA,
+    b: *const TB,
+    c: *mut TC,
+    alpha: *const TA,
+    beta: *const TC,
+    k: usize,
+    c_cs: usize,
+    m: usize,
+) {
+    let mut acc = [[v_splat(ZERO); MR_V]; N];
+    let mut a = a;
+    let mut b = b;
+    let mut k_i = 0;
+    while k_i < k {
+        let mut av = [v_splat(ZERO); MR_V];
+        for i in 0..MR_V {
+            av[i] = v128_load(a.add(i * VS) as *const v128);
+        }
+        for j in 0..N {
+            let bv = v_splat(*b.add(j));
+            for i in 0..MR_V {
+                acc[j][i] = v_fmadd(av[i], bv, acc[j][i]);
+            }
+        }
+        a = a.add(MR_V * VS);
+        b = b.add(N);
+        k_i += 1;
+    }
+
+    let alpha_v = v_splat(*alpha);
+    let beta_t = *beta;
+    for j in 0..N {
+        let c_j = c.add(j * c_cs);
+        for i in 0..MR_V {
+            // only the last vector of a partial panel is shorter than VS
+            let m_i = VS.min(m - i * VS);
+            v_scale_store(c_j.add(i * VS), acc[j][i], alpha_v, beta_t, m_i);
+        }
+    }
+}
+
+macro_rules! def_ukernel_simd128 {
+    ($mr_v:tt, $func_name:ident) => {
+        #[target_feature(enable = "simd128")]
+        pub(crate) unsafe fn $func_name(
+            a: *const TA,
+            b: *const TB,
+            c: *mut TC,
+            alpha: *const TA,
+            beta: *const TC,
+            k: usize,
+            _d_arr: [usize; 2],
+            c_cs: usize,
+            m: usize,
+            n: usize,
+        ) {
+            seq!(NI in 1..=4 {
+                if n == NI {
+                    ukernel_v::<$mr_v, NI>(a, b, c, alpha, beta, k, c_cs, m);
+                    return;
+                }
+            });
+        }
+    };
+}
+
+def_ukernel_simd128!(1, ukernel_1_bbp);
+def_ukernel_simd128!(2, ukernel_2_bbp);
+
+def_ukernel_simd128!(2, ukernel_bbc);
@@ -0,0 +1,187 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_avx, def_ukernel_avx_2,
+    init_ab_avx, init_ab_2, 
+    acc_3, acc_2, acc_1,
+    store_3, store_2, store_1, 
+    step_3, step_2, step_1,
+    mem, b_mem,
+};
+
+use super::super::avx::asm_ukernel::{
+    mask_ptr, load_mask,
+    bs, v_i, mask_and_offset,
+    vzeroall, vbroadcast,
+    loadp_unit, storep_unit,
+    alpha_scale_0,
+};
+
+
+type TS = TC;
+
+const ZERO_SCALAR: f64 = 0.0;
+const ONE_SCALAR: f64 = 1.0;
+
+macro_rules! vs {
+    () => { "0x20" };
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr, 1) => {
+        concat!(
+            "vaddpd ", $m0, ",%ymm", $r1, ",%ymm", $r1, "\n",
+        ) 
+    };
+    (P, $m0:expr, $r1:expr, 1) => {
+        concat!(
+            "vmaskmovpd ", $m0, ", %ymm1", ", %ymm2",  "\n",
+            "vaddpd %ymm2, %ymm", $r1, ",%ymm", $r1, "\n",
+        ) 
+    };
+    (C, $m0:expr, $r1:expr, 2) => {
+        concat!(
+            "vfmaddpd %ymm", $r1, ", ", $m0, ", %ymm0, %ymm", $r1, "\n",
+        ) 
+    };
+    (P, $m0:expr, $r1:expr, 2) => {
+        concat!(
+            "vmaskmovpd ", $m0, ", %ymm1", ", %ymm2",  "\n",
+            "vfmaddpd %ymm", $r1, ", %ymm2, %ymm0, %ymm", $r1, "\n",
+        ) 
+    };
+}
+
+macro_rules! c_load {
+    () => {
+        concat!(
+            "mov 16({dim_arrx}),{x0}\n",
+            "lea ({x0}, {x0}, 2), {x3}\n",
+            "lea ({cx}, {x3},), {x1}\n",
+            "lea ({x1}, {x3},), {x2}\n",
+            "lea ({x2}, {x3},), {x3}\n",
+        )
+    };
+}
+
+macro_rules! c_load_2 {
+    () => {
+        concat!(
+            "mov ({dim_arrx}),{x0}\n",
+            "lea ({x0}, {x0}, 2), {x3}\n",
+            "lea ({cx}, {x3},), {x1}\n",
+            "lea ({x1}, {x3},), {x2}\n",
+            "lea ({x2}, {x3},), {x3}\n",
+        )
+    };
+}
+
+macro_rules! vfmadd {
+    ($i:tt, $j:tt, $b_macro:tt) => {
+        concat!(
+            "vfmaddpd %ymm", cr!($i,$j), ", %ymm", $i, ", %ymm", $b_macro!($j), ", %ymm", cr!($i,$j), "\n",
+        )
+    };
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            vbroadcast!(), " ({betax}), %ymm0\n",
+        )
+    }
+}
+
+macro_rules! inc_b {
+    (S,$nr:tt) => { "add {x1},{bx} \n add {x1},{x3} \n" };
+    (B,$nr:tt) => { "" };
+    ($nr:tt) => { "" };
+}
+
+macro_rules! inc_b_k_unroll {
+    (S, $X:tt, $K:tt) => { "" };
+    (B, $X:tt, $K:tt) => {
+        concat!(
+            "add $8*", $K, "*", $X, ", {bx}", "\n",
+        )
+    };
+}
+
+macro_rules! vzero_kernel {
+    () => { vzeroall!(4,15) };
+}
+
+macro_rules! alpha_scale {
+    () => { alpha_scale_0!(4,15) };
+}
+
+macro_rules! prefetch {
+    (B, $nr:tt, 0, 0) => {
+        "prefetcht0 384({bx})\n"
+    };
+    (B, $nr:tt, 0, 2) => {
+        "prefetcht0 384+64({bx})\n"
+    };
+    ($b_layout:tt, $nr:tt, $ni:tt, $K:tt) => {
+        ""
+    };
+}
+
+macro_rules! load_b {
+    ($b_layout:tt, $nr:tt, $ni:tt, $K:tt, $b_macro:tt) => {
+        concat!(
+            prefetch!($b_layout, $nr, $ni, $K),
+            vbroadcast!(), " ", b_mem!($b_layout,$nr,$ni,$K), ",%ymm", $b_macro!($ni), "\n",
+        )
+    };
+}
+
+macro_rules! br_3 {
+    ($nr:tt) => { 3 };
+}
+
+macro_rules! br_2 {
+    (0) => { 2 };
+    (1) => { 3 };
+    (2) => { 2 };
+    (3) => { 3 };
+}
+
+macro_rules! br_1 {
+    (0) => { 1 };
+    (1) => { 2 };
+    (2) => { 3 };
+    (3) => { 5 };
+}
+
+macro_rules! cr {
+    (0,0) => { 4 };
+    (1,0) => { 5 };
+    (2,0) => { 6 };
+    (0,1) => { 7 };
+    (1,1) => { 8 };
+    (2,1) => { 9 };
+    (0,2) => { 10 };
+    (1,2) => { 11 };
+    (2,2) => { 12 };
+    (0,3) => { 13 };
+    (1,3) => { 14 };
+    (2,3) => { 15 };
+}
+
+def_ukernel_avx!(1, step_3, acc_3, store_3, 3, 4, B, P, ukernel_3_bbp);
+def_ukernel_avx!(1, step_2, acc_2, store_2, 2, 4, B, P, ukernel_2_bbp);
+def_ukernel_avx!(1, step_1, acc_1, store_1, 1, 4, B, P, ukernel_1_bbp);
+
+def_ukernel_avx!(1, step_3, acc_3, store_3, 3, 4, S, C, ukernel_bsc);
+
+def_ukernel_avx!(1, step_3, acc_3, store_3, 3, 4, S, P, ukernel_3_bsp);
+def_ukernel_avx!(1, step_2, acc_2, store_2, 2, 4, S, P, ukernel_2_bsp);
+def_ukernel_avx!(1, step_1, acc_1, store_1, 1, 4, S, P, ukernel_1_bsp);
+
+// based on l1 prefetching scheme is from openblas impl for skylax
+// see: https://github.com/OpenMathLib/OpenBLAS/pull/2300
+// this is adapted to our ukernel of 3
+// seems to stem from high bandwith of l1 cache (compared to other uarch e.g. haswell
+// where the same l1 prefetching does not benefit as much)
+def_ukernel_avx_2!(1, step_3, acc_3, store_3, 3, 4, 4, 64);
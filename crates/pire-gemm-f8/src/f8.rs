@@ -0,0 +1,134 @@
+/// 8-bit float with 4 exponent and 3 mantissa bits (OCP E4M3FN), no infinities, max finite value 448
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct F8E4M3(pub u8);
+
+/// 8-bit float with 5 exponent and 2 mantissa bits (OCP E5M2), ieee like with infinities, max finite value 57344
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct F8E5M2(pub u8);
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for super::F8E4M3 {}
+    impl Sealed for super::F8E5M2 {}
+}
+
+/// Common interface of the fp8 formats
+pub trait Fp8: sealed::Sealed + Copy + Default + Sync + Send + 'static {
+    const EXP_BITS: u32;
+    const MAN_BITS: u32;
+    const BIAS: i32;
+    /// Largest finite bit pattern without the sign bit
+    const MAX_BITS: u8;
+    /// Whether all ones exponent encodes inf / nan (E5M2) or only the all ones pattern is nan (E4M3)
+    const IEEE: bool;
+
+    fn from_bits(x: u8) -> Self;
+    fn to_bits(self) -> u8;
+
+    #[inline(always)]
+    fn to_f32(self) -> f32 {
+        f8_to_f32::<Self>(self.to_bits())
+    }
+
+    /// Rounds to nearest even, values out of range saturate to the largest finite value
+    #[inline]
+    fn from_f32(x: f32) -> Self {
+        Self::from_bits(f32_to_f8::<Self>(x))
+    }
+}
+
+impl F8E4M3 {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(0x7E);
+}
+
+impl F8E5M2 {
+    pub const ZERO: Self = Self(0);
+    pub const MAX: Self = Self(0x7B);
+}
+
+impl Fp8 for F8E4M3 {
+    const EXP_BITS: u32 = 4;
+    const MAN_BITS: u32 = 3;
+    const BIAS: i32 = 7;
+    const MAX_BITS: u8 = 0x7E;
+    const IEEE: bool = false;
+
+    #[inline(always)]
+    fn from_bits(x: u8) -> Self {
+        Self(x)
+    }
+    #[inline(always)]
+    fn to_bits(self) -> u8 {
+        self.0
+    }
+}
+
+impl Fp8 for F8E5M2 {
+    const EXP_BITS: u32 = 5;
+    const MAN_BITS: u32 = 2;
+    const BIAS: i32 = 15;
+    const MAX_BITS: u8 = 0x7B;
+    const IEEE: bool = true;
+
+    #[inline(always)]
+    fn from_bits(x: u8) -> Self {
+        Self(x)
+    }
+    #[inline(always)]
+    fn to_bits(self) -> u8 {
+        self.0
+    }
+}
+
+#[inline(always)]
+fn f8_to_f32<T: Fp8>(x: u8) -> f32 {
+    let sign = ((x >> 7) as u32) << 31;
+    let exp_mask = (1u32 << T::EXP_BITS) - 1;
+    let exp = (x as u32 >> T::MAN_BITS) & exp_mask;
+    let man = x as u32 & ((1 << T::MAN_BITS) - 1);
+    if exp == exp_mask && (T::IEEE || man == (1 << T::MAN_BITS) - 1) {
+        let nan_or_inf = if T::IEEE && man == 0 { 0x7f80_0000 } else { 0x7fc0_0000 };
+        return f32::from_bits(sign | nan_or_inf);
+    }
+    if exp == 0 {
+        // subnormal, man * 2^(1 - bias - man_bits) is exact in f32
+        let v = man as f32 * f32::from_bits(((127 + 1 - T::BIAS - T::MAN_BITS as i32) as u32) << 23);
+        return f32::from_bits(sign | v.to_bits());
+    }
+    let exp_f32 = (exp as i32 - T::BIAS + 127) as u32;
+    f32::from_bits(sign | (exp_f32 << 23) | (man << (23 - T::MAN_BITS)))
+}
+
+#[inline]
+fn round_ne(x: f32) -> f32 {
+    let r = x.round();
+    if (r - x).abs() == 0.5 && r % 2.0 != 0.0 {
+        r - x.signum()
+    } else {
+        r
+    }
+}
+
+fn f32_to_f8<T: Fp8>(x: f32) -> u8 {
+    let sign = ((x.to_bits() >> 31) as u8) << 7;
+    if x.is_nan() {
+        return sign | 0x7F;
+    }
+    let x = x.abs();
+    let max = f8_to_f32::<T>(T::MAX_BITS);
+    if x >= max {
+        return sign | T::MAX_BITS;
+    }
+    let min_normal_exp = 1 - T::BIAS;
+    // exponent of x in f32, values below the normal range of fp8 use the subnormal scale
+    let exp = ((x.to_bits() >> 23) as i32 - 127).max(min_normal_exp);
+    let scale = f32::from_bits(((T::MAN_BITS as i32 - exp + 127) as u32) << 23);
+    // x * scale is exact since scale is a power of two
+    let q = round_ne(x * scale) as u32;
+    // q includes the implicit bit of normal values, so a carry into the next binade is handled by the addition
+    let bits = (((exp - min_normal_exp) as u32) << T::MAN_BITS) + q;
+    sign | (bits as u8).min(T::MAX_BITS)
+}
@@ -0,0 +1,543 @@
+//! Gemm with fp8 (E4M3 / E5M2) `a` and `b` and f32 `c`
+//!
+//! fp8 values are widened to f32 while packing and multiplied with the f32 kernels, per tensor scales
+//! of `a` and `b` are folded into `alpha`. Every combination of the two formats for `a` and `b` is supported,
+//! e.g. [`pire_gemm_e4m3e5m2f32`] for E4M3 activations times E5M2 gradients.
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86_64_arch;
+
+pub(crate) mod f8;
+pub(crate) mod reference;
+
+pub use f8::{Fp8, F8E4M3, F8E5M2};
+
+#[cfg(target_arch = "x86_64")]
+use x86_64_arch::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, round_k_simd, round_m_simd, KernelDispatcher};
+
+use core::mem::size_of;
+
+pub(crate) type TC = f32;
+#[allow(unused)]
+const TC_SIZE: usize = size_of::<TC>();
+
+use paste::paste;
+use pire_base::{get_cache_params, has_f8f32_compute, GemmCache, UnaryFn};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
+use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
+
+pub trait UnaryFnC: UnaryFn<TC> {}
+impl<F: UnaryFn<TC>> UnaryFnC for F {}
+
+macro_rules! def_gemm_f8 {
+    ($ta:ty, $tb:ty, $name:ident) => {
+        paste! {
+            pub(crate) unsafe fn [<pire_gemm_ $name f32_fused_par>]<F: UnaryFnC>(
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: f32,
+                a: Array<$ta>,
+                b: Array<$tb>,
+                beta: f32,
+                c: ArrayMut<TC>,
+                f: F,
+                par: &PirePar,
+            ) {
+                if has_f8f32_compute() {
+                    #[cfg(target_arch = "x86_64")]
+                    {
+                        let hw_config = KernelDispatcher::new(f);
+                        x86_64_arch::[<pire_gemm_ $name>](&hw_config, m, n, k, alpha, a, b, beta, c, par);
+                        return;
+                    }
+                }
+
+                // if none of the optimized paths are available, use reference implementation
+                let hw_config = RefGemm::new(f);
+                reference::[<pire_gemm_ $name>](&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            }
+
+            /// Computes `c = alpha * (scale_a * a) * (scale_b * b) + beta * c`, where `scale_a` and `scale_b`
+            /// are the per tensor scales of `a` and `b`
+            ///
+            /// # Safety
+            ///
+            /// `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices with their strides
+            pub unsafe fn [<pire_gemm_ $name f32>](
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: f32,
+                a: *const $ta,
+                a_rs: usize,
+                a_cs: usize,
+                scale_a: f32,
+                b: *const $tb,
+                b_rs: usize,
+                b_cs: usize,
+                scale_b: f32,
+                beta: f32,
+                c: *mut TC,
+                c_rs: usize,
+                c_cs: usize,
+            ) {
+                let par = PirePar::default(m, n);
+                [<pire_gemm_ $name f32_par>](
+                    m, n, k, alpha, a, a_rs, a_cs, scale_a, b, b_rs, b_cs, scale_b, beta, c, c_rs, c_cs, &par,
+                );
+            }
+
+            /// Same as [`
+            #[doc = stringify!([<pire_gemm_ $name f32>])]
+            /// `] with thread configuration given by `par`
+            ///
+            /// # Safety
+            ///
+            /// See [`
+            #[doc = stringify!([<pire_gemm_ $name f32>])]
+            /// `]
+            pub unsafe fn [<pire_gemm_ $name f32_par>](
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: f32,
+                a: *const $ta,
+                a_rs: usize,
+                a_cs: usize,
+                scale_a: f32,
+                b: *const $tb,
+                b_rs: usize,
+                b_cs: usize,
+                scale_b: f32,
+                beta: f32,
+                c: *mut TC,
+                c_rs: usize,
+                c_cs: usize,
+                par: &PirePar,
+            ) {
+                // a and b are not exchanged for row major c, since their formats may differ
+                let a = Array::strided_matrix(a, a_rs, a_cs);
+                let b = Array::strided_matrix(b, b_rs, b_cs);
+                let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+                let alpha = alpha * scale_a * scale_b;
+                [<pire_gemm_ $name f32_fused_par>](m, n, k, alpha, a, b, beta, c, IdentityFn {}, par);
+            }
+
+            /// Same as [`
+            #[doc = stringify!([<pire_gemm_ $name f32>])]
+            /// `] followed by `f` on `c`, where `a` and `b` can be strided views or matrices packed with
+            /// `pack_a` / `pack_b` of [`
+            #[doc = stringify!($ta)]
+            /// `] and [`
+            #[doc = stringify!($tb)]
+            /// `]
+            ///
+            /// # Safety
+            ///
+            /// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+            /// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+            pub unsafe fn [<pire_gemm_ $name f32_packed>]<F: UnaryFnC>(
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: f32,
+                a: Array<$ta>,
+                scale_a: f32,
+                b: Array<$tb>,
+                scale_b: f32,
+                beta: f32,
+                c: ArrayMut<TC>,
+                f: F,
+            ) {
+                let par = PirePar::default(m, n);
+                [<pire_gemm_ $name f32_packed_par>](m, n, k, alpha, a, scale_a, b, scale_b, beta, c, f, &par);
+            }
+
+            /// Same as [`
+            #[doc = stringify!([<pire_gemm_ $name f32_packed>])]
+            /// `] with thread configuration given by `par`
+            ///
+            /// # Safety
+            ///
+            /// See [`
+            #[doc = stringify!([<pire_gemm_ $name f32_packed>])]
+            /// `]
+            pub unsafe fn [<pire_gemm_ $name f32_packed_par>]<F: UnaryFnC>(
+                m: usize,
+                n: usize,
+                k: usize,
+                alpha: f32,
+                a: Array<$ta>,
+                scale_a: f32,
+                b: Array<$tb>,
+                scale_b: f32,
+                beta: f32,
+                c: ArrayMut<TC>,
+                f: F,
+                par: &PirePar,
+            ) {
+                a.assert_packed_dims(m, k);
+                b.assert_packed_dims(n, k);
+                let alpha = alpha * scale_a * scale_b;
+                [<pire_gemm_ $name f32_fused_par>](m, n, k, alpha, a, b, beta, c, f, par);
+            }
+        }
+    };
+}
+
+def_gemm_f8!(F8E4M3, F8E4M3, e4m3e4m3);
+def_gemm_f8!(F8E4M3, F8E5M2, e4m3e5m2);
+def_gemm_f8!(F8E5M2, F8E4M3, e5m2e4m3);
+def_gemm_f8!(F8E5M2, F8E5M2, e5m2e5m2);
+
+fn dispatch_round_m() -> fn(usize) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_f8f32_compute() {
+            return round_m_simd;
+        }
+    }
+    round_m_ref
+}
+fn dispatch_round_k() -> fn(usize) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_f8f32_compute() {
+            return round_k_simd;
+        }
+    }
+    round_k_ref
+}
+
+fn dispatch_pack_a<X: Fp8>() -> unsafe fn(*const X, *mut X, usize, usize, usize, usize) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_f8f32_compute() {
+            return packa_fn_simd;
+        }
+    }
+    packa_fn_ref
+}
+
+fn dispatch_pack_b<X: Fp8>() -> unsafe fn(*const X, *mut X, usize, usize, usize, usize) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_f8f32_compute() {
+            return packb_fn_simd;
+        }
+    }
+    packb_fn_ref
+}
+
+fn dispatch_get_mcnckc() -> (usize, usize, usize) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if has_f8f32_compute() {
+            return get_mcnckc_simd();
+        }
+    }
+    get_cache_params()
+}
+
+macro_rules! def_packing_api {
+    ($t:ty, $mod_name:ident) => {
+        /// Packing api for
+        #[doc = stringify!($t)]
+        /// matrices, packed matrices stay in fp8 and are widened when they are used
+        pub mod $mod_name {
+            use super::*;
+            use pire_base::AB_ALIGN;
+
+            type TA = $t;
+            type TB = $t;
+
+            fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize) {
+                super::dispatch_pack_a::<TA>()
+            }
+            fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
+                super::dispatch_pack_b::<TB>()
+            }
+
+            pire_base::packing_api!(TA, TB);
+        }
+    };
+}
+
+def_packing_api!(F8E4M3, e4m3);
+def_packing_api!(F8E5M2, e5m2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aligned_vec::avec;
+    use pire_base::{matrix_size, AB_ALIGN};
+    use pire_dev::{
+        check_gemm_f32, generate_k_dims, generate_m_dims, generate_n_dims, layout_to_strides, random_matrix_uniform,
+        ABLayout,
+    };
+
+    #[test]
+    fn test_e4m3_cvt() {
+        for x in 0..=255u8 {
+            let v = F8E4M3(x).to_f32();
+            if v.is_nan() {
+                assert_eq!(x & 0x7F, 0x7F);
+                continue;
+            }
+            assert_eq!(F8E4M3::from_f32(v), F8E4M3(x), "x: {:#x}, v: {}", x, v);
+        }
+        assert_eq!(F8E4M3(0x7E).to_f32(), 448.0);
+        assert_eq!(F8E4M3(0x01).to_f32(), 2f32.powi(-9));
+        assert_eq!(F8E4M3(0xB8).to_f32(), -1.0);
+        assert_eq!(F8E4M3::from_f32(1000.0), F8E4M3::MAX);
+        // 1.0625 is halfway between 1.0 and 1.125, rounds to even mantissa
+        assert_eq!(F8E4M3::from_f32(1.0625).to_f32(), 1.0);
+        assert_eq!(F8E4M3::from_f32(1.1875).to_f32(), 1.25);
+    }
+
+    #[test]
+    fn test_e5m2_cvt() {
+        for x in 0..=255u8 {
+            let v = F8E5M2(x).to_f32();
+            if !v.is_finite() {
+                assert_eq!(x & 0x7C, 0x7C);
+                continue;
+            }
+            assert_eq!(F8E5M2::from_f32(v), F8E5M2(x), "x: {:#x}, v: {}", x, v);
+        }
+        assert_eq!(F8E5M2(0x7B).to_f32(), 57344.0);
+        assert_eq!(F8E5M2(0x7C).to_f32(), f32::INFINITY);
+        assert_eq!(F8E5M2(0x01).to_f32(), 2f32.powi(-16));
+        assert_eq!(F8E5M2::from_f32(-1e6), F8E5M2(0xFB));
+    }
+
+    #[allow(unreachable_code)]
+    pub(crate) fn get_mcnckc() -> (usize, usize, usize) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            return x86_64_arch::get_mcnckc_simd();
+        }
+        get_cache_params()
+    }
+
+    unsafe fn unary_fn_test(c: *mut TC, m: usize) {
+        for i in 0..m {
+            *c.add(i) *= 2.0;
+        }
+    }
+
+    const EPS: f64 = 2e-2;
+
+    static ALPHA_ARR: [f32; 1] = [1.23];
+    static BETA_ARR: [f32; 1] = [1.17];
+
+    const SCALE_A: f32 = 0.5;
+    const SCALE_B: f32 = 0.25;
+
+    fn random_f8<X: Fp8>(x: &mut [X]) -> Vec<f32> {
+        let mut x_f32 = vec![0f32; x.len()];
+        random_matrix_uniform(&mut x_f32);
+        for (y, v) in x.iter_mut().zip(x_f32.iter_mut()) {
+            *y = X::from_f32(*v);
+            *v = y.to_f32();
+        }
+        x_f32
+    }
+
+    type GemmFn<TA, TB> = unsafe fn(
+        usize,
+        usize,
+        usize,
+        f32,
+        Array<TA>,
+        f32,
+        Array<TB>,
+        f32,
+        f32,
+        ArrayMut<TC>,
+        unsafe fn(*mut TC, usize),
+    );
+    type PackFn<X> = fn(usize, usize, &[X], usize, usize, &mut [X]) -> Array<X>;
+
+    fn test_gemm<TA: Fp8, TB: Fp8>(
+        layout: &ABLayout,
+        is_a_packed: bool,
+        is_b_packed: bool,
+        gemm: GemmFn<TA, TB>,
+        (a_size_packed, pack_a): (fn(usize, usize) -> usize, PackFn<TA>),
+        (b_size_packed, pack_b): (fn(usize, usize) -> usize, PackFn<TB>),
+    ) {
+        let c_stride_scale = 2;
+        let (mc, nc, kc) = get_mcnckc();
+        let (mr, nr, kr) = (48, 8, 8);
+        let m_dims = generate_m_dims(mc, mr);
+        let n_dims = generate_n_dims(nc, nr);
+        let k_dims = generate_k_dims(kc, kr);
+        let unary_fn: unsafe fn(*mut TC, usize) = unary_fn_test;
+        for &m in &m_dims {
+            for &n in &n_dims {
+                let c_size = matrix_size(m, n) * c_stride_scale;
+                let mut c = vec![0.0; c_size];
+                let mut c_ref = vec![0.0; c_size];
+                for &k in &k_dims {
+                    let mut a = vec![TA::default(); matrix_size(m, k)];
+                    let mut b = vec![TB::default(); matrix_size(k, n)];
+                    let a_f32 = random_f8(&mut a);
+                    let b_f32 = random_f8(&mut b);
+                    let ap_size = if is_a_packed { a_size_packed(m, k) } else { 0 };
+                    let mut ap = avec![[AB_ALIGN]| TA::default(); ap_size];
+                    let bp_size = if is_b_packed { b_size_packed(n, k) } else { 0 };
+                    let mut bp = avec![[AB_ALIGN]| TB::default(); bp_size];
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = layout_to_strides(&layout, m, n, k);
+                    let (c_rs, c_cs) = (c_rs * c_stride_scale, c_cs * c_stride_scale);
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for alpha in ALPHA_ARR {
+                        for beta in BETA_ARR {
+                            random_matrix_uniform(&mut c);
+                            c_ref.copy_from_slice(&c);
+                            let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), c_rs, c_cs);
+                            unsafe {
+                                gemm(m, n, k, alpha, a_matrix, SCALE_A, b_matrix, SCALE_B, beta, c_matrix, unary_fn);
+                            }
+                            // products of fp8 values are exact in f32, so the f32 gemm on widened a and b is the reference
+                            let diff_max = unsafe {
+                                check_gemm_f32(
+                                    m,
+                                    n,
+                                    k,
+                                    alpha * SCALE_A * SCALE_B,
+                                    a_f32.as_ptr(),
+                                    a_rs,
+                                    a_cs,
+                                    b_f32.as_ptr(),
+                                    b_rs,
+                                    b_cs,
+                                    beta,
+                                    &mut c,
+                                    c_rs,
+                                    c_cs,
+                                    &mut c_ref,
+                                    unary_fn,
+                                    EPS,
+                                )
+                            };
+                            assert!(
+                                diff_max < EPS,
+                                "diff_max: {}, m: {}, n: {}, k: {}, alpha: {}, beta: {}",
+                                diff_max,
+                                m,
+                                n,
+                                k,
+                                alpha,
+                                beta
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    const E4M3_PACK: (fn(usize, usize) -> usize, PackFn<F8E4M3>) = (e4m3::a_size_packed, e4m3::pack_a);
+    const E4M3_PACK_B: (fn(usize, usize) -> usize, PackFn<F8E4M3>) = (e4m3::b_size_packed, e4m3::pack_b);
+    const E5M2_PACK: (fn(usize, usize) -> usize, PackFn<F8E5M2>) = (e5m2::a_size_packed, e5m2::pack_a);
+    const E5M2_PACK_B: (fn(usize, usize) -> usize, PackFn<F8E5M2>) = (e5m2::b_size_packed, e5m2::pack_b);
+
+    #[test]
+    fn test_e4m3e4m3_nn_col() {
+        test_gemm(&ABLayout::NN, false, false, pire_gemm_e4m3e4m3f32_packed, E4M3_PACK, E4M3_PACK_B);
+    }
+    #[test]
+    fn test_e4m3e4m3_tt_col_apbp() {
+        test_gemm(&ABLayout::TT, true, true, pire_gemm_e4m3e4m3f32_packed, E4M3_PACK, E4M3_PACK_B);
+    }
+    #[test]
+    fn test_e4m3e5m2_nt_col_ap() {
+        test_gemm(&ABLayout::NT, true, false, pire_gemm_e4m3e5m2f32_packed, E4M3_PACK, E5M2_PACK_B);
+    }
+    #[test]
+    fn test_e4m3e5m2_tn_col_bp() {
+        test_gemm(&ABLayout::TN, false, true, pire_gemm_e4m3e5m2f32_packed, E4M3_PACK, E5M2_PACK_B);
+    }
+    #[test]
+    fn test_e5m2e4m3_tt_col() {
+        test_gemm(&ABLayout::TT, false, false, pire_gemm_e5m2e4m3f32_packed, E5M2_PACK, E4M3_PACK_B);
+    }
+    #[test]
+    fn test_e5m2e4m3_nn_col_apbp() {
+        test_gemm(&ABLayout::NN, true, true, pire_gemm_e5m2e4m3f32_packed, E5M2_PACK, E4M3_PACK_B);
+    }
+    #[test]
+    fn test_e5m2e5m2_tn_col() {
+        test_gemm(&ABLayout::TN, false, false, pire_gemm_e5m2e5m2f32_packed, E5M2_PACK, E5M2_PACK_B);
+    }
+    #[test]
+    fn test_e5m2e5m2_nt_col_apbp() {
+        test_gemm(&ABLayout::NT, true, true, pire_gemm_e5m2e5m2f32_packed, E5M2_PACK, E5M2_PACK_B);
+    }
+
+    #[test]
+    fn test_strided_api() {
+        let (m, n, k) = (37, 19, 45);
+        let mut a = vec![F8E4M3::ZERO; m * k];
+        let mut b = vec![F8E5M2::ZERO; k * n];
+        let a_f32 = random_f8(&mut a);
+        let b_f32 = random_f8(&mut b);
+        // row major c
+        let mut c = vec![1.0; m * n];
+        let mut c_ref = c.clone();
+        unsafe {
+            pire_gemm_e4m3e5m2f32(
+                m,
+                n,
+                k,
+                1.0,
+                a.as_ptr(),
+                k,
+                1,
+                2.0,
+                b.as_ptr(),
+                n,
+                1,
+                0.5,
+                0.0,
+                c.as_mut_ptr(),
+                n,
+                1,
+            );
+        }
+        let identity: unsafe fn(*mut TC, usize) = |_, _| {};
+        let diff_max = unsafe {
+            check_gemm_f32(
+                m,
+                n,
+                k,
+                1.0,
+                a_f32.as_ptr(),
+                k,
+                1,
+                b_f32.as_ptr(),
+                n,
+                1,
+                0.0,
+                &c,
+                n,
+                1,
+                &mut c_ref,
+                identity,
+                EPS,
+            )
+        };
+        assert!(diff_max < EPS, "diff_max: {}", diff_max);
+    }
+}
@@ -0,0 +1,18 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TC};
+
+const VS: usize = 16;
+const VS_MAX: usize = VS;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+const ZERO: f32 = 0.0;
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(f32, f32, TC, f32, false, T, 1, 3, 8, 96, 8);
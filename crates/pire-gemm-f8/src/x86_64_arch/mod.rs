@@ -0,0 +1,328 @@
+pub(crate) mod avx512f;
+pub(crate) mod avx_fma;
+
+const AVX512F_VS: usize = 16;
+const AVX_VS: usize = 8;
+
+const AVX512F_MR: usize = 48;
+const AVXFMA_MR: usize = 24;
+
+const AVX512F_NR: usize = 8;
+const AVXFMA_NR: usize = 4;
+
+use paste::paste;
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, HWModel, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
+    RUNTIME_HW_CONFIG,
+};
+
+use crate::{Fp8, GemmCache, IdentityFn, UnaryFnC, TC};
+use crate::{F8E4M3, F8E5M2};
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    let (mr, nr) = if features.avx512f { (AVX512F_MR, AVX512F_NR) } else { (AVXFMA_MR, AVXFMA_NR) };
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        HWModel::Skylake => (4800, 384, 1024),
+        HWModel::Haswell => (4800, 320, 192),
+        _ => get_cache_params(),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+// panels of mr rows stored column by column, the last panel has its row count rounded up to vs
+#[inline(always)]
+unsafe fn pack_panel<X: Copy, Y>(
+    m: usize,
+    k: usize,
+    a: *const X,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut Y,
+    mr: usize,
+    vs: usize,
+    cvt: impl Fn(X) -> Y,
+) {
+    let mut m_idx = 0;
+    while m_idx < m {
+        let mr_cur = mr.min(m - m_idx);
+        let mr_eff = if mr_cur == mr { mr } else { (mr_cur + vs - 1) / vs * vs };
+        let a_cur = a.add(m_idx * a_rs);
+        let ap_cur = ap.add(m_idx * k);
+        for p in 0..k {
+            for i in 0..mr_cur {
+                *ap_cur.add(p * mr_eff + i) = cvt(*a_cur.add(i * a_rs + p * a_cs));
+            }
+        }
+        m_idx += mr;
+    }
+}
+
+fn mr_vs() -> (usize, usize, usize) {
+    if (*RUNTIME_HW_CONFIG).cpu_ft.avx512f {
+        (AVX512F_MR, AVX512F_NR, AVX512F_VS)
+    } else {
+        (AVXFMA_MR, AVXFMA_NR, AVX_VS)
+    }
+}
+
+pub(crate) unsafe fn packa_fn_simd_f32<X: Fp8>(x: *const X, y: *mut f32, m: usize, k: usize, rs: usize, cs: usize) {
+    let (mr, _, vs) = mr_vs();
+    pack_panel(m, k, x, rs, cs, y, mr, vs, |v: X| v.to_f32());
+}
+pub(crate) unsafe fn packb_fn_simd_f32<X: Fp8>(x: *const X, y: *mut f32, n: usize, k: usize, rs: usize, cs: usize) {
+    let (_, nr, _) = mr_vs();
+    pack_panel(n, k, x, cs, rs, y, nr, 1, |v: X| v.to_f32());
+}
+
+// packed api stores fp8 in the layout of f32 panels and it is widened to f32 when the packed matrix is used
+pub(crate) unsafe fn packa_fn_simd<X: Fp8>(x: *const X, y: *mut X, m: usize, k: usize, rs: usize, cs: usize) {
+    let (mr, _, vs) = mr_vs();
+    pack_panel(m, k, x, rs, cs, y, mr, vs, |v: X| v);
+}
+pub(crate) unsafe fn packb_fn_simd<X: Fp8>(x: *const X, y: *mut X, n: usize, k: usize, rs: usize, cs: usize) {
+    let (_, nr, _) = mr_vs();
+    pack_panel(n, k, x, cs, rs, y, nr, 1, |v: X| v);
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    let (_, _, vs) = mr_vs();
+    (m + vs - 1) / vs * vs
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    k
+}
+
+pub(crate) enum RegDim {
+    Reg48x8,
+    Reg24x4,
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDim,
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let features = hw_config.cpu_ft();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, reg_dim, vs) = if features.avx512f {
+            (AVX512F_MR, AVX512F_NR, RegDim::Reg48x8, AVX512F_VS)
+        } else {
+            (AVXFMA_MR, AVXFMA_NR, RegDim::Reg24x4, AVX_VS)
+        };
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        false
+    }
+
+    unsafe fn cvt_mixed<X: Fp8>(&self, x: *const X, y: *mut f32, m: usize) {
+        for i in 0..m {
+            *y.add(i) = (*x.add(i)).to_f32();
+        }
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        k
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    bp: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Reg48x8 => avx512f::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::Reg24x4 => avx_fma::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+#[allow(unused)]
+unsafe fn kernel_m<F: UnaryFnC, X: Fp8>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const X,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const f32,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+#[allow(unused)]
+unsafe fn kernel_n<F: UnaryFnC, X: Fp8>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const X,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut f32,
+    b: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+}
+
+unsafe fn pire_gemv<F: UnaryFnC, X: Fp8, Y: Fp8>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<X>,
+    x: Array<Y>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let (a_ptr, a_rs, a_cs) = (a.src(), a.rs(), a.cs());
+    let (x_ptr, inc_x) = (x.src(), x.rs());
+    let (y_ptr, incy) = (y.src(), y.rs());
+    // x is widened once, each element of a is read once anyway
+    let x_f32 = (0..n).map(|j| (*x_ptr.add(j * inc_x)).to_f32()).collect::<Vec<_>>();
+    for i in 0..m {
+        let mut acc = 0.0;
+        for j in 0..n {
+            acc += (*a_ptr.add(i * a_rs + j * a_cs)).to_f32() * x_f32[j];
+        }
+        let y_cur = y_ptr.add(i * incy);
+        *y_cur = if *beta == 0.0 { acc * *alpha } else { *y_cur * *beta + acc * *alpha };
+        hw_cfg.func.call(y_cur, 1);
+    }
+}
+
+macro_rules! def_pire_gemm_f8 {
+    ($ta:tt, $tb:tt, $name:ident) => {
+        paste! {
+            def_pire_gemm!(
+                KernelDispatcher,
+                $ta,
+                f32,
+                $tb,
+                f32,
+                TC,
+                f32,
+                f32,
+                [<PackArrTypeA $name>],
+                [<PackArrTypeB $name>],
+                1_f32,
+                [<pire_gemm_ $name>],
+                [<gemm_mt_ $name>],
+                [<gemm_goto_serial_ $name>],
+                kernel,
+                [<gemm_small_m_serial_ $name>],
+                kernel_m,
+                [<gemm_small_n_serial_ $name>],
+                kernel_n,
+                pire_gemv,
+                pire_gemv,
+                [<packa0_ $name>],
+                [<packb0_ $name>],
+                packa_fn_simd_f32,
+                packb_fn_simd_f32,
+                false,
+                false,
+                into_pack_array2,
+                T,
+            );
+        }
+    };
+}
+
+def_pire_gemm_f8!(F8E4M3, F8E4M3, e4m3e4m3);
+def_pire_gemm_f8!(F8E4M3, F8E5M2, e4m3e5m2);
+def_pire_gemm_f8!(F8E5M2, F8E4M3, e5m2e4m3);
+def_pire_gemm_f8!(F8E5M2, F8E5M2, e5m2e5m2);
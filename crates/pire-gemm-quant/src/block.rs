@@ -0,0 +1,47 @@
+use half::f16;
+
+/// Number of elements along k in one quantization block
+pub const QK: usize = 32;
+
+/// 8-bit block, `x[i] = d * qs[i]`, the same layout as `block_q8_0` of ggml
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct BlockQ8_0 {
+    pub d: f16,
+    pub qs: [i8; QK],
+}
+
+/// 4-bit block, `x[i] = d * (qs[i] & 0xF - 8)` and `x[i + 16] = d * (qs[i] >> 4 - 8)` for `i < 16`,
+/// the same layout as `block_q4_0` of ggml
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct BlockQ4_0 {
+    pub d: f16,
+    pub qs: [u8; QK / 2],
+}
+
+/// Block of [`QK`] quantized elements with a scale
+pub trait QuantBlock: Copy + Sync {
+    fn dequant(&self, y: &mut [f32; QK]);
+}
+
+impl QuantBlock for BlockQ8_0 {
+    #[inline(always)]
+    fn dequant(&self, y: &mut [f32; QK]) {
+        let d = self.d.to_f32();
+        for i in 0..QK {
+            y[i] = self.qs[i] as f32 * d;
+        }
+    }
+}
+
+impl QuantBlock for BlockQ4_0 {
+    #[inline(always)]
+    fn dequant(&self, y: &mut [f32; QK]) {
+        let d = self.d.to_f32();
+        for i in 0..QK / 2 {
+            y[i] = ((self.qs[i] & 0xF) as i32 - 8) as f32 * d;
+            y[i + QK / 2] = ((self.qs[i] >> 4) as i32 - 8) as f32 * d;
+        }
+    }
+}
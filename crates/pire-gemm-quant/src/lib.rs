@@ -0,0 +1,400 @@
+//! Gemm with f32 or f16 activations `a` and block quantized weights `b` (ggml style Q4_0 / Q8_0 blocks)
+//!
+//! `b` is `k x n` where column `j` (i.e. output channel `j`) is stored as `k / 32` consecutive blocks
+//! starting at `b + j * ldb`, this is the row major `n x k` weight layout of ggml.
+//! The weights are dequantized to f32 per kc block right before the f32 gemm packs them,
+//! into its packing buffer, so no f32 copy of `b` is made
+
+pub(crate) mod block;
+
+pub use block::{BlockQ4_0, BlockQ8_0, QuantBlock, QK};
+
+use half::f16;
+pub use pire_base::PirePar;
+use pire_base::{get_cache_params, put_pool_buf, take_pool_buf, IdentityFn, UnaryFn, AB_ALIGN};
+use pire_gemm_f32::{pire_sgemm_src_b_par, SrcB};
+
+pub trait UnaryFnC: UnaryFn<f32> {}
+impl<F: UnaryFn<f32>> UnaryFnC for F {}
+
+/// Activation type of a quantized gemm, f32 is used as is and f16 is widened one kc block of columns at a time
+pub trait Activation: Copy + Sync {
+    /// Runs `f(p, kc_len, a_p, a_rs, a_cs)` for consecutive blocks of columns `p..p + kc_len` of `a` covering `0..k`,
+    /// with `a_p` the block as f32 and its row and column strides, converted into a buffer of the pack pool if needed.
+    /// Every `p` is a multiple of [`QK`], `f` runs at least once, with `kc_len == 0` for `k == 0`
+    ///
+    /// # Safety
+    ///
+    /// `a` must be valid for `m x k` with the given strides
+    unsafe fn with_f32<G: FnMut(usize, usize, *const f32, usize, usize)>(
+        a: *const Self,
+        a_rs: usize,
+        a_cs: usize,
+        m: usize,
+        k: usize,
+        f: G,
+    );
+}
+
+impl Activation for f32 {
+    unsafe fn with_f32<G: FnMut(usize, usize, *const f32, usize, usize)>(
+        a: *const Self,
+        a_rs: usize,
+        a_cs: usize,
+        _m: usize,
+        k: usize,
+        mut f: G,
+    ) {
+        f(0, k, a, a_rs, a_cs);
+    }
+}
+
+impl Activation for f16 {
+    unsafe fn with_f32<G: FnMut(usize, usize, *const f32, usize, usize)>(
+        a: *const Self,
+        a_rs: usize,
+        a_cs: usize,
+        m: usize,
+        k: usize,
+        mut f: G,
+    ) {
+        let (_, _, kc) = get_cache_params();
+        let kc = (kc / QK * QK).max(QK);
+        let (slot, mut pool_vec) = take_pool_buf(m * kc.min(k) * core::mem::size_of::<f32>() + AB_ALIGN);
+        let buf = pool_vec.as_mut_ptr();
+        let buf = buf.add(buf.align_offset(AB_ALIGN)) as *mut f32;
+        let mut p = 0;
+        loop {
+            let kc_len = kc.min(k - p);
+            for pp in 0..kc_len {
+                for i in 0..m {
+                    *buf.add(i + pp * m) = (*a.add(i * a_rs + (p + pp) * a_cs)).to_f32();
+                }
+            }
+            f(p, kc_len, buf, 1, m);
+            p += kc_len;
+            if p >= k {
+                break;
+            }
+        }
+        put_pool_buf(slot, pool_vec);
+    }
+}
+
+// b[kc_i..kc_i + kc_len, j] as f32, the blocks overlapping the rows are dequantized whole
+unsafe fn load_q<Q: QuantBlock>(b: *const u8, ldb: usize, kc_i: usize, kc_len: usize, j: usize, dst: *mut f32) {
+    let b_j = (b as *const Q).add(j * ldb);
+    let kc_end = kc_i + kc_len;
+    let mut y = [0f32; QK];
+    let mut p = kc_i;
+    while p < kc_end {
+        let (p_block, p_off) = (p / QK, p % QK);
+        let len = (QK - p_off).min(kc_end - p);
+        (*b_j.add(p_block)).dequant(&mut y);
+        core::ptr::copy_nonoverlapping(y.as_ptr().add(p_off), dst.add(p - kc_i), len);
+        p += len;
+    }
+}
+
+pub(crate) unsafe fn pire_gemm_quant_fused_par<A: Activation, Q: QuantBlock, F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const A,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const Q,
+    ldb: usize,
+    beta: f32,
+    c: *mut f32,
+    c_rs: usize,
+    c_cs: usize,
+    f: F,
+    par: &PirePar,
+) {
+    assert!(k % QK == 0, "k must be a multiple of the block size {}", QK);
+    if m == 0 || n == 0 {
+        return;
+    }
+    A::with_f32(a, a_rs, a_cs, m, k, |p, kc_len, a, a_rs, a_cs| {
+        // rows p.. of b start p / QK blocks into every column, beta and f apply to the first and last block of k
+        let b = SrcB::new(b.add(p / QK) as *const u8, ldb, load_q::<Q>);
+        let beta = if p == 0 { beta } else { 1.0 };
+        if p + kc_len == k {
+            pire_sgemm_src_b_par(m, n, kc_len, alpha, a, a_rs, a_cs, &b, beta, c, c_rs, c_cs, f, par);
+        } else {
+            pire_sgemm_src_b_par(m, n, kc_len, alpha, a, a_rs, a_cs, &b, beta, c, c_rs, c_cs, IdentityFn {}, par);
+        }
+    });
+}
+
+macro_rules! def_quant_gemm {
+    ($name:ident, $name_fused:ident, $ta:ty, $tq:ty) => {
+        /// Computes `c = alpha * a * b + beta * c` with quantized `b`, see the crate docs for the layout of `b`
+        ///
+        /// # Safety
+        ///
+        /// `a` and `c` must be valid for `m x k` and `m x n` with their strides, `b` must be valid for `n` columns
+        /// of `k / 32` blocks with column stride `ldb` (in blocks)
+        ///
+        /// # Panics
+        ///
+        /// If `k` is not a multiple of 32
+        pub unsafe fn $name(
+            m: usize,
+            n: usize,
+            k: usize,
+            alpha: f32,
+            a: *const $ta,
+            a_rs: usize,
+            a_cs: usize,
+            b: *const $tq,
+            ldb: usize,
+            beta: f32,
+            c: *mut f32,
+            c_rs: usize,
+            c_cs: usize,
+        ) {
+            let par = PirePar::default(m, n);
+            pire_gemm_quant_fused_par(m, n, k, alpha, a, a_rs, a_cs, b, ldb, beta, c, c_rs, c_cs, IdentityFn {}, &par);
+        }
+
+        /// Same as [`
+        #[doc = stringify!($name)]
+        /// `] followed by `f` on `c` with thread configuration given by `par`
+        ///
+        /// # Safety
+        ///
+        /// See [`
+        #[doc = stringify!($name)]
+        /// `]
+        pub unsafe fn $name_fused<F: UnaryFnC>(
+            m: usize,
+            n: usize,
+            k: usize,
+            alpha: f32,
+            a: *const $ta,
+            a_rs: usize,
+            a_cs: usize,
+            b: *const $tq,
+            ldb: usize,
+            beta: f32,
+            c: *mut f32,
+            c_rs: usize,
+            c_cs: usize,
+            f: F,
+            par: &PirePar,
+        ) {
+            pire_gemm_quant_fused_par(m, n, k, alpha, a, a_rs, a_cs, b, ldb, beta, c, c_rs, c_cs, f, par);
+        }
+    };
+}
+
+def_quant_gemm!(pire_gemm_f32q8_0, pire_gemm_f32q8_0_fused_par, f32, BlockQ8_0);
+def_quant_gemm!(pire_gemm_f32q4_0, pire_gemm_f32q4_0_fused_par, f32, BlockQ4_0);
+def_quant_gemm!(pire_gemm_f16q8_0, pire_gemm_f16q8_0_fused_par, f16, BlockQ8_0);
+def_quant_gemm!(pire_gemm_f16q4_0, pire_gemm_f16q4_0_fused_par, f16, BlockQ4_0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pire_base::get_cache_params;
+    use pire_dev::random_matrix_uniform;
+
+    trait TestFloat: Activation {
+        fn from_f32(x: f32) -> Self;
+        fn to_f32(self) -> f32;
+    }
+
+    impl TestFloat for f32 {
+        fn from_f32(x: f32) -> Self {
+            x
+        }
+        fn to_f32(self) -> f32 {
+            self
+        }
+    }
+
+    impl TestFloat for f16 {
+        fn from_f32(x: f32) -> Self {
+            f16::from_f32(x)
+        }
+        fn to_f32(self) -> f32 {
+            f16::to_f32(self)
+        }
+    }
+
+    fn q8_0_matrix(n: usize, k: usize) -> Vec<BlockQ8_0> {
+        let mut qs = vec![0i8; n * k];
+        random_matrix_uniform(&mut qs);
+        qs.chunks(QK)
+            .enumerate()
+            .map(|(i, x)| BlockQ8_0 { d: f16::from_f32(0.01 * (i % 7 + 1) as f32), qs: x.try_into().unwrap() })
+            .collect()
+    }
+
+    fn q4_0_matrix(n: usize, k: usize) -> Vec<BlockQ4_0> {
+        (0..n * k / QK)
+            .map(|i| {
+                let qs = core::array::from_fn(|t| ((i * 7 + t * 13) % 256) as u8);
+                BlockQ4_0 { d: f16::from_f32(0.02 * (i % 5 + 1) as f32), qs }
+            })
+            .collect()
+    }
+
+    unsafe fn unary_fn_test(c: *mut f32, m: usize) {
+        for i in 0..m {
+            *c.add(i) *= 2.0;
+        }
+    }
+
+    fn test_gemm<A: TestFloat, Q: QuantBlock>(
+        b: &[Q],
+        gemm: unsafe fn(usize, usize, usize, f32, *const A, usize, usize, *const Q, usize, f32, *mut f32, usize, usize),
+        fused: unsafe fn(
+            usize,
+            usize,
+            usize,
+            f32,
+            *const A,
+            usize,
+            usize,
+            *const Q,
+            usize,
+            f32,
+            *mut f32,
+            usize,
+            usize,
+            unsafe fn(*mut f32, usize),
+            &PirePar,
+        ),
+        n: usize,
+        k: usize,
+    ) {
+        let ldb = k / QK;
+        let mut b_f32 = vec![0f32; k * n];
+        for j in 0..n {
+            for p in 0..ldb {
+                let mut y = [0f32; QK];
+                b[j * ldb + p].dequant(&mut y);
+                b_f32[j * k + p * QK..j * k + (p + 1) * QK].copy_from_slice(&y);
+            }
+        }
+        let (alpha, beta) = (1.5, 0.5);
+        for &m in &[1, 5, 33] {
+            let a = (0..m * k).map(|i| A::from_f32(((i * 5) % 11) as f32 * 0.25 - 1.25)).collect::<Vec<_>>();
+            // a row major, c column major
+            let (a_rs, a_cs, c_rs, c_cs) = (k, 1, 1, m);
+            let c0 = (0..m * n).map(|i| (i % 3) as f32).collect::<Vec<_>>();
+            let mut c_ref = c0.clone();
+            for j in 0..n {
+                for i in 0..m {
+                    let mut acc = 0f64;
+                    for p in 0..k {
+                        acc += a[i * a_rs + p * a_cs].to_f32() as f64 * b_f32[j * k + p] as f64;
+                    }
+                    let c_ij = &mut c_ref[i * c_rs + j * c_cs];
+                    *c_ij = (alpha as f64 * acc + beta as f64 * *c_ij as f64) as f32;
+                }
+            }
+            let mut c = c0.clone();
+            let mut c_fused = c0.clone();
+            let par = PirePar::default(m, n);
+            unsafe {
+                gemm(m, n, k, alpha, a.as_ptr(), a_rs, a_cs, b.as_ptr(), ldb, beta, c.as_mut_ptr(), c_rs, c_cs);
+                let f: unsafe fn(*mut f32, usize) = unary_fn_test;
+                fused(
+                    m,
+                    n,
+                    k,
+                    alpha,
+                    a.as_ptr(),
+                    a_rs,
+                    a_cs,
+                    b.as_ptr(),
+                    ldb,
+                    beta,
+                    c_fused.as_mut_ptr(),
+                    c_rs,
+                    c_cs,
+                    f,
+                    &par,
+                );
+            }
+            for i in 0..m * n {
+                let eps = 1e-4 * (1.0 + c_ref[i].abs());
+                assert!((c[i] - c_ref[i]).abs() < eps, "m: {}, n: {}, k: {}, {} vs {}", m, n, k, c[i], c_ref[i]);
+                assert!((c_fused[i] - 2.0 * c_ref[i]).abs() < 2.0 * eps, "fused m: {}, n: {}, k: {}", m, n, k);
+            }
+        }
+    }
+
+    // the last k spans several kc blocks of the packing, n = 150 runs the goto path rather than the small n one
+    fn k_dims() -> [usize; 3] {
+        let (_, _, kc) = get_cache_params();
+        [32, 96, kc * 2 + 64]
+    }
+
+    #[test]
+    fn test_f32q8_0() {
+        for k in k_dims() {
+            for n in [1, 17, 150] {
+                test_gemm::<f32, _>(&q8_0_matrix(n, k), pire_gemm_f32q8_0, pire_gemm_f32q8_0_fused_par, n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_f32q4_0() {
+        for k in k_dims() {
+            for n in [1, 17, 150] {
+                test_gemm::<f32, _>(&q4_0_matrix(n, k), pire_gemm_f32q4_0, pire_gemm_f32q4_0_fused_par, n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_f16q8_0() {
+        for k in k_dims() {
+            for n in [1, 17, 150] {
+                test_gemm::<f16, _>(&q8_0_matrix(n, k), pire_gemm_f16q8_0, pire_gemm_f16q8_0_fused_par, n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_f16q4_0() {
+        for k in k_dims() {
+            for n in [1, 17, 150] {
+                test_gemm::<f16, _>(&q4_0_matrix(n, k), pire_gemm_f16q4_0, pire_gemm_f16q4_0_fused_par, n, k);
+            }
+        }
+    }
+
+    #[test]
+    fn test_load_q_split_blocks() {
+        let (n, k) = (3, 4 * QK);
+        let b = q8_0_matrix(n, k);
+        let mut col = vec![0f32; k];
+        let mut y = vec![0f32; k];
+        unsafe {
+            load_q::<BlockQ8_0>(b.as_ptr() as *const u8, k / QK, 0, k, 2, col.as_mut_ptr());
+            // rows that start and end inside a block
+            load_q::<BlockQ8_0>(b.as_ptr() as *const u8, k / QK, 5, 2 * QK + 3, 2, y.as_mut_ptr());
+        }
+        assert_eq!(&y[..2 * QK + 3], &col[5..2 * QK + 8]);
+    }
+
+    #[test]
+    fn test_dequant_q4_0() {
+        let mut qs = [0u8; QK / 2];
+        qs[0] = 0x9F;
+        let x = BlockQ4_0 { d: f16::from_f32(0.5), qs };
+        let mut y = [0f32; QK];
+        x.dequant(&mut y);
+        assert_eq!(y[0], 3.5);
+        assert_eq!(y[QK / 2], 0.5);
+        assert_eq!(y[1], -4.0);
+    }
+}
@@ -0,0 +1,317 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_neon_i16,
+    acc_3, acc_2, acc_1,
+    store_3, store_2, store_1,
+};
+
+type TS = f32;
+
+const ONE_SCALAR: TS = 1f32;
+const ZERO_SCALAR: TS = 0f32;
+
+// one k per step, smlal widens 4 i16 rows of a and multiplies them by one i16 lane of b
+// v0-v2: a, v4-v6: b, v8-v31: c
+
+macro_rules! inc_a {
+    ($mr:tt) => {
+        concat!("add {ax}, {ax}, #8*", $mr, " \n")
+    };
+}
+
+macro_rules! v_i {
+    ($m0:tt, $ni:tt) => {
+        concat!("[", $m0, ", #", $ni, "*0x10]")
+    }
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "add v", $r1, ".4s, v", $r1, ".4s, v1.4s\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "add v", $r1, ".4s, v", $r1, ".4s, v1.4s\n",
+        )
+    };
+
+    (C, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "scvtf v1.4s, v1.4s\n",
+            "scvtf v", $r1, ".4s, v", $r1, ".4s\n",
+            "fmla v", $r1, ".4s, v1.4s, v0.s[0]\n",
+            "fcvtzs v", $r1, ".4s, v", $r1, ".4s\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "scvtf v1.4s, v1.4s\n",
+            "scvtf v", $r1, ".4s, v", $r1, ".4s\n",
+            "fmla v", $r1, ".4s, v1.4s, v0.s[0]\n",
+            "fcvtzs v", $r1, ".4s, v", $r1, ".4s\n",
+        )
+    };
+}
+
+macro_rules! vzeroall {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(#("dup v", r, ".4s, wzr \n",)*)
+        })
+    }
+}
+
+macro_rules! vfmadd {
+    ($i:tt, $j:tt, $nr:tt) => {
+        concat!(
+            "smlal v", cr!($i,$j), ".4s, v", $i, ".4h, ", br!($nr, $j), "\n",
+        )
+    };
+}
+
+macro_rules! loadp_unit {
+    ($m0:expr, $r1:expr) => {
+        concat!(
+            "ldr d", $r1, ", [", $m0, ", #", $r1, "*8] \n",
+        )
+    };
+}
+
+macro_rules! storep_unit {
+    ($l:tt, $r1:expr, $m0:expr) => {
+        concat!(
+            "str q", $r1, ", ", $m0,  "\n",
+        )
+    };
+}
+
+macro_rules! alpha_scale_0 {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(
+                "ldr s1, [{alphax}]", "\n",
+                #(
+                    "scvtf v", r, ".4s, v", r, ".4s\n",
+                    "fmul  v", r, ".4s, v", r, ".4s, v1.s[0]\n",
+                    "fcvtzs v", r, ".4s, v", r, ".4s\n",
+                )*
+            )
+        })
+    }
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            "ldr s0, [{betax}]", "\n",
+            "/* {betax} */", "\n",
+        )
+    }
+}
+
+macro_rules! init_ab {
+    (B) => {
+        concat!(
+            "/* {x11} */", "\n",
+            "/* {x10} */", "\n",
+            "/* {x9} */", "\n",
+            "/* {x8} */", "\n",
+            "/* {x7} */", "\n",
+            "/* {x6} */", "\n",
+            "/* {x5} */", "\n",
+            "/* {x4} */", "\n",
+            "/* {x3} */", "\n",
+            "/* {x2} */", "\n",
+            "/* {x1} */", "\n",
+            "ldr {x0}, [{dim_arrx}, #24]", "\n",
+        )
+    };
+}
+
+
+macro_rules! c_load {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0} \n",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+        )
+    };
+}
+
+
+macro_rules! vzero_kernel {
+    () => {vzeroall!(8,31)};
+}
+
+macro_rules! inc_b {
+    (B,$nr:tt) => {
+        concat!(
+            "add {bx}, {bx}, #", $nr, "*2 \n",
+        )
+    };
+}
+
+
+macro_rules! alpha_scale {
+    () => {
+        alpha_scale_0!(8,31)
+    };
+}
+
+macro_rules! cr {
+    (0,0) => { 8 };
+    (1,0) => { 9 };
+    (2,0) => { 10 };
+
+    (0,1) => { 11 };
+    (1,1) => { 12 };
+    (2,1) => { 13 };
+
+    (0,2) => { 14 };
+    (1,2) => { 15 };
+    (2,2) => { 16 };
+
+    (0,3) => { 17 };
+    (1,3) => { 18 };
+    (2,3) => { 19 };
+
+    (0,4) => { 20 };
+    (1,4) => { 21 };
+    (2,4) => { 22 };
+
+    (0,5) => { 23 };
+    (1,5) => { 24 };
+    (2,5) => { 25 };
+
+    (0,6) => { 26 };
+    (1,6) => { 27 };
+    (2,6) => { 28 };
+
+    (0,7) => { 29 };
+    (1,7) => { 30 };
+    (2,7) => { 31 };
+}
+
+// b of one k is nr consecutive i16, loaded exactly so that the last panel is not read past its end.
+// a full panel is one q register, otherwise columns 0..4 go to v4 and 4..8 to v5,
+// the odd third column of a 4 column group goes to v6
+macro_rules! br {
+    (8, 4) => { "v4.h[4]" };
+    (8, 5) => { "v4.h[5]" };
+    (8, 6) => { "v4.h[6]" };
+    (8, 7) => { "v4.h[7]" };
+    (3, 2) => { "v6.h[0]" };
+    (7, 6) => { "v6.h[0]" };
+    ($nr:tt, 0) => { "v4.h[0]" };
+    ($nr:tt, 1) => { "v4.h[1]" };
+    ($nr:tt, 2) => { "v4.h[2]" };
+    ($nr:tt, 3) => { "v4.h[3]" };
+    ($nr:tt, 4) => { "v5.h[0]" };
+    ($nr:tt, 5) => { "v5.h[1]" };
+    ($nr:tt, 6) => { "v5.h[2]" };
+    ($nr:tt, 7) => { "v5.h[3]" };
+}
+
+macro_rules! load_b {
+    (B, 1) => { "ldr h4, [{bx}]\n" };
+    (B, 2) => { "ldr s4, [{bx}]\n" };
+    (B, 3) => { "ldr s4, [{bx}]\n ldr h6, [{bx}, #4]\n" };
+    (B, 4) => { "ldr d4, [{bx}]\n" };
+    (B, 5) => { "ldr d4, [{bx}]\n ldr h5, [{bx}, #8]\n" };
+    (B, 6) => { "ldr d4, [{bx}]\n ldr s5, [{bx}, #8]\n" };
+    (B, 7) => { "ldr d4, [{bx}]\n ldr s5, [{bx}, #8]\n ldr h6, [{bx}, #12]\n" };
+    (B, 8) => { "ldr q4, [{bx}]\n" };
+}
+
+macro_rules! step_3 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                load_b!($b_layout, $nr),
+                #(
+                    vfmadd!(0, n, $nr),
+                    vfmadd!(1, n, $nr),
+                    vfmadd!(2, n, $nr),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! step_2 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                load_b!($b_layout, $nr),
+                #(
+                    vfmadd!(0, n, $nr),
+                    vfmadd!(1, n, $nr),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! step_1 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                load_b!($b_layout, $nr),
+                #(
+                    vfmadd!(0, n, $nr),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! prefetch_c {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0}\n ",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+            "prfm pldl1keep, [{cx}] \n",
+            "prfm pldl1keep, [{cx},#40]\n",
+            "prfm pldl1keep, [{x1}] \n",
+            "prfm pldl1keep, [{x1},#40]\n",
+            "prfm pldl1keep, [{x2}] \n",
+            "prfm pldl1keep, [{x2},#40]\n",
+            "prfm pldl1keep, [{x3}] \n",
+            "prfm pldl1keep, [{x3},#40]\n",
+            "prfm pldl1keep, [{x4}] \n",
+            "prfm pldl1keep, [{x4},#40]\n",
+            "prfm pldl1keep, [{x5}] \n",
+            "prfm pldl1keep, [{x5},#40]\n",
+            "prfm pldl1keep, [{x6}] \n",
+            "prfm pldl1keep, [{x6},#40]\n",
+            "prfm pldl1keep, [{x7}] \n",
+            "prfm pldl1keep, [{x7},#40]\n",
+        )
+    };
+}
+
+
+def_ukernel_neon_i16!(step_1, acc_1, store_1, 1, 8, B, M, ukernel_1_bbp);
+def_ukernel_neon_i16!(step_2, acc_2, store_2, 2, 8, B, M, ukernel_2_bbp);
+def_ukernel_neon_i16!(step_3, acc_3, store_3, 3, 8, B, M, ukernel_3_bbp);
+
+def_ukernel_neon_i16!(step_3, acc_3, store_3, 3, 8, B, C, ukernel_bbc);
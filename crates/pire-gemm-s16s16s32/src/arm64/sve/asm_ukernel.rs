@@ -0,0 +1,359 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_sve_i16,
+    acc_2, acc_1,
+    store_2, store_1,
+};
+use super::super::sve_vs;
+
+type TS = f32;
+
+const ONE_SCALAR: TS = 1f32;
+const ZERO_SCALAR: TS = 0f32;
+
+// sdot on 16-bit lanes accumulates 4 k of a row into a 64-bit lane,
+// so one c vector of i32 is held in two accumulators of i64 and narrowed with uzp1 before the store
+// z0-z3: a, z4-z6: b (two columns per 128-bit segment), z8-z31: c
+
+macro_rules! v_i {
+    ($m0:tt, $ni:tt) => {
+        concat!("[", $m0, ", #", $ni, ", MUL VL]")
+    }
+}
+
+macro_rules! set_predicate {
+    (M) => { "mov {m_s}, #0 \n whilelo p1.s, {m_s}, {m_e} \n" };
+    (C) => { "/* {m_s}, {m_e} */" }
+}
+
+macro_rules! inc_a {
+    ($mr:tt) => {
+        concat!("add {ax}, {ax}, {incax} \n")
+    };
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ld1w {{ z1.s }}, p0/z, ", $m0, "\n",
+            "add z", $r1, ".s, p0/m, z", $r1, ".s, z1.s\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ld1w {{ z1.s }}, p1/z, ", $m0, "\n",
+            "add z", $r1, ".s, p1/m, z", $r1, ".s, z1.s\n",
+        )
+    };
+
+    (C, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ld1w {{ z1.s }}, p0/z, ", $m0, "\n",
+            "scvtf z1.s, p0/m, z1.s\n",
+            "scvtf z", $r1, ".s, p0/m, z", $r1, ".s\n",
+            "fmla z", $r1, ".s, z1.s, z0.s[0]\n",
+            "fcvtzs z", $r1, ".s, p0/m, z", $r1, ".s\n",
+        )
+    };
+    (M, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ld1w {{ z1.s }}, p1/z, ", $m0, "\n",
+            "scvtf z1.s, p0/m, z1.s\n",
+            "scvtf z", $r1, ".s, p0/m, z", $r1, ".s\n",
+            "fmla z", $r1, ".s, z1.s, z0.s[0]\n",
+            "fcvtzs z", $r1, ".s, p0/m, z", $r1, ".s\n",
+        )
+    };
+}
+
+macro_rules! vzeroall {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(#("dup z", r, ".s, #0 \n",)*)
+        })
+    }
+}
+
+macro_rules! vfmadd {
+    ($r:tt, $ni:tt) => {
+        concat!(
+            "sdot z", dr!($r,$ni), ".d, z", $r, ".h, z", br!($ni), ".h[", bl!($ni), "]\n",
+        )
+    };
+}
+
+macro_rules! loadp_unit {
+    ($m0:expr, 0) => {
+        concat!(
+            "ld1h {{ z0.h }}, p0/z, [", $m0, "]\n",
+            "ld1h {{ z1.h }}, p0/z, [", $m0, ", #1, MUL VL]\n",
+        )
+    };
+    ($m0:expr, 1) => {
+        concat!(
+            "ld1h {{ z2.h }}, p0/z, [", $m0, ", #2, MUL VL]\n",
+            "ld1h {{ z3.h }}, p0/z, [", $m0, ", #3, MUL VL]\n",
+        )
+    };
+}
+
+macro_rules! storep_unit {
+    (C, $r1:expr, $m0:expr) => {
+        concat!(
+            "st1w {{ z", $r1, ".s }}, p0, ", $m0, "\n",
+        )
+    };
+    (M, $r1:expr, $m0:expr) => {
+        concat!(
+            "st1w {{ z", $r1, ".s }}, p1, ", $m0, "\n",
+        )
+    };
+}
+
+macro_rules! alpha_scale_0 {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(
+                "ld1rqw {{ z1.s }}, p0/z, [{alphax}]", "\n",
+
+                #(
+                    "scvtf z", r, ".s, p0/m, z", r, ".s\n",
+                    "fmul  z", r, ".s, z", r, ".s, z1.s[0]\n",
+                    "fcvtzs z", r, ".s, p0/m, z", r, ".s\n",
+                )*
+            )
+        })
+    }
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            "ld1rqw {{ z0.s }}, p0/z, [{betax}]", "\n",
+        )
+    }
+}
+
+macro_rules! init_ab {
+    (B) => {
+        concat!(
+            "/* {x11} */", "\n",
+            "/* {x10} */", "\n",
+            "/* {x9} */", "\n",
+            "/* {x8} */", "\n",
+            "/* {x7} */", "\n",
+            "/* {x6} */", "\n",
+            "/* {x5} */", "\n",
+            "/* {x4} */", "\n",
+            "/* {x3} */", "\n",
+            "/* {x2} */", "\n",
+            "/* {x1} */", "\n",
+
+            "ldr {x0}, [{dim_arrx}, #24]", "\n",
+        )
+    };
+}
+
+// narrow the i64 accumulators of each c vector into i32, the low halves are the wrapped i32 sums
+macro_rules! c_load {
+    () => {
+        concat!(
+            seq!(j in 0..6 {
+                concat!(
+                    #(
+                        "uzp1 z", cr!(0,j), ".s, z", dr!(0,j), ".s, z", dr!(1,j), ".s\n",
+                        "uzp1 z", cr!(1,j), ".s, z", dr!(2,j), ".s, z", dr!(3,j), ".s\n",
+                    )*
+                )
+            }),
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0} \n",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+        )
+    };
+}
+
+macro_rules! vzero_kernel {
+    () => {
+        concat!(
+            vzeroall!(8,31),
+            "ptrue p2.h, vl4\n",
+        )
+    };
+}
+
+macro_rules! inc_b {
+    (B,$nr:tt) => {
+        concat!(
+            "add {bx}, {bx}, #", $nr, "*8 \n",
+        )
+    };
+}
+
+macro_rules! alpha_scale {
+    () => {
+        alpha_scale_0!(8,31)
+    };
+}
+
+// c vector i of column n
+macro_rules! cr {
+    (0,0) => { 8 };
+    (1,0) => { 10 };
+
+    (0,1) => { 12 };
+    (1,1) => { 14 };
+
+    (0,2) => { 16 };
+    (1,2) => { 18 };
+
+    (0,3) => { 20 };
+    (1,3) => { 22 };
+
+    (0,4) => { 24 };
+    (1,4) => { 26 };
+
+    (0,5) => { 28 };
+    (1,5) => { 30 };
+}
+
+// i64 accumulator of a register r of column n
+macro_rules! dr {
+    (0,0) => { 8 };
+    (1,0) => { 9 };
+    (2,0) => { 10 };
+    (3,0) => { 11 };
+
+    (0,1) => { 12 };
+    (1,1) => { 13 };
+    (2,1) => { 14 };
+    (3,1) => { 15 };
+
+    (0,2) => { 16 };
+    (1,2) => { 17 };
+    (2,2) => { 18 };
+    (3,2) => { 19 };
+
+    (0,3) => { 20 };
+    (1,3) => { 21 };
+    (2,3) => { 22 };
+    (3,3) => { 23 };
+
+    (0,4) => { 24 };
+    (1,4) => { 25 };
+    (2,4) => { 26 };
+    (3,4) => { 27 };
+
+    (0,5) => { 28 };
+    (1,5) => { 29 };
+    (2,5) => { 30 };
+    (3,5) => { 31 };
+}
+
+macro_rules! br {
+    (0) => { 4 };
+    (1) => { 4 };
+    (2) => { 5 };
+    (3) => { 5 };
+    (4) => { 6 };
+    (5) => { 6 };
+}
+
+macro_rules! bl {
+    (0) => { 0 };
+    (1) => { 1 };
+    (2) => { 0 };
+    (3) => { 1 };
+    (4) => { 0 };
+    (5) => { 1 };
+}
+
+// b of a 4 k block is 4 consecutive k of each column, ld1rqh brings two columns into every 128-bit segment.
+// the last column of an odd panel is loaded with p2 (4 lanes) to not read past the panel
+macro_rules! load_b {
+    (B, 1, 0) => {
+        "ld1rqh {{ z4.h }}, p2/z, [{bx}]\n"
+    };
+    (B, 3, 2) => {
+        "ld1rqh {{ z5.h }}, p2/z, [{bx}, #0x10]\n"
+    };
+    (B, 5, 4) => {
+        "ld1rqh {{ z6.h }}, p2/z, [{bx}, #0x20]\n"
+    };
+    (B, $nr:tt, 0) => {
+        "ld1rqh {{ z4.h }}, p0/z, [{bx}]\n"
+    };
+    (B, $nr:tt, 2) => {
+        "ld1rqh {{ z5.h }}, p0/z, [{bx}, #0x10]\n"
+    };
+    (B, $nr:tt, 4) => {
+        "ld1rqh {{ z6.h }}, p0/z, [{bx}, #0x20]\n"
+    };
+    (B, $nr:tt, $ni:tt) => {
+        ""
+    };
+}
+
+macro_rules! step_2 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, $nr, n),
+                    vfmadd!(0, n),
+                    vfmadd!(1, n),
+                    vfmadd!(2, n),
+                    vfmadd!(3, n),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! step_1 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, $nr, n),
+                    vfmadd!(0, n),
+                    vfmadd!(1, n),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! prefetch_c {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0}\n ",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "prfm pldl1keep, [{cx}] \n",
+            "prfm pldl1keep, [{cx},#64]\n",
+            "prfm pldl1keep, [{x1}] \n",
+            "prfm pldl1keep, [{x1},#64]\n",
+            "prfm pldl1keep, [{x2}] \n",
+            "prfm pldl1keep, [{x2},#64]\n",
+            "prfm pldl1keep, [{x3}] \n",
+            "prfm pldl1keep, [{x3},#64]\n",
+            "prfm pldl1keep, [{x4}] \n",
+            "prfm pldl1keep, [{x4},#64]\n",
+            "prfm pldl1keep, [{x5}] \n",
+            "prfm pldl1keep, [{x5},#64]\n",
+        )
+    };
+}
+
+def_ukernel_sve_i16!(step_1, acc_1, store_1, 1, 6, B, M, ukernel_1_bbp);
+def_ukernel_sve_i16!(step_2, acc_2, store_2, 2, 6, B, M, ukernel_2_bbp);
+
+def_ukernel_sve_i16!(step_2, acc_2, store_2, 2, 6, B, C, ukernel_bbc);
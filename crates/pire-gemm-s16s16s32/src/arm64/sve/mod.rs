@@ -0,0 +1,22 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TC};
+
+unsafe fn simd_vector_length() -> usize {
+    super::sve_vs()
+}
+
+const ZERO: TC = 0i32;
+
+const VS_MAX: usize = 2048 / (8 * core::mem::size_of::<TC>());
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(i16, i16, i32, f32, false, F, 4, 2, 6, 0, 0);
+
+use super::pack_sve::packa_panel;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(i16, i16, i16, i32, f32, false, F, packa_panel, 4, 2, 6, 0, 0);
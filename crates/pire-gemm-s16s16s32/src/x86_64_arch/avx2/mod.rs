@@ -41,7 +41,7 @@ pub unsafe fn axpy<F: UnaryFnC>(
     if a_rs == 1 && incy == 1 {
         axpy_v(m, n, alpha, a, a_cs, x, incx, beta, y);
         // move this inside axpy_v, and benchmark
-        f.call(y, m);
+        f.call_tile(y, m, 0, 0);
         return;
     }
 
@@ -54,7 +54,7 @@ pub unsafe fn axpy<F: UnaryFnC>(
             acc += *a_cur as i32 * *x_cur as i32;
         }
         *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
-        f.call(y_cur, 1);
+        f.call_tile(y_cur, 1, i, 0);
     }
 }
 
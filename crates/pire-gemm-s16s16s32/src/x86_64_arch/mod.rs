@@ -1,14 +1,15 @@
 pub(crate) mod avx2;
 pub(crate) mod avx512_vnni;
 pub(crate) mod avx512bw;
+pub(crate) mod avx_vnni;
 pub(crate) mod pack_avx;
 pub(crate) mod pack_sse;
 pub(crate) mod sse;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, TransposedFn, PACK_POOL,
     RUNTIME_HW_CONFIG,
 };
 
@@ -20,11 +21,13 @@ const SSE_VS: usize = 4;
 
 const AVX512_VNNI_MR: usize = 48;
 const AVX512BW_MR: usize = 32;
+const AVX_VNNI_MR: usize = 16;
 const AVX2_MR: usize = 16;
 const SSE_MR: usize = 8;
 
 const AVX512_VNNI_NR: usize = 8;
 const AVX512BW_NR: usize = 8;
+const AVX_VNNI_NR: usize = 4;
 const AVX2_NR: usize = 4;
 const SSE_NR: usize = 4;
 
@@ -35,6 +38,8 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
         (AVX512_VNNI_MR, AVX512_VNNI_NR)
     } else if features.avx512bw {
         (AVX512BW_MR, AVX512BW_NR)
+    } else if features.avx_vnni && features.avx2 {
+        (AVX_VNNI_MR, AVX_VNNI_NR)
     } else if features.avx2 {
         (AVX2_MR, AVX2_NR)
     } else {
@@ -95,6 +100,7 @@ pub(crate) fn round_k_simd(k: usize) -> usize {
 pub(crate) enum RegDim {
     Avx512VNNI,
     Avx512BW,
+    AvxVnni,
     Avx2,
     Sse,
 }
@@ -125,6 +131,8 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
             (AVX512_VNNI_MR, AVX512_VNNI_NR, RegDim::Avx512VNNI)
         } else if features.avx512bw {
             (AVX512BW_MR, AVX512BW_NR, RegDim::Avx512BW)
+        } else if features.avx_vnni && features.avx2 {
+            (AVX_VNNI_MR, AVX_VNNI_NR, RegDim::AvxVnni)
         } else if features.avx2 {
             (AVX2_MR, AVX2_NR, RegDim::Avx2)
         } else {
@@ -164,6 +172,10 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
     pub(crate) fn round_m(&self, m: usize) -> usize {
         (m + self.vs - 1) / self.vs * self.vs
     }
+
+    pub(crate) fn nr(&self) -> usize {
+        self.nr
+    }
 }
 
 impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
@@ -201,20 +213,25 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
         }
@@ -236,6 +253,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     panic!("Not implemented");
@@ -256,18 +275,18 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512VNNI => {
-                avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::Avx512BW => {
-                avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Avx512VNNI => avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx512BW => avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::AvxVnni => avx_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
@@ -276,13 +295,14 @@ unsafe fn kernel_n<F: UnaryFnC>(
                 avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
             }
             RegDim::Avx512BW => avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+            RegDim::AvxVnni => avx_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
         }
     }
 }
 
-unsafe fn pire_gemv<F: UnaryFnC>(
+unsafe fn gemv_fused<F: UnaryFnC, G: UnaryFnC>(
     hw_cfg: &KernelDispatcher<F>,
     m: usize,
     n: usize,
@@ -291,20 +311,48 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     x: Array<TB>,
     beta: *const f32,
     y: ArrayMut<TC>,
+    f: G,
 ) {
     let x_ptr = x.src();
     let inc_x = x.rs();
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::Avx2 => {
-            avx2::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::AvxVnni | RegDim::Avx2 => {
+            avx2::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, f)
         }
-        RegDim::Sse => sse::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Sse => sse::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, f),
     }
     return;
 }
 
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    gemv_fused(hw_cfg, m, n, alpha, a, x, beta, y, hw_cfg.func);
+}
+
+// m == 1 runs on the transposed problem, y is a row of c
+unsafe fn pire_gemv2<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    gemv_fused(hw_cfg, m, n, alpha, a, x, beta, y, TransposedFn(hw_cfg.func));
+}
+
 def_pire_gemm!(
     KernelDispatcher,
     i16,
@@ -326,7 +374,7 @@ def_pire_gemm!(
     gemm_small_n_serial,
     kernel_n,
     pire_gemv,
-    pire_gemv,
+    pire_gemv2,
     packa0,
     packb0,
     packa_fn_simd,
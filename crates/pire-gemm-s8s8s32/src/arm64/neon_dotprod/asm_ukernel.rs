@@ -0,0 +1,391 @@
+use seq_macro::seq;
+use crate::{TA, TB, TC, TC_SIZE};
+use pire_base::{
+    def_ukernel_neon_dotprod,
+    acc_2, acc_1,
+    store_2, store_1,
+};
+
+type TS = f32;
+
+const ONE_SCALAR: TS = 1f32;
+const ZERO_SCALAR: TS = 0f32;
+
+// v0-v3: a, v4: b, v8-v31: c
+
+macro_rules! inc_a {
+    ($mr:tt) => {
+        concat!("add {ax}, {ax}, #32*", $mr, " \n")
+    };
+}
+
+macro_rules! v_i {
+    ($m0:tt, $ni:tt) => {
+        concat!("[", $m0, ", #", $ni, "*0x10]")
+    }
+}
+
+macro_rules! beta_fmadd {
+    (C, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "add v", $r1, ".4s, v", $r1, ".4s, v1.4s\n",
+        ) 
+    };
+    (M, $m0:expr, $r1:expr,1) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "add v", $r1, ".4s, v", $r1, ".4s, v1.4s\n",
+        ) 
+    };
+
+    (C, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "scvtf v1.4s, v1.4s\n",
+            "scvtf v", $r1, ".4s, v", $r1, ".4s\n",
+            "fmla v", $r1, ".4s, v1.4s, v0.s[0]\n",
+            "fcvtzs v", $r1, ".4s, v", $r1, ".4s\n",
+        ) 
+    };
+    (M, $m0:expr, $r1:expr,2) => {
+        concat!(
+            "ldr q1, ", $m0, "\n",
+            "scvtf v1.4s, v1.4s\n",
+            "scvtf v", $r1, ".4s, v", $r1, ".4s\n",
+            "fmla v", $r1, ".4s, v1.4s, v0.s[0]\n",
+            "fcvtzs v", $r1, ".4s, v", $r1, ".4s\n",
+        ) 
+    };
+}
+
+macro_rules! vzeroall {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(#("dup v", r, ".4s, wzr \n",)*)
+        })
+    }
+}
+
+macro_rules! vfmadd {
+    ($r1:expr, $r2:expr, $l:expr) => {
+        concat!(
+            "sdot v", $r1, ".4s, v", $r2, ".16b, v4.4b[", $l, "]\n",
+        )
+    };
+}
+
+// a of each 8 k block is packed as k0..4 of mr rows followed by k4..8 of mr rows
+macro_rules! loadp_unit {
+    ($m0:expr, 0) => {
+        concat!(
+            "ldp q0, q1, [", $m0, "]\n",
+        )
+    };
+    ($m0:expr, 1) => {
+        concat!(
+            "ldp q2, q3, [", $m0, ", #32]\n",
+        )
+    };
+}
+
+macro_rules! storep_unit {
+    ($l:tt, $r1:expr, $m0:expr) => {
+        concat!(
+            "str q", $r1, ", ", $m0,  "\n",
+        )
+    };
+}
+
+macro_rules! alpha_scale_0 {
+    ($r0:tt, $r1:tt) => {
+        seq!(r in $r0..=$r1 {
+            concat!(
+                "ldr s1, [{alphax}]", "\n",
+                #(
+                    "scvtf v", r, ".4s, v", r, ".4s\n",
+                    "fmul  v", r, ".4s, v", r, ".4s, v1.s[0]\n",
+                    "fcvtzs v", r, ".4s, v", r, ".4s\n",
+                )*
+            )
+        })
+    }
+}
+
+macro_rules! load_beta {
+    () => {
+        concat!(
+            "ldr s0, [{betax}]", "\n",
+            "/* {betax} */", "\n",
+        )
+    }
+}
+
+macro_rules! init_ab {
+    (B) => {
+        concat!(
+            "/* {x11} */", "\n",
+            "/* {x10} */", "\n",
+            "/* {x9} */", "\n",
+            "/* {x8} */", "\n",
+            "/* {x7} */", "\n",
+            "/* {x6} */", "\n",
+            "/* {x5} */", "\n",
+            "/* {x4} */", "\n",
+            "/* {x3} */", "\n",
+            "/* {x2} */", "\n",
+            "/* {x1} */", "\n",
+
+            "ldr {x0}, [{dim_arrx}, #24]", "\n",
+        )
+    };
+    (S) => {
+        concat!(
+            // mov cs_b to reg
+            "mov ({dim_arrx}), {x1}", "\n",
+            // "mov 8({dim_arrx}), {x2}", "\n",
+            "ldr {x0}, [{dim_arrx}, #24]", "\n",
+        )
+    };
+}
+
+
+macro_rules! c_load {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0} \n",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+            "add {x8}, {x7}, {x0} \n",
+            "add {x9}, {x8}, {x0} \n",
+            "add {x10}, {x9}, {x0} \n",
+            "add {x11}, {x10}, {x0} \n",
+        )
+    };
+}
+
+
+macro_rules! vzero_kernel {
+    () => {vzeroall!(8,31)};
+}
+
+macro_rules! inc_b {
+    (B,$nr:tt) => {
+        concat!(
+            "add {bx}, {bx}, #", $nr, "*8 \n",
+        )
+    };
+}
+
+
+macro_rules! alpha_scale {
+    () => {
+        alpha_scale_0!(8,31)
+    };
+}
+
+macro_rules! cr {
+    (0,0) => { 8 };
+    (1,0) => { 9 };
+
+    (0,1) => { 10 };
+    (1,1) => { 11 };
+
+    (0,2) => { 12 };
+    (1,2) => { 13 };
+
+    (0,3) => { 14 };
+    (1,3) => { 15 };
+
+    (0,4) => { 16 };
+    (1,4) => { 17 };
+
+    (0,5) => { 18 };
+    (1,5) => { 19 };
+
+    (0,6) => { 20 };
+    (1,6) => { 21 };
+
+    (0,7) => { 22 };
+    (1,7) => { 23 };
+
+    (0,8) => { 24 };
+    (1,8) => { 25 };
+
+    (0,9) => { 26 };
+    (1,9) => { 27 };
+
+    (0,10) => { 28 };
+    (1,10) => { 29 };
+
+    (0,11) => { 30 };
+    (1,11) => { 31 };
+}
+
+// sdot lane of b for the k0..4 and k4..8 halves of column n, two columns per b register
+macro_rules! bl0 {
+    (0) => { 0 };
+    (1) => { 2 };
+    (2) => { 0 };
+    (3) => { 2 };
+    (4) => { 0 };
+    (5) => { 2 };
+    (6) => { 0 };
+    (7) => { 2 };
+    (8) => { 0 };
+    (9) => { 2 };
+    (10) => { 0 };
+    (11) => { 2 };
+}
+
+macro_rules! bl1 {
+    (0) => { 1 };
+    (1) => { 3 };
+    (2) => { 1 };
+    (3) => { 3 };
+    (4) => { 1 };
+    (5) => { 3 };
+    (6) => { 1 };
+    (7) => { 3 };
+    (8) => { 1 };
+    (9) => { 3 };
+    (10) => { 1 };
+    (11) => { 3 };
+}
+
+macro_rules! load_b {
+    (B, 0) => {
+        concat!(
+            "ldr q4, [{bx}]", "\n",
+        )
+    };
+    (B, 2) => {
+        concat!(
+            "ldr q4, [{bx}, #0x10]", "\n",
+        )
+    };
+    (B, 4) => {
+        concat!(
+            "ldr q4, [{bx}, #0x20]", "\n",
+        )
+    };
+    (B, 6) => {
+        concat!(
+            "ldr q4, [{bx}, #0x30]", "\n",
+        )
+    };
+    (B, 8) => {
+        concat!(
+            "ldr q4, [{bx}, #0x40]", "\n",
+        )
+    };
+    (B, 10) => {
+        concat!(
+            "ldr q4, [{bx}, #0x50]", "\n",
+        )
+    };
+    (B, $nr:tt) => {
+        ""
+    };
+}
+
+macro_rules! fmadd_2 {
+    ($ni:tt) => {
+        concat!(
+            vfmadd!(cr!(0,$ni), 0, bl0!($ni)),
+            vfmadd!(cr!(1,$ni), 1, bl0!($ni)),
+            vfmadd!(cr!(0,$ni), 2, bl1!($ni)),
+            vfmadd!(cr!(1,$ni), 3, bl1!($ni)),
+        )
+    };
+}
+
+macro_rules! step_2 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, n),
+                    fmadd_2!(n),
+                )*
+            )
+        })
+    };
+}
+
+
+macro_rules! fmadd_1 {
+    ($ni:tt) => {
+        concat!(
+            vfmadd!(cr!(0,$ni), 0, bl0!($ni)),
+            vfmadd!(cr!(0,$ni), 1, bl1!($ni)),
+        )
+    };
+}
+
+macro_rules! step_1 {
+    ($b_layout:tt, $nr:tt) => {
+        seq!(n in 0..$nr {
+            concat!(
+                #(
+                    load_b!($b_layout, n),
+                    fmadd_1!(n),
+                )*
+            )
+        })
+    };
+}
+
+macro_rules! prefetch_c {
+    () => {
+        concat!(
+            "ldr {x0}, [{dim_arrx}, #16]\n",
+            "add {x1}, {cx}, {x0}\n ",
+            "add {x2}, {x1}, {x0} \n",
+            "add {x3}, {x2}, {x0} \n",
+            "add {x4}, {x3}, {x0} \n",
+            "add {x5}, {x4}, {x0} \n",
+            "add {x6}, {x5}, {x0} \n",
+            "add {x7}, {x6}, {x0} \n",
+            "add {x8}, {x7}, {x0} \n",
+            "add {x9}, {x8}, {x0} \n",
+            "add {x10}, {x9}, {x0} \n",
+            "add {x11}, {x10}, {x0} \n",
+            "prfm pldl1keep, [{cx}] \n",
+            "prfm pldl1keep, [{cx},#56]\n",
+            "prfm pldl1keep, [{x1}] \n",
+            "prfm pldl1keep, [{x1},#56]\n",
+            "prfm pldl1keep, [{x2}] \n",
+            "prfm pldl1keep, [{x2},#56]\n",
+            "prfm pldl1keep, [{x3}] \n",
+            "prfm pldl1keep, [{x3},#56]\n",
+            "prfm pldl1keep, [{x4}] \n",
+            "prfm pldl1keep, [{x4},#56]\n",
+            "prfm pldl1keep, [{x5}] \n",
+            "prfm pldl1keep, [{x5},#56]\n",
+            "prfm pldl1keep, [{x6}] \n",
+            "prfm pldl1keep, [{x6},#56]\n",
+            "prfm pldl1keep, [{x7}] \n",
+            "prfm pldl1keep, [{x7},#56]\n",
+            "prfm pldl1keep, [{x8}] \n",
+            "prfm pldl1keep, [{x8},#56]\n",
+            "prfm pldl1keep, [{x9}] \n",
+            "prfm pldl1keep, [{x9},#56]\n",
+            "prfm pldl1keep, [{x10}] \n",
+            "prfm pldl1keep, [{x10},#56]\n",
+            "prfm pldl1keep, [{x11}] \n",
+            "prfm pldl1keep, [{x11},#56]\n",
+        )
+    };
+}
+
+
+def_ukernel_neon_dotprod!(step_1, acc_1, store_1, 1, 12, B, M, ukernel_1_bbp);
+def_ukernel_neon_dotprod!(step_2, acc_2, store_2, 2, 12, B, M, ukernel_2_bbp);
+
+def_ukernel_neon_dotprod!(step_2, acc_2, store_2, 2, 12, B, C, ukernel_bbc);
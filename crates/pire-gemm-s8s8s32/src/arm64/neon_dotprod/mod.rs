@@ -0,0 +1,23 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TC};
+
+const VS: usize = 4;
+const VS_MAX: usize = VS;
+
+const ZERO: TC = 0i32;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(i8, i8, i32, f32, true, F, 8, 2, 12, 0, 0);
+
+use super::pack_dotprod::packa_panel_8;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(i8, i8, i8, i32, f32, true, F, packa_panel_8, 8, 2, 12, 0, 0);
@@ -0,0 +1,103 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::{UnaryFnC, TA, TB, TC};
+
+unsafe fn simd_vector_length() -> usize {
+    super::sve_vs()
+}
+
+const ZERO: TC = 0i32;
+
+const VS_MAX: usize = 2048 / (8 * core::mem::size_of::<TC>());
+
+#[target_feature(enable = "neon")]
+pub unsafe fn axpy<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const TB,
+    incx: usize,
+    beta: *const f32,
+    y: *mut TC,
+    incy: usize,
+    f: F,
+) {
+    // if a_cs == 1 && incx == 1 {
+    //     axpy_d(m, n, alpha, a, a_rs, x, beta, y, incy);
+    //     for i in 0..m {
+    //         f.call(y.add(i * incy), 1);
+    //     }
+    //     return;
+    // }
+    // if a_rs == 1 && incy == 1 {
+    //     axpy_v(m, n, alpha, a, a_cs, x, incx, beta, y);
+    //     f.call(y, m);
+    //     return;
+    // }
+
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = 0i32;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = x.add(j * incx);
+            acc += *a_cur as i32 * *x_cur as i32;
+        }
+        *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
+        f.call(y_cur, 1);
+    }
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn axpy2<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: *const TB,
+    a_rs: usize,
+    a_cs: usize,
+    x: *const TA,
+    incx: usize,
+    beta: *const f32,
+    y: *mut TC,
+    incy: usize,
+    f: F,
+) {
+    // if a_cs == 1 && incx == 1 {
+    //     axpy_d(m, n, alpha, a, a_rs, x, beta, y, incy);
+    //     for i in 0..m {
+    //         f.call(y.add(i * incy), 1);
+    //     }
+    //     return;
+    // }
+    // if a_rs == 1 && incy == 1 {
+    //     axpy_v(m, n, alpha, a, a_cs, x, incx, beta, y);
+    //     f.call(y, m);
+    //     return;
+    // }
+
+    for i in 0..m {
+        let y_cur = y.add(i * incy);
+        let mut acc = 0i32;
+        for j in 0..n {
+            let a_cur = a.add(i * a_rs + j * a_cs);
+            let x_cur = x.add(j * incx);
+            acc += *a_cur as i32 * *x_cur as i32;
+        }
+        *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
+        f.call(y_cur, 1);
+    }
+}
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(i8, i8, i32, f32, false, F, 8, 2, 12, 0, 0);
+
+use super::pack_sve::packa_panel;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(i8, i8, i8, i32, f32, false, F, packa_panel, 8, 2, 12, 0, 0);
@@ -0,0 +1,815 @@
+#[cfg(target_arch = "aarch64")]
+pub(crate) mod arm64;
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod x86_64_arch;
+
+#[cfg(target_arch = "x86_64")]
+use x86_64_arch::{
+    get_mcnckc_simd, has_vnni, packa_fn_simd, packb_fn_simd, pire_gemm, pire_gemm_i16, round_k_simd, round_m_simd,
+    KernelDispatcher, KernelDispatcherI16,
+};
+
+#[cfg(target_arch = "aarch64")]
+use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
+
+pub(crate) mod reference;
+
+use core::mem::size_of;
+
+pub(crate) type TA = i8;
+pub(crate) type TB = i8;
+pub(crate) type TC = i32;
+#[allow(unused)]
+const TC_SIZE: usize = size_of::<TC>();
+
+use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
+
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+use pire_base::{get_cache_params, has_i8i32_compute, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
+
+pub trait UnaryFnC: UnaryFn<TC> {}
+impl<F: UnaryFn<TC>> UnaryFnC for F {}
+
+pub(crate) unsafe fn pire_gemm_s8s8s32_fused<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    let par = PirePar::default(m, n);
+    pire_gemm_s8s8s32_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_gemm_s8s8s32_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    if has_i8i32_compute() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if has_vnni() {
+                let hw_config = KernelDispatcher::new(f);
+                pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            } else {
+                let hw_config = KernelDispatcherI16::new(f);
+                pire_gemm_i16(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            }
+            return;
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let hw_config = KernelDispatcher::new(f);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+            return;
+        }
+    }
+    // if none of the optimized paths are available, use reference implementation
+    let hw_config = RefGemm::new(f);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
+}
+
+pub unsafe fn pire_gemm_s8s8s32(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    // let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+    //  (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    // } else {
+    // 	(m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    // };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_gemm_s8s8s32_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
+}
+
+/// Same as [`pire_gemm_s8s8s32`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_gemm_s8s8s32_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    // let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+    //  (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    // } else {
+    // 	(m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    // };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_gemm_s8s8s32_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_gemm_s8s8s32`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_gemm_s8s8s32_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_gemm_s8s8s32_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_gemm_s8s8s32_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_gemm_s8s8s32_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_gemm_s8s8s32_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_gemm_s8s8s32`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_gemm_s8s8s32_grouped(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+) {
+    pire_gemm_s8s8s32_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_gemm_s8s8s32_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_gemm_s8s8s32_grouped_par(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_gemm_s8s8s32_par(
+            g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par,
+        );
+    });
+}
+
+#[cfg(feature = "fuse")]
+pub unsafe fn pire_gemm_s8s8s32_fn_ptr(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    unary: unsafe fn(*mut TC, usize),
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    // let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+    //     (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    // } else {
+    //     (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    // };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    pire_gemm_s8s8s32_fused(m, n, k, alpha, a, b, beta, c, unary);
+}
+
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_gemm_s8s8s32_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_s8s8s32_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_gemm_s8s8s32_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_gemm_s8s8s32_packed`]
+pub unsafe fn pire_gemm_s8s8s32_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_s8s8s32_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Safe counterpart of [`pire_gemm_s8s8s32`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_gemm_s8s8s32_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: f32,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| (beta * v as f32).round() as i32);
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_gemm_s8s8s32(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
+fn dispatch_round_m() -> fn(usize) -> usize {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            return round_m_simd;
+        }
+    }
+    round_m_ref
+}
+fn dispatch_round_k() -> fn(usize) -> usize {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            return round_k_simd;
+        }
+    }
+    round_k_ref
+}
+
+fn dispatch_pack_a() -> unsafe fn(*const TA, *mut TA, usize, usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            return packa_fn_simd;
+        }
+    }
+    packa_fn_ref
+}
+
+fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            return packb_fn_simd;
+        }
+    }
+    packb_fn_ref
+}
+
+fn dispatch_get_mcnckc() -> (usize, usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            return get_mcnckc_simd();
+        }
+    }
+    get_cache_params()
+}
+
+pire_base::packing_api!(TA, TB);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aligned_vec::avec;
+    use pire_base::{get_cache_params, matrix_size};
+    use pire_dev::{
+        check_gemm_s8s8s32, generate_k_dims, generate_m_dims, generate_n_dims, layout_to_strides,
+        random_matrix_full_range, random_matrix_uniform, run_test_with_env, ABLayout,
+    };
+    #[test]
+    fn test_pack_a() {
+        let a_stride_scale = 1;
+        let (mc, _, kc) = get_mcnckc();
+        let (mr, _, kr) = (48, 8, 8);
+        let m_dims = generate_m_dims(mc, mr);
+        let k_dims = generate_k_dims(kc, kr);
+
+        for &m in &m_dims {
+            for &k in &k_dims {
+                let a_rs = 1 * a_stride_scale;
+                let a_cs = m * a_stride_scale;
+                let a_size = a_size_packed(m, k);
+                let a = vec![0i8; m * k * a_stride_scale];
+                let mut ap = avec![[AB_ALIGN]| 0i8; a_size];
+                let ap_array = pack_a(m, k, &a, a_rs, a_cs, &mut ap);
+                assert!(!ap_array.is_strided() || m == 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pack_b() {
+        let b_stride_scale = 1;
+        let (_, nc, kc) = get_mcnckc();
+        let (_, nr, kr) = (48, 8, 8);
+        let n_dims = generate_n_dims(nc, nr);
+        let k_dims = generate_k_dims(kc, kr);
+
+        for &n in &n_dims {
+            for &k in &k_dims {
+                let b_rs = 1 * b_stride_scale;
+                let b_cs = k * b_stride_scale;
+                let b_size = b_size_packed(n, k);
+                let b = vec![0i8; n * k * b_stride_scale];
+                let mut bp = avec![[AB_ALIGN]| 0i8; b_size];
+                let bp_array = pack_b(n, k, &b, b_rs, b_cs, &mut bp);
+                assert!(!bp_array.is_strided() || n == 1);
+            }
+        }
+    }
+
+    // packed sizes and kc block offsets over three kc blocks, with the last one partial
+    #[test]
+    fn test_packed_size() {
+        let (_, _, kc) = dispatch_get_mcnckc();
+        let round_k = dispatch_round_k();
+        let round_m = dispatch_round_m();
+        let (m, n, k) = (53, 13, 2 * kc + 5);
+        let k_packed = 2 * round_k(kc) + round_k(5);
+        assert_eq!(a_size_packed(m, k), round_m(m) * k_packed);
+        assert_eq!(b_size_packed(n, k), n * k_packed);
+        assert_eq!(a_size_packed(m, kc), round_m(m) * round_k(kc));
+        assert_eq!(b_size_packed(n, 1), n * round_k(1));
+
+        let mut a = vec![0i8; m * k];
+        let mut b = vec![0i8; k * n];
+        random_matrix_full_range(&mut a, 43);
+        random_matrix_full_range(&mut b, 47);
+        let mut ap = avec![[AB_ALIGN]| 0i8; a_size_packed(m, k)];
+        let mut bp = avec![[AB_ALIGN]| 0i8; b_size_packed(n, k)];
+        let a_matrix = pack_a(m, k, &a, 1, m, &mut ap);
+        let b_matrix = pack_b(n, k, &b, 1, k, &mut bp);
+        let mut c = vec![0i32; m * n];
+        let mut c_ref = vec![0i32; m * n];
+        let unary_fn: unsafe fn(*mut TC, usize) = unary_fn_identity;
+        let diff_max = unsafe {
+            let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+            pire_gemm_s8s8s32_fused(m, n, k, 1.0, a_matrix, b_matrix, 0.0, c_matrix, unary_fn);
+            check_gemm_s8s8s32(
+                m,
+                n,
+                k,
+                1.0,
+                a.as_ptr(),
+                1,
+                m,
+                b.as_ptr(),
+                1,
+                k,
+                0.0,
+                &mut c,
+                1,
+                m,
+                &mut c_ref,
+                unary_fn,
+                EPS,
+            )
+        };
+        assert!(diff_max < EPS, "diff_max: {}, m: {}, n: {}, k: {}", diff_max, m, n, k);
+    }
+
+    #[allow(unreachable_code)]
+    pub(crate) fn get_mcnckc() -> (usize, usize, usize) {
+        #[cfg(target_arch = "x86_64")]
+        {
+            return x86_64_arch::get_mcnckc_simd();
+        }
+        get_cache_params()
+    }
+
+    unsafe fn unary_fn_test(c: *mut TC, m: usize) {
+        for i in 0..m {
+            *c.add(i) *= 2;
+        }
+    }
+
+    const EPS: f64 = 1e-1;
+
+    static ALPHA_ARR: [f32; 1] = [2.0];
+    static BETA_ARR: [f32; 1] = [3.0];
+
+    fn test_gemm(layout: &ABLayout, is_a_packed: bool, is_b_packed: bool) {
+        let a_stride_scale = 1;
+        let b_stride_scale = 1;
+        let c_stride_scale = 2;
+        let (mc, nc, kc) = get_mcnckc();
+        let (mr, nr, kr) = (48, 8, 8);
+        let m_dims = generate_m_dims(mc, mr);
+        let n_dims = generate_n_dims(nc, nr);
+        let k_dims = generate_k_dims(kc, kr);
+        let unary_fn: unsafe fn(*mut TC, usize) = unary_fn_test;
+        for &m in &m_dims {
+            for &n in &n_dims {
+                let c_size = matrix_size(m, n) * c_stride_scale;
+                let mut c = vec![0i32; c_size];
+                let mut c_ref = vec![0i32; c_size];
+                for &k in &k_dims {
+                    let a_size = matrix_size(m, k) * a_stride_scale;
+                    let b_size = matrix_size(k, n) * b_stride_scale;
+                    let mut a = vec![0i8; a_size];
+                    let mut b = vec![0i8; b_size];
+                    random_matrix_uniform(&mut a);
+                    random_matrix_uniform(&mut b);
+                    let ap_size = if is_a_packed { a_size_packed(m, k) } else { 0 };
+                    let mut ap = avec![[AB_ALIGN]| 0i8; ap_size];
+
+                    let bp_size = if is_b_packed { b_size_packed(n, k) } else { 0 };
+                    let mut bp = avec![[AB_ALIGN]| 0i8; bp_size];
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = layout_to_strides(&layout, m, n, k);
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = (
+                        a_rs * a_stride_scale,
+                        a_cs * a_stride_scale,
+                        b_rs * b_stride_scale,
+                        b_cs * b_stride_scale,
+                        c_rs * c_stride_scale,
+                        c_cs * c_stride_scale,
+                    );
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for alpha in ALPHA_ARR {
+                        for beta in BETA_ARR {
+                            random_matrix_uniform(&mut c);
+                            c_ref.copy_from_slice(&c);
+                            let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), c_rs, c_cs);
+                            unsafe {
+                                pire_gemm_s8s8s32_fused(m, n, k, alpha, a_matrix, b_matrix, beta, c_matrix, unary_fn);
+                            }
+                            let diff_max = unsafe {
+                                check_gemm_s8s8s32(
+                                    m,
+                                    n,
+                                    k,
+                                    alpha,
+                                    a.as_ptr(),
+                                    a_rs,
+                                    a_cs,
+                                    b.as_ptr(),
+                                    b_rs,
+                                    b_cs,
+                                    beta,
+                                    &mut c,
+                                    c_rs,
+                                    c_cs,
+                                    &mut c_ref,
+                                    unary_fn,
+                                    EPS,
+                                )
+                            };
+                            // if diff_max >= EPS {
+                            // 	println!("a: {:?}", a);
+                            // 	println!("b: {:?}", b);
+                            // 	println!("c:     {:?}", c);
+                            // 	println!("c_ref: {:?}", c_ref);
+                            // }
+                            assert!(
+                                diff_max < EPS,
+                                "diff_max: {}, m: {}, n: {}, k: {}, alpha: {}, beta: {}",
+                                diff_max,
+                                m,
+                                n,
+                                k,
+                                alpha,
+                                beta
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+    #[test]
+    fn test_nn_col() {
+        test_gemm(&ABLayout::NN, false, false);
+    }
+
+    #[test]
+    fn test_nt_col() {
+        test_gemm(&ABLayout::NT, false, false);
+    }
+
+    #[test]
+    fn test_tn_col() {
+        test_gemm(&ABLayout::TN, false, false);
+    }
+
+    #[test]
+    fn test_tt_col() {
+        test_gemm(&ABLayout::TT, false, false);
+    }
+    #[test]
+    fn test_nn_col_ap() {
+        test_gemm(&ABLayout::NN, true, false);
+    }
+    #[test]
+    fn test_nt_col_ap() {
+        test_gemm(&ABLayout::NT, true, false);
+    }
+    #[test]
+    fn test_tn_col_ap() {
+        test_gemm(&ABLayout::TN, true, false);
+    }
+    #[test]
+    fn test_tt_col_ap() {
+        test_gemm(&ABLayout::TT, true, false);
+    }
+    #[test]
+    fn test_nn_col_bp() {
+        test_gemm(&ABLayout::NN, false, true);
+    }
+    #[test]
+    fn test_nt_col_bp() {
+        test_gemm(&ABLayout::NT, false, true);
+    }
+    #[test]
+    fn test_tn_col_bp() {
+        test_gemm(&ABLayout::TN, false, true);
+    }
+    #[test]
+    fn test_tt_col_bp() {
+        test_gemm(&ABLayout::TT, false, true);
+    }
+
+    #[test]
+    fn test_nn_col_apbp() {
+        test_gemm(&ABLayout::NN, true, true);
+    }
+    #[test]
+    fn test_nt_col_apbp() {
+        test_gemm(&ABLayout::NT, true, true);
+    }
+    #[test]
+    fn test_tn_col_apbp() {
+        test_gemm(&ABLayout::TN, true, true);
+    }
+    #[test]
+    fn test_tt_col_apbp() {
+        test_gemm(&ABLayout::TT, true, true);
+    }
+
+    unsafe fn unary_fn_identity(_c: *mut TC, _m: usize) {}
+
+    // values over the whole i8 range, alpha = 1 and beta in {0, 1} keep the result exact
+    fn test_gemm_full_range(layout: &ABLayout, is_a_packed: bool, is_b_packed: bool) {
+        let (_, _, kc) = get_mcnckc();
+        let m_dims = [2, 16, 53, 137];
+        let n_dims = [3, 8, 13];
+        let k_dims = [1, 3, 64, 67, kc + 5];
+        for &m in &m_dims {
+            for &n in &n_dims {
+                let mut c = vec![0i32; m * n];
+                let mut c_ref = vec![0i32; m * n];
+                for &k in &k_dims {
+                    let mut a = vec![0i8; m * k];
+                    let mut b = vec![0i8; k * n];
+                    random_matrix_full_range(&mut a, 43);
+                    random_matrix_full_range(&mut b, 47);
+                    a[0] = i8::MIN;
+                    b[0] = i8::MIN;
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = layout_to_strides(layout, m, n, k);
+                    let mut ap = avec![[AB_ALIGN]| 0i8; a_size_packed(m, k)];
+                    let mut bp = avec![[AB_ALIGN]| 0i8; b_size_packed(n, k)];
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for beta in [0.0, 1.0] {
+                        random_matrix_uniform(&mut c);
+                        c_ref.copy_from_slice(&c);
+                        let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), c_rs, c_cs);
+                        let unary_fn: unsafe fn(*mut TC, usize) = unary_fn_identity;
+                        let diff_max = unsafe {
+                            pire_gemm_s8s8s32_fused(m, n, k, 1.0, a_matrix, b_matrix, beta, c_matrix, unary_fn);
+                            check_gemm_s8s8s32(
+                                m,
+                                n,
+                                k,
+                                1.0,
+                                a.as_ptr(),
+                                a_rs,
+                                a_cs,
+                                b.as_ptr(),
+                                b_rs,
+                                b_cs,
+                                beta,
+                                &mut c,
+                                c_rs,
+                                c_cs,
+                                &mut c_ref,
+                                unary_fn,
+                                EPS,
+                            )
+                        };
+                        assert!(diff_max < EPS, "diff_max: {}, m: {}, n: {}, k: {}, beta: {}", diff_max, m, n, k, beta);
+                    }
+                }
+            }
+        }
+    }
+
+    // extreme values everywhere, pairwise sums of the products do not fit in i16
+    fn test_gemm_extreme_values() {
+        let (m, n, k) = (16, 16, 64);
+        for (a_v, b_v) in [(127i8, 127i8), (i8::MIN, i8::MIN), (i8::MIN, 127), (127, i8::MIN)] {
+            let a = vec![a_v; m * k];
+            let b = vec![b_v; k * n];
+            let mut c = vec![0i32; m * n];
+            unsafe {
+                pire_gemm_s8s8s32(m, n, k, 1.0, a.as_ptr(), 1, m, b.as_ptr(), 1, k, 0.0, c.as_mut_ptr(), 1, m);
+            }
+            let expected = a_v as i32 * b_v as i32 * k as i32;
+            assert!(c.iter().all(|&x| x == expected), "a: {}, b: {}, c: {}, expected: {}", a_v, b_v, c[0], expected);
+        }
+    }
+
+    #[test]
+    fn test_full_range() {
+        test_gemm_extreme_values();
+        for layout in [ABLayout::NN, ABLayout::NT, ABLayout::TN, ABLayout::TT] {
+            test_gemm_full_range(&layout, false, false);
+        }
+        test_gemm_full_range(&ABLayout::NN, true, false);
+        test_gemm_full_range(&ABLayout::NN, false, true);
+        test_gemm_full_range(&ABLayout::TT, true, true);
+    }
+
+    // the kernels of other cpu features are reached by turning features off in a child process,
+    // in order: avx512bw, avx_vnni, avx2, sse (the next available one on cpus without some of them)
+    #[test]
+    fn test_full_range_avx512bw() {
+        run_test_with_env("tests::test_full_range", &["PIRE_AVX512_VNNI_OFF"]);
+    }
+
+    #[test]
+    fn test_full_range_avx_vnni() {
+        run_test_with_env("tests::test_full_range", &["PIRE_AVX512_VNNI_OFF", "PIRE_AVX512BW_OFF"]);
+    }
+
+    #[test]
+    fn test_full_range_avx2() {
+        run_test_with_env(
+            "tests::test_full_range",
+            &["PIRE_AVX512_VNNI_OFF", "PIRE_AVX512BW_OFF", "PIRE_AVX_VNNI_OFF"],
+        );
+    }
+
+    #[test]
+    fn test_full_range_sse() {
+        run_test_with_env(
+            "tests::test_full_range",
+            &["PIRE_AVX512_VNNI_OFF", "PIRE_AVX512BW_OFF", "PIRE_AVX_VNNI_OFF", "PIRE_AVX2_OFF"],
+        );
+    }
+}
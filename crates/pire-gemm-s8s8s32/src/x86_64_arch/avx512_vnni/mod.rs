@@ -0,0 +1,18 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::UnaryFnC;
+
+const VS: usize = 16;
+const VS_MAX: usize = VS;
+
+const ZERO: i32 = 0;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(i8, i8, i32, f32, false, F, 4, 3, 8, 0, 0);
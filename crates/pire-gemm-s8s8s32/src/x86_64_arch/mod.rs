@@ -0,0 +1,579 @@
+pub(crate) mod avx2;
+pub(crate) mod avx512_vnni;
+pub(crate) mod avx512bw;
+pub(crate) mod avx_vnni;
+pub(crate) mod pack_avx;
+pub(crate) mod pack_i16;
+pub(crate) mod sse;
+
+use pire_base::{
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, HWModel, PArray, PArrayMixed, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
+    RUNTIME_HW_CONFIG,
+};
+
+use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
+
+const AVX512F_VS: usize = 16;
+const AVX_VS: usize = 8;
+const SSE_VS: usize = 4;
+
+const AVX512_VNNI_MR: usize = 48;
+const AVX512BW_MR: usize = 32;
+const AVX_VNNI_MR: usize = 16;
+const AVX2_MR: usize = 16;
+const SSE_MR: usize = 8;
+
+const AVX512_VNNI_NR: usize = 8;
+const AVX512BW_NR: usize = 8;
+const AVX_VNNI_NR: usize = 4;
+const AVX2_NR: usize = 4;
+const SSE_NR: usize = 4;
+
+// vnni kernels take a as the unsigned operand of vpdpbusd, a is packed shifted by +128 and
+// packed b panels carry their column sums for the correction, see pack_avx.
+// The other kernels sign extend a and b to i16 during packing and use vpmaddwd, see pack_i16.
+// avx512bw is preferred over avx_vnni, same order as get_mcnckc_simd
+pub(crate) fn has_vnni() -> bool {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    features.avx512_vnni || (!features.avx512bw && features.avx_vnni && features.avx2)
+}
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    let (mr, nr) = if features.avx512_vnni {
+        (AVX512_VNNI_MR, AVX512_VNNI_NR)
+    } else if features.avx512bw {
+        (AVX512BW_MR, AVX512BW_NR)
+    } else if features.avx_vnni && features.avx2 {
+        (AVX_VNNI_MR, AVX_VNNI_NR)
+    } else if features.avx2 {
+        (AVX2_MR, AVX2_NR)
+    } else {
+        (SSE_MR, SSE_NR)
+    };
+    // let mc = std::env::var("PIRE_MC").unwrap_or("5400".to_string()).parse::<usize>().unwrap();
+    // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
+    // let kc = std::env::var("PIRE_KC").unwrap_or("512".to_string()).parse::<usize>().unwrap();
+    // return (mc, nc, kc);
+    let (mc, nc, kc) = match (*RUNTIME_HW_CONFIG).hw_model {
+        HWModel::Skylake => (4800, 192, 1440),
+        HWModel::Haswell => (4800, 320, 768),
+        _ => get_cache_params(),
+    };
+    (mc / mr * mr, nc / nr * nr, kc)
+}
+
+// packing for the packed api, without vnni the i8 values are kept in the layout of the i16 kernels
+// and widened when the driver loads them, see KernelDispatcherI16::cvt_mixed
+pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
+    let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
+    if feature.avx512_vnni {
+        pack_avx::packa_panel_48(m, k, x, rs, cs, y, AVX512F_VS);
+    } else if feature.avx512bw {
+        pack_i16::packa_panel_scalar::<AVX512BW_MR, TA>(m, k, x, rs, cs, y, AVX512F_VS);
+    } else if feature.avx_vnni && feature.avx2 {
+        pack_avx::packa_panel_16(m, k, x, rs, cs, y, AVX_VS);
+    } else if feature.avx2 {
+        pack_i16::packa_panel_scalar::<AVX2_MR, TA>(m, k, x, rs, cs, y, AVX_VS);
+    } else {
+        pack_i16::packa_panel_scalar::<SSE_MR, TA>(m, k, x, rs, cs, y, SSE_VS);
+    }
+}
+
+pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
+    let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
+    if feature.avx512_vnni {
+        pack_avx::packb_panel_8(n, k, x, cs, rs, y);
+    } else if feature.avx512bw {
+        pack_i16::packb_panel_scalar::<AVX512BW_NR, TB>(n, k, x, cs, rs, y);
+    } else if feature.avx_vnni && feature.avx2 {
+        pack_avx::packb_panel_4(n, k, x, cs, rs, y);
+    } else if feature.avx2 {
+        pack_i16::packb_panel_scalar::<AVX2_NR, TB>(n, k, x, cs, rs, y);
+    } else {
+        pack_i16::packb_panel_scalar::<SSE_NR, TB>(n, k, x, cs, rs, y);
+    }
+}
+
+pub(crate) unsafe fn packa_fn_i16(x: *const TA, y: *mut i16, m: usize, k: usize, rs: usize, cs: usize) {
+    let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
+    if feature.avx512bw {
+        pack_i16::packa_panel_32(m, k, x, rs, cs, y, AVX512F_VS);
+    } else if feature.avx2 {
+        pack_i16::packa_panel_16(m, k, x, rs, cs, y, AVX_VS);
+    } else {
+        pack_i16::packa_panel_scalar::<SSE_MR, i16>(m, k, x, rs, cs, y, SSE_VS);
+    }
+}
+
+pub(crate) unsafe fn packb_fn_i16(x: *const TB, y: *mut i16, n: usize, k: usize, rs: usize, cs: usize) {
+    let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
+    if feature.avx512bw {
+        pack_i16::packb_panel_8(n, k, x, cs, rs, y);
+    } else if feature.avx2 {
+        pack_i16::packb_panel_4(n, k, x, cs, rs, y);
+    } else {
+        pack_i16::packb_panel_scalar::<SSE_NR, i16>(n, k, x, cs, rs, y);
+    }
+}
+
+pub(crate) fn round_m_simd(m: usize) -> usize {
+    let hw_config = &*RUNTIME_HW_CONFIG;
+    let vs = if hw_config.cpu_ft.avx512_vnni || hw_config.cpu_ft.avx512bw {
+        AVX512F_VS
+    } else if hw_config.cpu_ft.avx2 {
+        AVX_VS
+    } else {
+        SSE_VS
+    };
+    (m + vs - 1) / vs * vs
+}
+
+pub(crate) fn round_k_simd(k: usize) -> usize {
+    if has_vnni() {
+        round_k_vnni(k)
+    } else {
+        (k + 1) / 2 * 2
+    }
+}
+
+// groups of 4 k, plus one more group that holds the column sums in b panels and zeros in a panels
+fn round_k_vnni(k: usize) -> usize {
+    (k + 3) / 4 * 4 + 4
+}
+
+pub(crate) enum RegDim {
+    Avx512VNNI,
+    AvxVnni,
+}
+
+pub(crate) struct KernelDispatcher<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDim,
+    // TODO: Cech jr parallelism is beneificial for perf
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    // features: CpuFeatures,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcher<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let features = hw_config.cpu_ft();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, reg_dim, vs) = if features.avx512_vnni {
+            (AVX512_VNNI_MR, AVX512_VNNI_NR, RegDim::Avx512VNNI, AVX512F_VS)
+        } else {
+            (AVX_VNNI_MR, AVX_VNNI_NR, RegDim::AvxVnni, AVX_VS)
+        };
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            // features,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        true
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        round_k_vnni(k)
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+    // panels hold one more k group than kc, see round_k_vnni
+    fn get_ap_pool_size(&self, ic_par: usize) -> usize {
+        self.get_mc_eff(ic_par) * self.round_k(self.kc)
+    }
+    fn get_ap_pool_size2(&self) -> usize {
+        self.mr * self.round_k(self.kc)
+    }
+    fn get_bp_pool_size(&self, jc_par: usize) -> usize {
+        self.get_nc_eff(jc_par) * self.round_k(self.kc)
+    }
+}
+
+pub(crate) enum RegDimI16 {
+    Avx512BW,
+    Avx2,
+    Sse,
+}
+
+pub(crate) struct KernelDispatcherI16<T: UnaryFnC = IdentityFn> {
+    mc: usize,
+    nc: usize,
+    kc: usize,
+    mr: usize,
+    nr: usize,
+    pub(crate) vs: usize,
+    pub(crate) reg_dim: RegDimI16,
+    // is_l1_shared: bool,
+    is_l2_shared: bool,
+    is_l3_shared: bool,
+    func: T,
+}
+
+impl<F: UnaryFnC> KernelDispatcherI16<F> {
+    pub(crate) fn new(f: F) -> Self {
+        let hw_config = &*RUNTIME_HW_CONFIG;
+        let (mc, nc, kc) = get_mcnckc_simd();
+        let features = hw_config.cpu_ft();
+        let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
+        let (mr, nr, reg_dim, vs) = if features.avx512bw {
+            (AVX512BW_MR, AVX512BW_NR, RegDimI16::Avx512BW, AVX512F_VS)
+        } else if features.avx2 {
+            (AVX2_MR, AVX2_NR, RegDimI16::Avx2, AVX_VS)
+        } else {
+            (SSE_MR, SSE_NR, RegDimI16::Sse, SSE_VS)
+        };
+        Self {
+            mc,
+            nc,
+            kc,
+            mr,
+            nr,
+            vs,
+            reg_dim,
+            // is_l1_shared,
+            is_l2_shared,
+            is_l3_shared,
+            func: f,
+        }
+    }
+
+    pub(crate) fn is_compute_native(&self) -> bool {
+        false
+    }
+
+    // packed i8 panels are in the layout of the i16 ones, see packa_fn_simd
+    unsafe fn cvt_mixed(&self, x: *const TA, y: *mut i16, m: usize) {
+        for i in 0..m {
+            *y.add(i) = *x.add(i) as i16;
+        }
+    }
+
+    pub(crate) fn round_k(&self, k: usize) -> usize {
+        (k + 1) / 2 * 2
+    }
+
+    pub(crate) fn round_m(&self, m: usize) -> usize {
+        (m + self.vs - 1) / self.vs * self.vs
+    }
+}
+
+impl<T: UnaryFnC> GemmCache for KernelDispatcherI16<T> {
+    fn mr(&self) -> usize {
+        self.mr
+    }
+    fn get_kc_eff(&self) -> usize {
+        self.kc
+    }
+    fn get_mc_eff(&self, par: usize) -> usize {
+        if self.is_l3_shared {
+            (self.mc / (self.mr * par)) * self.mr
+        } else {
+            self.mc
+        }
+    }
+    fn get_nc_eff(&self, par: usize) -> usize {
+        if self.is_l2_shared {
+            (self.nc / (self.nr * par)) * self.nr
+        } else {
+            self.nc
+        }
+    }
+}
+
+unsafe fn kernel<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TA,
+    bp: *const TB,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    // the kernels run over the extra k group as well, it multiplies the column sums of b with zeros
+    let k = hw_cfg.round_k(k);
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+unsafe fn kernel_i16<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherI16<F>,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const i16,
+    bp: *const i16,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
+        match hw_cfg.reg_dim {
+            RegDimI16::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDimI16::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDimI16::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+        }
+    } else {
+        let null_fn = IdentityFn {};
+        match hw_cfg.reg_dim {
+            RegDimI16::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDimI16::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDimI16::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+        }
+    }
+}
+
+// small m and small n paths are off, the kernels only run on packed a and b
+#[allow(unused)]
+unsafe fn kernel_m<D, TP>(
+    hw_cfg: &D,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    ap: *const TP,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    panic!("Not implemented");
+}
+
+#[allow(unused)]
+unsafe fn kernel_n<D, TP>(
+    hw_cfg: &D,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: *const f32,
+    beta: *const f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TP,
+    b: *const TP,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    i0: usize,
+    j0: usize,
+    kc_last: bool,
+) {
+    panic!("Not implemented");
+}
+
+unsafe fn pire_gemv<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    avx2::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x.src(), x.rs(), beta, y.src(), y.rs(), hw_cfg.func);
+}
+
+unsafe fn pire_gemv2<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcher<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TB>,
+    x: Array<TA>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    avx2::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x.src(), x.rs(), beta, y.src(), y.rs(), hw_cfg.func);
+}
+
+unsafe fn pire_gemv_i16<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherI16<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TA>,
+    x: Array<TB>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    match hw_cfg.reg_dim {
+        RegDimI16::Avx512BW | RegDimI16::Avx2 => {
+            avx2::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
+        RegDimI16::Sse => sse::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+    }
+}
+
+unsafe fn pire_gemv2_i16<F: UnaryFnC>(
+    hw_cfg: &KernelDispatcherI16<F>,
+    m: usize,
+    n: usize,
+    alpha: *const f32,
+    a: Array<TB>,
+    x: Array<TA>,
+    beta: *const f32,
+    y: ArrayMut<TC>,
+) {
+    let x_ptr = x.src();
+    let inc_x = x.rs();
+    let y_ptr = y.src();
+    let incy = y.rs();
+    match hw_cfg.reg_dim {
+        RegDimI16::Avx512BW | RegDimI16::Avx2 => {
+            avx2::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
+        RegDimI16::Sse => {
+            sse::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
+    }
+}
+
+def_pire_gemm!(
+    KernelDispatcher,
+    i8,
+    i8,
+    i8,
+    i8,
+    i32,
+    f32,
+    f32,
+    PackArrTypeA,
+    PackArrTypeB,
+    1_f32,
+    pire_gemm,
+    gemm_mt,
+    gemm_goto_serial,
+    kernel,
+    gemm_small_m_serial,
+    kernel_m,
+    gemm_small_n_serial,
+    kernel_n,
+    pire_gemv,
+    pire_gemv2,
+    packa0,
+    packb0,
+    packa_fn_simd,
+    packb_fn_simd,
+    false,
+    false,
+    into_pack_array,
+    F,
+);
+
+def_pire_gemm!(
+    KernelDispatcherI16,
+    i8,
+    i16,
+    i8,
+    i16,
+    i32,
+    f32,
+    f32,
+    PackArrTypeAM,
+    PackArrTypeBM,
+    1_f32,
+    pire_gemm_i16,
+    gemm_mt_i16,
+    gemm_goto_serial_i16,
+    kernel_i16,
+    gemm_small_m_serial_i16,
+    kernel_m,
+    gemm_small_n_serial_i16,
+    kernel_n,
+    pire_gemv_i16,
+    pire_gemv2_i16,
+    packa0_i16,
+    packb0_i16,
+    packa_fn_i16,
+    packb_fn_i16,
+    false,
+    false,
+    into_pack_array2,
+    T,
+);
@@ -0,0 +1,599 @@
+use crate::{TA, TB};
+use seq_macro::seq;
+
+use paste::paste;
+
+use std::arch::x86_64::*;
+
+use std::ptr::{copy_nonoverlapping, write_bytes};
+
+// a is packed as a + 128 (i.e. sign bit flipped) since vpdpbusd takes it as the unsigned operand,
+// a * b = (a + 128) * b - 128 * sum_k(b), the column sums of b are stored with the packed b panels
+#[inline(always)]
+unsafe fn store_t<const SHIFT: bool, const N: usize>(t0: &mut [i8; N], ap: *mut TA) {
+    if SHIFT {
+        for x in t0.iter_mut() {
+            *x ^= i8::MIN;
+        }
+    }
+    copy_nonoverlapping(t0.as_ptr(), ap, N);
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn pack_scalar_k<const SHIFT: bool>(
+    m_left: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    vs: usize,
+) {
+    let mr = (m_left + vs - 1) / vs * vs;
+    let k4 = k / 4 * 4;
+    let kl = k % 4;
+    let kl_4 = if kl == 0 { 0 } else { 4 };
+    let shift = if SHIFT { i8::MIN } else { 0 };
+    for i in 0..m_left {
+        let mut j = 0;
+        while j < k4 {
+            *ap.add(j * mr + i * 4) = *a.add(j * a_cs + i * a_rs) ^ shift;
+            *ap.add(j * mr + i * 4 + 1) = *a.add((j + 1) * a_cs + i * a_rs) ^ shift;
+            *ap.add(j * mr + i * 4 + 2) = *a.add((j + 2) * a_cs + i * a_rs) ^ shift;
+            *ap.add(j * mr + i * 4 + 3) = *a.add((j + 3) * a_cs + i * a_rs) ^ shift;
+            j += 4;
+        }
+        let mut jl = 0;
+        while jl < kl {
+            *ap.add(j * mr + i * 4 + jl) = *a.add((j + jl) * a_cs + i * a_rs) ^ shift;
+            jl += 1;
+        }
+        while jl < kl_4 {
+            *ap.add(j * mr + i * 4 + jl) = 0;
+            jl += 1;
+        }
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn interleave_t<const M: usize, const SHIFT: bool>(a: *const TA, ap: *mut TA, lda: usize) {
+    if M == 4 {
+        let mut t0 = [0_i8; 16];
+        seq!(i in 0..4 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), 4);
+        });
+        store_t::<SHIFT, 16>(&mut t0, ap);
+        return;
+    }
+
+    if M == 8 {
+        let mut t0 = [0_i8; 32];
+        seq!(i in 0..8 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), 4);
+        });
+        store_t::<SHIFT, 32>(&mut t0, ap);
+        return;
+    }
+
+    if M == 16 {
+        let mut t0 = [0_i8; 64];
+        seq!(i in 0..16 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), 4);
+        });
+        store_t::<SHIFT, 64>(&mut t0, ap);
+        return;
+    }
+
+    if M == 32 {
+        let mut t0 = [0_i8; 128];
+        seq!(i in 0..32 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), 4);
+        });
+        store_t::<SHIFT, 128>(&mut t0, ap);
+        return;
+    }
+
+    if M == 48 {
+        let mut t0 = [0_i8; 192];
+        seq!(i in 0..48 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), 4);
+        });
+        store_t::<SHIFT, 192>(&mut t0, ap);
+        return;
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn interleave<const M: usize, const SHIFT: bool>(a: *const TA, ap: *mut TA, lda: usize) {
+    if M == 4 {
+        let mut t0 = [0_i8; 16];
+        seq!(i in 0..4 {
+            t0[i*4] = *a.add(i);
+            t0[i*4+1] = *a.add(lda+i);
+            t0[i*4+2] = *a.add(2*lda+i);
+            t0[i*4+3] = *a.add(3*lda+i);
+        });
+        store_t::<SHIFT, 16>(&mut t0, ap);
+        return;
+    }
+
+    if M == 8 {
+        let mut t0 = [0_i8; 32];
+        seq!(i in 0..8 {
+            t0[i*4] = *a.add(i);
+            t0[i*4+1] = *a.add(lda+i);
+            t0[i*4+2] = *a.add(2*lda+i);
+            t0[i*4+3] = *a.add(3*lda+i);
+        });
+        store_t::<SHIFT, 32>(&mut t0, ap);
+        return;
+    }
+
+    if M == 16 {
+        let mut t0 = [0_i8; 64];
+        seq!(i in 0..16 {
+            t0[i*4] = *a.add(i);
+            t0[i*4+1] = *a.add(lda+i);
+            t0[i*4+2] = *a.add(2*lda+i);
+            t0[i*4+3] = *a.add(3*lda+i);
+        });
+        store_t::<SHIFT, 64>(&mut t0, ap);
+        return;
+    }
+
+    if M == 32 {
+        let mut t0 = [0_i8; 128];
+        seq!(i in 0..32 {
+            t0[i*4] = *a.add(i);
+            t0[i*4+1] = *a.add(lda+i);
+            t0[i*4+2] = *a.add(2*lda+i);
+            t0[i*4+3] = *a.add(3*lda+i);
+        });
+        store_t::<SHIFT, 128>(&mut t0, ap);
+        return;
+    }
+
+    if M == 48 {
+        let mut t0 = [0_i8; 192];
+        seq!(i in 0..48 {
+            t0[i*4] = *a.add(i);
+            t0[i*4+1] = *a.add(lda+i);
+            t0[i*4+2] = *a.add(2*lda+i);
+            t0[i*4+3] = *a.add(3*lda+i);
+        });
+        store_t::<SHIFT, 192>(&mut t0, ap);
+        return;
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn interleave_left<const M: usize, const SHIFT: bool>(a: *const TA, ap: *mut TA, kl: usize, lda: usize) {
+    if M == 4 {
+        let mut t0 = [0_i8; 16];
+        for i in 0..kl {
+            t0[i] = *a.add(i * lda);
+            t0[i + 4] = *a.add(i * lda + 1);
+            t0[i + 8] = *a.add(i * lda + 2);
+            t0[i + 12] = *a.add(i * lda + 3);
+        }
+        store_t::<SHIFT, 16>(&mut t0, ap);
+        return;
+    }
+
+    if M == 8 {
+        let mut t0 = [0_i8; 32];
+        for i in 0..kl {
+            seq!(j in 0..8 {
+                t0[i+4*j] = *a.add(i*lda+j);
+            });
+        }
+        store_t::<SHIFT, 32>(&mut t0, ap);
+        return;
+    }
+
+    if M == 16 {
+        let mut t0 = [0_i8; 64];
+        for i in 0..kl {
+            seq!(j in 0..16 {
+                t0[i+4*j] = *a.add(i*lda+j);
+            });
+        }
+        store_t::<SHIFT, 64>(&mut t0, ap);
+        return;
+    }
+
+    if M == 32 {
+        let mut t0 = [0_i8; 128];
+        for i in 0..kl {
+            seq!(j in 0..32 {
+                t0[i+4*j] = *a.add(i*lda+j);
+            });
+        }
+        store_t::<SHIFT, 128>(&mut t0, ap);
+        return;
+    }
+
+    if M == 48 {
+        let mut t0 = [0_i8; 192];
+        for i in 0..kl {
+            seq!(j in 0..48 {
+                t0[i+4*j] = *a.add(i*lda+j);
+            });
+        }
+        store_t::<SHIFT, 192>(&mut t0, ap);
+        return;
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn interleave_left_t<const M: usize, const SHIFT: bool>(a: *const TA, ap: *mut TA, kl: usize, lda: usize) {
+    if M == 4 {
+        let mut t0 = [0_i8; 16];
+        copy_nonoverlapping(a, t0.as_mut_ptr(), kl);
+        copy_nonoverlapping(a.add(lda), t0.as_mut_ptr().add(4), kl);
+        copy_nonoverlapping(a.add(2 * lda), t0.as_mut_ptr().add(8), kl);
+        copy_nonoverlapping(a.add(3 * lda), t0.as_mut_ptr().add(12), kl);
+        store_t::<SHIFT, 16>(&mut t0, ap);
+        return;
+    }
+
+    if M == 8 {
+        let mut t0 = [0_i8; 32];
+        seq!(i in 0..8 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), kl);
+        });
+        store_t::<SHIFT, 32>(&mut t0, ap);
+        return;
+    }
+
+    if M == 16 {
+        let mut t0 = [0_i8; 64];
+        seq!(i in 0..16 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), kl);
+        });
+        store_t::<SHIFT, 64>(&mut t0, ap);
+        return;
+    }
+
+    if M == 32 {
+        let mut t0 = [0_i8; 128];
+        seq!(i in 0..32 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), kl);
+        });
+        store_t::<SHIFT, 128>(&mut t0, ap);
+        return;
+    }
+
+    if M == 48 {
+        let mut t0 = [0_i8; 192];
+        seq!(i in 0..48 {
+            copy_nonoverlapping(a.add(lda*i), t0.as_mut_ptr().add(4*i), kl);
+        });
+        store_t::<SHIFT, 192>(&mut t0, ap);
+        return;
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn pack_k_v0<const M: usize, const MR: usize, const SHIFT: bool>(k: usize, a: *const TA, lda: usize, ap: *mut TA) {
+    let k8 = k / 8 * 8;
+    let k4 = k / 4 * 4;
+    let mut k_i = 0;
+    let a0 = a;
+    let ap0 = ap;
+    while k_i < k8 {
+        let a = a0.add(k_i * lda);
+        let ap = ap0.add(k_i * MR);
+        seq!(i in 0..2 {
+            interleave::<M, SHIFT>(a.add(lda*4*i), ap.add(MR*4*i), lda);
+        });
+
+        k_i += 8;
+    }
+
+    while k_i < k4 {
+        let a = a0.add(k_i * lda);
+        let ap = ap0.add(k_i * MR);
+        interleave::<M, SHIFT>(a, ap, lda);
+        k_i += 4;
+    }
+
+    let kl = k % 4;
+    if kl != 0 {
+        let a = a0.add(k_i * lda);
+        let ap = ap0.add(k_i * MR);
+        interleave_left::<M, SHIFT>(a, ap, kl, lda);
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+pub(crate) unsafe fn pack_k_v1<const M: usize, const MR: usize, const SHIFT: bool>(k: usize, a: *const TA, lda: usize, ap: *mut TA) {
+    let k8 = k / 8 * 8;
+    let k4 = k / 4 * 4;
+    let mut k_i = 0;
+    let a0 = a;
+    let ap0 = ap;
+    while k_i < k8 {
+        let a = a0.add(k_i);
+        let ap = ap0.add(k_i * MR);
+        seq!(i in 0..2 {
+            interleave_t::<M, SHIFT>(a.add(4*i), ap.add(MR*4*i), lda);
+        });
+
+        k_i += 8;
+    }
+
+    while k_i < k4 {
+        let a = a0.add(k_i);
+        let ap = ap0.add(k_i * MR);
+        interleave_t::<M, SHIFT>(a, ap, lda);
+        k_i += 4;
+    }
+
+    let kl = k % 4;
+    if kl != 0 {
+        let a = a0.add(k_i);
+        let ap = ap0.add(k_i * MR);
+        interleave_left_t::<M, SHIFT>(a, ap, kl, lda);
+    }
+}
+
+// #[target_feature(enable = "avx,avx2")]
+// pub(crate) unsafe fn pack_kx16_v0(
+//     k: usize,
+//     a: *const TA, lda: usize,
+//     ap: *mut TA,
+// ) {
+//     let mut k_i = 0;
+//     let mut a = a;
+//     let mut ap = ap;
+//     const MR: usize = 16;
+//     while k_i < k8 {
+//         // use vector intrinscs
+//         seq!(i in 0..4 {
+//             let a0 = _mm256_loadu_si256(a.add(lda*2*i) as *const __m256i);
+//             let b0 = _mm256_loadu_si256(a.add(lda*(2*i+1)) as *const __m256i);
+//             let t0 = _mm256_unpacklo_epi16(a0, b0);
+//             let t1 = _mm256_unpackhi_epi16(a0, b0);
+//             let a0 = _mm256_permute2f128_si256(t0, t1, 0b0010_0000);
+//             let b0 = _mm256_permute2f128_si256(t0, t1, 0b0011_0001);
+//             _mm256_storeu_si256(ap.add(MR*2*i) as *mut __m256i, a0);
+//             _mm256_storeu_si256(ap.add(MR*2*i+16) as *mut __m256i, b0);
+//         });
+
+//         ap = ap.add(MR*8);
+//         a = a.add(8*lda);
+
+//         k_i += 1;
+//     }
+//     k_i = 0;
+//     while k_i < k_left / 2 {
+//         let a0 = _mm256_loadu_si256(a as *const __m256i);
+//         let b0 = _mm256_loadu_si256(a.add(lda) as *const __m256i);
+//         let t0 = _mm256_unpacklo_epi16(a0, b0);
+//         let t1 = _mm256_unpackhi_epi16(a0, b0);
+//         let a0 = _mm256_permute2f128_si256(t0, t1, 0b0010_0000);
+//         let b0 = _mm256_permute2f128_si256(t0, t1, 0b0011_0001);
+//         _mm256_storeu_si256(ap as *mut __m256i, a0);
+//         _mm256_storeu_si256(ap.add(16) as *mut __m256i, b0);
+
+//         ap = ap.add(MR*2);
+//         a = a.add(lda*2);
+//         k_i += 1;
+//     }
+//     if k_left % 2 != 0 {
+//         let a0 = _mm256_loadu_si256(a as *const __m256i);
+//         let b0 = _mm256_setzero_si256();
+//         let t0 = _mm256_unpacklo_epi16(a0, b0);
+//         let t1 = _mm256_unpackhi_epi16(a0, b0);
+//         let a0 = _mm256_permute2f128_si256(t0, t1, 0b0010_0000);
+//         let b0 = _mm256_permute2f128_si256(t0, t1, 0b0011_0001);
+//         _mm256_storeu_si256(ap as *mut __m256i, a0);
+//         _mm256_storeu_si256(ap.add(16) as *mut __m256i, b0);
+//     }
+// }
+
+// #[target_feature(enable = "avx,avx2")]
+// pub(crate) unsafe fn pack_kx16_v1(
+//     k: usize,
+//     a: *const TA, lda: usize,
+//     ap: *mut TA,
+// ) {
+//     let mut k_i = 0;
+//     let mut a = a;
+//     let mut ap = ap;
+//     const MR: usize = 16;
+//     while k_i < k8 {
+//         // pack_t::<MR>(a, lda, ap);
+//         // pack_t::<MR>(a.add(8*lda), lda, ap.add(8));
+
+//         ap = ap.add(MR*8);
+//         a = a.add(8);
+//         k_i += 1;
+//     }
+
+//     k_i = 0;
+
+//     while k_i < k_left {
+//         seq!(i in 0..16 {
+//             *ap.add(i) = *a.add(i*lda);
+//         });
+
+//         ap = ap.add(MR);
+//         a = a.add(1);
+//         k_i += 1;
+//     }
+// }
+
+// column sums of a packed b panel, stored as i32 right after its k_eff / 4 groups of nr x 4 values
+#[target_feature(enable = "avx,avx2")]
+unsafe fn store_col_sum<const NR: usize>(bp: *mut TB, k_eff: usize) {
+    let ones_u8 = _mm256_set1_epi8(1);
+    let ones_i16 = _mm256_set1_epi16(1);
+    // one group of nr = 8 columns per 32 bytes, two groups of nr = 4 columns
+    let k_32 = k_eff * NR / 32;
+    let mut acc = _mm256_setzero_si256();
+    for i in 0..k_32 {
+        let x = _mm256_loadu_si256(bp.add(i * 32) as *const __m256i);
+        // pairs of i8 fit in i16 without saturation
+        let x = _mm256_maddubs_epi16(ones_u8, x);
+        acc = _mm256_add_epi32(acc, _mm256_madd_epi16(x, ones_i16));
+    }
+    let sum_ptr = bp.add(NR * k_eff);
+    if NR == 8 {
+        _mm256_storeu_si256(sum_ptr as *mut __m256i, acc);
+    } else {
+        let mut acc = _mm_add_epi32(_mm256_castsi256_si128(acc), _mm256_extracti128_si256(acc, 1));
+        if k_eff * NR % 32 != 0 {
+            let x = _mm_loadu_si128(bp.add(k_32 * 32) as *const __m128i);
+            let x = _mm_maddubs_epi16(_mm256_castsi256_si128(ones_u8), x);
+            acc = _mm_add_epi32(acc, _mm_madd_epi16(x, _mm256_castsi256_si128(ones_i16)));
+        }
+        _mm_storeu_si128(sum_ptr as *mut __m128i, acc);
+    }
+}
+
+unsafe fn store_col_sum_scalar(n: usize, bp: *mut TB, k_eff: usize) {
+    let sum_ptr = bp.add(n * k_eff) as *mut i32;
+    for j in 0..n {
+        let mut acc = 0i32;
+        for p in 0..k_eff / 4 {
+            for q in 0..4 {
+                acc += *bp.add(p * n * 4 + j * 4 + q) as i32;
+            }
+        }
+        sum_ptr.add(j).write_unaligned(acc);
+    }
+}
+
+// each panel of nr columns is followed by their sums (nr i32 values, i.e. one more k group)
+macro_rules! def_packb {
+    ($nr:tt) => {
+        paste! {
+        #[target_feature(enable = "avx,avx2")]
+        pub(crate) unsafe fn [<packb_panel_ $nr>](
+                n: usize, k: usize,
+                b: *const TB, b_rs: usize, b_cs: usize,
+                bp: *mut TB,
+            ) {
+                let k_eff = (k+3) / 4 * 4;
+                let k_panel = k_eff + 4;
+                let bp0 = bp;
+                let b0 = b;
+                const NR: usize = $nr;
+                let n_rounded = n / NR * NR;
+                let mut n_idx = 0;
+                if b_rs == 1 {
+                    let ldb = b_cs;
+                    while n_idx < n_rounded {
+                        let b = b0.add(n_idx);
+                        let bp = bp0.add(n_idx*k_panel);
+                        pack_k_v0::<NR,NR,false>(k, b, ldb, bp);
+                        store_col_sum::<NR>(bp, k_eff);
+                        n_idx += NR;
+                    }
+                    let n_left = n - n_idx;
+                    if n_left > 0 {
+                        let bp = bp0.add(n_idx*k_panel);
+                        pack_scalar_k::<false>(
+                            n_left, k,
+                            b0.add(n_idx), b_rs, b_cs,
+                            bp, 1
+                        );
+                        store_col_sum_scalar(n_left, bp, k_eff);
+                    }
+                } else if b_cs == 1 {
+                    let ldb = b_rs;
+                    while n_idx < n_rounded {
+                        let b = b0.add(n_idx*ldb);
+                        let bp = bp0.add(n_idx*k_panel);
+                        pack_k_v1::<NR,NR,false>(k, b, ldb, bp);
+                        store_col_sum::<NR>(bp, k_eff);
+                        n_idx += NR;
+                    }
+                    let n_left = n - n_idx;
+                    if n_left > 0 {
+                        let bp = bp0.add(n_idx*k_panel);
+                        pack_scalar_k::<false>(
+                            n_left, k,
+                            b0.add(n_idx*ldb), b_rs, b_cs,
+                            bp, 1
+                        );
+                        store_col_sum_scalar(n_left, bp, k_eff);
+                    }
+                }
+            }
+        }
+    };
+}
+
+def_packb!(4);
+def_packb!(8);
+
+// a is shifted by 128, each panel ends with a zero k group so that the column sums of b
+// do not contribute to the product
+macro_rules! def_packa {
+    ($mr:tt) => {
+        paste! {
+            #[target_feature(enable = "avx,avx2")]
+            pub(crate) unsafe fn [<packa_panel_ $mr>](
+                m: usize, k: usize,
+                a: *const TA, a_rs: usize, a_cs: usize,
+                ap: *mut TA, vs: usize
+            ) {
+                let k_eff = (k+3) / 4 * 4;
+                let k_panel = k_eff + 4;
+                let ap0 = ap;
+                let a0 = a;
+                const MR: usize = $mr;
+                let m_rounded = m / MR * MR;
+                let mut m_idx = 0;
+                if a_rs == 1 {
+                    let lda = a_cs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx);
+                        let ap = ap0.add(m_idx*k_panel);
+                        pack_k_v0::<$mr,$mr,true>(k, a, lda, ap);
+                        write_bytes(ap.add(MR*k_eff), 0, MR*4);
+                        m_idx += MR;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        let ap = ap0.add(m_idx*k_panel);
+                        pack_scalar_k::<true>(
+                            m_left, k,
+                            a0.add(m_idx), a_rs, a_cs,
+                            ap, vs
+                        );
+                        let mr_left = (m_left + vs - 1) / vs * vs;
+                        write_bytes(ap.add(mr_left*k_eff), 0, mr_left*4);
+                    }
+
+                } else if a_cs == 1 {
+                    let lda = a_rs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx*lda);
+                        let ap = ap0.add(m_idx*k_panel);
+                        pack_k_v1::<$mr,$mr,true>(k, a, lda, ap);
+                        write_bytes(ap.add(MR*k_eff), 0, MR*4);
+                        m_idx += MR;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        let ap = ap0.add(m_idx*k_panel);
+                        pack_scalar_k::<true>(
+                            m_left, k,
+                            a0.add(m_idx*lda), a_rs, a_cs,
+                            ap, vs
+                        );
+                        let mr_left = (m_left + vs - 1) / vs * vs;
+                        write_bytes(ap.add(mr_left*k_eff), 0, mr_left*4);
+                    }
+                }
+            }
+        }
+    };
+}
+
+def_packa!(16);
+def_packa!(48);
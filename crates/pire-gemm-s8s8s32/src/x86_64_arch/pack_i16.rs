@@ -0,0 +1,180 @@
+use crate::{TA, TB};
+
+use paste::paste;
+
+use std::arch::x86_64::*;
+
+// packing for the kernels without vnni, i8 values are sign extended to i16 and interleaved in pairs of k
+// (k rounded up to even, the zero pad of odd k does not contribute), the layout of the vpmaddwd kernels.
+// This keeps the products exact, vpmaddubsw would saturate the pairwise i16 sums
+
+pub(crate) unsafe fn pack_scalar_k<T: Copy + From<i8>>(
+    m_left: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut T,
+    vs: usize,
+) {
+    let mr = (m_left + vs - 1) / vs * vs;
+    for i in 0..m_left {
+        for j in 0..k / 2 {
+            *ap.add(j * 2 * mr + i * 2) = T::from(*a.add(2 * j * a_cs + i * a_rs));
+            *ap.add(j * 2 * mr + i * 2 + 1) = T::from(*a.add((2 * j + 1) * a_cs + i * a_rs));
+        }
+    }
+    if k % 2 != 0 {
+        for i in 0..m_left {
+            *ap.add(k / 2 * 2 * mr + i * 2) = T::from(*a.add(2 * (k / 2) * a_cs + i * a_rs));
+            *ap.add(k / 2 * 2 * mr + i * 2 + 1) = T::from(0);
+        }
+    }
+}
+
+// two k rows of M values contiguous in m, widened and interleaved 8 at a time
+#[target_feature(enable = "avx,avx2")]
+unsafe fn interleave<const M: usize>(a0: *const TA, a1: *const TA, ap: *mut i16) {
+    if M % 8 == 4 {
+        let x0 = _mm_cvtepi8_epi16(_mm_cvtsi32_si128((a0 as *const i32).read_unaligned()));
+        let x1 = _mm_cvtepi8_epi16(_mm_cvtsi32_si128((a1 as *const i32).read_unaligned()));
+        _mm_storeu_si128(ap as *mut __m128i, _mm_unpacklo_epi16(x0, x1));
+        return;
+    }
+    let mut i = 0;
+    while i < M {
+        let x0 = _mm_cvtepi8_epi16(_mm_loadl_epi64(a0.add(i) as *const __m128i));
+        let x1 = _mm_cvtepi8_epi16(_mm_loadl_epi64(a1.add(i) as *const __m128i));
+        _mm_storeu_si128(ap.add(2 * i) as *mut __m128i, _mm_unpacklo_epi16(x0, x1));
+        _mm_storeu_si128(ap.add(2 * i + 8) as *mut __m128i, _mm_unpackhi_epi16(x0, x1));
+        i += 8;
+    }
+}
+
+#[target_feature(enable = "avx,avx2")]
+unsafe fn pack_k_v0<const M: usize>(k: usize, a: *const TA, lda: usize, ap: *mut i16) {
+    let zero = [0_i8; M];
+    let mut p = 0;
+    while p + 2 <= k {
+        interleave::<M>(a.add(p * lda), a.add((p + 1) * lda), ap.add(p * M));
+        p += 2;
+    }
+    if p < k {
+        interleave::<M>(a.add(p * lda), zero.as_ptr(), ap.add(p * M));
+    }
+}
+
+// contiguous in k, pairs of k of each row are written as one i32
+#[target_feature(enable = "avx,avx2")]
+unsafe fn pack_k_v1<const M: usize>(k: usize, a: *const TA, lda: usize, ap: *mut i16) {
+    let ap = ap as *mut i32;
+    for i in 0..M {
+        let a = a.add(i * lda);
+        for p in 0..k / 2 {
+            let lo = *a.add(2 * p) as i16 as u16 as u32;
+            let hi = *a.add(2 * p + 1) as i16 as u16 as u32;
+            *ap.add(p * M + i) = (lo | hi << 16) as i32;
+        }
+        if k % 2 != 0 {
+            *ap.add(k / 2 * M + i) = *a.add(k - 1) as i16 as u16 as i32;
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn pack_panel<const MR: usize>(
+    m: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut i16,
+    vs: usize,
+) {
+    let k_eff = (k + 1) / 2 * 2;
+    let m_rounded = m / MR * MR;
+    let mut m_idx = 0;
+    if a_rs == 1 {
+        while m_idx < m_rounded {
+            pack_k_v0::<MR>(k, a.add(m_idx), a_cs, ap.add(m_idx * k_eff));
+            m_idx += MR;
+        }
+    } else if a_cs == 1 {
+        while m_idx < m_rounded {
+            pack_k_v1::<MR>(k, a.add(m_idx * a_rs), a_rs, ap.add(m_idx * k_eff));
+            m_idx += MR;
+        }
+    }
+    while m_idx < m {
+        let m_left = MR.min(m - m_idx);
+        pack_scalar_k(m_left, k, a.add(m_idx * a_rs), a_rs, a_cs, ap.add(m_idx * k_eff), vs);
+        m_idx += MR;
+    }
+}
+
+// for the sse kernels that run on cpus without the sse4.1 widening, and for the packed api
+// that keeps i8 values in the same layout
+pub(crate) unsafe fn packa_panel_scalar<const MR: usize, T: Copy + From<i8>>(
+    m: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut T,
+    vs: usize,
+) {
+    let k_eff = (k + 1) / 2 * 2;
+    let mut m_idx = 0;
+    while m_idx < m {
+        let m_left = MR.min(m - m_idx);
+        pack_scalar_k(m_left, k, a.add(m_idx * a_rs), a_rs, a_cs, ap.add(m_idx * k_eff), vs);
+        m_idx += MR;
+    }
+}
+
+pub(crate) unsafe fn packb_panel_scalar<const NR: usize, T: Copy + From<i8>>(
+    n: usize,
+    k: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    bp: *mut T,
+) {
+    packa_panel_scalar::<NR, T>(n, k, b, b_rs, b_cs, bp, 1);
+}
+
+macro_rules! def_packa {
+    ($mr:tt) => {
+        paste! {
+            #[target_feature(enable = "avx,avx2")]
+            pub(crate) unsafe fn [<packa_panel_ $mr>](
+                m: usize, k: usize,
+                a: *const TA, a_rs: usize, a_cs: usize,
+                ap: *mut i16, vs: usize
+            ) {
+                pack_panel::<$mr>(m, k, a, a_rs, a_cs, ap, vs);
+            }
+        }
+    };
+}
+
+macro_rules! def_packb {
+    ($nr:tt) => {
+        paste! {
+            #[target_feature(enable = "avx,avx2")]
+            pub(crate) unsafe fn [<packb_panel_ $nr>](
+                n: usize, k: usize,
+                b: *const TB, b_rs: usize, b_cs: usize,
+                bp: *mut i16,
+            ) {
+                pack_panel::<$nr>(n, k, b, b_rs, b_cs, bp, 1);
+            }
+        }
+    };
+}
+
+def_packa!(16);
+def_packa!(32);
+
+def_packb!(4);
+def_packb!(8);
@@ -1,12 +1,15 @@
 pub(crate) mod neon;
+pub(crate) mod neon_dotprod;
+pub(crate) mod pack_dotprod;
 pub(crate) mod pack_neon;
 pub(crate) mod pack_sve;
 pub(crate) mod sve;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_mem_pool_size_goto, get_mem_pool_size_small_m,
-    get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range, split_range, Array, ArrayMut,
-    GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL, RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, TransposedFn, PACK_POOL,
+    RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -20,7 +23,7 @@ const SVE_NR: usize = 12;
 #[inline(always)]
 pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
     let features = (*RUNTIME_HW_CONFIG).cpu_ft();
-    let (mr, nr) = if features.sve { (unsafe { sve_vs() * 2 }, SVE_NR) } else { (NEON_MR, NEON_NR) };
+    let (mr, nr) = if features.i8mm && features.sve { (unsafe { sve_vs() * 2 }, SVE_NR) } else { (NEON_MR, NEON_NR) };
     // let mc = std::env::var("PIRE_MC").unwrap_or("4800".to_string()).parse::<usize>().unwrap();
     // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
     // let kc = std::env::var("PIRE_KC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
@@ -33,11 +36,13 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
 
 pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
     let hw_config = &*RUNTIME_HW_CONFIG;
-    if hw_config.cpu_ft.sve {
+    if hw_config.cpu_ft.i8mm && hw_config.cpu_ft.sve {
         let vs = sve_vs();
         pack_sve::packa_panel(m, k, x, rs, cs, y, vs);
-    } else {
+    } else if hw_config.cpu_ft.i8mm {
         pack_neon::packa_panel_8(m, k, x, rs, cs, y, NEON_VS);
+    } else {
+        pack_dotprod::packa_panel_8(m, k, x, rs, cs, y, NEON_VS);
     }
 }
 
@@ -51,7 +56,7 @@ pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize,
 
 pub(crate) fn round_m_simd(m: usize) -> usize {
     let hw_config = &*RUNTIME_HW_CONFIG;
-    let vs = if hw_config.cpu_ft.sve { unsafe { sve_vs() } } else { NEON_VS };
+    let vs = if hw_config.cpu_ft.i8mm && hw_config.cpu_ft.sve { unsafe { sve_vs() } } else { NEON_VS };
     (m + vs - 1) / vs * vs
 }
 
@@ -61,6 +66,7 @@ pub(crate) fn round_k_simd(k: usize) -> usize {
 
 pub(crate) enum RegDim {
     Neon,
+    NeonDotprod,
     Sve,
 }
 
@@ -97,12 +103,15 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
         let features = hw_config.cpu_ft();
         let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
 
-        let (mr, nr, reg_dim) = if features.sve {
+        // the neon and sve kernels need i8mm, dotprod only cores use sdot kernels
+        let (mr, nr, reg_dim) = if features.i8mm && features.sve {
             (unsafe { sve_vs() * 2 }, SVE_NR, RegDim::Sve)
-        } else {
+        } else if features.i8mm {
             (NEON_MR, NEON_NR, RegDim::Neon)
+        } else {
+            (NEON_MR, NEON_NR, RegDim::NeonDotprod)
         };
-        let vs = if features.sve { unsafe { sve_vs() } } else { NEON_VS };
+        let vs = if features.i8mm && features.sve { unsafe { sve_vs() } } else { NEON_VS };
         Self {
             mc,
             nc,
@@ -130,6 +139,10 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
     pub(crate) fn round_m(&self, m: usize) -> usize {
         (m + self.vs - 1) / self.vs * self.vs
     }
+
+    pub(crate) fn nr(&self) -> usize {
+        self.nr
+    }
 }
 
 impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
@@ -167,17 +180,22 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::NeonDotprod => neon_dotprod::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Neon => neon::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::NeonDotprod => neon_dotprod::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Sve => sve::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
         }
     }
@@ -198,6 +216,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
 }
@@ -217,17 +237,26 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::NeonDotprod => {
+                neon_dotprod::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f)
+            }
+            RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
             RegDim::Neon => neon::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+            RegDim::NeonDotprod => {
+                neon_dotprod::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
+            }
             RegDim::Sve => sve::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
         }
     }
@@ -248,7 +277,9 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Neon => neon::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Neon | RegDim::NeonDotprod => {
+            neon::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        }
         RegDim::Sve => sve::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
     }
 }
@@ -268,8 +299,23 @@ unsafe fn pire_gemv2<F: UnaryFnC>(
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Neon => neon::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
-        RegDim::Sve => sve::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
+        RegDim::Neon | RegDim::NeonDotprod => neon::axpy2(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            TransposedFn(hw_cfg.func),
+        ),
+        RegDim::Sve => {
+            sve::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, TransposedFn(hw_cfg.func))
+        }
     }
 }
 
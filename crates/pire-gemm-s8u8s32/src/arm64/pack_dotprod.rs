@@ -0,0 +1,97 @@
+use crate::TA;
+
+use paste::paste;
+
+// sdot works on groups of 4 k, so each 8 k block of the panel is stored as two halves,
+// k0..4 of all rows followed by k4..8 of all rows, the 4 k of a row being consecutive within a half
+#[target_feature(enable = "neon")]
+pub(crate) unsafe fn pack_scalar_k(
+    m_left: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ap: *mut TA,
+    vs: usize,
+) {
+    let mr = (m_left + vs - 1) / vs * vs;
+    let k_eff = (k + 7) / 8 * 8;
+    for i in 0..m_left {
+        let mut j = 0;
+        while j < k_eff {
+            let ap_h0 = ap.add(j * mr + i * 4);
+            let ap_h1 = ap_h0.add(mr * 4);
+            for jl in 0..4 {
+                *ap_h0.add(jl) = if j + jl < k { *a.add((j + jl) * a_cs + i * a_rs) } else { 0 };
+                *ap_h1.add(jl) = if j + 4 + jl < k { *a.add((j + 4 + jl) * a_cs + i * a_rs) } else { 0 };
+            }
+            j += 8;
+        }
+    }
+}
+
+macro_rules! def_packa {
+    ($mr:tt) => {
+        paste! {
+            #[target_feature(enable = "neon")]
+            pub(crate) unsafe fn [<packa_panel_ $mr>](
+                m: usize, k: usize,
+                a: *const TA, a_rs: usize, a_cs: usize,
+                ap: *mut TA, vs: usize,
+            ) {
+                let mr = $mr;
+                let k_eff = (k+7) / 8 * 8;
+                let ap0 = ap;
+                let a0 = a;
+                let m_rounded = m / mr * mr;
+                let mut m_idx = 0;
+                if a_rs == 1 {
+                    let lda = a_cs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx);
+                        let ap = ap0.add(m_idx*k_eff);
+                        pack_scalar_k(
+                            mr, k,
+                            a, 1, lda,
+                            ap, vs
+                        );
+                        // pack_k_v0::<$mr,$mr>(k, a, lda, ap);
+                        m_idx += mr;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        pack_scalar_k(
+                            m_left, k,
+                            a0.add(m_idx), a_rs, a_cs,
+                            ap0.add(m_idx*k_eff), vs
+                        );
+                    }
+
+                } else if a_cs == 1 {
+                    let lda = a_rs;
+                    while m_idx < m_rounded {
+                        let a = a0.add(m_idx*lda);
+                        let ap = ap0.add(m_idx*k_eff);
+                        pack_scalar_k(
+                            mr, k,
+                            a, a_rs, a_cs,
+                            ap, vs
+                        );
+                        // pack_k_v1::<$mr,$mr>(k, a, lda, ap);
+                        m_idx += mr;
+                    }
+                    let m_left = m - m_idx;
+                    if m_left > 0 {
+                        pack_scalar_k(
+                            m_left, k,
+                            a0.add(m_idx*lda), a_rs, a_cs,
+                            ap0.add(m_idx*k_eff), vs
+                        );
+                    }
+                }
+            }
+        }
+    };
+}
+
+def_packa!(8);
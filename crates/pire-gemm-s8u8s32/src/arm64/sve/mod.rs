@@ -52,7 +52,7 @@ pub unsafe fn axpy<F: UnaryFnC>(
             acc += *a_cur as i32 * *x_cur as i32;
         }
         *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
-        f.call(y_cur, 1);
+        f.call_tile(y_cur, 1, i, 0);
     }
 }
 
@@ -93,7 +93,7 @@ pub unsafe fn axpy2<F: UnaryFnC>(
             acc += *a_cur as i32 * *x_cur as i32;
         }
         *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
-        f.call(y_cur, 1);
+        f.call_tile(y_cur, 1, i, 0);
     }
 }
 use pire_base::def_kernel_bb_v0;
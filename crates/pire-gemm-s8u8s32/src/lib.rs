@@ -18,6 +18,7 @@ use x86_arch::{
 #[cfg(target_arch = "aarch64")]
 use arm64::{get_mcnckc_simd, packa_fn_simd, packb_fn_simd, pire_gemm, round_k_simd, round_m_simd, KernelDispatcher};
 
+pub(crate) mod offset;
 pub(crate) mod reference;
 
 use core::mem::size_of;
@@ -28,11 +29,16 @@ pub(crate) type TC = i32;
 #[allow(unused)]
 const TC_SIZE: usize = size_of::<TC>();
 
+use offset::pire_gemm_s8u8s32_offset_fused_par;
 use reference::{packa_fn_ref, packb_fn_ref, round_k_ref, round_m_ref, RefGemm};
 
-use pire_base::{
-    get_cache_params, has_i8i32_compute, Array, ArrayMut, GemmCache, IdentityFn, PirePar, UnaryFn, AB_ALIGN,
-};
+pub use pire_base::batch::GemmGroup;
+use pire_base::batch::{batch_par_default, pool_par, run_batch_strided, run_grouped};
+use pire_base::checked::{check_gemm_args, scale_c};
+pub use pire_base::checked::{GemmError, MatLayout};
+pub use pire_base::dequant::{DequantFn, DequantOut};
+use pire_base::{get_cache_params, has_i8i32_compute, put_pool_buf, take_pool_buf, GemmCache, UnaryFn, AB_ALIGN};
+pub use pire_base::{Array, ArrayMut, IdentityFn, PirePar};
 
 pub trait UnaryFnC: UnaryFn<TC> {}
 impl<F: UnaryFn<TC>> UnaryFnC for F {}
@@ -49,17 +55,32 @@ pub(crate) unsafe fn pire_gemm_s8u8s32_fused<F: UnaryFnC>(
     f: F,
 ) {
     let par = PirePar::default(m, n);
+    pire_gemm_s8u8s32_fused_par(m, n, k, alpha, a, b, beta, c, f, &par);
+}
+
+pub(crate) unsafe fn pire_gemm_s8u8s32_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
     if has_i8i32_compute() {
         #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
         {
             let hw_config = KernelDispatcher::new(f);
-            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+            pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
             return;
         }
     }
     // if none of the optimized paths are available, use reference implementation
     let hw_config = RefGemm::new(f);
-    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, &par);
+    reference::pire_gemm(&hw_config, m, n, k, alpha, a, b, beta, c, par);
 }
 
 pub unsafe fn pire_gemm_s8u8s32(
@@ -91,6 +112,127 @@ pub unsafe fn pire_gemm_s8u8s32(
     pire_gemm_s8u8s32_fused(m, n, k, alpha, a, b, beta, c, identity_fn);
 }
 
+/// Same as [`pire_gemm_s8u8s32`] with thread configuration given by `par`,
+/// use `PirePar::single_thread()` to run on the calling thread only
+pub unsafe fn pire_gemm_s8u8s32_par(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    par: &PirePar,
+) {
+    // transpose if c is row strided i.e. c_cs == 1 and c_rs != 1
+    // let (m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b) = if c_cs == 1 && c_rs != 1 {
+    //  (n, m, b_cs, b_rs, a_cs, a_rs, c_cs, c_rs, b, a)
+    // } else {
+    // 	(m, n, a_rs, a_cs, b_rs, b_cs, c_rs, c_cs, a, b)
+    // };
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let identity_fn = IdentityFn {};
+    pire_gemm_s8u8s32_fused_par(m, n, k, alpha, a, b, beta, c, identity_fn, par);
+}
+
+/// Strided batched gemm, runs [`pire_gemm_s8u8s32`] for every `i` in `0..batch`
+/// with a, b and c offset by `i * stride_a`, `i * stride_b` and `i * stride_c` elements
+///
+/// Threads are split between batch items and each gemm, see [`pire_base::batch::batch_par`]
+pub unsafe fn pire_gemm_s8u8s32_batch_strided(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+) {
+    let par = batch_par_default(batch, m, n);
+    pire_gemm_s8u8s32_batch_strided_par(
+        batch, m, n, k, alpha, a, a_rs, a_cs, stride_a, b, b_rs, b_cs, stride_b, beta, c, c_rs, c_cs, stride_c, &par,
+    );
+}
+
+/// Same as [`pire_gemm_s8u8s32_batch_strided`] with the threads of the whole batch given by `par`
+pub unsafe fn pire_gemm_s8u8s32_batch_strided_par(
+    batch: usize,
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    stride_a: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    stride_b: usize,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    stride_c: usize,
+    par: &PirePar,
+) {
+    run_batch_strided(batch, m, n, a, stride_a, b, stride_b, c, stride_c, par, |a, b, c, par| {
+        pire_gemm_s8u8s32_par(m, n, k, alpha, a, a_rs, a_cs, b, b_rs, b_cs, beta, c, c_rs, c_cs, par);
+    });
+}
+
+/// Grouped gemm, runs [`pire_gemm_s8u8s32`] for problem i of every group with `a[i]`, `b[i]`, `c[i]`
+/// and the dims, scalars and strides of its group, pointers of all groups are stored one after another
+///
+/// Problems are load balanced over threads, see [`pire_base::batch::run_grouped`]
+///
+/// # Safety
+///
+/// Every pointer must be valid for the dims and strides of its group and c of distinct problems must not overlap
+pub unsafe fn pire_gemm_s8u8s32_grouped(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+) {
+    pire_gemm_s8u8s32_grouped_par(groups, a, b, c, &pool_par());
+}
+
+/// Same as [`pire_gemm_s8u8s32_grouped`] with the threads of all problems given by `par`
+pub unsafe fn pire_gemm_s8u8s32_grouped_par(
+    groups: &[GemmGroup<f32, f32>],
+    a: &[*const TA],
+    b: &[*const TB],
+    c: &[*mut TC],
+    par: &PirePar,
+) {
+    run_grouped(groups, a, b, c, par, |g, a, b, c, par| {
+        pire_gemm_s8u8s32_par(
+            g.m, g.n, g.k, g.alpha, a, g.a_rs, g.a_cs, b, g.b_rs, g.b_cs, g.beta, c, g.c_rs, g.c_cs, par,
+        );
+    });
+}
+
 #[cfg(feature = "fuse")]
 pub unsafe fn pire_gemm_s8u8s32_fn_ptr(
     m: usize,
@@ -121,6 +263,254 @@ pub unsafe fn pire_gemm_s8u8s32_fn_ptr(
     pire_gemm_s8u8s32_fused(m, n, k, alpha, a, b, beta, c, unary);
 }
 
+/// Computes `c = alpha * a * b + beta * c` followed by `f` on `c`, where `a` and `b` can be
+/// strided views or matrices packed with [`pack_a`] / [`pack_b`].
+///
+/// # Safety
+///
+/// Strided `a`, `b` and `c` must point to valid memory for `m x k`, `k x n` and `m x n` matrices
+/// with their strides. Packed `a` (`b`) must be packed with the same `m` (`n`) and `k`
+pub unsafe fn pire_gemm_s8u8s32_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_s8u8s32_fused(m, n, k, alpha, a, b, beta, c, f);
+}
+
+/// Same as [`pire_gemm_s8u8s32_packed`] with thread configuration given by `par`
+///
+/// # Safety
+///
+/// See [`pire_gemm_s8u8s32_packed`]
+pub unsafe fn pire_gemm_s8u8s32_packed_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    b: Array<TB>,
+    beta: f32,
+    c: ArrayMut<TC>,
+    f: F,
+    par: &PirePar,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    pire_gemm_s8u8s32_fused_par(m, n, k, alpha, a, b, beta, c, f, par);
+}
+
+/// Safe counterpart of [`pire_gemm_s8u8s32`] on slices, returns an error instead of
+/// reading or writing out of bounds
+pub fn pire_gemm_s8u8s32_checked(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: &[TA],
+    a_layout: MatLayout,
+    b: &[TB],
+    b_layout: MatLayout,
+    beta: f32,
+    c: &mut [TC],
+    c_layout: MatLayout,
+) -> Result<(), GemmError> {
+    check_gemm_args(m, n, k, a, a_layout, b, b_layout, c, c_layout)?;
+    if m == 0 || n == 0 {
+        return Ok(());
+    }
+    if k == 0 {
+        scale_c(m, n, c, c_layout, |v| (beta * v as f32).round() as i32);
+        return Ok(());
+    }
+    // safety: sizes and strides of a, b, c are validated above
+    unsafe {
+        pire_gemm_s8u8s32(
+            m,
+            n,
+            k,
+            alpha,
+            a.as_ptr(),
+            a_layout.rs,
+            a_layout.cs,
+            b.as_ptr(),
+            b_layout.rs,
+            b_layout.cs,
+            beta,
+            c.as_mut_ptr(),
+            c_layout.rs,
+            c_layout.cs,
+        );
+    }
+    Ok(())
+}
+
+/// Offset added to c by [`pire_gemm_s8u8s32_offset`], the same as `CBLAS_OFFSET` of cblas
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OffsetC {
+    /// `c[i, j] += co[0]`
+    Fix,
+    /// `c[i, j] += co[j]`, co has `n` elements
+    Row,
+    /// `c[i, j] += co[i]`, co has `m` elements
+    Col,
+}
+
+/// Computes `c = alpha * (a + ao) * (b + bo) + beta * c + co` with the offset of c selected by `offsetc`,
+/// the same as `cblas_gemm_s8u8s32` of mkl
+///
+/// # Safety
+///
+/// See [`pire_gemm_s8u8s32`], `co` must point to 1, `n` or `m` elements for [`OffsetC::Fix`],
+/// [`OffsetC::Row`] or [`OffsetC::Col`]
+pub unsafe fn pire_gemm_s8u8s32_offset(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    ao: i8,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    bo: i8,
+    beta: f32,
+    c: *mut TC,
+    c_rs: usize,
+    c_cs: usize,
+    offsetc: OffsetC,
+    co: *const TC,
+) {
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    let c = ArrayMut::strided_matrix(c, c_rs, c_cs);
+    let par = PirePar::default(m, n);
+    pire_gemm_s8u8s32_offset_fused_par(m, n, k, alpha, a, ao, b, bo, beta, c, offsetc, co, IdentityFn {}, &par);
+}
+
+/// Same as [`pire_gemm_s8u8s32_offset`] for strided or packed `a` and `b`, followed by `f` on `c`
+///
+/// # Safety
+///
+/// See [`pire_gemm_s8u8s32_packed`] and [`pire_gemm_s8u8s32_offset`]
+pub unsafe fn pire_gemm_s8u8s32_offset_packed<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    ao: i8,
+    b: Array<TB>,
+    bo: i8,
+    beta: f32,
+    c: ArrayMut<TC>,
+    offsetc: OffsetC,
+    co: *const TC,
+    f: F,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    let par = PirePar::default(m, n);
+    pire_gemm_s8u8s32_offset_fused_par(m, n, k, alpha, a, ao, b, bo, beta, c, offsetc, co, f, &par);
+}
+
+/// Computes `c[i, j] = scale_a[i] * scale_b[j] * (a * b)[i, j] + bias[j]` with f32, bf16 or f16 `c`,
+/// scales and bias are applied to the i32 result in the store step of the kernel
+///
+/// # Safety
+///
+/// `a`, `b` and `c` as in [`pire_gemm_s8u8s32`], `scale_a` must point to `m` and `scale_b` to `n` elements,
+/// `bias` to `n` elements or be null for no bias
+pub unsafe fn pire_gemm_s8u8s32_dequant<T: DequantOut>(
+    m: usize,
+    n: usize,
+    k: usize,
+    a: *const TA,
+    a_rs: usize,
+    a_cs: usize,
+    b: *const TB,
+    b_rs: usize,
+    b_cs: usize,
+    scale_a: *const f32,
+    scale_b: *const f32,
+    bias: *const f32,
+    c: *mut T,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    let a = Array::strided_matrix(a, a_rs, a_cs);
+    let b = Array::strided_matrix(b, b_rs, b_cs);
+    pire_gemm_s8u8s32_dequant_packed(m, n, k, a, b, scale_a, scale_b, bias, c, c_rs, c_cs);
+}
+
+/// Same as [`pire_gemm_s8u8s32_dequant`] for strided or packed `a` and `b`
+///
+/// # Safety
+///
+/// See [`pire_gemm_s8u8s32_packed`] and [`pire_gemm_s8u8s32_dequant`]
+pub unsafe fn pire_gemm_s8u8s32_dequant_packed<T: DequantOut>(
+    m: usize,
+    n: usize,
+    k: usize,
+    a: Array<TA>,
+    b: Array<TB>,
+    scale_a: *const f32,
+    scale_b: *const f32,
+    bias: *const f32,
+    c: *mut T,
+    c_rs: usize,
+    c_cs: usize,
+) {
+    a.assert_packed_dims(m, k);
+    b.assert_packed_dims(n, k);
+    let f = DequantFn::new(scale_a, scale_b, bias, c, c_rs, c_cs);
+    if k == 0 {
+        f.fill_bias(m, n);
+        return;
+    }
+    if T::IN_PLACE {
+        // the i32 result is stored into c and turned into f32 in place
+        let c = ArrayMut::strided_matrix(c as *mut TC, c_rs, c_cs);
+        pire_gemm_s8u8s32_fused(m, n, k, 1.0, a, b, 0.0, c, f);
+    } else {
+        // smaller outputs do not hold the i32 result between kc blocks, it goes to an i32 scratch of the pack pool
+        // for one mc x nc block of c at a time, call_tile of f writes the block into c.
+        // packed a (b) can not be split into rows (columns), then the block spans all of m (n)
+        let (mc, nc, _) = dispatch_get_mcnckc();
+        let mc = if a.is_strided() { mc.min(m) } else { m };
+        let nc = if b.is_strided() { nc.min(n) } else { n };
+        let (slot, mut pool_vec) = take_pool_buf(mc * nc * size_of::<TC>() + AB_ALIGN);
+        let c_acc = pool_vec.as_mut_ptr();
+        let c_acc = c_acc.add(c_acc.align_offset(AB_ALIGN)) as *mut TC;
+        let mut j = 0;
+        while j < n {
+            let nc_len = nc.min(n - j);
+            let b_j = if b.is_strided() { Array::strided_matrix(b.src().add(j * b.cs()), b.rs(), b.cs()) } else { b };
+            let mut i = 0;
+            while i < m {
+                let mc_len = mc.min(m - i);
+                let a_i =
+                    if a.is_strided() { Array::strided_matrix(a.src().add(i * a.rs()), a.rs(), a.cs()) } else { a };
+                let c_acc = ArrayMut::strided_matrix(c_acc, 1, mc_len);
+                pire_gemm_s8u8s32_fused(mc_len, nc_len, k, 1.0, a_i, b_j, 0.0, c_acc, f.at(i, j));
+                i += mc_len;
+            }
+            j += nc_len;
+        }
+        put_pool_buf(slot, pool_vec);
+    }
+}
+
 fn dispatch_round_m() -> fn(usize) -> usize {
     #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
     {
@@ -160,6 +550,19 @@ fn dispatch_pack_b() -> unsafe fn(*const TB, *mut TB, usize, usize, usize, usize
     packb_fn_ref
 }
 
+// register block sizes, packing of a (b) can be split at multiples of mr (nr)
+fn dispatch_mr_nr() -> (usize, usize) {
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
+    {
+        if has_i8i32_compute() {
+            let hw_config = KernelDispatcher::new(IdentityFn {});
+            return (hw_config.mr(), hw_config.nr());
+        }
+    }
+    let hw_config = RefGemm::new(IdentityFn {});
+    (hw_config.mr(), hw_config.nr())
+}
+
 fn dispatch_get_mcnckc() -> (usize, usize, usize) {
     #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "aarch64"))]
     {
@@ -176,10 +579,12 @@ pire_base::packing_api!(TA, TB);
 mod tests {
     use super::*;
     use aligned_vec::avec;
+    use half::{bf16, f16};
     use pire_base::{get_cache_params, matrix_size};
     use pire_dev::{
-        check_gemm_s8u8s32, generate_k_dims, generate_m_dims, generate_n_dims, layout_to_strides,
-        random_matrix_uniform, ABLayout,
+        check_gemm_s8u8s32, check_gemm_s8u8s32_offset, gemm_fallback_s8u8s32, generate_k_dims, generate_m_dims,
+        generate_n_dims, layout_to_strides, random_matrix_uniform, ABLayout, CblasColOffset, CblasFixOffset,
+        CblasRowOffset,
     };
     #[test]
     fn test_pack_a() {
@@ -408,4 +813,261 @@ mod tests {
     fn test_tt_col_apbp() {
         test_gemm(&ABLayout::TT, true, true);
     }
+
+    fn test_gemm_offset(layout: &ABLayout, is_a_packed: bool, is_b_packed: bool) {
+        let offset_arr =
+            [(OffsetC::Fix, CblasFixOffset), (OffsetC::Row, CblasRowOffset), (OffsetC::Col, CblasColOffset)];
+        for &m in &[1, 17, 67] {
+            for &n in &[1, 9, 33] {
+                // k = 1031 spans several kc blocks of the packing that takes the sums
+                for &k in &[1, 35, 130, 1031] {
+                    let mut a = vec![0i8; m * k];
+                    let mut b = vec![0u8; k * n];
+                    random_matrix_uniform(&mut a);
+                    random_matrix_uniform(&mut b);
+                    let mut co = vec![0i32; m.max(n)];
+                    random_matrix_uniform(&mut co);
+                    let (a_rs, a_cs, b_rs, b_cs, c_rs, c_cs) = layout_to_strides(&layout, m, n, k);
+                    let mut ap = avec![[AB_ALIGN]| 0i8; a_size_packed(m, k)];
+                    let mut bp = avec![[AB_ALIGN]| 0u8; b_size_packed(n, k)];
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for (offsetc, offsetc_ref) in offset_arr {
+                        for (ao, bo) in [(0, 0), (-3, 0), (0, 5), (7, -20)] {
+                            for (alpha, beta) in [(1.0, 0.0), (1.0, 1.0), (2.0, 0.0), (2.0, 3.0)] {
+                                let mut c = vec![0i32; m * n];
+                                random_matrix_uniform(&mut c);
+                                let mut c_ref = c.clone();
+                                let c_matrix = ArrayMut::strided_matrix(c.as_mut_ptr(), c_rs, c_cs);
+                                unsafe {
+                                    pire_gemm_s8u8s32_offset_packed(
+                                        m,
+                                        n,
+                                        k,
+                                        alpha,
+                                        a_matrix,
+                                        ao,
+                                        b_matrix,
+                                        bo,
+                                        beta,
+                                        c_matrix,
+                                        offsetc,
+                                        co.as_ptr(),
+                                        IdentityFn {},
+                                    );
+                                }
+                                let diff_max = unsafe {
+                                    check_gemm_s8u8s32_offset(
+                                        m,
+                                        n,
+                                        k,
+                                        alpha,
+                                        a.as_ptr(),
+                                        a_rs,
+                                        a_cs,
+                                        ao,
+                                        b.as_ptr(),
+                                        b_rs,
+                                        b_cs,
+                                        bo,
+                                        beta,
+                                        &c,
+                                        c_rs,
+                                        c_cs,
+                                        &mut c_ref,
+                                        offsetc_ref,
+                                        co.as_ptr(),
+                                        EPS,
+                                    )
+                                };
+                                assert!(
+                                    diff_max < EPS,
+                                    "diff_max: {}, m: {}, n: {}, k: {}, ao: {}, bo: {}, offsetc: {:?}",
+                                    diff_max,
+                                    m,
+                                    n,
+                                    k,
+                                    ao,
+                                    bo,
+                                    offsetc
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_offset_nn_col() {
+        test_gemm_offset(&ABLayout::NN, false, false);
+    }
+    #[test]
+    fn test_offset_tn_col() {
+        test_gemm_offset(&ABLayout::TN, false, false);
+    }
+    #[test]
+    fn test_offset_nt_col_apbp() {
+        test_gemm_offset(&ABLayout::NT, true, true);
+    }
+    #[test]
+    fn test_offset_tt_col_apbp() {
+        test_gemm_offset(&ABLayout::TT, true, true);
+    }
+
+    fn test_gemm_dequant<T: DequantOut + Into<f32>>(layout: &ABLayout, is_a_packed: bool, is_b_packed: bool, tol: f32) {
+        let (_, nc, kc) = get_mcnckc();
+        for &m in &[1, 17, 67] {
+            // nc + 9 spans two column blocks of the i32 scratch of f16/bf16 outputs
+            for &n in &[1, 9, 33, nc + 9] {
+                for &k in &[1, 35, kc + 29] {
+                    let mut a = vec![0i8; m * k];
+                    let mut b = vec![0u8; k * n];
+                    random_matrix_uniform(&mut a);
+                    random_matrix_uniform(&mut b);
+                    let scale_a = (0..m).map(|i| 0.5 + i as f32 * 0.01).collect::<Vec<_>>();
+                    let scale_b = (0..n).map(|j| 0.25 - (j % 33) as f32 * 0.02).collect::<Vec<_>>();
+                    let bias = (0..n).map(|j| j as f32 - 3.0).collect::<Vec<_>>();
+                    let (a_rs, a_cs, b_rs, b_cs, _, _) = layout_to_strides(&layout, m, n, k);
+                    let mut ap = avec![[AB_ALIGN]| 0i8; a_size_packed(m, k)];
+                    let mut bp = avec![[AB_ALIGN]| 0u8; b_size_packed(n, k)];
+                    let a_matrix = if is_a_packed {
+                        pack_a(m, k, &a, a_rs, a_cs, &mut ap)
+                    } else {
+                        Array::strided_matrix(a.as_ptr(), a_rs, a_cs)
+                    };
+                    let b_matrix = if is_b_packed {
+                        pack_b(n, k, &b, b_rs, b_cs, &mut bp)
+                    } else {
+                        Array::strided_matrix(b.as_ptr(), b_rs, b_cs)
+                    };
+                    for (c_rs, c_cs) in [(1, m), (n, 1), (2, 2 * m + 1)] {
+                        let c_len = (m - 1) * c_rs + (n - 1) * c_cs + 1;
+                        let mut c = vec![T::from_f32(f32::NAN); c_len];
+                        let mut c_ref = vec![0i32; c_len];
+                        unsafe {
+                            pire_gemm_s8u8s32_dequant_packed(
+                                m,
+                                n,
+                                k,
+                                a_matrix,
+                                b_matrix,
+                                scale_a.as_ptr(),
+                                scale_b.as_ptr(),
+                                bias.as_ptr(),
+                                c.as_mut_ptr(),
+                                c_rs,
+                                c_cs,
+                            );
+                            gemm_fallback_s8u8s32(
+                                m,
+                                n,
+                                k,
+                                1.0,
+                                a.as_ptr(),
+                                a_rs,
+                                a_cs,
+                                b.as_ptr(),
+                                b_rs,
+                                b_cs,
+                                0.0,
+                                c_ref.as_mut_ptr(),
+                                c_rs,
+                                c_cs,
+                            );
+                        }
+                        for j in 0..n {
+                            for i in 0..m {
+                                let acc = c_ref[i * c_rs + j * c_cs] as f32;
+                                let expected = scale_a[i] * scale_b[j] * acc + bias[j];
+                                let c_ij: f32 = c[i * c_rs + j * c_cs].into();
+                                assert!(
+                                    (c_ij - expected).abs() <= tol * (1.0 + expected.abs()),
+                                    "c: {}, expected: {}, m: {}, n: {}, k: {}, i: {}, j: {}",
+                                    c_ij,
+                                    expected,
+                                    m,
+                                    n,
+                                    k,
+                                    i,
+                                    j
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_dequant_nn() {
+        test_gemm_dequant::<f32>(&ABLayout::NN, false, false, 1e-5);
+    }
+    #[test]
+    fn test_dequant_tn() {
+        test_gemm_dequant::<f32>(&ABLayout::TN, false, false, 1e-5);
+    }
+    #[test]
+    fn test_dequant_nt_apbp() {
+        test_gemm_dequant::<f32>(&ABLayout::NT, true, true, 1e-5);
+    }
+    #[test]
+    fn test_dequant_tt_apbp() {
+        test_gemm_dequant::<f32>(&ABLayout::TT, true, true, 1e-5);
+    }
+    #[test]
+    fn test_dequant_nn_f16() {
+        test_gemm_dequant::<f16>(&ABLayout::NN, false, false, 1e-3);
+    }
+    #[test]
+    fn test_dequant_tn_bf16_apbp() {
+        test_gemm_dequant::<bf16>(&ABLayout::TN, true, true, 8e-3);
+    }
+
+    #[test]
+    fn test_packed_api() {
+        let (m, n, k) = (197, 83, 613);
+        let mut a = vec![0i8; m * k];
+        let mut b = vec![0u8; k * n];
+        random_matrix_uniform(&mut a);
+        random_matrix_uniform(&mut b);
+        let mut ap = avec![[AB_ALIGN]| 0i8; a_size_packed(m, k)];
+        let mut bp = avec![[AB_ALIGN]| 0u8; b_size_packed(n, k)];
+        let ap_array = pack_a(m, k, &a, 1, m, &mut ap);
+        let bp_array = pack_b(n, k, &b, n, 1, &mut bp);
+        let mut c = vec![3i32; m * n];
+        let mut c_ref = c.clone();
+        unsafe {
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+            pire_gemm_s8u8s32_packed(m, n, k, 1.0, ap_array, bp_array, 2.0, c_array, IdentityFn {});
+            pire_gemm_s8u8s32(m, n, k, 1.0, a.as_ptr(), 1, m, b.as_ptr(), n, 1, 2.0, c_ref.as_mut_ptr(), 1, m);
+        }
+        assert_eq!(c, c_ref);
+    }
+
+    #[test]
+    #[should_panic(expected = "packed matrix dims")]
+    fn test_packed_dims_mismatch() {
+        let (m, n, k) = (17, 9, 33);
+        let a = vec![1i8; m * k];
+        let b = vec![1u8; k * n];
+        let mut bp = avec![[AB_ALIGN]| 0u8; b_size_packed(n, k)];
+        let bp_array = pack_b(n, k, &b, 1, k, &mut bp);
+        let mut c = vec![0i32; m * n];
+        unsafe {
+            let a_array = Array::strided_matrix(a.as_ptr(), 1, m);
+            let c_array = ArrayMut::strided_matrix(c.as_mut_ptr(), 1, m);
+            pire_gemm_s8u8s32_packed(m, n, k - 1, 1.0, a_array, bp_array, 0.0, c_array, IdentityFn {});
+        }
+    }
 }
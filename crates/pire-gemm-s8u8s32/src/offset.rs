@@ -0,0 +1,307 @@
+// (a + ao) * (b + bo) = a * b + bo * rowsum(a)[i] + ao * colsum(b)[j] + k * ao * bo
+//
+// The row sums of strided a (column sums of strided b) are taken in the pass that packs it, a and b are packed
+// once for the whole gemm into a buffer of PACK_POOL and the gemm runs on the packed matrices.
+// The offset terms, alpha, beta * c and co are applied to the i32 result in the store step of the kernel
+// by OffsetFn, so that alpha multiplies the whole (a + ao) * (b + bo) once, as in mkl.
+// With alpha = 1 and beta = 0 or 1 the kernel applies beta itself and c stays exact in integer arithmetic,
+// otherwise the kernel runs with beta = 0 and OffsetFn reads c from a copy made before the gemm.
+
+use core::mem::size_of;
+use core::ptr::null_mut;
+
+use pire_base::thread_pool::run_tasks;
+use pire_base::{put_pool_buf, split_range, take_pool_buf, AB_ALIGN};
+
+use crate::{
+    a_size_packed, b_size_packed, dispatch_get_mcnckc, dispatch_mr_nr, dispatch_pack_a, dispatch_pack_b,
+    dispatch_round_k, dispatch_round_m, pire_gemm_s8u8s32_fused_par, Array, ArrayMut, IdentityFn, OffsetC, PirePar,
+    UnaryFn, UnaryFnC, TA, TB, TC,
+};
+
+// runs f(start, end) on the ranges of 0..len split at multiples of unit over num_threads threads
+fn for_each_range<G: Fn(usize, usize) + Sync>(len: usize, unit: usize, num_threads: usize, f: G) {
+    let num_tasks = num_threads.min((len + unit - 1) / unit).max(1);
+    run_tasks(num_tasks, |t_id| {
+        let (start, end) = split_range(len, unit, t_id, num_tasks);
+        if start < end {
+            f(start, end);
+        }
+    });
+}
+
+// Packs strided a into ap with the layout of pack_a and adds its row sums to a_sum, null ap only takes the sums.
+// Every kc block holds round_m(m) rows with row i at i * kc_len_eff, so the rows are split over the threads
+// at multiples of mr and each block is summed right after it is packed
+unsafe fn pack_sum_a(m: usize, k: usize, a: Array<TA>, ap: *mut TA, a_sum: *mut TC, num_threads: usize) -> Array<TA> {
+    let pack_fn = dispatch_pack_a();
+    let round_k_fn = dispatch_round_k();
+    let m_round = dispatch_round_m()(m);
+    let (_, _, kc) = dispatch_get_mcnckc();
+    let (mr, _) = dispatch_mr_nr();
+    let (a_rs, a_cs) = (a.rs(), a.cs());
+    // pointers as addresses so that the closure is Sync, rows handled by different threads are disjoint
+    let (a_addr, ap_addr, sum_addr) = (a.src() as usize, ap as usize, a_sum as usize);
+    for_each_range(m, mr, num_threads, |start, end| {
+        let (a, a_sum) = (a_addr as *const TA, sum_addr as *mut TC);
+        let mut ap_cur = ap_addr as *mut TA;
+        for p in (0..k).step_by(kc) {
+            let kc_len = kc.min(k - p);
+            let kc_len_eff = round_k_fn(kc_len);
+            if !ap_cur.is_null() {
+                let a_cur = a.add(start * a_rs + p * a_cs);
+                pack_fn(a_cur, ap_cur.add(start * kc_len_eff), end - start, kc_len, a_rs, a_cs);
+                ap_cur = ap_cur.add(m_round * kc_len_eff);
+            }
+            for i in start..end {
+                let mut acc = 0 as TC;
+                for kk in p..p + kc_len {
+                    acc += *a.add(i * a_rs + kk * a_cs) as TC;
+                }
+                *a_sum.add(i) += acc;
+            }
+        }
+    });
+    if ap.is_null() {
+        a
+    } else {
+        Array::packed_matrix(ap, m, k)
+    }
+}
+
+// Same as pack_sum_a for b with the layout of pack_b, column j of a kc block is at j * kc_len_eff
+unsafe fn pack_sum_b(n: usize, k: usize, b: Array<TB>, bp: *mut TB, b_sum: *mut TC, num_threads: usize) -> Array<TB> {
+    let pack_fn = dispatch_pack_b();
+    let round_k_fn = dispatch_round_k();
+    let (_, _, kc) = dispatch_get_mcnckc();
+    let (_, nr) = dispatch_mr_nr();
+    let (b_rs, b_cs) = (b.rs(), b.cs());
+    let (b_addr, bp_addr, sum_addr) = (b.src() as usize, bp as usize, b_sum as usize);
+    for_each_range(n, nr, num_threads, |start, end| {
+        let (b, b_sum) = (b_addr as *const TB, sum_addr as *mut TC);
+        let mut bp_cur = bp_addr as *mut TB;
+        for p in (0..k).step_by(kc) {
+            let kc_len = kc.min(k - p);
+            let kc_len_eff = round_k_fn(kc_len);
+            if !bp_cur.is_null() {
+                let b_cur = b.add(start * b_cs + p * b_rs);
+                pack_fn(b_cur, bp_cur.add(start * kc_len_eff), end - start, kc_len, b_rs, b_cs);
+                bp_cur = bp_cur.add(n * kc_len_eff);
+            }
+            for j in start..end {
+                let mut acc = 0 as TC;
+                for kk in p..p + kc_len {
+                    acc += *b.add(kk * b_rs + j * b_cs) as TC;
+                }
+                *b_sum.add(j) += acc;
+            }
+        }
+    });
+    if bp.is_null() {
+        b
+    } else {
+        Array::packed_matrix(bp, n, k)
+    }
+}
+
+// the layout of matrices packed by the caller is arch specific, their sums are taken by the gemm itself
+unsafe fn sum_packed_a(m: usize, k: usize, a: Array<TA>, a_sum: *mut TC, par: &PirePar) {
+    let ones = vec![1 as TB; k];
+    let b = Array::strided_matrix(ones.as_ptr(), 1, 1);
+    let c = ArrayMut::strided_matrix(a_sum, 1, 1);
+    pire_gemm_s8u8s32_fused_par(m, 1, k, 1.0, a, b, 0.0, c, IdentityFn {}, par);
+}
+
+unsafe fn sum_packed_b(n: usize, k: usize, b: Array<TB>, b_sum: *mut TC, par: &PirePar) {
+    let ones = vec![1 as TA; k];
+    let a = Array::strided_matrix(ones.as_ptr(), 1, 1);
+    let c = ArrayMut::strided_matrix(b_sum, 1, 1);
+    pire_gemm_s8u8s32_fused_par(1, n, k, 1.0, a, b, 0.0, c, IdentityFn {}, par);
+}
+
+// Epilogue of the offset gemm, with acc the i32 result of the kernel:
+// c[i, j] = acc + a_off[i] + b_off[j] + co if exact (the kernel applied beta),
+// c[i, j] = round(alpha * (acc + a_off[i] + b_off[j]) + beta * c_old[i, j]) + co otherwise, followed by f
+#[derive(Copy, Clone)]
+struct OffsetFn<F> {
+    // bo * rowsum(a)[i] + k * ao * bo and ao * colsum(b)[j]
+    a_off: *const TC,
+    b_off: *const TC,
+    offsetc: OffsetC,
+    co: *const TC,
+    exact: bool,
+    alpha: f32,
+    beta: f32,
+    // column major m x n copy of c, null if beta == 0
+    c_old: *const TC,
+    c_old_cs: usize,
+    i0: usize,
+    j0: usize,
+    f: F,
+}
+
+// offsets and c_old are only read, every thread writes its own part of c
+unsafe impl<F: Sync> Sync for OffsetFn<F> {}
+
+impl<F: UnaryFnC> UnaryFn<TC> for OffsetFn<F> {
+    #[inline(always)]
+    unsafe fn call(self, c: *mut TC, m: usize) {
+        self.call_tile(c, m, 0, 0);
+    }
+
+    #[inline(always)]
+    unsafe fn call_tile(self, c: *mut TC, m: usize, i: usize, j: usize) {
+        let (i0, j0) = (self.i0 + i, self.j0 + j);
+        let b_off_j = *self.b_off.add(j0);
+        for t in 0..m {
+            let x = (*c.add(t)).wrapping_add(*self.a_off.add(i0 + t)).wrapping_add(b_off_j);
+            let x = if self.exact {
+                x
+            } else {
+                let c_old = if self.c_old.is_null() { 0 } else { *self.c_old.add(i0 + t + j0 * self.c_old_cs) };
+                (self.alpha * x as f32 + self.beta * c_old as f32).round() as TC
+            };
+            let co = match self.offsetc {
+                OffsetC::Fix => *self.co,
+                OffsetC::Row => *self.co.add(j0),
+                OffsetC::Col => *self.co.add(i0 + t),
+            };
+            *c.add(t) = x.wrapping_add(co);
+        }
+        self.f.call_tile(c, m, i, j);
+    }
+
+    #[inline(always)]
+    fn at(self, i: usize, j: usize) -> Self {
+        Self { i0: self.i0 + i, j0: self.j0 + j, f: self.f.at(i, j), ..self }
+    }
+}
+
+pub(crate) unsafe fn pire_gemm_s8u8s32_offset_fused_par<F: UnaryFnC>(
+    m: usize,
+    n: usize,
+    k: usize,
+    alpha: f32,
+    a: Array<TA>,
+    ao: i8,
+    b: Array<TB>,
+    bo: i8,
+    beta: f32,
+    c: ArrayMut<TC>,
+    offsetc: OffsetC,
+    co: *const TC,
+    f: F,
+    par: &PirePar,
+) {
+    if m == 0 || n == 0 {
+        return;
+    }
+    let num_threads = par.num_threads;
+    let (c_ptr, c_rs, c_cs) = (c.src() as usize, c.rs(), c.cs());
+    if k == 0 {
+        // no products, c = beta * c + co
+        let co_addr = co as usize;
+        for_each_range(n, 1, num_threads, |start, end| {
+            let (c, co) = (c_ptr as *mut TC, co_addr as *const TC);
+            for j in start..end {
+                for i in 0..m {
+                    let c_ij = c.add(i * c_rs + j * c_cs);
+                    let x = if beta == 0.0 {
+                        0
+                    } else if beta == 1.0 {
+                        *c_ij
+                    } else {
+                        (beta * *c_ij as f32).round() as TC
+                    };
+                    let co = match offsetc {
+                        OffsetC::Fix => *co,
+                        OffsetC::Row => *co.add(j),
+                        OffsetC::Col => *co.add(i),
+                    };
+                    *c_ij = x.wrapping_add(co);
+                }
+            }
+        });
+        return;
+    }
+    let exact = alpha == 1.0 && (beta == 0.0 || beta == 1.0);
+    let keep_c = !exact && beta != 0.0;
+    // a (b) is not packed for the gemv paths, where it is read only once
+    let pack_a = bo != 0 && a.is_strided() && m > 1 && n > 1;
+    let pack_b = ao != 0 && b.is_strided() && m > 1 && n > 1;
+    let ap_size = if pack_a { a_size_packed(m, k) * size_of::<TA>() + AB_ALIGN } else { 0 };
+    let bp_size = if pack_b { b_size_packed(n, k) * size_of::<TB>() + AB_ALIGN } else { 0 };
+    let c_old_size = if keep_c { m * n * size_of::<TC>() + AB_ALIGN } else { 0 };
+    let pool_size = ap_size + bp_size + c_old_size;
+    let mut pool = if pool_size > 0 { Some(take_pool_buf(pool_size)) } else { None };
+    let (ap, bp, c_old) = match pool.as_mut() {
+        Some((_, pool_vec)) => {
+            let p = pool_vec.as_mut_ptr();
+            let aligned = |x: *mut u8| x.add(x.align_offset(AB_ALIGN));
+            let ap = if pack_a { aligned(p) as *mut TA } else { null_mut() };
+            let bp = if pack_b { aligned(p.add(ap_size)) as *mut TB } else { null_mut() };
+            let c_old = if keep_c { aligned(p.add(ap_size + bp_size)) as *mut TC } else { null_mut() };
+            (ap, bp, c_old)
+        }
+        None => (null_mut(), null_mut(), null_mut()),
+    };
+
+    let (ao, bo) = (ao as TC, bo as TC);
+    let mut a_off = vec![0 as TC; m];
+    let mut b_off = vec![0 as TC; n];
+    let a = if bo == 0 {
+        a
+    } else if a.is_strided() {
+        pack_sum_a(m, k, a, ap, a_off.as_mut_ptr(), num_threads)
+    } else {
+        sum_packed_a(m, k, a, a_off.as_mut_ptr(), par);
+        a
+    };
+    let b = if ao == 0 {
+        b
+    } else if b.is_strided() {
+        pack_sum_b(n, k, b, bp, b_off.as_mut_ptr(), num_threads)
+    } else {
+        sum_packed_b(n, k, b, b_off.as_mut_ptr(), par);
+        b
+    };
+    let k_ab = (k as TC).wrapping_mul(ao).wrapping_mul(bo);
+    for x in a_off.iter_mut() {
+        *x = bo.wrapping_mul(*x).wrapping_add(k_ab);
+    }
+    for x in b_off.iter_mut() {
+        *x = ao.wrapping_mul(*x);
+    }
+    if keep_c {
+        let c_old_addr = c_old as usize;
+        for_each_range(n, 1, num_threads, |start, end| {
+            let (c, c_old) = (c_ptr as *const TC, c_old_addr as *mut TC);
+            for j in start..end {
+                for i in 0..m {
+                    *c_old.add(i + j * m) = *c.add(i * c_rs + j * c_cs);
+                }
+            }
+        });
+    }
+
+    let f = OffsetFn {
+        a_off: a_off.as_ptr(),
+        b_off: b_off.as_ptr(),
+        offsetc,
+        co,
+        exact,
+        alpha,
+        beta,
+        c_old,
+        c_old_cs: m,
+        i0: 0,
+        j0: 0,
+        f,
+    };
+    let beta = if exact { beta } else { 0.0 };
+    pire_gemm_s8u8s32_fused_par(m, n, k, 1.0, a, b, beta, c, f, par);
+
+    if let Some((slot, pool_vec)) = pool {
+        put_pool_buf(slot, pool_vec);
+    }
+}
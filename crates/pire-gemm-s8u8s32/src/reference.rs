@@ -1,7 +1,7 @@
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, GemmPool, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, TransposedFn, UnaryFn, PACK_POOL,
     RUNTIME_HW_CONFIG,
 };
 
@@ -116,6 +116,9 @@ impl<F: UnaryFnC> RefGemm<F> {
     pub(crate) fn round_m(&self, m: usize) -> usize {
         m
     }
+    pub(crate) fn nr(&self) -> usize {
+        self.nr
+    }
 }
 
 impl<T: UnaryFnC> GemmCache for RefGemm<T> {
@@ -153,6 +156,8 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const i8,
     bp: *const u8,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     let mut i = 0;
@@ -186,7 +191,7 @@ unsafe fn kernel<F: UnaryFnC>(
                     let c_cur = c.add(i * c_rs + j * c_cs + ii * c_rs + jj * c_cs);
                     *c_cur = ((*c_cur as f32) * *beta + acc[ii * nr_eff + jj] as f32 * *alpha) as i32;
                     if kc_last {
-                        hw_cfg.func.call(c_cur, 1);
+                        hw_cfg.func.at(i0, j0).call_tile(c_cur, 1, i + ii, j + jj);
                     }
                     acc[ii * nr_eff + jj] = 0;
                     jj += 1;
@@ -215,6 +220,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const i8,
+    i0: usize,
+    j0: usize,
     _kc_last: bool,
 ) {
 }
@@ -235,6 +242,8 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut i32,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     _kc_last: bool,
 ) {
 }
@@ -266,7 +275,7 @@ unsafe fn pire_gemv<F: UnaryFnC>(
             j += 1;
         }
         *y_ptr.add(i * incy) = ((*y_ptr.add(i * incy) as f32) * *beta + acc as f32 * *alpha) as i32;
-        hw_cfg.func.call(y_ptr.add(i * incy), 1);
+        hw_cfg.func.call_tile(y_ptr.add(i * incy), 1, i, 0);
         i += 1;
     }
 }
@@ -298,7 +307,7 @@ unsafe fn pire_gemv2<F: UnaryFnC>(
             j += 1;
         }
         *y_ptr.add(i * incy) = ((*y_ptr.add(i * incy) as f32) * *beta + acc as f32 * *alpha) as i32;
-        hw_cfg.func.call(y_ptr.add(i * incy), 1);
+        TransposedFn(hw_cfg.func).call_tile(y_ptr.add(i * incy), 1, i, 0);
         i += 1;
     }
 }
@@ -0,0 +1,23 @@
+#[rustfmt::skip]
+mod asm_ukernel;
+
+use asm_ukernel::*;
+
+use crate::UnaryFnC;
+
+const VS: usize = 8;
+const VS_MAX: usize = VS;
+
+const ZERO: i32 = 0;
+
+const fn simd_vector_length() -> usize {
+    VS
+}
+
+use pire_base::def_kernel_bb_v0;
+def_kernel_bb_v0!(i8, u8, i32, f32, false, F, 4, 2, 4, 0, 0);
+
+use super::pack_avx::packa_panel_16;
+
+use pire_base::def_kernel_sb_v0;
+def_kernel_sb_v0!(i8, i8, u8, i32, f32, false, F, packa_panel_16, 4, 2, 4, 0, 0);
@@ -1,15 +1,18 @@
+pub(crate) mod amx_int8;
 pub(crate) mod avx2;
 pub(crate) mod avx512_vnni;
 pub(crate) mod avx512bw;
+pub(crate) mod avx_vnni;
+pub(crate) mod pack_amx;
 pub(crate) mod pack_avx;
 pub(crate) mod pack_sse;
 pub(crate) mod sse;
 
 use pire_base::{
-    acquire, def_pa, def_pire_gemm, extend, get_apbp_barrier, get_cache_params, get_mem_pool_size_goto,
-    get_mem_pool_size_small_m, get_mem_pool_size_small_n, is_mixed, run_small_m, run_small_n, split_c_range,
-    split_range, Array, ArrayMut, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, PACK_POOL,
-    RUNTIME_HW_CONFIG,
+    acquire, def_pa, def_pire_gemm, extend, get_cache_params, get_mem_pool_size_goto, get_mem_pool_size_small_m,
+    get_mem_pool_size_small_n, is_mixed, run_par, run_small_m, run_small_n, split_c_range, split_range, Array,
+    ArrayMut, CpuFeatures, GemmPool, HWModel, PArray, PirePar, PireThreadConfig, PoolSize, PtrData, TransposedFn,
+    PACK_POOL, RUNTIME_HW_CONFIG,
 };
 
 use crate::{GemmCache, IdentityFn, UnaryFnC, TA, TB, TC};
@@ -18,28 +21,41 @@ const AVX512F_VS: usize = 16;
 const AVX_VS: usize = 8;
 const SSE_VS: usize = 4;
 
+const AMX_INT8_MR: usize = amx_int8::MR;
 const AVX512_VNNI_MR: usize = 48;
 const AVX512BW_MR: usize = 32;
+const AVX_VNNI_MR: usize = 16;
 const AVX2_MR: usize = 16;
 const SSE_MR: usize = 8;
 
+const AMX_INT8_NR: usize = amx_int8::NR;
 const AVX512_VNNI_NR: usize = 8;
 const AVX512BW_NR: usize = 8;
+const AVX_VNNI_NR: usize = 4;
 const AVX2_NR: usize = 4;
 const SSE_NR: usize = 4;
 
-#[inline(always)]
-pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
-    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
-    let (mr, nr) = if features.avx512_vnni {
-        (AVX512_VNNI_MR, AVX512_VNNI_NR)
+// kernel used for the given features, amx only if the os granted it (amx_int8 is off otherwise)
+pub(crate) fn select_kernel(features: &CpuFeatures) -> (usize, usize, RegDim) {
+    if features.amx_int8 {
+        (AMX_INT8_MR, AMX_INT8_NR, RegDim::AmxInt8)
+    } else if features.avx512_vnni {
+        (AVX512_VNNI_MR, AVX512_VNNI_NR, RegDim::Avx512VNNI)
     } else if features.avx512bw {
-        (AVX512BW_MR, AVX512BW_NR)
+        (AVX512BW_MR, AVX512BW_NR, RegDim::Avx512BW)
+    } else if features.avx_vnni && features.avx2 {
+        (AVX_VNNI_MR, AVX_VNNI_NR, RegDim::AvxVnni)
     } else if features.avx2 {
-        (AVX2_MR, AVX2_NR)
+        (AVX2_MR, AVX2_NR, RegDim::Avx2)
     } else {
-        (SSE_MR, SSE_NR)
-    };
+        (SSE_MR, SSE_NR, RegDim::Sse)
+    }
+}
+
+#[inline(always)]
+pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
+    let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+    let (mr, nr, _) = select_kernel(&features);
     // let mc = std::env::var("PIRE_MC").unwrap_or("5400".to_string()).parse::<usize>().unwrap();
     // let nc = std::env::var("PIRE_NC").unwrap_or("192".to_string()).parse::<usize>().unwrap();
     // let kc = std::env::var("PIRE_KC").unwrap_or("512".to_string()).parse::<usize>().unwrap();
@@ -49,12 +65,16 @@ pub(crate) fn get_mcnckc_simd() -> (usize, usize, usize) {
         HWModel::Haswell => (4800, 320, 768),
         _ => get_cache_params(),
     };
+    // kc is a whole number of amx k steps so that the packed size of k is the sum over kc blocks
+    let kc = if features.amx_int8 { (kc / 64).max(1) * 64 } else { kc };
     (mc / mr * mr, nc / nr * nr, kc)
 }
 
 pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize, rs: usize, cs: usize) {
     let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
-    if feature.avx512_vnni {
+    if feature.amx_int8 {
+        pack_amx::packa_panel(m, k, x, rs, cs, y, AMX_INT8_MR);
+    } else if feature.avx512_vnni {
         pack_avx::packa_panel_48(m, k, x, rs, cs, y, AVX512F_VS);
     } else if feature.avx512bw {
         pack_avx::packa_panel_32(m, k, x, rs, cs, y, AVX512F_VS);
@@ -67,7 +87,9 @@ pub(crate) unsafe fn packa_fn_simd(x: *const TA, y: *mut TA, m: usize, k: usize,
 
 pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize, rs: usize, cs: usize) {
     let feature = (*RUNTIME_HW_CONFIG).cpu_ft();
-    if feature.avx512_vnni {
+    if feature.amx_int8 {
+        pack_amx::packb(n, k, x, rs, cs, y);
+    } else if feature.avx512_vnni {
         pack_avx::packb_panel_8(n, k, x, cs, rs, y);
     } else if feature.avx512bw {
         pack_avx::packb_panel_8(n, k, x, cs, rs, y);
@@ -80,7 +102,7 @@ pub(crate) unsafe fn packb_fn_simd(x: *const TB, y: *mut TB, n: usize, k: usize,
 
 pub(crate) fn round_m_simd(m: usize) -> usize {
     let hw_config = &*RUNTIME_HW_CONFIG;
-    let vs = if hw_config.cpu_ft.avx512_vnni || hw_config.cpu_ft.avx512bw {
+    let vs = if hw_config.cpu_ft.amx_int8 || hw_config.cpu_ft.avx512_vnni || hw_config.cpu_ft.avx512bw {
         AVX512F_VS
     } else if hw_config.cpu_ft.avx2 {
         AVX_VS
@@ -91,12 +113,18 @@ pub(crate) fn round_m_simd(m: usize) -> usize {
 }
 
 pub(crate) fn round_k_simd(k: usize) -> usize {
-    (k + 3) / 4 * 4
+    if (*RUNTIME_HW_CONFIG).cpu_ft.amx_int8 {
+        pack_amx::round_k(k)
+    } else {
+        (k + 3) / 4 * 4
+    }
 }
 
 pub(crate) enum RegDim {
+    AmxInt8,
     Avx512VNNI,
     Avx512BW,
+    AvxVnni,
     Avx2,
     Sse,
 }
@@ -123,16 +151,8 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
         let (mc, nc, kc) = get_mcnckc_simd();
         let features = hw_config.cpu_ft();
         let (_, is_l2_shared, is_l3_shared) = hw_config.get_cache_info();
-        let (mr, nr, reg_dim) = if features.avx512_vnni {
-            (AVX512_VNNI_MR, AVX512_VNNI_NR, RegDim::Avx512VNNI)
-        } else if features.avx512bw {
-            (AVX512BW_MR, AVX512BW_NR, RegDim::Avx512BW)
-        } else if features.avx2 {
-            (AVX2_MR, AVX2_NR, RegDim::Avx2)
-        } else {
-            (SSE_MR, SSE_NR, RegDim::Sse)
-        };
-        let vs = if hw_config.cpu_ft.avx512_vnni || hw_config.cpu_ft.avx512bw {
+        let (mr, nr, reg_dim) = select_kernel(&features);
+        let vs = if features.amx_int8 || features.avx512_vnni || features.avx512bw {
             AVX512F_VS
         } else if hw_config.cpu_ft.avx2 {
             AVX_VS
@@ -160,12 +180,19 @@ impl<F: UnaryFnC> KernelDispatcher<F> {
     }
 
     pub(crate) fn round_k(&self, k: usize) -> usize {
-        (k + 3) / 4 * 4
+        match self.reg_dim {
+            RegDim::AmxInt8 => pack_amx::round_k(k),
+            _ => (k + 3) / 4 * 4,
+        }
     }
 
     pub(crate) fn round_m(&self, m: usize) -> usize {
         (m + self.vs - 1) / self.vs * self.vs
     }
+
+    pub(crate) fn nr(&self) -> usize {
+        self.nr
+    }
 }
 
 impl<T: UnaryFnC> GemmCache for KernelDispatcher<T> {
@@ -203,20 +230,27 @@ unsafe fn kernel<F: UnaryFnC>(
     c_cs: usize,
     ap: *const TA,
     bp: *const TB,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
-            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, hw_cfg.func),
+            RegDim::AmxInt8 => amx_int8::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
+            RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
+            RegDim::AmxInt8 => amx_int8::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx512VNNI => avx512_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx512BW => avx512bw::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
+            RegDim::AvxVnni => avx_vnni::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Avx2 => avx2::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
             RegDim::Sse => sse::kernel(m, n, k, alpha, beta, c, c_rs, c_cs, ap, bp, null_fn),
         }
@@ -238,6 +272,8 @@ unsafe fn kernel_m<F: UnaryFnC>(
     c_rs: usize,
     c_cs: usize,
     ap: *const TA,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     panic!("Not implemented");
@@ -258,26 +294,29 @@ unsafe fn kernel_n<F: UnaryFnC>(
     c: *mut TC,
     c_rs: usize,
     c_cs: usize,
+    i0: usize,
+    j0: usize,
     kc_last: bool,
 ) {
     if kc_last {
+        let f = hw_cfg.func.at(i0, j0);
         match hw_cfg.reg_dim {
-            RegDim::Avx512VNNI => {
-                avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::Avx512BW => {
-                avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func)
-            }
-            RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
-            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, hw_cfg.func),
+            RegDim::AmxInt8 => amx_int8::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx512VNNI => avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx512BW => avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::AvxVnni => avx_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
+            RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, f),
         }
     } else {
         let null_fn = IdentityFn {};
         match hw_cfg.reg_dim {
+            RegDim::AmxInt8 => amx_int8::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Avx512VNNI => {
                 avx512_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn)
             }
             RegDim::Avx512BW => avx512bw::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
+            RegDim::AvxVnni => avx_vnni::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Avx2 => avx2::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
             RegDim::Sse => sse::kernel_sb(m, n, k, alpha, beta, a, a_rs, a_cs, b, c, c_rs, c_cs, ap, null_fn),
         }
@@ -299,7 +338,7 @@ unsafe fn pire_gemv<F: UnaryFnC>(
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::Avx2 => {
+        RegDim::AmxInt8 | RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::AvxVnni | RegDim::Avx2 => {
             avx2::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
         }
         RegDim::Sse => sse::axpy(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
@@ -322,10 +361,23 @@ unsafe fn pire_gemv2<F: UnaryFnC>(
     let y_ptr = y.src();
     let incy = y.rs();
     match hw_cfg.reg_dim {
-        RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::Avx2 => {
-            avx2::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func)
+        RegDim::AmxInt8 | RegDim::Avx512VNNI | RegDim::Avx512BW | RegDim::AvxVnni | RegDim::Avx2 => avx2::axpy2(
+            m,
+            n,
+            alpha,
+            a.src(),
+            a.rs(),
+            a.cs(),
+            x_ptr,
+            inc_x,
+            beta,
+            y_ptr,
+            incy,
+            TransposedFn(hw_cfg.func),
+        ),
+        RegDim::Sse => {
+            sse::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, TransposedFn(hw_cfg.func))
         }
-        RegDim::Sse => sse::axpy2(m, n, alpha, a.src(), a.rs(), a.cs(), x_ptr, inc_x, beta, y_ptr, incy, hw_cfg.func),
     }
     return;
 }
@@ -361,3 +413,58 @@ def_pire_gemm!(
     into_pack_array,
     F,
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every feature of the isa set to x, amx as given
+    fn features(x: bool, amx: bool) -> CpuFeatures {
+        CpuFeatures {
+            sse: x,
+            sse2: x,
+            sse3: x,
+            ssse3: x,
+            avx: x,
+            avx2: x,
+            avx512f: x,
+            avx512f16: x,
+            avx512bf16: x,
+            avx512bw: x,
+            avx512_vnni: x,
+            avx_vnni: x,
+            amx_tile: amx,
+            amx_int8: amx,
+            amx_bf16: amx,
+            fma: x,
+            fma4: x,
+            f16c: x,
+        }
+    }
+
+    #[test]
+    fn test_select_kernel_amx() {
+        for x in [false, true] {
+            let (mr, nr, reg_dim) = select_kernel(&features(x, false));
+            assert!(!matches!(reg_dim, RegDim::AmxInt8));
+            assert!(mr != AMX_INT8_MR || nr != AMX_INT8_NR);
+        }
+        let (_, _, reg_dim) = select_kernel(&features(true, false));
+        assert!(matches!(reg_dim, RegDim::Avx512VNNI));
+        let (_, _, reg_dim) = select_kernel(&features(false, false));
+        assert!(matches!(reg_dim, RegDim::Sse));
+        let (mr, nr, reg_dim) = select_kernel(&features(true, true));
+        assert!(matches!(reg_dim, RegDim::AmxInt8));
+        assert_eq!((mr, nr), (AMX_INT8_MR, AMX_INT8_NR));
+    }
+
+    #[test]
+    fn test_runtime_kernel_amx() {
+        // amx is only reported (and so dispatched to) together with the os permission for the tile state
+        let features = (*RUNTIME_HW_CONFIG).cpu_ft();
+        assert!(!features.amx_int8 || features.amx_tile);
+        let hw_config = KernelDispatcher::new(IdentityFn {});
+        assert_eq!(matches!(hw_config.reg_dim, RegDim::AmxInt8), features.amx_int8);
+        assert_eq!(round_k_simd(1) == pack_amx::round_k(1), features.amx_int8);
+    }
+}
@@ -54,7 +54,7 @@ pub unsafe fn axpy<F: UnaryFnC>(
             acc += *a_cur as i32 * *x_cur as i32;
         }
         *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
-        f.call(y_cur, 1);
+        f.call_tile(y_cur, 1, i, 0);
     }
 }
 
@@ -96,7 +96,7 @@ pub unsafe fn axpy2<F: UnaryFnC>(
             acc += *a_cur as i32 * *x_cur as i32;
         }
         *y_cur = (*beta * *y_cur as f32 + *alpha * acc as f32) as i32;
-        f.call(y_cur, 1);
+        f.call_tile(y_cur, 1, i, 0);
     }
 }
 